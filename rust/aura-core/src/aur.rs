@@ -142,7 +142,11 @@ where
 }
 
 /// Clone a package's AUR repository and return the full path to the clone.
-pub fn clone_aur_repo(root: Option<&Path>, package: &str) -> Result<PathBuf, crate::git::Error> {
+pub fn clone_aur_repo(
+    root: Option<&Path>,
+    package: &str,
+    retry: &crate::git::Retry,
+) -> Result<PathBuf, crate::git::Error> {
     let mut url: PathBuf = [AUR_BASE_URL, package].iter().collect();
 
     if let Some(ext) = url.extension() {
@@ -158,7 +162,7 @@ pub fn clone_aur_repo(root: Option<&Path>, package: &str) -> Result<PathBuf, cra
         Some(r) => r.join(package),
     };
 
-    crate::git::shallow_clone(&url, &clone_path).map(|_| clone_path)
+    crate::git::shallow_clone(&url, &clone_path, retry).map(|_| clone_path)
 }
 
 /// Yield a path to the local git clone of the given package. The path won't
@@ -167,7 +171,12 @@ pub fn clone_aur_repo(root: Option<&Path>, package: &str) -> Result<PathBuf, cra
 ///
 /// Either way, if there was no local clone present, this will cause a `git
 /// clone` to occur.
-pub fn clone_path_of_pkgbase<F, E>(clone_d: &Path, pkg: &str, fetch: &F) -> Result<PathBuf, Error>
+pub fn clone_path_of_pkgbase<F, E>(
+    clone_d: &Path,
+    pkg: &str,
+    retry: &crate::git::Retry,
+    fetch: &F,
+) -> Result<PathBuf, Error>
 where
     F: Fn(&str) -> Result<Vec<crate::faur::Package>, E>,
 {
@@ -184,7 +193,7 @@ where
         if has_local_aur_clone(clone_d, &fp.package_base) {
             clone_d.join(&fp.package_base)
         } else {
-            clone_aur_repo(Some(clone_d), &fp.package_base)?
+            clone_aur_repo(Some(clone_d), &fp.package_base, retry)?
         }
     };
 
@@ -1,5 +1,6 @@
 //! AUR package dependency solving.
 
+use crate::git::Retry;
 use applying::Apply;
 use log::debug;
 use log::info;
@@ -76,6 +77,9 @@ pub struct Resolution {
     /// by some package, but under a slightly different name. This also takes
     /// split packages into account.
     provided: HashSet<String>,
+    /// Every package that building will produce, with what it provides and
+    /// conflicts with.
+    pub identities: Vec<Identity>,
 }
 
 impl Resolution {
@@ -108,6 +112,7 @@ impl Resolution {
             to_build,
             satisfied: HashSet::new(),
             provided: HashSet::new(),
+            identities: Vec::new(),
         }
     }
 }
@@ -185,6 +190,90 @@ impl Hash for Buildable {
     }
 }
 
+/// What a package is known as, and what it can't be installed alongside.
+#[derive(Debug, Clone, Default)]
+pub struct Identity {
+    /// The name of the package.
+    pub name: String,
+    /// Other names that it satisfies dependencies on.
+    pub provides: Vec<String>,
+    /// The names of the packages it can't be installed alongside.
+    pub conflicts: Vec<String>,
+}
+
+impl Identity {
+    fn known_as(&self, name: &str) -> bool {
+        self.name == name || self.provides.iter().any(|p| p == name)
+    }
+
+    /// Can't these two packages be installed together? Versions of the same
+    /// package never clash, since one simply replaces the other.
+    fn clashes(&self, other: &Identity) -> bool {
+        self.name != other.name
+            && (self.conflicts.iter().any(|c| other.known_as(c))
+                || other.conflicts.iter().any(|c| self.known_as(c)))
+    }
+}
+
+/// Two packages that can't be installed together.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Conflict {
+    /// A package to be installed.
+    pub pkg: String,
+    /// What it clashes with.
+    pub with: String,
+}
+
+/// The conflicts that some packages would have once installed.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Conflicts {
+    /// Between the packages to be installed, which can't all be.
+    pub among: Vec<Conflict>,
+    /// With packages already installed, which Pacman would offer to remove.
+    pub installed: Vec<Conflict>,
+}
+
+/// Find the conflicts between packages to be installed, and with those already
+/// installed. Each clash is reported only once. Version restrictions on
+/// conflicts aren't considered, so any version of a named package clashes.
+pub fn conflicts(incoming: &[Identity], installed: &[Identity]) -> Conflicts {
+    let mut among: Vec<_> = incoming
+        .iter()
+        .enumerate()
+        .flat_map(|(ix, a)| incoming[ix + 1..].iter().map(move |b| (a, b)))
+        .filter(|(a, b)| a.clashes(b))
+        .map(|(a, b)| {
+            let (pkg, with) = if a.name < b.name { (a, b) } else { (b, a) };
+            Conflict {
+                pkg: pkg.name.clone(),
+                with: with.name.clone(),
+            }
+        })
+        .collect();
+
+    // Installed packages that are about to be replaced don't count.
+    let mut installed: Vec<_> = incoming
+        .iter()
+        .flat_map(|a| {
+            installed
+                .iter()
+                .filter(|i| incoming.iter().any(|b| b.name == i.name).not())
+                .filter(move |i| a.clashes(i))
+                .map(move |i| Conflict {
+                    pkg: a.name.clone(),
+                    with: i.name.clone(),
+                })
+        })
+        .collect();
+
+    among.sort();
+    among.dedup();
+    installed.sort();
+    installed.dedup();
+
+    Conflicts { among, installed }
+}
+
 fn confirm_base_devel<M, E>(pool: Pool<M>, mutx: Arc<Mutex<Resolution>>) -> Result<(), Error<E>>
 where
     M: ManageConnection<Connection = Alpm>,
@@ -209,6 +298,7 @@ pub fn resolve<M, F, E>(
     pool: Pool<M>,
     fetch: &F,
     clone_d: &Path,
+    retry: &Retry,
     nocheck: bool,
     pkgs: &HashSet<&str>,
 ) -> Result<Resolution, Error<E>>
@@ -228,7 +318,17 @@ where
     pkgs.par_iter()
         .map(|pkg| {
             let pool = pool.clone();
-            resolve_one(pool, arc.clone(), fetch, clone_d, pkgs, None, pkg, nocheck)
+            resolve_one(
+                pool,
+                arc.clone(),
+                fetch,
+                clone_d,
+                retry,
+                pkgs,
+                None,
+                pkg,
+                nocheck,
+            )
         })
         .collect::<Validated<(), Error<E>>>()
         .ok()
@@ -252,6 +352,7 @@ fn resolve_one<M, F, E>(
     mutx: Arc<Mutex<Resolution>>,
     fetch: &F,
     clone_d: &Path,
+    retry: &Retry,
     orig: &HashSet<&str>,
     parent: Option<&str>,
     pkg_raw: &str,
@@ -328,7 +429,17 @@ where
                         .map(|d| {
                             let p = Some(prnt.as_str());
                             let pool = pool.clone();
-                            resolve_one(pool, mutx.clone(), fetch, clone_d, orig, p, &d, nocheck)
+                            resolve_one(
+                                pool,
+                                mutx.clone(),
+                                fetch,
+                                clone_d,
+                                retry,
+                                orig,
+                                p,
+                                &d,
+                                nocheck,
+                            )
                         })
                         .collect::<Validated<(), Error<E>>>()
                         .ok()
@@ -339,7 +450,7 @@ where
                     drop(alpm);
 
                     debug!("{} is may be an AUR package.", pr);
-                    let path = pull_or_clone(fetch, clone_d, parent, &pkg)?;
+                    let path = pull_or_clone(fetch, clone_d, retry, parent, &pkg)?;
                     debug!("Parsing .SRCINFO for {}", pkg);
                     let full = path.join(".SRCINFO");
                     let info = Srcinfo::parse_file(&full).map_err(|e| Error::Srcinfo(full, e))?;
@@ -347,6 +458,7 @@ where
 
                     // --- Package identities provided by this one --- //
                     let prov: HashSet<_> = info.pkgs.iter().map(|p| p.pkgname.clone()).collect();
+                    let identities = identities(&info, orig);
 
                     // --- All possible deps to consider --- //
                     let deps: HashSet<String> = info
@@ -378,6 +490,7 @@ where
 
                     mutx.lock().map_err(|_| Error::PoisonedMutex).map(|mut r| {
                         r.to_build.insert(buildable);
+                        r.identities.extend(identities);
 
                         info.pkg
                             .provides
@@ -395,7 +508,17 @@ where
                         .map(|p| {
                             let prnt = Some(parent.as_str());
                             let pool = pool.clone();
-                            resolve_one(pool, mutx.clone(), fetch, clone_d, orig, prnt, &p, nocheck)
+                            resolve_one(
+                                pool,
+                                mutx.clone(),
+                                fetch,
+                                clone_d,
+                                retry,
+                                orig,
+                                prnt,
+                                &p,
+                                nocheck,
+                            )
                         })
                         .collect::<Validated<(), Error<E>>>()
                         .ok()
@@ -408,6 +531,37 @@ where
     Ok(())
 }
 
+/// Every package that building from some `.SRCINFO` will actually install.
+/// Like the build itself, this keeps only the split packages asked for and
+/// those they depend on, if any were. Split packages that don't set their own
+/// `provides` or `conflicts` inherit those of the base.
+fn identities(info: &Srcinfo, requested: &HashSet<&str>) -> Vec<Identity> {
+    let names = |own: &[srcinfo::ArchVec], base: &[srcinfo::ArchVec]| -> Vec<String> {
+        let avs = if own.is_empty() { base } else { own };
+        avs.iter()
+            .flat_map(|av| av.vec.iter())
+            .map(strip_version)
+            .collect()
+    };
+
+    let interdeps = Interdeps::from_srcinfo(info);
+    let wanted = interdeps.wanted(requested.iter().copied());
+
+    info.pkgs
+        .iter()
+        .filter(|p| {
+            wanted
+                .as_ref()
+                .is_none_or(|w| w.contains(p.pkgname.as_str()))
+        })
+        .map(|p| Identity {
+            name: p.pkgname.clone(),
+            provides: names(&p.provides, &info.pkg.provides),
+            conflicts: names(&p.conflicts, &info.pkg.conflicts),
+        })
+        .collect()
+}
+
 /// Consider "checkdeps" as well, unless specifically instructed not to.
 fn respect_checkdeps<T>(nocheck: bool, deps: Vec<T>) -> Vec<T> {
     if nocheck {
@@ -432,6 +586,7 @@ fn respect_checkdeps<T>(nocheck: bool, deps: Vec<T>) -> Vec<T> {
 fn pull_or_clone<S, F, E>(
     fetch: &F,
     clone_d: &Path,
+    retry: &Retry,
     parent: Option<S>,
     pkg: &str,
 ) -> Result<PathBuf, Error<E>>
@@ -478,7 +633,8 @@ where
             // crate::git::pull(&path)?; // Here. Potentially avoid this.
             Ok(path)
         } else {
-            let path = crate::aur::clone_aur_repo(Some(clone_d), &base).map_err(Error::Git)?;
+            let path =
+                crate::aur::clone_aur_repo(Some(clone_d), &base, retry).map_err(Error::Git)?;
             Ok(path)
         }
     }
@@ -555,6 +711,35 @@ pub fn build_order<E>(to_build: &[Buildable]) -> Result<Vec<Vec<&str>>, Error<E>
         })
 }
 
+/// Given some packages that failed to build, which others can no longer be
+/// built, due to depending on them either directly or transitively?
+///
+/// The failed packages themselves are not included in the result.
+pub fn dependents_of<'a>(to_build: &'a [Buildable], failed: &HashSet<String>) -> HashSet<&'a str> {
+    let mut blocked: HashSet<&str> = HashSet::new();
+
+    loop {
+        let before = blocked.len();
+
+        for b in to_build {
+            let name = b.name.as_str();
+
+            if failed.contains(name).not()
+                && blocked.contains(name).not()
+                && b.deps
+                    .iter()
+                    .any(|d| failed.contains(d) || blocked.contains(d.as_str()))
+            {
+                blocked.insert(name);
+            }
+        }
+
+        if blocked.len() == before {
+            break blocked;
+        }
+    }
+}
+
 fn shortest_cycle<N, E>(ix: NodeIndex, graph: &Graph<N, E>) -> Vec<NodeIndex> {
     petgraph::algo::all_simple_paths::<Vec<_>, _>(&graph, ix, ix, 0, None)
         .fold(None, |acc, cycle| match acc {
@@ -594,7 +779,7 @@ where
     S: AsRef<str> + Into<String>,
 {
     stri.as_ref()
-        .split_once(['=', '>'])
+        .split_once(['=', '>', '<'])
         .map(|(good, _)| good.to_string())
         .unwrap_or_else(|| stri.into())
 }
@@ -637,6 +822,24 @@ impl<'a> Interdeps<'a> {
         }
     }
 
+    /// Of the packages split from this base, those asked for and everything
+    /// they depend on within it. `None` if none of them come from this base.
+    pub fn wanted<I>(&'a self, requested: I) -> Option<HashSet<&'a str>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let wanted = requested
+            .into_iter()
+            .filter_map(|r| self.0.get_key_value(r.as_ref()).map(|(k, _)| *k))
+            .fold(HashSet::new(), |mut acc, name| {
+                acc.insert(name);
+                self.transitive_work(name, acc)
+            });
+
+        wanted.is_empty().not().then_some(wanted)
+    }
+
     fn transitive_work(&'a self, dep: &'a str, curr: HashSet<&'a str>) -> HashSet<&'a str> {
         match self.0.get(dep) {
             None => curr,
@@ -682,11 +885,92 @@ mod test {
         assert_eq!(nes!["nxproxy", "libxcomp"], trans);
     }
 
+    #[test]
+    fn split_identities() {
+        let vim = Srcinfo::parse_file("tests/vim-split.SRCINFO").unwrap();
+        let names =
+            |ids: Vec<Identity>| -> Vec<String> { ids.into_iter().map(|i| i.name).collect() };
+
+        // Only the requested variant and what it needs are built, so its
+        // siblings can't clash with it.
+        let requested = HashSet::from(["vim-git", "firefox"]);
+        let ids = identities(&vim, &requested);
+        assert_eq!(vec!["vim-git-runtime", "vim-git"], names(ids.clone()));
+        assert!(conflicts(&ids, &[]).among.is_empty());
+
+        // Had both been asked for, they would.
+        let requested = HashSet::from(["vim-git", "gvim-git"]);
+        let ids = identities(&vim, &requested);
+        assert_eq!(3, ids.len());
+        assert_eq!(
+            vec![Conflict {
+                pkg: "gvim-git".to_string(),
+                with: "vim-git".to_string(),
+            }],
+            conflicts(&ids, &[]).among
+        );
+
+        // When nothing in the base was asked for by name, as for a dependency,
+        // everything is built.
+        let ids = identities(&vim, &HashSet::from(["neovim"]));
+        assert_eq!(3, ids.len());
+
+        let deps = Interdeps::from_srcinfo(&vim);
+        assert_eq!(
+            Some(HashSet::from(["gvim-git", "vim-git-runtime"])),
+            deps.wanted(["gvim-git"])
+        );
+        assert_eq!(None, deps.wanted(["neovim"]));
+    }
+
     #[test]
     fn version_stripping() {
         assert_eq!("gcc6", strip_version("gcc6"));
         assert_eq!("gcc6", strip_version("gcc6=6.5.0-7"));
         assert_eq!("glibc", strip_version("glibc>=2.25"));
+        assert_eq!("python", strip_version("python<3.13"));
+    }
+
+    #[test]
+    fn conflicting_targets() {
+        let fixture = std::fs::read_to_string("tests/conflicts.txt").unwrap();
+        let (mut incoming, mut installed) = (Vec::new(), Vec::new());
+
+        for line in fixture.lines().filter(|l| l.starts_with('#').not()) {
+            let (status, rest) = line.split_once(' ').unwrap();
+            let (name, rest) = rest.split_once(':').unwrap();
+            let (provides, conflicts) = rest.split_once('|').unwrap();
+            let words = |s: &str| s.split_whitespace().map(String::from).collect();
+            let identity = Identity {
+                name: name.to_string(),
+                provides: words(provides),
+                conflicts: words(conflicts),
+            };
+
+            match status {
+                "incoming" => incoming.push(identity),
+                _ => installed.push(identity),
+            }
+        }
+
+        let conflict = |pkg: &str, with: &str| Conflict {
+            pkg: pkg.to_string(),
+            with: with.to_string(),
+        };
+
+        // The deps of two targets, `jack2` and `pipewire-jack`, both provide
+        // `jack`, which the latter also conflicts with. Installed packages
+        // about to be replaced by a new version don't clash.
+        assert_eq!(
+            Conflicts {
+                among: vec![conflict("jack2", "pipewire-jack")],
+                installed: vec![conflict("pipewire-pulse", "pulseaudio")],
+            },
+            conflicts(&incoming, &installed)
+        );
+
+        let clear = conflicts(&incoming[..2], &[]);
+        assert!(clear.among.is_empty() && clear.installed.is_empty());
     }
 
     #[test]
@@ -782,4 +1066,43 @@ mod test {
         }
         assert_eq!(vec![vec!["d"], vec!["b", "c"], vec!["a", "e", "f"]], o);
     }
+
+    #[test]
+    fn failed_dependents() {
+        let v = vec![
+            Buildable {
+                name: "a".to_string(),
+                deps: vec!["b".to_string(), "c".to_string()].into_iter().collect(),
+            },
+            Buildable {
+                name: "b".to_string(),
+                deps: vec!["d".to_string()].into_iter().collect(),
+            },
+            Buildable {
+                name: "c".to_string(),
+                deps: HashSet::new(),
+            },
+            Buildable {
+                name: "d".to_string(),
+                deps: vec!["glibc".to_string()].into_iter().collect(),
+            },
+            Buildable {
+                name: "e".to_string(),
+                deps: vec!["c".to_string()].into_iter().collect(),
+            },
+        ];
+
+        // `d` failing blocks everything above it in the chain, but `c` and `e`
+        // are unaffected.
+        let failed: HashSet<_> = ["d".to_string()].into();
+        let exp: HashSet<_> = ["b", "a"].into();
+        assert_eq!(exp, dependents_of(&v, &failed));
+
+        let failed: HashSet<_> = ["c".to_string()].into();
+        let exp: HashSet<_> = ["a", "e"].into();
+        assert_eq!(exp, dependents_of(&v, &failed));
+
+        let failed: HashSet<_> = ["a".to_string()].into();
+        assert!(dependents_of(&v, &failed).is_empty());
+    }
 }
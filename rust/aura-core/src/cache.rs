@@ -1,11 +1,15 @@
 //! Cache manipulation internals.
 
 use crate::Package;
+use alpm::SigLevel;
 use r2d2_alpm::Alpm;
+use sha2::Digest;
+use sha2::Sha256;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::ffi::OsString;
+use std::fs::File;
 use std::fs::Metadata;
 use std::path::Path;
 use std::path::PathBuf;
@@ -112,6 +116,44 @@ pub struct CacheInfo {
     pub available: Vec<String>,
 }
 
+/// The outcome of verifying a single cached tarball.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Verification {
+    /// The tarball is intact and correctly signed.
+    Valid,
+    /// The tarball can't be loaded, or doesn't match its recorded checksum.
+    Corrupt,
+    /// The tarball is intact, but has no signature file.
+    Unsigned,
+    /// The tarball is intact, but its signature doesn't match it.
+    BadSignature,
+}
+
+impl Verification {
+    /// Combine the results of the individual checks. A `signature` of `None`
+    /// means that there was no `.sig` file to check.
+    ///
+    /// ```
+    /// use aura_core::cache::Verification;
+    ///
+    /// assert_eq!(Verification::Corrupt, Verification::new(false, Some(true)));
+    /// assert_eq!(Verification::Unsigned, Verification::new(true, None));
+    /// ```
+    pub fn new(intact: bool, signature: Option<bool>) -> Verification {
+        match (intact, signature) {
+            (false, _) => Verification::Corrupt,
+            (true, None) => Verification::Unsigned,
+            (true, Some(false)) => Verification::BadSignature,
+            (true, Some(true)) => Verification::Valid,
+        }
+    }
+
+    /// Should this tarball be removed from the cache?
+    pub fn is_invalid(&self) -> bool {
+        matches!(self, Verification::Corrupt | Verification::BadSignature)
+    }
+}
+
 /// All package tarball filenames that match a given string.
 pub fn search<'a, P>(caches: &'a [P], term: &'a str) -> impl Iterator<Item = PathBuf> + 'a
 where
@@ -250,6 +292,50 @@ pub fn missing_tarballs<'a>(
     })
 }
 
+/// Verify a tarball without modifying anything.
+///
+/// The tarball is compared against the checksum recorded in the sync databases
+/// (if that exact version is still available there), loaded by ALPM, and
+/// checked against its `.sig` file, if it has one.
+pub fn verify<A>(alpm: A, pp: &PkgPath) -> Verification
+where
+    A: AsRef<alpm::Alpm>,
+{
+    let alpm = alpm.as_ref();
+    let pkg = pp.as_package();
+
+    let path = match pp.as_path().to_str() {
+        Some(p) => p,
+        None => return Verification::Corrupt,
+    };
+
+    let checksum = alpm
+        .syncdbs()
+        .pkg(pkg.name.as_ref())
+        .ok()
+        .filter(|p| pkg.same_version(p.version().as_str()))
+        .and_then(|p| p.sha256sum())
+        .map(|expected| sha256(pp.as_path()).is_ok_and(|actual| actual == expected))
+        .unwrap_or(true);
+
+    let intact = checksum && alpm.pkg_load(path, true, SigLevel::NONE).is_ok();
+    let signature = pp
+        .sig_file()
+        .exists()
+        .then(|| alpm.pkg_load(path, true, SigLevel::PACKAGE).is_ok());
+
+    Verification::new(intact, signature)
+}
+
+/// The SHA-256 checksum of a file, as lowercase hex.
+pub fn sha256(path: &Path) -> Result<String, std::io::Error> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
 // TODO Provide a similar function for signature files.
 /// Is a given `Path` a legal Arch Linux package tarball?
 ///
@@ -3,10 +3,14 @@
 use crate::DbLike;
 use itertools::Itertools;
 use log::debug;
+use petgraph::algo::tarjan_scc;
 use petgraph::graph::NodeIndex;
 use petgraph::Directed;
+use petgraph::Direction;
 use petgraph::Graph;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
 
 /// A dependency relationship between parent and child.
 pub enum DepType {
@@ -44,6 +48,27 @@ pub struct PkgGraph<'a> {
     foreigns: &'a [&'a str],
 }
 
+/// A single line of a [`PkgGraph::tree`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct Branch<'a> {
+    /// The package's true name.
+    pub name: &'a str,
+    /// How far below its focus the package sits. Focii themselves are at 0.
+    pub depth: u8,
+    /// Was this package already listed, along with its dependencies, earlier in
+    /// the tree? If so, it isn't expanded again.
+    pub seen: bool,
+}
+
+/// A package as seen while building a graph: its true name (which may differ
+/// from the name it was sought by, if it only provides that), its group, and the
+/// packages it has edges to.
+struct Node<'a> {
+    name: &'a str,
+    group: Option<Group<'a>>,
+    edges: Vec<(String, DepType)>,
+}
+
 impl<'a> PkgGraph<'a> {
     /// Create a new `PkgGraph` of given packages and all their dependencies.
     pub fn by_deps<D>(
@@ -56,12 +81,27 @@ impl<'a> PkgGraph<'a> {
     where
         D: DbLike,
     {
-        let mut graph = Graph::default();
-        let mut indices = HashMap::new();
-
-        for p in focii {
-            PkgGraph::add_dep(db, &mut graph, &mut indices, limit, optional, p);
-        }
+        let graph = PkgGraph::build(focii, limit, false, |wanted| {
+            let true_pkg = db.get_pkg(wanted).ok().or_else(|| db.provides(wanted))?;
+            debug!("Found {} providing {}", true_pkg.name(), wanted);
+
+            // Dependencies required at runtime.
+            let hard = true_pkg
+                .depends()
+                .iter()
+                .map(|d| (d.name().to_string(), DepType::Hard));
+            let opts = true_pkg
+                .optdepends()
+                .iter()
+                .filter(|_| optional)
+                .map(|d| (d.name().to_string(), DepType::Opt));
+
+            Some(Node {
+                name: true_pkg.name(),
+                group: true_pkg.groups().first(),
+                edges: hard.chain(opts).collect(),
+            })
+        });
 
         PkgGraph {
             graph,
@@ -81,14 +121,28 @@ impl<'a> PkgGraph<'a> {
     where
         D: DbLike,
     {
-        let mut graph = Graph::default();
-        let mut indices = HashMap::new();
-
         debug!("Focii: {:?}", focii);
 
-        for p in focii {
-            PkgGraph::add_parent(db, &mut graph, &mut indices, limit, optional, p);
-        }
+        let graph = PkgGraph::build(focii, limit, true, |wanted| {
+            let true_pkg = db.get_pkg(wanted).ok().or_else(|| db.provides(wanted))?;
+            debug!("Found {} providing {}", true_pkg.name(), wanted);
+
+            let hard = true_pkg
+                .required_by()
+                .into_iter()
+                .map(|p| (p, DepType::Hard));
+            let opts = optional
+                .then(|| true_pkg.optional_for())
+                .into_iter()
+                .flatten()
+                .map(|p| (p, DepType::Opt));
+
+            Some(Node {
+                name: true_pkg.name(),
+                group: true_pkg.groups().first(),
+                edges: hard.chain(opts).collect(),
+            })
+        });
 
         PkgGraph {
             graph,
@@ -97,97 +151,178 @@ impl<'a> PkgGraph<'a> {
         }
     }
 
-    /// Recursively add dependencies to the package `Graph`.
-    fn add_dep<D>(
-        db: &'a D,
-        graph: &mut Graph<(&'a str, Option<Group<'a>>), DepType, Directed, u16>,
-        indices: &mut HashMap<&'a str, NodeIndex<u16>>,
+    /// Breadth-first, add the focii and everything reachable from them within
+    /// `limit` layers, where `resolve` finds the neighbours of each package.
+    /// Each package is only expanded once, and at the shallowest depth it's
+    /// found, so cycles can't prevent termination. For a `reverse` graph, edges
+    /// point from each neighbour to the package it was found through.
+    fn build<F>(
+        focii: &[&str],
         limit: Option<u8>,
-        optional: bool,
-        parent: &'a str,
-    ) -> Option<NodeIndex<u16>>
+        reverse: bool,
+        mut resolve: F,
+    ) -> Graph<(&'a str, Option<Group<'a>>), DepType, Directed, u16>
     where
-        D: DbLike,
+        F: FnMut(&str) -> Option<Node<'a>>,
     {
-        let true_pkg = db.get_pkg(parent).ok().or_else(|| db.provides(parent))?;
-        let name = true_pkg.name();
-        debug!("Found {} providing {}", name, parent);
-
-        indices.get(name).cloned().or_else(|| {
-            let ix = graph.add_node((name, true_pkg.groups().first()));
-            debug!("Added {} at {}", name, ix.index());
-            indices.insert(name, ix);
-            let next = limit.map(|l| l - 1);
-
-            if next.is_none() || next > Some(0) {
-                // Dependencies required at runtime.
-                for d in true_pkg.depends().iter() {
-                    if let Some(dix) =
-                        PkgGraph::add_dep(db, graph, indices, next, optional, d.name())
-                    {
-                        graph.update_edge(ix, dix, DepType::Hard);
+        let mut graph = Graph::default();
+        let mut indices: HashMap<&'a str, NodeIndex<u16>> = HashMap::new();
+        let mut queue: VecDeque<_> = focii.iter().map(|p| (p.to_string(), 1, None)).collect();
+
+        while let Some((wanted, depth, from)) = queue.pop_front() {
+            let Some(node) = resolve(&wanted) else {
+                continue;
+            };
+
+            let ix = match indices.get(node.name) {
+                Some(ix) => *ix,
+                None => {
+                    let ix = graph.add_node((node.name, node.group));
+                    debug!("Added {} at {}", node.name, ix.index());
+                    indices.insert(node.name, ix);
+
+                    if limit.map(|l| depth < l).unwrap_or(true) {
+                        let next = depth.saturating_add(1);
+                        queue.extend(
+                            node.edges
+                                .into_iter()
+                                .map(|(n, dt)| (n, next, Some((ix, dt)))),
+                        );
                     }
+
+                    ix
                 }
+            };
 
-                if optional {
-                    for d in true_pkg.optdepends().iter() {
-                        if let Some(dix) =
-                            PkgGraph::add_dep(db, graph, indices, next, optional, d.name())
-                        {
-                            graph.update_edge(ix, dix, DepType::Opt);
-                        }
-                    }
+            if let Some((fix, dt)) = from {
+                if reverse {
+                    graph.update_edge(ix, fix, dt);
+                } else {
+                    graph.update_edge(fix, ix, dt);
                 }
             }
+        }
 
-            Some(ix)
-        })
+        graph
     }
 
-    fn add_parent<D>(
-        db: &'a D,
-        graph: &mut Graph<(&'a str, Option<Group<'a>>), DepType, Directed, u16>,
-        indices: &mut HashMap<&'a str, NodeIndex<u16>>,
-        limit: Option<u8>,
-        optional: bool,
-        // No lifetime because `required_by` yields owned Strings, which can't
-        // have a `'a` lifetime.
-        child: &str,
-    ) -> Option<NodeIndex<u16>>
-    where
-        D: DbLike,
-    {
-        let true_pkg = db.get_pkg(child).ok().or_else(|| db.provides(child))?;
-        let name = true_pkg.name();
-        debug!("Found {} providing {}", name, child);
-
-        indices.get(name).cloned().or_else(|| {
-            let ix = graph.add_node((name, true_pkg.groups().first()));
-            indices.insert(name, ix);
-            let next = limit.map(|l| l - 1);
-
-            if next.is_none() || next > Some(0) {
-                for parent in true_pkg.required_by() {
-                    if let Some(pix) =
-                        PkgGraph::add_parent(db, graph, indices, next, optional, &parent)
-                    {
-                        graph.update_edge(pix, ix, DepType::Hard);
-                    }
-                }
+    /// Depth-first, each focus and everything below it as the lines of an
+    /// indented tree, descending at most `depth` levels. Dependencies of a
+    /// package are listed by name. A package reached again after it has been
+    /// expanded, from the same focus or an earlier one, is marked as `seen` and
+    /// not expanded a second time.
+    ///
+    /// A graph built with a `limit` is only complete enough for a `depth` of
+    /// one less than it.
+    pub fn tree(&self, depth: Option<u8>) -> Vec<Branch<'a>> {
+        let mut seen = HashSet::new();
+        let mut branches = Vec::new();
+
+        for focus in self.focii {
+            let found = self
+                .graph
+                .node_indices()
+                .find(|ix| self.graph[*ix].0 == *focus);
+
+            if let Some(ix) = found {
+                self.descend(ix, 0, depth, &mut seen, &mut branches);
+            }
+        }
 
-                if optional {
-                    for parent in true_pkg.optional_for() {
-                        if let Some(pix) =
-                            PkgGraph::add_parent(db, graph, indices, next, optional, &parent)
-                        {
-                            graph.update_edge(pix, ix, DepType::Opt);
-                        }
-                    }
+        branches
+    }
+
+    fn descend(
+        &self,
+        ix: NodeIndex<u16>,
+        level: u8,
+        depth: Option<u8>,
+        seen: &mut HashSet<NodeIndex<u16>>,
+        branches: &mut Vec<Branch<'a>>,
+    ) {
+        let name = self.graph[ix].0;
+
+        if seen.contains(&ix) {
+            branches.push(Branch {
+                name,
+                depth: level,
+                seen: true,
+            });
+        } else {
+            branches.push(Branch {
+                name,
+                depth: level,
+                seen: false,
+            });
+
+            // Packages at the bottom of the tree aren't counted as seen, since
+            // their own dependencies haven't been shown.
+            if depth.map(|d| level < d).unwrap_or(true) {
+                seen.insert(ix);
+
+                for child in self
+                    .graph
+                    .neighbors_directed(ix, Direction::Outgoing)
+                    .sorted_by_key(|c| self.graph[*c].0)
+                {
+                    self.descend(child, level.saturating_add(1), depth, seen, branches);
                 }
             }
+        }
+    }
 
-            Some(ix)
-        })
+    /// The packages that nothing else in the graph depends on, sorted by name.
+    /// In a graph built [`PkgGraph::by_parents`], these are the topmost
+    /// dependents of the focii.
+    pub fn roots(&self) -> Vec<&'a str> {
+        self.ends(Direction::Incoming)
+    }
+
+    /// The packages with no dependencies of their own in the graph, sorted by
+    /// name. Packages at the edge of a graph built with a `limit` count too,
+    /// since their dependencies were never followed.
+    pub fn leaves(&self) -> Vec<&'a str> {
+        self.ends(Direction::Outgoing)
+    }
+
+    /// Packages with no edges in the given direction, besides to themselves.
+    fn ends(&self, direction: Direction) -> Vec<&'a str> {
+        self.graph
+            .node_indices()
+            .filter(|ix| {
+                self.graph
+                    .neighbors_directed(*ix, direction)
+                    .all(|n| n == *ix)
+            })
+            .map(|ix| self.graph[ix].0)
+            .sorted()
+            .collect()
+    }
+
+    /// The packages caught in each dependency cycle, sorted by name.
+    pub fn cycles(&self) -> Vec<Vec<&'a str>> {
+        self.cyclic_sets()
+            .into_iter()
+            .map(|set| {
+                set.into_iter()
+                    .filter_map(|ix| self.graph.node_weight(ix).map(|(name, _)| *name))
+                    .sorted()
+                    .collect::<Vec<_>>()
+            })
+            .sorted()
+            .collect()
+    }
+
+    /// Groups of nodes that can all reach one another. A lone node only counts
+    /// if it depends on itself.
+    fn cyclic_sets(&self) -> Vec<Vec<NodeIndex<u16>>> {
+        tarjan_scc(&self.graph)
+            .into_iter()
+            .filter(|scc| match scc.as_slice() {
+                [ix] => self.graph.contains_edge(*ix, *ix),
+                _ => true,
+            })
+            .collect()
     }
 }
 
@@ -262,17 +397,265 @@ impl std::fmt::Display for PkgGraph<'_> {
 
         writeln!(f)?;
 
+        // Edges within a cycle are drawn in red.
+        let cycle_of: HashMap<NodeIndex<u16>, usize> = self
+            .cyclic_sets()
+            .into_iter()
+            .enumerate()
+            .flat_map(|(n, set)| set.into_iter().map(move |ix| (ix, n)))
+            .collect();
+
         // Render edges.
         for e in graph.raw_edges().iter() {
+            let cyclic = cycle_of
+                .get(&e.source())
+                .is_some_and(|n| cycle_of.get(&e.target()) == Some(n));
+
             writeln!(
                 f,
-                "    {} -> {} [{}]",
+                "    {} -> {} [{}{}]",
                 e.source().index(),
                 e.target().index(),
-                e.weight.edge_style()
+                e.weight.edge_style(),
+                if cyclic { ", color=red" } else { "" }
             )?;
         }
 
         write!(f, "}}")
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::ops::Not;
+
+    /// A small package set in which `a`, `b`, and `c` depend on each other in a
+    /// ring, and `d` hangs off of it.
+    fn resolve(wanted: &str) -> Option<Node<'static>> {
+        let (name, deps): (&'static str, &[&str]) = match wanted {
+            "a" => ("a", &["b"]),
+            "b" => ("b", &["c"]),
+            "c" => ("c", &["a", "d"]),
+            "d" => ("d", &["e"]),
+            "e" => ("e", &[]),
+            _ => return None,
+        };
+
+        Some(Node {
+            name,
+            group: None,
+            edges: deps
+                .iter()
+                .map(|d| (d.to_string(), DepType::Hard))
+                .collect(),
+        })
+    }
+
+    fn graph(limit: Option<u8>) -> PkgGraph<'static> {
+        PkgGraph {
+            graph: PkgGraph::build(&["a"], limit, false, resolve),
+            focii: &["a"],
+            foreigns: &[],
+        }
+    }
+
+    #[test]
+    fn cycles_terminate() {
+        let g = graph(None);
+
+        assert_eq!(5, g.graph.node_count());
+        assert_eq!(5, g.graph.edge_count());
+        assert_eq!(vec![vec!["a", "b", "c"]], g.cycles());
+
+        let dot = g.to_string();
+        assert_eq!(3, dot.matches("color=red").count());
+    }
+
+    #[test]
+    fn limits() {
+        // Only `a` and its direct dependency.
+        let g = graph(Some(2));
+        assert_eq!(2, g.graph.node_count());
+        assert!(g.cycles().is_empty());
+
+        assert_eq!(1, graph(Some(1)).graph.node_count());
+        assert_eq!(1, graph(Some(0)).graph.node_count());
+
+        // `c` is found, but not expanded.
+        let g = graph(Some(3));
+        assert_eq!(3, g.graph.node_count());
+        assert!(g.cycles().is_empty());
+
+        // Enough to close the ring, but not to reach `e`.
+        let g = graph(Some(4));
+        assert_eq!(4, g.graph.node_count());
+        assert_eq!(vec![vec!["a", "b", "c"]], g.cycles());
+    }
+
+    #[test]
+    fn trees() {
+        let g = graph(None);
+        let lines: Vec<_> = g
+            .tree(None)
+            .into_iter()
+            .map(|b| (b.name, b.depth, b.seen))
+            .collect();
+
+        // `a` closes the ring, so it's marked rather than expanded again.
+        assert_eq!(
+            vec![
+                ("a", 0, false),
+                ("b", 1, false),
+                ("c", 2, false),
+                ("a", 3, true),
+                ("d", 3, false),
+                ("e", 4, false),
+            ],
+            lines
+        );
+
+        // Limited to one less than the graph itself.
+        let g = graph(Some(3));
+        let names: Vec<_> = g.tree(Some(2)).into_iter().map(|b| b.name).collect();
+        assert_eq!(vec!["a", "b", "c"], names);
+    }
+
+    #[test]
+    fn shared_branches() {
+        let focii = ["d", "c"];
+        let g = PkgGraph {
+            graph: PkgGraph::build(&focii, None, false, resolve),
+            focii: &focii,
+            foreigns: &[],
+        };
+        let lines: Vec<_> = g
+            .tree(Some(1))
+            .into_iter()
+            .map(|b| (b.name, b.depth, b.seen))
+            .collect();
+
+        // `d` was expanded under its own root, but `a` only reached the bottom
+        // of the tree.
+        assert_eq!(
+            vec![
+                ("d", 0, false),
+                ("e", 1, false),
+                ("c", 0, false),
+                ("a", 1, false),
+                ("d", 1, true),
+            ],
+            lines
+        );
+    }
+
+    /// Packages and their dependencies, as listed in a fixture file.
+    fn subgraph() -> HashMap<String, Vec<String>> {
+        std::fs::read_to_string("tests/subgraph.txt")
+            .unwrap()
+            .lines()
+            .filter(|l| l.starts_with('#').not())
+            .filter_map(|l| l.split_once(':'))
+            .map(|(name, deps)| {
+                let deps = deps.split_whitespace().map(|d| d.to_string()).collect();
+                (name.to_string(), deps)
+            })
+            .collect()
+    }
+
+    fn fixture<'a>(
+        pkgs: &'a HashMap<String, Vec<String>>,
+        focii: &'a [&'a str],
+        limit: Option<u8>,
+        reverse: bool,
+    ) -> PkgGraph<'a> {
+        let graph = PkgGraph::build(focii, limit, reverse, |wanted| {
+            let (name, deps) = pkgs.get_key_value(wanted)?;
+
+            let edges = if reverse {
+                pkgs.iter()
+                    .filter(|(_, ds)| ds.contains(name))
+                    .map(|(p, _)| (p.clone(), DepType::Hard))
+                    .collect()
+            } else {
+                deps.iter().map(|d| (d.clone(), DepType::Hard)).collect()
+            };
+
+            Some(Node {
+                name: name.as_str(),
+                group: None,
+                edges,
+            })
+        });
+
+        PkgGraph {
+            graph,
+            focii,
+            foreigns: &[],
+        }
+    }
+
+    #[test]
+    fn roots_and_leaves() {
+        let pkgs = subgraph();
+
+        let g = fixture(&pkgs, &["gimp", "inkscape"], None, false);
+        assert_eq!(vec!["gimp", "inkscape"], g.roots());
+        assert_eq!(vec!["babl", "glib2", "gsl", "harfbuzz"], g.leaves());
+
+        // `gtk3` is needed by `gimp`, so it isn't a root of their graph.
+        let g = fixture(&pkgs, &["gtk3", "gimp"], None, false);
+        assert_eq!(vec!["gimp"], g.roots());
+
+        // Packages cut off by a limit are leaves.
+        let g = fixture(&pkgs, &["gimp"], Some(2), false);
+        assert_eq!(vec!["gimp"], g.roots());
+        assert_eq!(vec!["babl", "gegl", "gtk3"], g.leaves());
+
+        // Reversed, the topmost dependents are the roots.
+        let g = fixture(&pkgs, &["glib2"], None, true);
+        assert_eq!(vec!["gimp", "inkscape"], g.roots());
+        assert_eq!(vec!["glib2"], g.leaves());
+
+        // Nothing in a ring is a root or a leaf.
+        let g = graph(None);
+        assert!(g.roots().is_empty());
+        assert_eq!(vec!["e"], g.leaves());
+    }
+
+    #[test]
+    fn shared_nodes() {
+        let pkgs = subgraph();
+        let g = fixture(&pkgs, &["gimp", "inkscape"], None, false);
+        let dot = g.to_string();
+        let nodes = |pkg: &str| {
+            let label = format!("label=\"{pkg}\"");
+            dot.lines().filter(|l| l.contains(&label)).count()
+        };
+
+        // Both focii need `gtk3`, and through it, `glib2`.
+        assert_eq!(1, nodes("gtk3"));
+        assert_eq!(1, nodes("glib2"));
+        assert_eq!(1, nodes("gimp"));
+        assert_eq!(1, nodes("inkscape"));
+
+        // Each edge is drawn once, no matter how many paths lead to it.
+        let gtk3 = dot
+            .lines()
+            .find(|l| l.contains("label=\"gtk3\""))
+            .and_then(|l| l.split_whitespace().next())
+            .unwrap();
+        let into_gtk3 = format!("-> {gtk3} ");
+        assert_eq!(2, dot.lines().filter(|l| l.contains(&into_gtk3)).count());
+    }
+
+    #[test]
+    fn reversed_edges() {
+        let g = PkgGraph::build(&["a"], None, true, resolve);
+        let a = NodeIndex::new(0);
+        let b = NodeIndex::new(1);
+
+        assert!(g.contains_edge(b, a));
+        assert!(g.contains_edge(a, b).not());
+    }
+}
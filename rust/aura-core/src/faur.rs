@@ -2,10 +2,16 @@
 
 use serde::Deserialize;
 use serde::Serialize;
+use std::collections::HashSet;
 
 /// The main `faur` instance.
 pub const FAUR_URL: &str = "https://faur.fosskers.ca";
 
+/// The longest comma-separated list of names to send in a single request. The
+/// AUR's RPC rejects URIs longer than 4443 characters, so this leaves room for
+/// the rest of the URL.
+const MAX_QUERY: usize = 4000;
+
 /// Package information returned from a `faur` instance. Identical in format to
 /// the AUR's RPC, but yields empty lists for missing fields.
 #[derive(Deserialize, Serialize)]
@@ -80,27 +86,75 @@ pub struct Package {
     pub version: String,
 }
 
+/// The package fields that [`search`] matches terms against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchBy {
+    /// Package names only.
+    Name,
+    /// Package names and descriptions.
+    #[default]
+    NameDesc,
+}
+
+impl SearchBy {
+    /// The value of the endpoint's `by` parameter.
+    fn param(self) -> &'static str {
+        match self {
+            SearchBy::Name => "name",
+            SearchBy::NameDesc => "desc",
+        }
+    }
+}
+
 /// Look up K-many packages by name in a database of N packages: `O(klogn)`
+///
+/// Duplicate names are dropped, and the rest are sent in as few requests as the
+/// endpoint allows.
 pub fn info<'a, I, F, E>(pkgs: I, fetch: &F) -> Result<Vec<Package>, E>
 where
     F: Fn(&str) -> Result<Vec<Package>, E>,
     I: IntoIterator<Item = &'a str>,
 {
-    let s = combine_and_sanitize(pkgs);
-    let url = format!("{}/packages?names={}", FAUR_URL, s);
-    fetch(&url)
+    let mut found = Vec::new();
+
+    for batch in batches(pkgs) {
+        let url = format!("{}/packages?names={}", FAUR_URL, batch);
+        found.extend(fetch(&url)?);
+    }
+
+    Ok(found)
 }
 
-/// Look up packages whose names and/or descriptions contain all of the K-many
-/// given terms: `O(klogn)`
-pub fn search<'a, I, F, E>(terms: I, fetch: &F) -> Result<Vec<Package>, E>
+/// Look up packages whose names (and descriptions, depending on `by`) contain
+/// all of the K-many given terms: `O(klogn)`
+///
+/// As with [`info`], terms are sent in as few requests as possible. When more
+/// than one is needed, only the packages matched by every request are kept.
+pub fn search<'a, I, F, E>(terms: I, by: SearchBy, fetch: &F) -> Result<Vec<Package>, E>
 where
     F: Fn(&str) -> Result<Vec<Package>, E>,
     I: IntoIterator<Item = &'a str>,
 {
-    let s = combine_and_sanitize(terms);
-    let url = format!("{}/packages?names={}&by=desc", FAUR_URL, s);
-    fetch(&url)
+    let mut matches: Option<Vec<Package>> = None;
+
+    for batch in batches(terms) {
+        let url = format!("{}/packages?names={}&by={}", FAUR_URL, batch, by.param());
+        let found = fetch(&url)?;
+
+        matches = match matches {
+            None => Some(found),
+            Some(prev) => {
+                let names: HashSet<_> = found.into_iter().map(|p| p.name).collect();
+                Some(
+                    prev.into_iter()
+                        .filter(|p| names.contains(&p.name))
+                        .collect(),
+                )
+            }
+        };
+    }
+
+    Ok(matches.unwrap_or_default())
 }
 
 /// Look up packages that provide the given "package identity": `O(logn)`
@@ -121,12 +175,36 @@ where
     // FIXME Thu May  5 2022 Use `intersperse` once it stabilises.
     terms
         .into_iter()
+        .map(sanitize)
         .collect::<Vec<_>>()
         .join(",")
-        .chars()
-        // Poor man's URL encoding. This fixes the lookup for packages like `libstdc++5`.
-        .map(|c| if c == '+' { "%2B".to_string() } else { String::from(c) })
-        .collect()
+}
+
+/// Like [`combine_and_sanitize`], but without duplicates and split such that no
+/// combined list exceeds [`MAX_QUERY`].
+fn batches<'a, I>(terms: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut seen = HashSet::new();
+    let mut batches: Vec<String> = Vec::new();
+
+    for term in terms.into_iter().filter(|t| seen.insert(*t)).map(sanitize) {
+        match batches.last_mut() {
+            Some(batch) if batch.len() + 1 + term.len() <= MAX_QUERY => {
+                batch.push(',');
+                batch.push_str(&term);
+            }
+            _ => batches.push(term),
+        }
+    }
+
+    batches
+}
+
+/// Poor man's URL encoding. This fixes the lookup for packages like `libstdc++5`.
+fn sanitize(term: &str) -> String {
+    term.replace('+', "%2B")
 }
 
 #[cfg(test)]
@@ -150,4 +228,80 @@ mod test {
 
         assert_eq!(3, pkgs.len());
     }
+
+    fn fixture() -> Vec<Package> {
+        let file = File::open("tests/faur.json").unwrap();
+        serde_json::from_reader(BufReader::new(file)).unwrap()
+    }
+
+    #[test]
+    fn batching() {
+        let terms: Vec<String> = (0..1000).map(|n| format!("pkg-{:06}", n)).collect();
+        let bs = batches(terms.iter().map(|t| t.as_str()).chain(["pkg-000001"]));
+
+        // 1000 terms of 10 characters, plus commas.
+        assert_eq!(3, bs.len());
+        assert!(bs.iter().all(|b| b.len() <= MAX_QUERY));
+        assert_eq!(1000, bs.iter().map(|b| b.split(',').count()).sum::<usize>());
+        assert_eq!(vec!["aura,aura-bin"], batches(["aura", "aura-bin", "aura"]));
+    }
+
+    #[test]
+    fn batched_info() {
+        let calls = std::cell::Cell::new(0);
+        let terms: Vec<String> = (0..1000).map(|n| format!("pkg-{:06}", n)).collect();
+        let fetch = |_: &str| -> Result<Vec<Package>, ()> {
+            calls.set(calls.get() + 1);
+            Ok(fixture())
+        };
+
+        let found = info(terms.iter().map(|t| t.as_str()), &fetch).unwrap();
+        assert_eq!(3, calls.get());
+        assert_eq!(9, found.len());
+    }
+
+    #[test]
+    fn batched_search() {
+        let calls = std::cell::Cell::new(0);
+        let terms: Vec<String> = (0..1000).map(|n| format!("pkg-{:06}", n)).collect();
+        let fetch = |_: &str| -> Result<Vec<Package>, ()> {
+            calls.set(calls.get() + 1);
+            let mut pkgs = fixture();
+            // Each further request matches fewer packages.
+            pkgs.truncate(4 - calls.get());
+            Ok(pkgs)
+        };
+
+        let found = search(
+            terms.iter().map(|t| t.as_str()),
+            SearchBy::default(),
+            &fetch,
+        )
+        .unwrap();
+        let names: Vec<_> = found.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(3, calls.get());
+        assert_eq!(vec!["aura"], names);
+    }
+
+    #[test]
+    fn search_fields() {
+        let url = |by| {
+            let seen = std::cell::RefCell::new(String::new());
+            let fetch = |u: &str| -> Result<Vec<Package>, ()> {
+                seen.replace(u.to_string());
+                Ok(Vec::new())
+            };
+            search(["aura"], by, &fetch).unwrap();
+            seen.into_inner()
+        };
+
+        assert_eq!(
+            "https://faur.fosskers.ca/packages?names=aura&by=name",
+            url(SearchBy::Name)
+        );
+        assert_eq!(
+            "https://faur.fosskers.ca/packages?names=aura&by=desc",
+            url(SearchBy::NameDesc)
+        );
+    }
 }
@@ -0,0 +1,98 @@
+//! Approximate string matching, for searches that forgive typos.
+
+/// The fewest single-character edits (insertions, deletions, substitutions, or
+/// swaps of neighbouring characters) that would make `term` appear somewhere
+/// within `text`. Case is ignored.
+///
+/// ```
+/// use aura_core::fuzzy::distance;
+///
+/// assert_eq!(0, distance("python", "python-requests"));
+/// assert_eq!(1, distance("pyton", "python-requests"));
+/// ```
+pub fn distance(term: &str, text: &str) -> usize {
+    let t: Vec<char> = term.to_lowercase().chars().collect();
+    let s: Vec<char> = text.to_lowercase().chars().collect();
+
+    // The best distances for the term so far, when its match ends at each
+    // point in the text. Since a match may begin anywhere, the empty term
+    // costs nothing.
+    let mut before: Vec<usize> = Vec::new();
+    let mut prev: Vec<usize> = vec![0; s.len() + 1];
+
+    for (i, &tc) in t.iter().enumerate() {
+        let mut curr = vec![i + 1; s.len() + 1];
+
+        for (j, &sc) in s.iter().enumerate() {
+            let cost = usize::from(tc != sc);
+            let mut best = (prev[j + 1] + 1).min(curr[j] + 1).min(prev[j] + cost);
+
+            if i > 0 && j > 0 && tc == s[j - 1] && t[i - 1] == sc {
+                best = best.min(before[j - 1] + 1);
+            }
+
+            curr[j + 1] = best;
+        }
+
+        before = std::mem::replace(&mut prev, curr);
+    }
+
+    prev.into_iter().min().unwrap_or(0)
+}
+
+/// If `term` is close enough to appearing in `text`, how close? Longer terms
+/// are allowed more edits, but terms shorter than four characters must
+/// appear exactly.
+///
+/// ```
+/// use aura_core::fuzzy::score;
+///
+/// assert_eq!(Some(1), score("pyton", "python"));
+/// assert_eq!(None, score("pyton", "ruby"));
+/// ```
+pub fn score(term: &str, text: &str) -> Option<usize> {
+    let d = distance(term, text);
+    (d <= term.chars().count() / 4).then_some(d)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn exact() {
+        assert_eq!(0, distance("python", "python"));
+        assert_eq!(0, distance("requests", "python-requests"));
+        assert_eq!(0, distance("", "python"));
+        assert_eq!(6, distance("python", ""));
+    }
+
+    #[test]
+    fn transpositions() {
+        assert_eq!(1, distance("pyhton", "python"));
+        assert_eq!(1, distance("ptyhon", "python-requests"));
+    }
+
+    #[test]
+    fn missing_characters() {
+        assert_eq!(1, distance("pyton", "python"));
+        assert_eq!(2, distance("pthon-rquests", "python-requests"));
+        assert_eq!(1, distance("pytthon", "python"));
+    }
+
+    #[test]
+    fn case_insensitive() {
+        assert_eq!(0, distance("PYTHON", "python"));
+        assert_eq!(0, distance("python", "Python-Requests"));
+        assert_eq!(1, distance("PyTon", "python"));
+    }
+
+    #[test]
+    fn thresholds() {
+        assert_eq!(Some(0), score("vim", "gvim"));
+        assert_eq!(None, score("vin", "vim"));
+        assert_eq!(Some(1), score("pyhton", "python"));
+        assert_eq!(None, score("pthn", "python"));
+        assert_eq!(None, score("xyzzy", "python"));
+    }
+}
@@ -2,20 +2,41 @@
 
 use applying::Apply;
 use log::debug;
+use std::net::TcpStream;
+use std::net::ToSocketAddrs;
+use std::ops::Not;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
 use std::process::Stdio;
+use std::time::Duration;
+
+/// Messages of git and curl that mean the remote couldn't be reached, or that
+/// the connection to it failed partway. Only these are worth retrying.
+const TRANSPORT_ERRORS: &[&str] = &[
+    "Could not resolve host",
+    "Failed to connect to",
+    "Connection timed out",
+    "Connection refused",
+    "Connection reset",
+    "Operation timed out",
+    "Operation too slow",
+    "The remote end hung up unexpectedly",
+    "early EOF",
+    "RPC failed",
+    "TLS connection was non-properly terminated",
+    "The requested URL returned error: 5",
+];
 
 /// A git-related error.
 #[derive(Debug)]
 pub enum Error {
     /// Some IO action failed.
     Io(std::io::Error),
-    /// A git clone failed.
-    Clone(PathBuf),
-    /// A git pull failed.
-    Pull(PathBuf),
+    /// A git clone failed, after the given number of attempts.
+    Clone(PathBuf, u32),
+    /// A git pull failed, after the given number of attempts.
+    Pull(PathBuf, u32),
     /// A git diff failed.
     Diff(PathBuf),
     /// Converting a git hash to a Rust string failed.
@@ -26,54 +47,234 @@ impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::Io(e) => write!(f, "{}", e),
-            Error::Clone(p) => write!(f, "A git clone failed: {}", p.display()),
-            Error::Pull(p) => write!(f, "A git pull failed: {}", p.display()),
+            Error::Clone(p, n) => write!(
+                f,
+                "A git clone failed after {n} attempt(s): {}",
+                p.display()
+            ),
+            Error::Pull(p, n) => {
+                write!(f, "A git pull failed after {n} attempt(s): {}", p.display())
+            }
             Error::ReadHash(e) => write!(f, "Reading a git hash into Rust failed: {e}"),
             Error::Diff(p) => write!(f, "A git diff failed: {}", p.display()),
         }
     }
 }
 
+/// How persistently to retry the git operations that touch the network, namely
+/// [`shallow_clone`] and [`pull`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retry {
+    /// How many times to try again after the first attempt fails.
+    pub retries: u32,
+    /// The wait before the first retry, doubled after each one.
+    pub backoff: Duration,
+    /// Give up on an attempt whose remote can't be connected to within this
+    /// long.
+    pub connect_timeout: Option<Duration>,
+    /// Abandon a transfer that has received nothing for this long.
+    pub read_timeout: Option<Duration>,
+}
+
+impl Default for Retry {
+    /// A single attempt, with no timeout beyond git's own.
+    fn default() -> Self {
+        Retry {
+            retries: 0,
+            backoff: Duration::from_secs(2),
+            connect_timeout: None,
+            read_timeout: None,
+        }
+    }
+}
+
+/// How a single attempt at a git operation went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Attempt {
+    Succeeded,
+    /// The remote couldn't be reached, which may well pass on its own.
+    Transient,
+    /// Anything else, like a diverged history, which another attempt won't fix.
+    Failed,
+}
+
+/// Run some git operation until it succeeds, fails for some reason other than
+/// the network, or the retries run out. On failure, `failed` is given the total
+/// number of attempts made.
+fn with_retries<F, S, E>(
+    retry: &Retry,
+    mut attempt: F,
+    mut sleep: S,
+    failed: E,
+) -> Result<(), Error>
+where
+    F: FnMut() -> Result<Attempt, Error>,
+    S: FnMut(Duration),
+    E: FnOnce(u32) -> Error,
+{
+    let mut wait = retry.backoff;
+    let mut made = 0;
+
+    loop {
+        made += 1;
+
+        match attempt()? {
+            Attempt::Succeeded => return Ok(()),
+            Attempt::Transient if made <= retry.retries => {
+                debug!("git failed. Retrying in {}s.", wait.as_secs());
+                sleep(wait);
+                wait *= 2;
+            }
+            Attempt::Transient | Attempt::Failed => return Err(failed(made)),
+        }
+    }
+}
+
+/// Run a network-bound `git` call once.
+///
+/// Git has no connection timeout of its own, so if one is set, the remote is
+/// first connected to directly.
+fn attempt(retry: &Retry, remote: Option<&str>, git: &mut Command) -> Result<Attempt, Error> {
+    if let (Some(t), Some(remote)) = (retry.connect_timeout, remote) {
+        if reachable(remote, t).not() {
+            debug!("Couldn't connect to {remote} within {}s.", t.as_secs());
+            return Ok(Attempt::Transient);
+        }
+    }
+
+    if let Some(t) = retry.read_timeout {
+        git.env("GIT_HTTP_LOW_SPEED_LIMIT", "1")
+            .env("GIT_HTTP_LOW_SPEED_TIME", t.as_secs().max(1).to_string());
+    }
+
+    // The error is read to tell network failures apart, so it must be in
+    // English.
+    let output = git
+        .env("LC_ALL", "C")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(Error::Io)?;
+
+    if output.status.success() {
+        Ok(Attempt::Succeeded)
+    } else {
+        let err = String::from_utf8_lossy(&output.stderr);
+        debug!("git: {}", err.trim());
+
+        if is_transport_error(&err) {
+            Ok(Attempt::Transient)
+        } else {
+            Ok(Attempt::Failed)
+        }
+    }
+}
+
+/// Does some error output of git say that the remote couldn't be reached?
+fn is_transport_error(err: &str) -> bool {
+    TRANSPORT_ERRORS.iter().any(|e| err.contains(e))
+}
+
+/// Can a connection be opened to the host of the given remote URL? Remotes
+/// that aren't URLs, like local paths, are assumed to be reachable.
+fn reachable(remote: &str, timeout: Duration) -> bool {
+    match host_and_port(remote) {
+        None => true,
+        Some(hp) => hp
+            .to_socket_addrs()
+            .map(|mut addrs| addrs.any(|a| TcpStream::connect_timeout(&a, timeout).is_ok()))
+            .unwrap_or(false),
+    }
+}
+
+/// The host and port that some remote URL would be fetched from.
+fn host_and_port(remote: &str) -> Option<(&str, u16)> {
+    let (scheme, rest) = remote.split_once("://")?;
+    let default = match scheme {
+        "https" => 443,
+        "http" => 80,
+        "ssh" => 22,
+        "git" => 9418,
+        _ => return None,
+    };
+    let authority = rest.split('/').next()?;
+    let authority = authority.rsplit_once('@').map_or(authority, |(_, a)| a);
+
+    match authority.rsplit_once(':') {
+        Some((host, port)) => Some((host, port.parse().ok()?)),
+        None if authority.is_empty() => None,
+        None => Some((authority, default)),
+    }
+}
+
+/// The URL of the `origin` of a local clone.
+fn origin(dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("remote")
+        .arg("get-url")
+        .arg("origin")
+        .current_dir(dir)
+        .output()
+        .ok()?;
+
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
 // FIXME This seems to succeed for non-existant repos!
 /// Perform a shallow clone frrom a given repository url, and save it to a given
 /// `Path` on the filesystem.
-pub fn shallow_clone(url: &Path, target: &Path) -> Result<(), Error> {
+///
+/// Attempts that fail to reach the remote are retried as the given [`Retry`]
+/// allows.
+pub fn shallow_clone(url: &Path, target: &Path, retry: &Retry) -> Result<(), Error> {
     debug!("Cloning {}", url.display());
 
-    Command::new("git")
-        .arg("clone")
-        .arg("--depth=1")
-        .arg(url)
-        .arg(target)
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .map_err(Error::Io)?
-        .success()
-        .then_some(())
-        .ok_or_else(|| Error::Clone(url.to_path_buf()))
+    let remote = url.to_string_lossy();
+    let clone = || {
+        attempt(
+            retry,
+            Some(remote.as_ref()),
+            Command::new("git")
+                .arg("clone")
+                .arg("--depth=1")
+                .arg(url)
+                .arg(target),
+        )
+    };
+
+    with_retries(retry, clone, std::thread::sleep, |n| {
+        Error::Clone(url.to_path_buf(), n)
+    })
 }
 
 /// Given a `Path` that is known to be a Git repository, visit it and pull the
 /// latest commits.
 ///
 /// Uses the `--ff-only` merge strategy, so the commit history can't have
-/// diverged from the `origin` or this will fail.
-pub fn pull(dir: &Path) -> Result<(), Error> {
+/// diverged from the `origin` or this will fail. Attempts that fail to reach
+/// the remote are retried as the given [`Retry`] allows, but no others.
+pub fn pull(dir: &Path, retry: &Retry) -> Result<(), Error> {
     debug!("Pulling {}", dir.display());
 
-    Command::new("git")
-        .arg("pull")
-        .arg("--quiet")
-        .arg("--ff-only")
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .current_dir(dir)
-        .status()
-        .map_err(Error::Io)?
-        .success()
-        .then_some(())
-        .ok_or_else(|| Error::Pull(dir.to_path_buf()))
+    let remote = retry.connect_timeout.and_then(|_| origin(dir));
+    let pull = || {
+        attempt(
+            retry,
+            remote.as_deref(),
+            Command::new("git")
+                .arg("pull")
+                .arg("--quiet")
+                .arg("--ff-only")
+                .current_dir(dir),
+        )
+    };
+
+    with_retries(retry, pull, std::thread::sleep, |n| {
+        Error::Pull(dir.to_path_buf(), n)
+    })
 }
 
 /// Given a `Path` to a known local git repo, find out the hash of its latest
@@ -106,3 +307,225 @@ pub fn diff(dir: &Path, hash: &str) -> Result<(), Error> {
         .then_some(())
         .ok_or_else(|| Error::Diff(dir.to_path_buf()))
 }
+
+/// Like [`diff`], but the diff is piped into some other program to be shown,
+/// like `delta` or `diff-so-fancy`, which must read it from stdin.
+pub fn diff_through(dir: &Path, hash: &str, viewer: &mut Command) -> Result<(), Error> {
+    debug!("git diff: {}", dir.display());
+
+    let mut git = Command::new("git")
+        .arg("diff")
+        .arg("--no-color")
+        .arg(hash)
+        .current_dir(dir)
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(Error::Io)?;
+    let diff = git
+        .stdout
+        .take()
+        .ok_or_else(|| Error::Diff(dir.to_path_buf()))?;
+    let shown = viewer.stdin(diff).status().map_err(Error::Io)?.success();
+    let diffed = git.wait().map_err(Error::Io)?.success();
+
+    (shown && diffed)
+        .then_some(())
+        .ok_or_else(|| Error::Diff(dir.to_path_buf()))
+}
+
+/// The size of the changes made to a single file, as reported by
+/// `git diff --numstat`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DiffStat {
+    /// The file that was changed, relative to the repository root.
+    pub file: String,
+    /// The number of lines added.
+    pub insertions: usize,
+    /// The number of lines removed.
+    pub deletions: usize,
+}
+
+/// Like [`diff`], but only yield per-file counts of inserted and deleted lines.
+pub fn diff_stat(dir: &Path, hash: &str) -> Result<Vec<DiffStat>, Error> {
+    debug!("git diff --numstat: {}", dir.display());
+
+    let output = Command::new("git")
+        .arg("diff")
+        .arg("--numstat")
+        .arg(hash)
+        .current_dir(dir)
+        .output()
+        .map_err(Error::Io)?;
+
+    if output.status.success() {
+        let stats = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(parse_numstat)
+            .collect();
+
+        Ok(stats)
+    } else {
+        Err(Error::Diff(dir.to_path_buf()))
+    }
+}
+
+/// Parse a single line of `git diff --numstat` output. Binary files, whose
+/// counts are reported as `-`, are considered to have no line changes.
+fn parse_numstat(line: &str) -> Option<DiffStat> {
+    let mut split = line.splitn(3, '\t');
+    let insertions = split.next()?.parse().unwrap_or(0);
+    let deletions = split.next()?.parse().unwrap_or(0);
+    let file = split.next()?.to_string();
+
+    Some(DiffStat {
+        file,
+        insertions,
+        deletions,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn numstat_parsing() {
+        let raw = std::fs::read_to_string("tests/PKGBUILD.numstat").unwrap();
+        let stats: Vec<_> = raw.lines().filter_map(parse_numstat).collect();
+        let exp = vec![
+            DiffStat {
+                file: "PKGBUILD".to_string(),
+                insertions: 4,
+                deletions: 2,
+            },
+            DiffStat {
+                file: ".SRCINFO".to_string(),
+                insertions: 3,
+                deletions: 3,
+            },
+            DiffStat {
+                file: "foo.install".to_string(),
+                insertions: 12,
+                deletions: 0,
+            },
+            DiffStat {
+                file: "logo.png".to_string(),
+                insertions: 0,
+                deletions: 0,
+            },
+        ];
+
+        assert_eq!(exp, stats);
+    }
+
+    #[test]
+    fn retries_with_backoff() {
+        let retry = Retry {
+            retries: 3,
+            ..Retry::default()
+        };
+        let backoff = retry.backoff;
+
+        // Success on the third attempt.
+        let mut calls = 0;
+        let mut waits = Vec::new();
+        let res = with_retries(
+            &retry,
+            || {
+                calls += 1;
+                match calls {
+                    3 => Ok(Attempt::Succeeded),
+                    _ => Ok(Attempt::Transient),
+                }
+            },
+            |d| waits.push(d),
+            |n| Error::Pull(PathBuf::from("aura"), n),
+        );
+        assert!(res.is_ok());
+        assert_eq!(3, calls);
+        assert_eq!(vec![backoff, backoff * 2], waits);
+
+        // Every attempt is counted on final failure.
+        let mut waits = Vec::new();
+        let res = with_retries(
+            &retry,
+            || Ok(Attempt::Transient),
+            |d| waits.push(d),
+            |n| Error::Clone(PathBuf::from("aura"), n),
+        );
+        assert!(matches!(res, Err(Error::Clone(_, 4))));
+        assert_eq!(vec![backoff, backoff * 2, backoff * 4], waits);
+
+        // By default, there is only one attempt.
+        let res = with_retries(
+            &Retry::default(),
+            || Ok(Attempt::Transient),
+            |_| panic!("No backoff"),
+            |n| Error::Pull(PathBuf::from("aura"), n),
+        );
+        assert!(matches!(res, Err(Error::Pull(_, 1))));
+
+        // Failures unrelated to the network, like a diverged history, aren't
+        // retried.
+        let mut calls = 0;
+        let res = with_retries(
+            &retry,
+            || {
+                calls += 1;
+                Ok(Attempt::Failed)
+            },
+            |_| panic!("No backoff"),
+            |n| Error::Pull(PathBuf::from("aura"), n),
+        );
+        assert!(matches!(res, Err(Error::Pull(_, 1))));
+        assert_eq!(1, calls);
+
+        // Failing to run git at all isn't retried.
+        let mut calls = 0;
+        let res = with_retries(
+            &retry,
+            || {
+                calls += 1;
+                Err(Error::Io(std::io::Error::other("no git")))
+            },
+            |_| panic!("No backoff"),
+            |n| Error::Pull(PathBuf::from("aura"), n),
+        );
+        assert!(matches!(res, Err(Error::Io(_))));
+        assert_eq!(1, calls);
+    }
+
+    #[test]
+    fn transport_errors() {
+        let dns = "fatal: unable to access 'https://aur.archlinux.org/aura.git/': Could not resolve host: aur.archlinux.org";
+        let stall = "error: RPC failed; curl 28 Operation too slow. Less than 1 bytes/sec transferred the last 60 seconds";
+        let busy = "fatal: unable to access 'https://aur.archlinux.org/aura.git/': The requested URL returned error: 503";
+        let diverged = "fatal: Not possible to fast-forward, aborting.";
+        let missing = "fatal: unable to access 'https://aur.archlinux.org/aura.git/': The requested URL returned error: 404";
+
+        assert!(is_transport_error(dns));
+        assert!(is_transport_error(stall));
+        assert!(is_transport_error(busy));
+        assert!(is_transport_error(diverged).not());
+        assert!(is_transport_error(missing).not());
+    }
+
+    #[test]
+    fn remote_hosts() {
+        assert_eq!(
+            Some(("aur.archlinux.org", 443)),
+            host_and_port("https://aur.archlinux.org/aura.git")
+        );
+        assert_eq!(
+            Some(("aur.archlinux.org", 22)),
+            host_and_port("ssh://aur@aur.archlinux.org/aura.git")
+        );
+        assert_eq!(
+            Some(("localhost", 8080)),
+            host_and_port("http://localhost:8080/aura.git")
+        );
+        assert_eq!(None, host_and_port("/home/colin/aura"));
+        assert_eq!(None, host_and_port("aur@aur.archlinux.org:aura.git"));
+        assert_eq!(None, host_and_port("file:///home/colin/aura"));
+    }
+}
@@ -7,9 +7,11 @@ pub mod aur;
 pub mod cache;
 pub mod deps;
 pub mod faur;
+pub mod fuzzy;
 pub mod git;
 pub mod logs;
 pub mod snapshot;
+pub mod vercmp;
 
 use alpm::AlpmList;
 use alpm::Db;
@@ -20,6 +22,7 @@ use log::debug;
 use r2d2_alpm::Alpm;
 use std::borrow::Cow;
 use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::fs::DirEntry;
 use std::path::Path;
 use versions::Versioning;
@@ -167,9 +170,7 @@ impl<'a> Package<'a> {
     /// Does some given version string have the same value as the one in this
     /// `Package`?
     pub fn same_version(&self, other: &str) -> bool {
-        Versioning::new(other)
-            .map(|o| self.version.cmp(&o).is_eq())
-            .unwrap_or(false)
+        vercmp::vercmp(&self.version.to_string(), other).is_eq()
     }
 
     /// Attempt to convert from package data from ALPM.
@@ -209,8 +210,14 @@ impl<'a> PartialOrd for Package<'a> {
 
 impl<'a> Ord for Package<'a> {
     fn cmp(&self, other: &Self) -> Ordering {
+        // Versions are compared as Pacman compares them. Those it considers
+        // equal, like `1.0` and `1.0-1`, are still told apart so that the order
+        // stays total.
         match self.name.cmp(&other.name) {
-            Ordering::Equal => self.version.cmp(&other.version),
+            Ordering::Equal => {
+                vercmp::vercmp(&self.version.to_string(), &other.version.to_string())
+                    .then_with(|| self.version.cmp(&other.version))
+            }
             otherwise => otherwise,
         }
     }
@@ -252,12 +259,85 @@ where
     A: AsRef<alpm::Alpm>,
 {
     alpm.as_ref().localdb().pkgs().into_iter().filter(|p| {
-        p.reason() == PackageReason::Explicit
-            && p.required_by().is_empty()
-            && p.optional_for().is_empty()
+        is_leaf(
+            p.reason(),
+            p.required_by().into_iter().chain(p.optional_for()),
+        )
     })
 }
 
+/// Is a package a "leaf", given its install `reason` and the packages that
+/// (optionally) require it? Only an explicitly installed package that nothing
+/// else needs is one.
+fn is_leaf<I>(reason: PackageReason, reqs: I) -> bool
+where
+    I: IntoIterator,
+{
+    reason == PackageReason::Explicit && reqs.into_iter().next().is_none()
+}
+
+/// Packages that aren't (optionally) required by any other package, along with
+/// those that would become so were the former removed, and so on. Together
+/// these can be removed as a cluster.
+///
+/// Only packages of the given install `reason` are considered, if one is given.
+/// So a `Depend` reason yields orphans, and the packages only they require.
+pub fn unrequired<A>(alpm: &A, reason: Option<PackageReason>) -> Vec<&alpm::Package>
+where
+    A: AsRef<alpm::Alpm>,
+{
+    let candidates: Vec<(&alpm::Package, Vec<String>)> = alpm
+        .as_ref()
+        .localdb()
+        .pkgs()
+        .into_iter()
+        .filter(|p| reason.map(|r| p.reason() == r).unwrap_or(true))
+        .map(|p| {
+            let reqs = p.required_by().into_iter().chain(p.optional_for());
+            (p, reqs.collect())
+        })
+        .collect();
+
+    let removable = removable(candidates.iter().map(|&(p, ref reqs)| (p.name(), reqs)));
+
+    candidates
+        .into_iter()
+        .map(|(p, _)| p)
+        .filter(|p| removable.contains(p.name()))
+        .collect()
+}
+
+/// Given packages paired with the names of those that (optionally) require
+/// them, find all that could be removed without breaking a package outside of
+/// that set. Unmentioned packages are assumed to stay installed.
+fn removable<'a, I, R, S>(pkgs: I) -> HashSet<&'a str>
+where
+    I: IntoIterator<Item = (&'a str, R)>,
+    R: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let pkgs: Vec<(&str, Vec<S>)> = pkgs
+        .into_iter()
+        .map(|(p, reqs)| (p, reqs.into_iter().collect()))
+        .collect();
+    let mut set = HashSet::new();
+
+    // Each pass may free up the dependencies of the packages found by the last.
+    loop {
+        let before = set.len();
+
+        for (p, reqs) in pkgs.iter() {
+            if reqs.iter().all(|r| set.contains(r.as_ref())) {
+                set.insert(*p);
+            }
+        }
+
+        if set.len() == before {
+            break set;
+        }
+    }
+}
+
 /// Does the given `Path` point to a valid tarball that can can loaded by ALPM?
 pub fn is_valid_package<A>(alpm: A, path: &Path) -> bool
 where
@@ -312,3 +392,48 @@ where
         .map(|meta| meta.len())
         .sum()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::ops::Not;
+
+    #[test]
+    fn removable_chain() {
+        let fixture = std::fs::read_to_string("tests/unrequired.txt").unwrap();
+        let pkgs = fixture
+            .lines()
+            .filter(|l| l.starts_with('#').not())
+            .filter_map(|l| l.split_once(':'))
+            .map(|(p, reqs)| (p, reqs.split_whitespace()));
+
+        let expected: HashSet<&str> = ["python-foo", "python-bar", "python-baz", "lonely"]
+            .into_iter()
+            .collect();
+
+        assert_eq!(expected, removable(pkgs));
+    }
+
+    #[test]
+    fn leaves_only() {
+        let fixture = std::fs::read_to_string("tests/leaves.txt").unwrap();
+        let leaves: Vec<&str> = fixture
+            .lines()
+            .filter(|l| l.starts_with('#').not())
+            .filter_map(|l| l.split_once(':'))
+            .filter_map(|(p, reqs)| p.split_once(' ').map(|(p, r)| (p, r, reqs)))
+            .filter(|(_, reason, reqs)| {
+                let reason = match *reason {
+                    "explicit" => PackageReason::Explicit,
+                    _ => PackageReason::Depend,
+                };
+                is_leaf(reason, reqs.split_whitespace())
+            })
+            .map(|(p, _, _)| p)
+            .collect();
+
+        // Neither explicit packages that something depends on, nor unrequired
+        // dependencies.
+        assert_eq!(vec!["firefox", "gimp"], leaves);
+    }
+}
@@ -1,8 +1,10 @@
 //! Log manipulation internals.
 
+use serde::Serialize;
 use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
+use std::ops::Not;
 use std::path::Path;
 
 /// A collation of information about log entries for a particular package.
@@ -46,3 +48,225 @@ pub fn info(path: &Path, package: String) -> Option<LogEntry> {
         Some(entry)
     }
 }
+
+/// A single change to a package, as recorded in the Pacman log.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Event {
+    /// The name of the package.
+    pub package: String,
+    /// One of `installed`, `upgraded`, `downgraded`, `reinstalled`, or `removed`.
+    pub action: String,
+    /// The version of the package after the change, or for removals, the
+    /// version that was removed.
+    pub version: String,
+    /// The version before the change. Nothing for installs.
+    pub from_version: Option<String>,
+    /// The version after the change. Nothing for removals.
+    pub to_version: Option<String>,
+    /// As written in the log, e.g. `2024-08-01T09:12:58+0900`.
+    pub timestamp: String,
+}
+
+/// Every package change recorded in the Pacman log, oldest first. Given some
+/// `packages`, only the changes to them, and given a `search` term, only those
+/// whose log line contains it.
+pub fn events(
+    path: &Path,
+    packages: &[String],
+    search: Option<&str>,
+) -> Result<Vec<Event>, std::io::Error> {
+    let file = File::open(path)?;
+    let events = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| search.map(|s| line.contains(s)).unwrap_or(true))
+        .filter_map(|line| event(&line))
+        .filter(|e| packages.is_empty() || packages.contains(&e.package))
+        .collect();
+
+    Ok(events)
+}
+
+/// Read a package change from a single line of the log, if it is one.
+fn event(line: &str) -> Option<Event> {
+    let (timestamp, entry) = line.strip_prefix('[')?.split_once("] [ALPM] ")?;
+    let (action, rest) = entry.split_once(' ')?;
+
+    if matches!(
+        action,
+        "installed" | "upgraded" | "downgraded" | "reinstalled" | "removed"
+    )
+    .not()
+    {
+        return None;
+    }
+
+    let (package, versions) = rest.split_once(" (")?;
+    let versions = versions.strip_suffix(')')?;
+    // Upgrades and downgrades give both the old and new versions.
+    let (from, to) = match versions.split_once(" -> ") {
+        Some((old, new)) => (Some(old), Some(new)),
+        None if action == "installed" => (None, Some(versions)),
+        None if action == "removed" => (Some(versions), None),
+        None => (Some(versions), Some(versions)),
+    };
+
+    Some(Event {
+        package: package.to_string(),
+        action: action.to_string(),
+        version: to.or(from)?.to_string(),
+        from_version: from.map(String::from),
+        to_version: to.map(String::from),
+        timestamp: timestamp.to_string(),
+    })
+}
+
+/// The names of the packages changed by the most recent completed upgrade in
+/// the Pacman log, in the order they were changed. Packages that were removed
+/// aren't included.
+pub fn last_upgrade(path: &Path) -> Result<Vec<String>, std::io::Error> {
+    let file = File::open(path)?;
+    let lines = BufReader::new(file).lines().map_while(Result::ok);
+
+    Ok(last_upgrade_of(lines))
+}
+
+fn last_upgrade_of<I>(lines: I) -> Vec<String>
+where
+    I: Iterator<Item = String>,
+{
+    let mut last = Vec::new();
+    let mut current = Vec::new();
+    let mut upgrades = false;
+
+    for line in lines {
+        let Some((_, entry)) = line.split_once("] [ALPM] ") else {
+            continue;
+        };
+
+        match entry {
+            "transaction started" => {
+                current.clear();
+                upgrades = false;
+            }
+            "transaction completed" if upgrades => last = std::mem::take(&mut current),
+            _ => {
+                if let Some((action, rest)) = entry.split_once(' ') {
+                    let changed = matches!(
+                        action,
+                        "upgraded" | "installed" | "downgraded" | "reinstalled"
+                    );
+
+                    if let Some((name, _)) = rest.split_once(" (").filter(|_| changed) {
+                        current.push(name.to_string());
+                        upgrades |= action == "upgraded";
+                    }
+                }
+            }
+        }
+    }
+
+    last
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn most_recent_upgrade() {
+        let pkgs = last_upgrade(Path::new("tests/pacman.log")).unwrap();
+        assert_eq!(vec!["linux", "python-packaging", "firefox", "emacs"], pkgs);
+    }
+
+    #[test]
+    fn package_events() {
+        let pkgs = ["cmake".to_string(), "firefox".to_string()];
+        let events = events(Path::new("tests/pacman.log"), &pkgs, None).unwrap();
+        let summary: Vec<_> = events
+            .iter()
+            .map(|e| (e.package.as_str(), e.action.as_str(), e.version.as_str()))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("cmake", "installed", "3.30.2-1"),
+                ("firefox", "downgraded", "128.0.3-1"),
+                ("cmake", "removed", "3.30.2-1"),
+            ],
+            summary
+        );
+        assert_eq!("2024-08-03T18:40:05+0900", events[0].timestamp);
+
+        assert_eq!(
+            None,
+            event("[2024-08-03T18:40:05+0900] [ALPM] transaction started")
+        );
+        assert_eq!(
+            None,
+            event("[2024-08-03T18:40:02+0900] [PACMAN] installed cmake (1)")
+        );
+    }
+
+    #[test]
+    fn structured_events() {
+        let day = events(Path::new("tests/pacman.log"), &[], Some("2024-08-05")).unwrap();
+        let json = serde_json::to_value(&day[..4]).unwrap();
+
+        assert_eq!(
+            serde_json::json!([
+                {
+                    "package": "python-typing_extensions",
+                    "action": "removed",
+                    "version": "4.12.2-1",
+                    "from_version": "4.12.2-1",
+                    "to_version": null,
+                    "timestamp": "2024-08-05T08:01:30+0900",
+                },
+                {
+                    "package": "linux",
+                    "action": "upgraded",
+                    "version": "6.10.3.arch1-1",
+                    "from_version": "6.10.2.arch1-1",
+                    "to_version": "6.10.3.arch1-1",
+                    "timestamp": "2024-08-05T08:01:31+0900",
+                },
+                {
+                    "package": "python-packaging",
+                    "action": "installed",
+                    "version": "24.1-1",
+                    "from_version": null,
+                    "to_version": "24.1-1",
+                    "timestamp": "2024-08-05T08:01:31+0900",
+                },
+                {
+                    "package": "firefox",
+                    "action": "downgraded",
+                    "version": "128.0.3-1",
+                    "from_version": "129.0-1",
+                    "to_version": "128.0.3-1",
+                    "timestamp": "2024-08-05T08:01:32+0900",
+                },
+            ]),
+            json
+        );
+        assert_eq!(5, day.len());
+
+        // Every change of the whole log, including those of a failed transaction.
+        let all = events(Path::new("tests/pacman.log"), &[], None).unwrap();
+        assert_eq!(10, all.len());
+
+        let firefox = ["firefox".to_string()];
+        let both = events(Path::new("tests/pacman.log"), &firefox, Some("2024-08-05")).unwrap();
+        assert_eq!(1, both.len());
+    }
+
+    #[test]
+    fn no_upgrades() {
+        let log = "[2024-08-03T18:40:05+0900] [ALPM] transaction started\n\
+                   [2024-08-03T18:40:05+0900] [ALPM] installed cmake (3.30.2-1)\n\
+                   [2024-08-03T18:40:06+0900] [ALPM] transaction completed\n";
+
+        assert!(last_upgrade_of(log.lines().map(String::from)).is_empty());
+    }
+}
@@ -19,6 +19,9 @@ pub struct Snapshot {
     pub time: OffsetDateTime,
     /// Should this `Snapshot` never be removed?
     pub pinned: bool,
+    /// A label to remember this `Snapshot` by, if it was given one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
     /// Every package name in the `Snapshot`, with its version.
     pub packages: HashMap<String, String>,
 }
@@ -40,6 +43,7 @@ impl Snapshot {
         Snapshot {
             time,
             pinned: false,
+            name: None,
             packages,
         }
     }
@@ -0,0 +1,52 @@
+//! Comparison of package versions, exactly as Pacman (and its `vercmp`) does it.
+
+use std::cmp::Ordering;
+
+/// Compare two full package versions, epochs and release numbers included.
+///
+/// ```
+/// use aura_core::vercmp::vercmp;
+/// use std::cmp::Ordering;
+///
+/// assert_eq!(Ordering::Less, vercmp("1.5.0-1", "1.5.0-2"));
+/// assert_eq!(Ordering::Less, vercmp("1.0rc", "1.0"));
+/// assert_eq!(Ordering::Greater, vercmp("1:1.0-1", "2.0-1"));
+/// assert_eq!(Ordering::Equal, vercmp("1.5", "1.5-1"));
+/// ```
+pub fn vercmp(a: &str, b: &str) -> Ordering {
+    alpm::vercmp(a, b)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::ops::Not;
+
+    #[test]
+    fn pacman_cases() {
+        let fixture = std::fs::read_to_string("tests/vercmp.txt").unwrap();
+        let cases: Vec<_> = fixture
+            .lines()
+            .filter(|l| l.is_empty().not() && l.starts_with('#').not())
+            .map(|l| {
+                let words: Vec<_> = l.split_whitespace().collect();
+                let expected = match words[2] {
+                    "-1" => Ordering::Less,
+                    "0" => Ordering::Equal,
+                    "1" => Ordering::Greater,
+                    other => panic!("Unexpected result: {other}"),
+                };
+
+                (words[0], words[1], expected)
+            })
+            .collect();
+
+        assert!(cases.is_empty().not());
+
+        // Like Pacman's own tests, every case must also hold in reverse.
+        for (a, b, expected) in cases {
+            assert_eq!(expected, vercmp(a, b), "{a} vs {b}");
+            assert_eq!(expected.reverse(), vercmp(b, a), "{b} vs {a}");
+        }
+    }
+}
@@ -134,7 +134,6 @@ C-downgrade-which = الذي تريده؟. { $pkg } ما هو اصدار
 # check-pconf-ignores = No overlapping ignored packages?
 # check-pconf-ignores-fix = The following packages are ignored in both pacman.conf and aura.toml: { $pkgs }
 # check-pconf-pacnew = All .pacnew files accounted for?
-# check-pconf-pacnew-broken = Error: Call to { $fd } utterly failed.
 
 # check-pconf-pacnew-old = { $path } is older than its .pacnew by { $days ->
 #    [one] 1 day.
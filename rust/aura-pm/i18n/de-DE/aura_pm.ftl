@@ -132,7 +132,6 @@ L-recent = Letzte Aktionen
 # check-pconf-ignores = No overlapping ignored packages?
 # check-pconf-ignores-fix = The following packages are ignored in both pacman.conf and aura.toml: { $pkgs }
 # check-pconf-pacnew = All .pacnew files accounted for?
-# check-pconf-pacnew-broken = Error: Call to { $fd } utterly failed.
 
 # check-pconf-pacnew-old = { $path } is older than its .pacnew by { $days ->
 #    [one] 1 day.
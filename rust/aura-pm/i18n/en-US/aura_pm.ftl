@@ -6,12 +6,20 @@ A-install-repo-pkgs = Repository dependencies:
 A-install-aur-pkgs = AUR packages:
 A-install-path-comp = Failed to extract final component of: { $path }
 A-install-ignored = { $file } is marked "ignored". Install anyway?
+A-install-conflicts = Some of the packages to be installed conflict with each other.
+A-install-conflicts-installed = { $pkg } conflicts with the installed { $with }.
 
 A-build-prep = Preparing build directories...
 A-build-pkg = Building { $pkg }...
 A-build-diff = Display diffs of build files?
+A-build-diff-none = No build files have changed.
+A-build-diff-stat = { $files ->
+    [one] 1 file changed
+   *[many] { $files } files changed
+}, { $ins } insertions(+), { $del } deletions(-)
 A-build-hotedit-pkgbuild = Edit the PKGBUILD?
 A-build-hotedit-install = Edit the .install file?
+A-build-tool-missing = { $tool } couldn't be found, so { $fallback } will be used instead.
 A-build-fail = Package failed to build, citing:
 A-build-e-pkgctl = Building within an isolated chroot failed.
 A-build-e-makepkg = makepkg failed.
@@ -23,6 +31,31 @@ A-build-e-perm = Failed to set file permissions for: { $dir }
 A-build-pkglist = Failed to determine makepkg output paths from: { $dir }
 A-build-pull = Failed to pull latest commits - you may be building an old version!
 A-build-continue = Continue building other packages?
+A-rollback = The transaction failed partway.
+A-rollback-failed = The rollback failed as well, so the system may be left partially changed.
+A-build-jobs = Building { $n } packages, up to { $jobs } at a time...
+A-build-log = Output of { $pkg }:
+A-build-fail-pkg = { $pkg } failed to build, citing:
+A-build-cancelled = { $n ->
+    [one] Cancelled 1 build that hadn't started yet.
+   *[many] Cancelled { $n } builds that hadn't started yet.
+}
+A-build-e-some = { $n ->
+    [one] 1 package failed to build.
+   *[many] { $n } packages failed to build.
+}
+A-build-key-missing = { $pkg } trusts a PGP key that isn't in the keyring: { $key }
+A-build-key-import = Import the missing keys?
+A-build-verified = Verified the signed sources of { $pkg }: { $files }
+A-build-verified-none = { $pkg } lists trusted PGP keys, but has no signed sources.
+A-build-verify-skip = Not verifying the source signatures of { $pkg }.
+A-build-e-keys = Refusing to build { $pkg } without its PGP keys.
+A-build-e-import = Failed to import PGP key: { $key }
+A-build-e-verify = The source signatures of { $pkg } failed to verify.
+A-summary = Build summary:
+A-summary-built = Built
+A-summary-failed = Failed
+A-summary-skipped = Skipped
 
 A-i-repo = Repository
 A-i-version = Version
@@ -37,6 +70,9 @@ A-i-depends = Depends On
 A-i-make = Make Deps
 A-i-opt = Optional Deps
 A-i-check = Check Deps
+A-i-dep-installed = installed
+A-i-dep-repo = repo
+A-i-dep-aur = AUR
 A-i-votes = Votes
 A-i-pop = Popularity
 A-i-desc = Description
@@ -49,6 +85,12 @@ A-u-comparing = Comparing package versions...
 A-u-no-upgrades = No AUR package upgrades necessary.
 A-u-to-upgrade = AUR packages to upgrade:
 A-u-git = VCS packages to rebuild:
+A-u-git-latest = latest commit
+A-u-retry = Retrying { $n ->
+    [one] 1 package
+   *[many] { $n } packages
+} that failed to build last time.
+A-u-retry-none = Nothing failed to build during the last upgrade.
 
 A-w = Cloning { $package }...
 
@@ -57,9 +99,20 @@ A-y-pulling = Pulling latest commits
 
 # Snapshots (-B)
 B-saved = Saved package state.
+B-saved-as = Saved package state as { $name }.
+B-bad-name = { $name } is not a legal snapshot name. Use letters, digits, - and _.
 B-clean = Remove stale snapshots?
 B-none = No usable snapshots found.
 B-select = Select a snapshot to restore:
+B-unknown = No snapshot named { $id } was found.
+B-missing = The cache is missing tarballs needed to restore this snapshot: { $pkgs }
+B-current = The system already matches this snapshot.
+B-r-install = Packages to install:
+B-r-downgrade = Packages to downgrade:
+B-r-upgrade = Packages to upgrade:
+B-r-remove = Packages to remove:
+B-rollback = Restore these packages to their versions from before the transaction?
+B-rollback-none = No packages were changed, so there is nothing to roll back.
 
 # Cache (-C)
 C-size = Current cache size: { $size }
@@ -68,6 +121,18 @@ C-b-file = { $target } already exists and is not a directory.
 C-b-nonempty = Target { $target } exists but is not empty!
 C-b-target = Backing up cache to { $target }
 C-b-curr = Failed to read current directory.
+C-b-done = Copied { $copied } files, skipped { $skipped } already backed up.
+C-b-space = Not enough space at { $target }: { $needed } needed, { $free } available.
+C-b-verifying = Verifying { $n } backed-up files...
+C-b-verified = Every backed-up file matches its original.
+C-b-mismatch = { $n ->
+    [one] 1 backed-up file doesn't match its original.
+   *[many] { $n } backed-up files don't match their originals.
+}
+C-b-failed = { $n ->
+    [one] 1 file couldn't be copied.
+   *[many] { $n } files couldn't be copied.
+}
 
 C-i-latest = Latest
 C-i-created = Created
@@ -75,28 +140,106 @@ C-i-installed = installed
 C-i-sig = Signature
 C-i-size = Tarball Size
 C-i-avail = Available Versions
+C-versions-none = No cached versions of { $pkg }.
 
 C-c-keep = { $pkgs } of each package file will be kept. The rest will be deleted.
 C-c-freed = { $bytes } freed.
+C-o-total = { $n ->
+    [one] 1 tarball is
+   *[many] { $n } tarballs are
+} older than the given age, totalling { $bytes }.
 
 C-downgrade-which = What version of { $pkg } do you want?
 
 C-y-no-work = Package cache already synchronized.
 C-t-invalids = Removing invalid package tarballs.
 
+C-v-checking = Verifying { $n ->
+    [one] 1 tarball...
+   *[many] { $n } tarballs...
+}
+C-v-valid = Valid
+C-v-corrupt = Corrupt
+C-v-unsigned = Unsigned
+C-v-bad-sig = Bad signature
+C-v-remove = Remove corrupt and badly signed tarballs?
+C-unchecked = { $n ->
+    [one] 1 tarball couldn't be checked, so it was left alone:
+   *[many] { $n } tarballs couldn't be checked, so they were left alone:
+}
+
 # Logs (-L)
 L-first = First Install
 L-upgrades = Upgrades
 L-recent = Recent Actions
 L-search-err = Searching your logs via { $cmd } failed.
+L-search-none = No matches found.
 L-view-err = Failed to open your ALPM log.
+L-no-upgrade = No completed upgrade was found in your ALPM log.
+L-no-changelog = No changelog available.
 
 # Orphans (-O)
 O-adopt = { $pkg } now marked as explicitly installed.
 O-explicit-err = Failed to mark { $pkg } as explicitly installed.
+O-disown = { $pkg } now marked as a dependency.
+O-depend-err = Failed to mark { $pkg } as a dependency.
+O-elderly-seen = (listed above)
+O-abandon-via = required only by { $pkgs }
+O-abandon-total = { $n ->
+    [one] 1 package would be removed
+   *[many] { $n } packages would be removed
+}, freeing { $bytes }.
+
+# PKGBUILD Analysis (-P)
+P-none = No suspicious PKGBUILD contents found.
+P-high = Potential PKGBUILD vulnerabilities detected.
+P-no-clone = No local PKGBUILD found for { $pkg }.
+P-low = LOW
+P-medium = MEDIUM
+P-high-label = HIGH
+P-piped-download = Downloaded code is piped directly into a shell.
+P-sudo = sudo indicates that someone may be trying to gain root access to your machine.
+P-outside-dirs = Writes to a location outside of $srcdir and $pkgdir.
+P-eval = eval executes arbitrary strings as code.
+P-base64 = Decoding base64 may hide the true contents of a command.
+P-lint-required = The { $field } field is missing, so makepkg will refuse to build.
+P-lint-recommended = The { $field } field is missing.
+P-lint-checksums = Sources are downloaded, but no checksums verify them.
+
+# Queries (-Q)
+Q-unknown = { $pkg } is not installed.
+Q-missing = { $n ->
+    [one] 1 package has files missing from disk.
+   *[many] { $n } packages have files missing from disk.
+}
+Q-missing-none = No owned files are missing.
+Q-owns-none = No package owns { $file }.
+Q-owns-unowned = { $n ->
+    [one] 1 file has no owner.
+   *[many] { $n } files have no owner.
+}
+
+# Removal (-R)
+R-not-found = Not installed: { $pkgs }
+
+# Syncing (-S)
+S-not-downloaded = Pacman reported { $file }, but it isn't in any cache.
+S-refreshing = Refreshing { $n } package databases...
+S-refreshed = { $repo } refreshed.
+S-current = { $repo } is up to date.
+S-failed = { $repo } could not be refreshed.
+S-refresh-failed = Failed to refresh: { $repos }
+S-locked = The package databases are locked by { $file }. Is Pacman already running?
+S-install = Failed to move the refreshed databases into place.
+S-stale = The sync databases were last updated { $days } days ago, so your mirrors may have stopped syncing. Consider choosing others, then running { $cmd }.
+
+# Tarball Installation (-U)
+U-unparseable = { $target } isn't named like a package tarball.
 
 # Opening Pages (open)
 open-err = Failed to open { $url }.
+open-no-browser = No browser could be found. Install `xdg-open`, or choose a program with `--browser` or with `browser` in the `[open]` section of your config.
+open-no-such-browser = The chosen browser could not be found on the PATH: { $prog }
 
 # System Statistics (stats)
 stats-local = Failed to load language data.
@@ -109,9 +252,19 @@ stats-aura-cache = Aura Package Cache
 stats-pacman-cache = Pacman Package Cache
 stats-aura-build = Aura Build Cache
 stats-tmp = /tmp Directory
+stats-ungrouped = (ungrouped)
+stats-group-origin = { $explicit } explicit, { $deps } as dependencies
+stats-cache-size = Total size
+stats-cache-tarballs = Tarballs
+stats-cache-old = Packages with more than { $keep } versions
+stats-cache-reclaim = Reclaimable by keeping { $keep }
 
 # System Validation (check)
 check-start = Validating your system.
+check-failed = { $n ->
+    [one] 1 check failed.
+   *[many] { $n } checks failed.
+}
 check-missing-exec = Fix: Please install { $exec } and/or ensure it's on your PATH.
 check-env = Environment
 check-env-editor = EDITOR variable set?
@@ -132,13 +285,20 @@ check-pconf-par-fix = Fix: { $setting } is off, or set to 1. Set { $set } for fa
 check-pconf-ignores = No overlapping ignored packages?
 check-pconf-ignores-fix = The following packages are ignored in both pacman.conf and aura.toml: { $pkgs }
 check-pconf-pacnew = All .pacnew files accounted for?
-check-pconf-pacnew-broken = Error: Call to { $fd } utterly failed.
 
 check-pconf-pacnew-old = { $path } is older than its .pacnew by { $days ->
     [one] 1 day.
    *[many] {$days} days.
 }
 
+check-pconf-pacsave = No .pacsave files left to review?
+check-pconf-pending = No config files awaiting review?
+check-pconf-pending-fix = { $count ->
+    [one] 1 .pacnew or .pacsave file awaits review.
+   *[many] { $count } .pacnew and .pacsave files await review.
+} See { $cmd }.
+check-pconf-mirrorlist = Mirrorlist recently updated?
+check-pconf-mirrorlist-old = { $path } was last updated { $days } days ago.
 check-aconf = Aura Configuration
 check-aconf-aura-exists = Aura config file exists?
 check-aconf-aura-exists-fix = Fix: Consider { $cmd }
@@ -162,6 +322,14 @@ check-cache-missing-for-fix = Fix: View the missing packages with { $cmd } and r
 check-pkgs = Package Status
 check-pkgs-old = All explicitly installed, non-dep packages are up to date?
 check-pkgs-old-warn = { $pkg } was last updated { $days } ago.
+check-pkgs-orphans = No orphaned dependencies?
+check-pkgs-orphans-fix = Fix: Remove { $n ->
+    [one] 1 orphan
+   *[many] { $n } orphans
+} with { $cmd }
+check-pkgs-sync-dbs = Sync databases recently updated?
+check-pkgs-sync-dbs-old = They were last updated { $days } days ago. Fix: Choose fresher mirrors, then run { $cmd }.
+check-pkgs-symlinks = No broken symlinks among package files?
 check-pkgs-empty = All package clones are populated?
 check-pkgs-empty-fix = Fix: Delete the following directories.
 
@@ -175,11 +343,52 @@ thanks-translators = Aura is localised by:
 
 # Configuration (conf)
 conf-toml-err = Failed to serialize current config.
+conf-no-viewer = No file viewer found. Set PAGER, or install less.
+conf-created = Created { $file } from the default template.
+conf-valid = The config is valid.
+conf-new-none = No .pacnew or .pacsave files await review.
+conf-invalid = { $file } is not valid TOML: { $err }
+conf-gen-unreadable = The generated config could not be read back. This is a bug.
+conf-gen-exists = { $file } already exists. Use --force to overwrite it.
+conf-gen-overwrite = Overwrite your live config at { $file }?
+conf-gen-written = Wrote the config to { $file }.
+
+# Declarative Environment (home)
+home-none = No package list found at { $path }. Consider creating one with aura home --dump.
+home-parse = Failed to parse { $path }.
+home-ok = The system matches your declared packages.
+home-missing = Declared packages that aren't explicitly installed:
+home-undeclared = Explicitly installed packages that aren't declared:
+home-drift = { $n ->
+    [one] 1 package differs from your declared set.
+   *[many] { $n } packages differ from your declared set.
+}
+home-overwrite = Overwrite { $file }?
+home-dumped = Wrote { $file }.
+
+# Package Holds (hold)
+hold-added = Held: { $pkgs }
+hold-removed = Released: { $pkgs }
+hold-already = Those packages are already held.
+hold-not-held = None of those packages are held.
 
 # Dependencies (deps)
 deps-io = Failed to generate the dependency image.
+deps-no-graphviz = Graphviz's `dot` could not be found. Please install the `graphviz` package.
+deps-format = Unsupported output format: { $file }. Use .svg, .png, .pdf, or .dot.
+deps-cycle = These packages depend on each other in a cycle: { $pkgs }
+deps-roots = Roots
+deps-leaves = Leaves
+
+# Combined Upgrades (-Syu --aur)
+Su-summary = Upgrade summary:
+Su-repo = Repository packages
+Su-aur = AUR packages
+Su-total = Total
 
 # Runtime Environment
+env-color = Unknown color: { $color }
+env-endpoint = Not a usable AUR endpoint: { $url }
 env-missing-editor = Provided EDITOR is not on the PATH.
 env-pconf = Failed to parse your pacman.conf file.
 
@@ -193,6 +402,7 @@ pacman-misc = A call to pacman gave a non-zero exit code.
 dir-mkdir = Failed to create the directory: { $dir }.
 dir-home = Unable to determine Aura's config directory.
 dir-cache = Unable to determine Aura's cache directory.
+dir-state = Unable to determine Aura's state directory.
 
 # Dependency Resolution
 dep-exist = The package { $pkg } does not exist.
@@ -206,6 +416,8 @@ git-diff = A git diff failed for: { $file }
 git-hash = Reading a git hash into Rust failed.
 git-pull = A git pull failed: { $dir }
 git-clone = A git clone failed: { $dir }
+git-pull-retried = A git pull failed after { $n } attempts: { $dir }
+git-clone-retried = A git clone failed after { $n } attempts: { $dir }
 git-io = Calling git somehow failed.
 
 # Faur Calls
@@ -217,6 +429,8 @@ faur-too-many = More results returned from Faur than expected: { $pkg }
 err-alpm = Failed to open ALPM handle.
 err-config-path = Failed to determine the path to Aura's config file.
 err-curl = A CURL transaction failed: { $err }
+err-curl-retried = A request to { $url } failed after { $n } attempts: { $err }
+err-exit = { $cmd } exited with code { $code }.
 err-file-del = Failed to delete: { $file }
 err-file-open = Failed to open file handle to: { $file }
 err-file-write = Failed to write file: { $file }
@@ -226,11 +440,13 @@ err-mutex = A mutex was poisoned.
 err-none-exist = None of the specified packages exist.
 err-pool-create = Failed to create an ALPM connection pool.
 err-pool-get = Failed to get an ALPM handle from the connection pool.
+err-rate-limited = The server at { $url } is rate-limiting requests. Please wait a few minutes and try again.
 err-read-dir = Failed to read directory: { $dir }
 err-srcinfo = Failed to parse .SRCINFO: { $file }
 err-sudo = Failed to raise privileges.
 err-time-conv = Failed to convert a timestamp.
 err-time-format = Failed to format a time string.
+err-unreachable = None of the { $n } configured AUR endpoints could be reached.
 err-user-input = Failed to get user input.
 err-utf8 = A UTF-8 conversion failed.
 err-write = Somehow failed to write to stdout.
@@ -245,6 +461,33 @@ common-no-work = Nothing to do.
 common-cancelled = Action cancelled.
 common-replace = You can delete { $old } in favour of { $new }.
 
+# Relative Dates
+time-now = just now
+time-minutes = { $n ->
+    [one] 1 minute ago
+   *[many] { $n } minutes ago
+}
+time-hours = { $n ->
+    [one] 1 hour ago
+   *[many] { $n } hours ago
+}
+time-days = { $n ->
+    [one] 1 day ago
+   *[many] { $n } days ago
+}
+time-weeks = { $n ->
+    [one] 1 week ago
+   *[many] { $n } weeks ago
+}
+time-months = { $n ->
+    [one] 1 month ago
+   *[many] { $n } months ago
+}
+time-years = { $n ->
+    [one] 1 year ago
+   *[many] { $n } years ago
+}
+
 # Misc.
 proceed = Proceed?
 proceed-affirmative = y
@@ -132,7 +132,6 @@ check-pconf-par-fix = 修正案： { $setting } はオフ、もしくは「１
 check-pconf-ignores = 「無視パッケージ」が重なっていない？
 check-pconf-ignores-fix = 以下のパッケージが pacman.conf でも Aura の設定でも「無視」にされている：
 check-pconf-pacnew = .pacnew ファイルは更新されている？
-check-pconf-pacnew-broken = Error: { $fd } の実行が完全に失敗した。
 check-pconf-pacnew-old = { $path } は .pacnew より { $days } 日間古い。
 
 check-aconf = Aura 設定
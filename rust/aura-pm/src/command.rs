@@ -1,14 +1,23 @@
 //! All Aura commands.
 
+pub(crate) mod analysis;
 pub(crate) mod aur;
 pub(crate) mod cache;
 pub(crate) mod check;
 pub(crate) mod conf;
 pub(crate) mod deps;
 pub(crate) mod free;
+pub(crate) mod hold;
+pub(crate) mod home;
 pub(crate) mod logs;
 pub(crate) mod misc;
+pub(crate) mod open;
 pub(crate) mod orphans;
+pub(crate) mod query;
+pub(crate) mod remove;
 pub(crate) mod snapshot;
 pub(crate) mod stats;
+pub(crate) mod sync;
+pub(crate) mod tarball;
 pub(crate) mod thanks;
+pub(crate) mod upgrade;
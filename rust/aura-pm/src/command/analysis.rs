@@ -0,0 +1,545 @@
+//! All functionality involving the `-P` command.
+
+use crate::env::Env;
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::theme::Role;
+use crate::theme::Themed;
+use crate::utils::PathStr;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::error;
+use r2d2_alpm::Alpm;
+use std::ops::Not;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Shells that a download might be piped into.
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "dash", "fish", "sudo"];
+
+/// Commands whose final argument is a write destination.
+const COPIERS: &[&str] = &["cp", "mv", "install", "ln", "rsync"];
+
+/// Commands that modify every path they're given.
+const MODIFIERS: &[&str] = &["rm", "mkdir", "touch", "chmod", "chown", "tee"];
+
+/// Locations a PKGBUILD is expected to write to.
+const ALLOWED: &[&str] = &[
+    "$srcdir",
+    "${srcdir}",
+    "$pkgdir",
+    "${pkgdir}",
+    "$startdir",
+    "${startdir}",
+    "/dev/null",
+    "/dev/stdout",
+    "/dev/stderr",
+    "/tmp",
+];
+
+/// Fields that `makepkg` refuses to build without.
+const REQUIRED: &[&str] = &["pkgname", "pkgver", "pkgrel", "arch"];
+
+/// Fields that every AUR package is expected to set.
+const RECOMMENDED: &[&str] = &["pkgdesc", "url", "license"];
+
+/// The checksum arrays understood by `makepkg`.
+const CHECKSUMS: &[&str] = &[
+    "cksums",
+    "md5sums",
+    "sha1sums",
+    "sha224sums",
+    "sha256sums",
+    "sha384sums",
+    "sha512sums",
+    "b2sums",
+];
+
+pub(crate) enum Error {
+    Io(PathBuf, std::io::Error),
+    Stdin(std::io::Error),
+    HighSeverity,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Io(_, e) => error!("{e}"),
+            Error::Stdin(e) => error!("{e}"),
+            Error::HighSeverity => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Io(p, _) => fl!(fll, "err-file-open", file = p.utf8()),
+            Error::Stdin(_) => fl!(fll, "err-user-input"),
+            Error::HighSeverity => fl!(fll, "P-high"),
+        }
+    }
+}
+
+/// How worried a user should be about a [`Finding`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    fn label(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Severity::Low => fl!(fll, "P-low"),
+            Severity::Medium => fl!(fll, "P-medium"),
+            Severity::High => fl!(fll, "P-high-label"),
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Severity::Low => crate::theme::color(Role::Highlight),
+            Severity::Medium => crate::theme::color(Role::Warning),
+            Severity::High => crate::theme::color(Role::Error),
+        }
+    }
+}
+
+/// A suspicious bash construct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Issue {
+    /// `curl ... | sh` and friends.
+    PipedDownload,
+    /// Any use of `sudo`.
+    Sudo,
+    /// Writing a file somewhere other than `$srcdir` or `$pkgdir`.
+    OutsideDirs,
+    /// Evaluation of arbitrary strings.
+    Eval,
+    /// Decoding of obfuscated content.
+    Base64,
+}
+
+impl Issue {
+    fn severity(&self) -> Severity {
+        match self {
+            Issue::PipedDownload => Severity::High,
+            Issue::Sudo => Severity::High,
+            Issue::OutsideDirs => Severity::Medium,
+            Issue::Eval => Severity::Low,
+            Issue::Base64 => Severity::Low,
+        }
+    }
+
+    fn explain(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Issue::PipedDownload => fl!(fll, "P-piped-download"),
+            Issue::Sudo => fl!(fll, "P-sudo"),
+            Issue::OutsideDirs => fl!(fll, "P-outside-dirs"),
+            Issue::Eval => fl!(fll, "P-eval"),
+            Issue::Base64 => fl!(fll, "P-base64"),
+        }
+    }
+}
+
+/// A packaging mistake in a PKGBUILD, in the spirit of `namcap`. Unlike an
+/// [`Issue`], these are never dangerous in themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lint {
+    /// A field without which `makepkg` would fail.
+    MissingRequired(&'static str),
+    /// A field that the AUR expects to be set.
+    MissingRecommended(&'static str),
+    /// Sources are downloaded, but nothing checks their integrity.
+    NoChecksums,
+}
+
+impl Lint {
+    fn severity(&self) -> Severity {
+        match self {
+            Lint::MissingRequired(_) => Severity::Medium,
+            Lint::MissingRecommended(_) => Severity::Low,
+            Lint::NoChecksums => Severity::Medium,
+        }
+    }
+
+    fn explain(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Lint::MissingRequired(f) => fl!(fll, "P-lint-required", field = *f),
+            Lint::MissingRecommended(f) => fl!(fll, "P-lint-recommended", field = *f),
+            Lint::NoChecksums => fl!(fll, "P-lint-checksums"),
+        }
+    }
+}
+
+/// A suspicious line in a PKGBUILD.
+#[derive(Debug, PartialEq, Eq)]
+struct Finding {
+    /// The line number, starting from 1.
+    line: usize,
+    issue: Issue,
+}
+
+/// Analyse a PKGBUILD given by path.
+pub(crate) fn file(fll: &FluentLanguageLoader, path: &Path) -> Result<(), Error> {
+    let content = std::fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+    let findings = analyse(&content);
+    report(fll, &path.utf8(), &content, &findings);
+    verdict(fll, &findings)
+}
+
+/// Analyse a PKGBUILD read from stdin, e.g. via `aura -Ap foo | aura -P`.
+pub(crate) fn stdin(fll: &FluentLanguageLoader) -> Result<(), Error> {
+    let content = std::io::read_to_string(std::io::stdin()).map_err(Error::Stdin)?;
+    let findings = analyse(&content);
+    report(fll, "stdin", &content, &findings);
+    verdict(fll, &findings)
+}
+
+/// Analyse the PKGBUILDs of every installed foreign package, as found in
+/// Aura's local package clones.
+pub(crate) fn audit(env: &Env, fll: &FluentLanguageLoader, alpm: &Alpm) -> Result<(), Error> {
+    let mut findings = Vec::new();
+
+    for p in aura_core::foreign_packages(alpm) {
+        let base = p.base().unwrap_or_else(|| p.name());
+        let path = env.aur.clones.join(base).join("PKGBUILD");
+
+        match std::fs::read_to_string(&path) {
+            Err(_) => {
+                let msg = fl!(fll, "P-no-clone", pkg = p.name());
+                crate::aln!(msg.warning());
+            }
+            Ok(content) => {
+                let fs = analyse(&content);
+                report(fll, &path.utf8(), &content, &fs);
+                findings.extend(fs);
+            }
+        }
+    }
+
+    verdict(fll, &findings)
+}
+
+/// Analyse and lint the given PKGBUILDs, each paired with where it came from,
+/// as a check before building them. Nothing is built.
+pub(crate) fn validate(
+    fll: &FluentLanguageLoader,
+    pkgbuilds: &[(String, String)],
+) -> Result<(), Error> {
+    let mut findings = Vec::new();
+
+    for (source, content) in pkgbuilds {
+        let fs = analyse(content);
+        let ls = lints(content);
+        report(fll, source, content, &fs);
+        report_lints(fll, source, fs.is_empty(), &ls);
+        findings.extend(fs);
+    }
+
+    verdict(fll, &findings)
+}
+
+/// Fail if anything serious was found.
+fn verdict(fll: &FluentLanguageLoader, findings: &[Finding]) -> Result<(), Error> {
+    if findings
+        .iter()
+        .any(|f| f.issue.severity() == Severity::High)
+    {
+        Err(Error::HighSeverity)
+    } else {
+        if findings.is_empty() {
+            crate::green!(fll, "P-none");
+        }
+        Ok(())
+    }
+}
+
+fn report(fll: &FluentLanguageLoader, source: &str, content: &str, findings: &[Finding]) {
+    if findings.is_empty() {
+        return;
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let labels: Vec<_> = findings
+        .iter()
+        .map(|f| f.issue.severity().label(fll))
+        .collect();
+    let width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+    println!("{}", source.bold());
+    for (f, label) in findings.iter().zip(labels) {
+        // Pad before colouring, such that escape codes don't affect the width.
+        let padded = format!("{:w$}", label, w = width);
+        println!(
+            "  [{}] {}: {}",
+            padded.color(f.issue.severity().color()),
+            f.line,
+            f.issue.explain(fll)
+        );
+        if let Some(l) = lines.get(f.line - 1) {
+            println!("      {}", l.trim().dimmed());
+        }
+    }
+}
+
+/// Like [`report`], but for lints. The `source` is only printed if it hasn't
+/// been already.
+fn report_lints(fll: &FluentLanguageLoader, source: &str, header: bool, lints: &[Lint]) {
+    if lints.is_empty() {
+        return;
+    }
+
+    let labels: Vec<_> = lints.iter().map(|l| l.severity().label(fll)).collect();
+    let width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+    if header {
+        println!("{}", source.bold());
+    }
+    for (l, label) in lints.iter().zip(labels) {
+        let padded = format!("{:w$}", label, w = width);
+        println!(
+            "  [{}] {}",
+            padded.color(l.severity().color()),
+            l.explain(fll)
+        );
+    }
+}
+
+/// Check a PKGBUILD for missing fields and unverified sources.
+fn lints(pkgbuild: &str) -> Vec<Lint> {
+    let assigned: Vec<&str> = pkgbuild
+        .lines()
+        .filter_map(|l| l.trim_start().split_once('='))
+        .map(|(name, _)| name.trim_end_matches('+'))
+        .collect();
+    let has = |field: &str| {
+        assigned
+            .iter()
+            .any(|a| *a == field || a.strip_prefix(field).is_some_and(|r| r.starts_with('_')))
+    };
+
+    let mut found: Vec<_> = REQUIRED
+        .iter()
+        .copied()
+        .filter(|f| has(f).not())
+        .map(Lint::MissingRequired)
+        .chain(
+            RECOMMENDED
+                .iter()
+                .copied()
+                .filter(|f| has(f).not())
+                .map(Lint::MissingRecommended),
+        )
+        .collect();
+
+    if has("source") && CHECKSUMS.iter().any(|c| has(c)).not() {
+        found.push(Lint::NoChecksums);
+    }
+
+    found
+}
+
+/// Scan the lines of a PKGBUILD for suspicious constructs.
+fn analyse(pkgbuild: &str) -> Vec<Finding> {
+    pkgbuild
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim_start().starts_with('#'))
+        .flat_map(|(i, l)| {
+            issues(l)
+                .into_iter()
+                .map(move |issue| Finding { line: i + 1, issue })
+        })
+        .collect()
+}
+
+/// All issues present on a single line.
+fn issues(line: &str) -> Vec<Issue> {
+    let mut found = Vec::new();
+    let segments: Vec<&str> = line.split('|').map(|s| s.trim()).collect();
+    let words = words(line);
+
+    let downloads = segments
+        .iter()
+        .position(|s| words_of(s).any(|w| w == "curl" || w == "wget"));
+    if let Some(i) = downloads {
+        let piped = segments[i + 1..]
+            .iter()
+            .filter_map(|s| words_of(s).next())
+            .any(|w| SHELLS.contains(&w));
+        if piped {
+            found.push(Issue::PipedDownload);
+        }
+    }
+
+    if words.iter().any(|w| *w == "sudo") {
+        found.push(Issue::Sudo);
+    }
+
+    if writes_outside(line) {
+        found.push(Issue::OutsideDirs);
+    }
+
+    if words.iter().any(|w| *w == "eval") {
+        found.push(Issue::Eval);
+    }
+
+    if line.contains("base64 -d") || line.contains("base64 --decode") {
+        found.push(Issue::Base64);
+    }
+
+    found
+}
+
+/// Does some command or redirect on this line write to an absolute path that
+/// isn't within the build directories?
+fn writes_outside(line: &str) -> bool {
+    let redirected = line
+        .match_indices('>')
+        .filter_map(|(i, _)| words_of(line[i..].trim_start_matches('>')).next())
+        .any(suspicious_target);
+
+    let commanded = line
+        .split(['|', ';', '&'])
+        .map(|cmd| {
+            words_of(cmd)
+                .filter(|w| !w.starts_with('-'))
+                .collect::<Vec<_>>()
+        })
+        .any(|ws| match ws.split_first() {
+            Some((c, args)) if COPIERS.contains(c) && args.len() > 1 => {
+                args.last().map(|a| suspicious_target(a)).unwrap_or(false)
+            }
+            Some((c, args)) if MODIFIERS.contains(c) => args.iter().any(|a| suspicious_target(a)),
+            _ => false,
+        });
+
+    redirected || commanded
+}
+
+fn suspicious_target(word: &str) -> bool {
+    let absolute = word.starts_with('/')
+        || word.starts_with('~')
+        || word.starts_with("$HOME")
+        || word.starts_with("${HOME}");
+
+    absolute && !ALLOWED.iter().any(|a| word.starts_with(a))
+}
+
+fn words(line: &str) -> Vec<&str> {
+    words_of(line).collect()
+}
+
+/// Split a line into shell-ish words, stripping quotes.
+fn words_of(s: &str) -> impl Iterator<Item = &str> {
+    s.split(|c: char| c.is_whitespace() || matches!(c, ';' | '&' | '|' | '(' | ')' | '`'))
+        .map(|w| w.trim_matches(|c| c == '"' || c == '\''))
+        .filter(|w| !w.is_empty())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn piped_download() {
+        assert_eq!(
+            vec![Issue::PipedDownload],
+            issues("curl -sL https://x.io/i | sh")
+        );
+        assert_eq!(
+            vec![Issue::PipedDownload],
+            issues("wget -O- https://x.io|bash -s")
+        );
+        assert!(issues("curl -sL https://x.io/i | tar xz").is_empty());
+        assert!(issues("curl -o foo https://x.io/i").is_empty());
+    }
+
+    #[test]
+    fn sudo() {
+        assert_eq!(vec![Issue::Sudo], issues("  sudo pacman -S aurvote"));
+        assert!(issues("echo pseudo").is_empty());
+    }
+
+    #[test]
+    fn outside_dirs() {
+        assert!(issues(r#"install -Dm644 foo "$pkgdir/usr/share/foo""#).is_empty());
+        assert!(issues(r#"cp /usr/share/foo "$srcdir/""#).is_empty());
+        assert!(issues("make 2> /dev/null").is_empty());
+        assert_eq!(vec![Issue::OutsideDirs], issues("cp foo /usr/bin/foo"));
+        assert_eq!(vec![Issue::OutsideDirs], issues("rm -rf ~/.config"));
+        assert_eq!(vec![Issue::OutsideDirs], issues("echo hi >> /etc/profile"));
+    }
+
+    #[test]
+    fn whole_pkgbuild() {
+        let pkgbuild = "pkgname=foo\n\
+                        # sudo is only mentioned here\n\
+                        build() {\n\
+                          cd \"$srcdir\"\n\
+                          sudo make install\n\
+                        }\n";
+
+        let expected = vec![Finding {
+            line: 5,
+            issue: Issue::Sudo,
+        }];
+
+        assert_eq!(expected, analyse(pkgbuild));
+    }
+
+    #[test]
+    fn validated_pkgbuild() {
+        let content = std::fs::read_to_string("tests/unsafe.PKGBUILD").unwrap();
+
+        let expected = vec![
+            Finding {
+                line: 11,
+                issue: Issue::PipedDownload,
+            },
+            Finding {
+                line: 15,
+                issue: Issue::Sudo,
+            },
+        ];
+        assert_eq!(expected, analyse(&content));
+        assert_eq!(
+            vec![Lint::MissingRecommended("license"), Lint::NoChecksums],
+            lints(&content)
+        );
+
+        let fll = crate::localization::load(None).unwrap();
+        let res = validate(&fll, &[("unsafe.PKGBUILD".to_string(), content)]);
+        assert!(matches!(res, Err(Error::HighSeverity)));
+    }
+
+    #[test]
+    fn lint_fields() {
+        let pkgbuild = "pkgname=foo\n\
+                        pkgver=1.0\n\
+                        pkgrel=1\n\
+                        arch=('any')\n\
+                        pkgdesc='Foo'\n\
+                        url='https://foo.org'\n\
+                        license=('MIT')\n\
+                        source_x86_64=(foo.tar.gz)\n\
+                        b2sums_x86_64=('abc')\n";
+        assert!(lints(pkgbuild).is_empty());
+
+        assert_eq!(
+            vec![
+                Lint::MissingRequired("pkgver"),
+                Lint::MissingRequired("pkgrel"),
+                Lint::MissingRequired("arch"),
+                Lint::MissingRecommended("pkgdesc"),
+                Lint::MissingRecommended("url"),
+                Lint::MissingRecommended("license"),
+            ],
+            lints("pkgname=foo\n")
+        );
+    }
+}
@@ -4,18 +4,34 @@ mod build;
 
 use crate::aln;
 use crate::aura;
+use crate::command::snapshot::Transaction;
 use crate::env::Env;
+use crate::error::Exited;
 use crate::error::Nested;
+use crate::fetch::Network;
 use crate::green;
 use crate::localization::Localised;
 use crate::proceed;
+use crate::red;
+use crate::satisfy::DepStatus;
+use crate::satisfy::Satisfier;
+use crate::theme::Role;
+use crate::theme::Themed;
 use crate::utils::Finished;
 use crate::utils::PathStr;
 use crate::utils::ResultVoid;
+use crate::utils::Succeeded;
 use crate::utils::NOTHING;
 use crate::yellow;
 use applying::Apply;
+use aura_core::aur::dependencies::Buildable;
+use aura_core::aur::dependencies::Conflict;
+use aura_core::aur::dependencies::Conflicts;
+use aura_core::aur::dependencies::Identity;
+use aura_core::aur::dependencies::Official;
 use aura_core::aur::dependencies::Resolution;
+use aura_core::faur::SearchBy;
+use aura_core::vercmp::vercmp;
 use aura_core::Package;
 use colored::ColoredString;
 use colored::Colorize;
@@ -29,6 +45,8 @@ use log::info;
 use r2d2_alpm::Alpm;
 use rayon::prelude::*;
 use srcinfo::Srcinfo;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fs::File;
 use std::io::BufRead;
@@ -45,7 +63,12 @@ use validated::Validated;
 
 const AUR_PKG_URL: &str = "https://aur.archlinux.org/packages/";
 
+/// The file, within Aura's state directory, that records which packages went
+/// unbuilt during the last `-Au`.
+const FAILED_BUILDS: &str = "failed-builds";
+
 pub(crate) enum Error {
+    Analysis(crate::command::analysis::Error),
     Backup(crate::command::snapshot::Error),
     Fetch(crate::fetch::Error),
     Git(aura_core::git::Error),
@@ -64,11 +87,15 @@ pub(crate) enum Error {
     Stdout,
     ReadDir(PathBuf, std::io::Error),
     CouldntOpen(String, std::io::Error),
+    BuildFailures(usize),
+    Conflicts(Vec<Conflict>),
+    Exit(String, u8),
 }
 
 impl Nested for Error {
     fn nested(&self) {
         match self {
+            Error::Analysis(e) => e.nested(),
             Error::Fetch(e) => e.nested(),
             Error::Git(e) => e.nested(),
             Error::Build(e) => e.nested(),
@@ -87,6 +114,9 @@ impl Nested for Error {
             Error::Backup(e) => e.nested(),
             Error::ReadDir(_, e) => error!("{e}"),
             Error::CouldntOpen(_, e) => error!("{e}"),
+            Error::BuildFailures(_) => {}
+            Error::Conflicts(cs) => cs.iter().for_each(|c| error!("{} <-> {}", c.pkg, c.with)),
+            Error::Exit(_, _) => {}
         }
     }
 }
@@ -94,6 +124,7 @@ impl Nested for Error {
 impl Localised for Error {
     fn localise(&self, fll: &FluentLanguageLoader) -> String {
         match self {
+            Error::Analysis(e) => e.localise(fll),
             Error::Fetch(e) => e.localise(fll),
             Error::Git(e) => e.localise(fll),
             Error::Build(e) => e.localise(fll),
@@ -112,6 +143,38 @@ impl Localised for Error {
             Error::Backup(e) => e.localise(fll),
             Error::ReadDir(p, _) => fl!(fll, "err-read-dir", dir = p.utf8()),
             Error::CouldntOpen(url, _) => fl!(fll, "open-err", url = url),
+            Error::BuildFailures(n) => fl!(fll, "A-build-e-some", n = *n),
+            Error::Conflicts(_) => fl!(fll, "A-install-conflicts"),
+            Error::Exit(cmd, code) => fl!(fll, "err-exit", cmd = cmd.as_str(), code = *code),
+        }
+    }
+}
+
+impl Exited for Error {
+    fn exit_code(&self) -> Option<u8> {
+        match self {
+            Error::Analysis(_) => None,
+            Error::Backup(e) => e.exit_code(),
+            Error::Fetch(_) => None,
+            Error::Git(_) => None,
+            Error::Build(_) => None,
+            Error::Deps(_) => None,
+            Error::Pacman(e) => e.exit_code(),
+            Error::Env(_) => None,
+            Error::Aur(_) => None,
+            Error::Srcinfo(_, _) => None,
+            Error::PathComponent(_) => None,
+            Error::FileOpen(_, _) => None,
+            Error::FileWrite(_, _) => None,
+            Error::DateConv(_) => None,
+            Error::NoPackages => None,
+            Error::Cancelled => None,
+            Error::Stdout => None,
+            Error::ReadDir(_, _) => None,
+            Error::CouldntOpen(_, _) => None,
+            Error::BuildFailures(_) => None,
+            Error::Conflicts(_) => None,
+            Error::Exit(_, code) => Some(*code),
         }
     }
 }
@@ -122,14 +185,91 @@ pub(crate) enum Mode {
     Upgrade,
 }
 
+/// The order in which to display search results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Order {
+    /// Alphabetically.
+    Name,
+    /// Most votes first.
+    Votes,
+    /// Most popular first.
+    Popularity,
+    /// Most recently updated first.
+    Updated,
+}
+
+impl Order {
+    /// `--abc` is shorthand for `--sort name`. Without either, results are
+    /// ordered by votes.
+    pub(crate) fn from_flags(abc: bool, sort: Option<&str>) -> Order {
+        match sort {
+            _ if abc => Order::Name,
+            Some("name") => Order::Name,
+            Some("popularity") => Order::Popularity,
+            Some("updated") => Order::Updated,
+            _ => Order::Votes,
+        }
+    }
+
+    /// Sort packages in this order, breaking ties by name.
+    fn sort(self, pkgs: &mut [aura_core::faur::Package]) {
+        pkgs.sort_by(|a, b| {
+            match self {
+                Order::Name => std::cmp::Ordering::Equal,
+                Order::Votes => b.num_votes.cmp(&a.num_votes),
+                Order::Popularity => b.popularity.total_cmp(&a.popularity),
+                Order::Updated => b.last_modified.cmp(&a.last_modified),
+            }
+            .then_with(|| a.name.cmp(&b.name))
+        });
+    }
+}
+
+/// Which search results to show, by whether they're installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Installed {
+    /// All of them.
+    Any,
+    /// Only those that are installed.
+    Only,
+    /// Only those that aren't installed.
+    Not,
+}
+
+impl Installed {
+    /// `--installed` and `--not-installed` can't be given together.
+    pub(crate) fn from_flags(installed: bool, not_installed: bool) -> Installed {
+        match () {
+            _ if installed => Installed::Only,
+            _ if not_installed => Installed::Not,
+            _ => Installed::Any,
+        }
+    }
+
+    /// Should a result be shown, given whether it's installed?
+    fn keeps(self, installed: bool) -> bool {
+        match self {
+            Installed::Any => true,
+            Installed::Only => installed,
+            Installed::Not => installed.not(),
+        }
+    }
+}
+
 /// View AUR package information.
-pub(crate) fn info(fll: &FluentLanguageLoader, packages: &[String]) -> Result<(), Error> {
+pub(crate) fn info(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    net: &Network,
+    relative: bool,
+    packages: &[String],
+) -> Result<(), Error> {
     info!("-Ai on {:?}", packages);
-    let r: Vec<aura_core::faur::Package> = aura_core::faur::info(
-        packages.iter().map(|s| s.as_str()),
-        &crate::fetch::fetch_json,
-    )
-    .map_err(Error::Fetch)?;
+    let r: Vec<aura_core::faur::Package> =
+        aura_core::faur::info(packages.iter().map(|s| s.as_str()), &|u: &str| {
+            crate::fetch::fetch_json(net, u)
+        })
+        .map_err(Error::Fetch)?;
     let mut w = BufWriter::new(std::io::stdout());
 
     let repo = fl!(fll, "A-i-repo");
@@ -152,6 +292,7 @@ pub(crate) fn info(fll: &FluentLanguageLoader, packages: &[String]) -> Result<()
     let keys = fl!(fll, "A-i-keywords");
     let sub = fl!(fll, "A-i-submitted");
     let upd = fl!(fll, "A-i-updated");
+    let now = OffsetDateTime::now_utc();
 
     for p in r {
         let pairs: Vec<(&str, ColoredString)> = vec![
@@ -161,47 +302,49 @@ pub(crate) fn info(fll: &FluentLanguageLoader, packages: &[String]) -> Result<()
                 if p.name == p.package_base {
                     p.name.bold()
                 } else {
-                    format!("{} ({})", p.name.bold(), p.package_base.cyan()).normal()
+                    format!("{} ({})", p.name.bold(), p.package_base.highlight()).normal()
                 },
             ),
             (&ver, p.version.normal()),
             (
                 &stat,
                 match p.out_of_date {
-                    None => "Up to Date".green(),
-                    Some(_) => "Out of Date!".red(),
+                    None => "Up to Date".success(),
+                    Some(_) => "Out of Date!".error(),
                 },
             ),
             (
                 &main,
                 match p.maintainer {
-                    None => "None".red(),
+                    None => "None".error(),
                     Some(m) => m.normal(),
                 },
             ),
             (
                 &proj,
-                p.url.map(|m| m.cyan()).unwrap_or_else(|| "None".red()),
+                p.url
+                    .map(|m| m.highlight())
+                    .unwrap_or_else(|| "None".error()),
             ),
             (&aur, package_url(&p.name).normal()),
             (&lic, p.license.join(" ").normal()),
             (&grp, p.groups.join(" ").normal()),
             (&prov, p.provides.join(" ").normal()),
-            (&deps, p.depends.join(" ").normal()),
-            (&make, p.make_depends.join(" ").normal()),
-            (&opts, p.opt_depends.join(" ").normal()),
-            (&check, p.check_depends.join(" ").normal()),
-            (&votes, format!("{}", p.num_votes).yellow()),
-            (&pop, format!("{:.2}", p.popularity).yellow()),
+            (&deps, annotated(fll, alpm.as_ref(), &p.depends)),
+            (&make, annotated(fll, alpm.as_ref(), &p.make_depends)),
+            (&opts, annotated(fll, alpm.as_ref(), &p.opt_depends)),
+            (&check, annotated(fll, alpm.as_ref(), &p.check_depends)),
+            (&votes, format!("{}", p.num_votes).warning()),
+            (&pop, format!("{:.2}", p.popularity).warning()),
             (
                 &desc,
                 p.description
                     .map(|d| d.normal())
-                    .unwrap_or_else(|| "None".red()),
+                    .unwrap_or_else(|| "None".error()),
             ),
-            (&keys, p.keywords.join(" ").cyan()),
-            (&sub, package_date(p.first_submitted)?),
-            (&upd, package_date(p.last_modified)?),
+            (&keys, p.keywords.join(" ").highlight()),
+            (&sub, package_date(fll, relative, p.first_submitted, now)?),
+            (&upd, package_date(fll, relative, p.last_modified, now)?),
         ];
         crate::utils::info(&mut w, fll.current_language(), &pairs).map_err(|_| Error::Stdout)?;
         writeln!(w).map_err(|_| Error::Stdout)?;
@@ -210,23 +353,112 @@ pub(crate) fn info(fll: &FluentLanguageLoader, packages: &[String]) -> Result<()
     Ok(())
 }
 
+/// Package databases that know what packages provide and conflict with.
+trait Identities {
+    /// The identity of some package in the official repos.
+    fn repo(&self, pkg: &str) -> Option<Identity>;
+
+    /// The identities of every installed package.
+    fn installed(&self) -> Vec<Identity>;
+}
+
+impl Identities for alpm::Alpm {
+    fn repo(&self, pkg: &str) -> Option<Identity> {
+        self.syncdbs().pkg(pkg).ok().map(identity)
+    }
+
+    fn installed(&self) -> Vec<Identity> {
+        self.localdb().pkgs().iter().map(identity).collect()
+    }
+}
+
+fn identity(p: &alpm::Package) -> Identity {
+    Identity {
+        name: p.name().to_string(),
+        provides: p.provides().iter().map(|d| d.name().to_string()).collect(),
+        conflicts: p.conflicts().iter().map(|d| d.name().to_string()).collect(),
+    }
+}
+
+/// Check that the packages to be installed and built can actually be installed
+/// together, before anything is built. Conflicts with installed packages are
+/// only warned about, since Pacman will offer to remove those.
+fn preflight<D>(
+    fll: &FluentLanguageLoader,
+    dbs: &D,
+    to_install: &[Official],
+    built: &[Identity],
+) -> Result<(), Error>
+where
+    D: Identities,
+{
+    let incoming: Vec<_> = to_install
+        .iter()
+        .filter_map(|o| dbs.repo(o.as_ref()))
+        .chain(built.iter().cloned())
+        .collect();
+    let Conflicts { among, installed } =
+        aura_core::aur::dependencies::conflicts(&incoming, &dbs.installed());
+
+    for c in installed {
+        let (pkg, with) = (c.pkg.as_str(), c.with.as_str());
+        yellow!(fll, "A-install-conflicts-installed", pkg = pkg, with = with);
+    }
+
+    if among.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Conflicts(among))
+    }
+}
+
+/// Dependencies, each marked by whether it's installed, available from the
+/// repos, or must itself be built from the AUR.
+fn annotated<D>(fll: &FluentLanguageLoader, dbs: &D, deps: &[String]) -> ColoredString
+where
+    D: Satisfier,
+{
+    deps.iter()
+        .map(|dep| {
+            // Optional dependencies may carry a reason: `foo: for bar support`.
+            let dep = dep.split_once(": ").map(|(d, _)| d).unwrap_or(dep);
+            let (label, role) = match DepStatus::of(dbs, dep) {
+                DepStatus::Installed => (fl!(fll, "A-i-dep-installed"), Role::Success),
+                DepStatus::Repo => (fl!(fll, "A-i-dep-repo"), Role::Highlight),
+                // What Pacman doesn't know of must itself come from the AUR.
+                DepStatus::Missing => (fl!(fll, "A-i-dep-aur"), Role::Warning),
+            };
+
+            format!(
+                "{} {}",
+                dep,
+                format!("({label})").color(crate::theme::color(role))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+        .normal()
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn provides<S>(
     alpm: &Alpm,
-    alpha: bool,
+    net: &Network,
+    order: Order,
     rev: bool,
     limit: Option<usize>,
     quiet: bool,
+    which: Installed,
     providing: S,
 ) -> Result<(), Error>
 where
     S: AsRef<str>,
 {
-    let mut matches: Vec<aura_core::faur::Package> =
-        aura_core::faur::provides(providing, &crate::fetch::fetch_json).map_err(Error::Fetch)?;
-
-    matches.sort_by(|a, b| a.name.cmp(&b.name));
+    let matches: Vec<aura_core::faur::Package> =
+        aura_core::faur::provides(providing, &|u: &str| crate::fetch::fetch_json(net, u))
+            .map_err(Error::Fetch)?;
 
-    render_search(alpm, alpha, rev, limit, quiet, matches);
+    render_search(alpm, order, rev, limit, quiet, which, matches);
 
     Ok(())
 }
@@ -234,12 +466,16 @@ where
 /// Search the AUR via a search string.
 ///
 /// Thanks to `clap`, the `terms` slice is guaranteed to be non-empty.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn search(
     alpm: &Alpm,
-    alpha: bool,
+    net: &Network,
+    order: Order,
     rev: bool,
     limit: Option<usize>,
     quiet: bool,
+    which: Installed,
+    by: SearchBy,
     terms: Vec<String>,
 ) -> Result<(), Error> {
     debug!("Searching for: {:?}", terms);
@@ -253,56 +489,53 @@ pub(crate) fn search(
 
     debug!("Sanitized terms: {:?}", cleaned);
 
-    let matches: Vec<aura_core::faur::Package> = aura_core::faur::search(
-        cleaned.iter().map(|s| s.as_str()),
-        &crate::fetch::fetch_json,
-    )
-    .map_err(Error::Fetch)?;
+    let matches: Vec<aura_core::faur::Package> =
+        aura_core::faur::search(cleaned.iter().map(|s| s.as_str()), by, &|u: &str| {
+            crate::fetch::fetch_json(net, u)
+        })
+        .map_err(Error::Fetch)?;
 
     debug!("Search matches: {}", matches.len());
 
-    render_search(alpm, alpha, rev, limit, quiet, matches);
+    render_search(alpm, order, rev, limit, quiet, which, matches);
 
     Ok(())
 }
 
-/// Render some search results. Orders by vote count by default.
+/// Render some search results in the given order.
 fn render_search(
     alpm: &Alpm,
-    alpha: bool,
+    order: Order,
     rev: bool,
     limit: Option<usize>,
     quiet: bool,
-    mut matches: Vec<aura_core::faur::Package>,
+    which: Installed,
+    matches: Vec<aura_core::faur::Package>,
 ) {
-    let db = alpm.alpm.localdb();
     let rep = "aur/".magenta();
+    let installed = alpm
+        .alpm
+        .localdb()
+        .pkgs()
+        .iter()
+        .map(|p| (p.name(), p.version().as_str()));
 
-    // Sort and filter the results as requested.
-    if alpha {
-        matches.sort_by(|a, b| a.name.cmp(&b.name));
-    } else {
-        matches.sort_by(|a, b| b.num_votes.cmp(&a.num_votes));
-    }
-    if rev {
-        matches.reverse();
-    }
-    let to_take = limit.unwrap_or(matches.len());
-
-    for p in matches.into_iter().take(to_take) {
+    for (p, local) in arrange(installed, order, rev, limit, which, matches) {
         if quiet {
             println!("{}", p.name);
         } else {
             let n = p.name.bold();
-            let vot = format!("{}", p.num_votes).yellow();
-            let pop = format!("{:.2}", p.popularity).yellow();
-            let ver = match p.out_of_date {
-                Some(_) => p.version.red(),
-                None => p.version.green(),
+            let vot = format!("{}", p.num_votes).warning();
+            let pop = format!("{:.2}", p.popularity).warning();
+            // An installed version other than the AUR's is likely out of date.
+            let ins = match local {
+                None => "".normal(),
+                Some(v) if vercmp(v, &p.version).is_eq() => format!("[installed {v}]").bold(),
+                Some(v) => format!("[installed {v}]").warning().bold(),
             };
-            let ins = match db.pkg(p.name) {
-                Err(_) => "".normal(),
-                Ok(_) => "[installed]".bold(),
+            let ver = match p.out_of_date {
+                Some(_) => p.version.error(),
+                None => p.version.success(),
             };
 
             // TODO Search term highlighting
@@ -312,12 +545,55 @@ fn render_search(
     }
 }
 
-/// View a package's PKGBUILD.
-pub(crate) fn pkgbuild(pkg: &str, clone_d: &Path) -> Result<(), Error> {
-    let path = aura_core::aur::clone_path_of_pkgbase(clone_d, pkg, &crate::fetch::fetch_json)
-        .map_err(Error::Aur)?
-        .join("PKGBUILD");
+/// Sort and filter search results as requested, pairing each with the version
+/// of it that's installed, if any. The limit applies after filtering, so that
+/// it counts only the results that are shown.
+fn arrange<'a, I>(
+    installed: I,
+    order: Order,
+    rev: bool,
+    limit: Option<usize>,
+    which: Installed,
+    mut matches: Vec<aura_core::faur::Package>,
+) -> Vec<(aura_core::faur::Package, Option<&'a str>)>
+where
+    I: IntoIterator<Item = (&'a str, &'a str)>,
+{
+    order.sort(&mut matches);
+    if rev {
+        matches.reverse();
+    }
 
+    let versions = installed_versions(installed, &matches);
+
+    matches
+        .into_iter()
+        .zip(versions)
+        .filter(|(_, v)| which.keeps(v.is_some()))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+/// The installed version of each of some search results, if any. The installed
+/// packages are only walked once, however many results there are.
+fn installed_versions<'a, I>(
+    installed: I,
+    matches: &[aura_core::faur::Package],
+) -> Vec<Option<&'a str>>
+where
+    I: IntoIterator<Item = (&'a str, &'a str)>,
+{
+    let versions: HashMap<&str, &str> = installed.into_iter().collect();
+
+    matches
+        .iter()
+        .map(|p| versions.get(p.name.as_str()).copied())
+        .collect()
+}
+
+/// View a package's PKGBUILD.
+pub(crate) fn pkgbuild(pkg: &str, clone_d: &Path, net: &Network) -> Result<(), Error> {
+    let path = pkgbuild_path(pkg, clone_d, net)?;
     let file = BufReader::new(File::open(&path).map_err(|e| Error::FileOpen(path, e))?);
     let mut out = BufWriter::new(std::io::stdout());
 
@@ -329,14 +605,126 @@ pub(crate) fn pkgbuild(pkg: &str, clone_d: &Path) -> Result<(), Error> {
     Ok(())
 }
 
+/// View the PKGBUILDs of several packages, one after another, each under a
+/// header naming its package. When someone is there to scroll through them,
+/// they're shown in a viewer. A single package is shown as [`pkgbuild`] does.
+pub(crate) fn pkgbuilds(
+    interactive: bool,
+    pkgs: &[String],
+    clone_d: &Path,
+    net: &Network,
+) -> Result<(), Error> {
+    if let [pkg] = pkgs {
+        return pkgbuild(pkg, clone_d, net);
+    }
+
+    let locate = |p: &str| pkgbuild_path(p, clone_d, net);
+
+    match crate::command::misc::viewer().filter(|_| interactive) {
+        None => {
+            let mut out = BufWriter::new(std::io::stdout());
+            concat_pkgbuilds(&mut out, pkgs, locate)?;
+            out.flush().map_err(|_| Error::Stdout)
+        }
+        Some(prog) => {
+            let mut child = Command::new(&prog)
+                .stdin(std::process::Stdio::piped())
+                .spawn()
+                .map_err(|e| Error::CouldntOpen(prog.utf8(), e))?;
+
+            // The viewer is waited on even if writing to it failed, as it may
+            // simply have been closed early.
+            let written = match child.stdin.take() {
+                Some(stdin) => concat_pkgbuilds(&mut BufWriter::new(stdin), pkgs, locate),
+                None => Err(Error::Stdout),
+            };
+            let status = child
+                .wait()
+                .map_err(|e| Error::CouldntOpen(prog.utf8(), e))?;
+
+            match written {
+                Err(Error::Stdout) => Ok(()),
+                other => other,
+            }?;
+
+            status
+                .succeeded()
+                .map_err(|code| Error::Exit(prog.utf8(), code))
+        }
+    }
+}
+
+/// The path to a package's PKGBUILD, cloning its repository if need be.
+fn pkgbuild_path(pkg: &str, clone_d: &Path, net: &Network) -> Result<PathBuf, Error> {
+    aura_core::aur::clone_path_of_pkgbase(clone_d, pkg, &net.git(), &|u: &str| {
+        crate::fetch::fetch_json(net, u)
+    })
+    .map_err(Error::Aur)
+    .map(|dir| dir.join("PKGBUILD"))
+}
+
+/// Write the PKGBUILD of each package in turn, each under a header, locating
+/// each only once the one before it has been written.
+fn concat_pkgbuilds<W, F>(w: &mut W, pkgs: &[String], locate: F) -> Result<(), Error>
+where
+    W: Write,
+    F: Fn(&str) -> Result<PathBuf, Error>,
+{
+    for (i, pkg) in pkgs.iter().enumerate() {
+        let path = locate(pkg)?;
+        let mut file = File::open(&path).map_err(|e| Error::FileOpen(path, e))?;
+
+        if i > 0 {
+            writeln!(w).map_err(|_| Error::Stdout)?;
+        }
+
+        writeln!(w, "# ==> {pkg} <==").map_err(|_| Error::Stdout)?;
+        std::io::copy(&mut file, w).map_err(|_| Error::Stdout)?;
+    }
+
+    Ok(())
+}
+
+/// Analyse and lint the PKGBUILDs of the given packages, cloning them first if
+/// need be. Nothing is built.
+pub(crate) fn validate(
+    fll: &FluentLanguageLoader,
+    clone_d: &Path,
+    net: &Network,
+    pkgs: &[String],
+) -> Result<(), Error> {
+    if pkgs.is_empty() {
+        return Err(Error::NoPackages);
+    }
+
+    let pkgbuilds = pkgs
+        .iter()
+        .map(|pkg| {
+            let path =
+                aura_core::aur::clone_path_of_pkgbase(clone_d, pkg, &net.git(), &|u: &str| {
+                    crate::fetch::fetch_json(net, u)
+                })
+                .map_err(Error::Aur)?
+                .join("PKGBUILD");
+            let content =
+                std::fs::read_to_string(&path).map_err(|e| Error::FileOpen(path.clone(), e))?;
+
+            Ok((path.utf8(), content))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    crate::command::analysis::validate(fll, &pkgbuilds).map_err(Error::Analysis)
+}
+
 /// Open a given package's AUR package in a browser.
 pub(crate) fn open(package: &str) -> Result<(), Error> {
     let url = package_url(package);
     Command::new("xdg-open")
         .arg(&url)
         .status()
-        .map_err(|e| Error::CouldntOpen(url, e))?;
-    Ok(())
+        .map_err(|e| Error::CouldntOpen(url, e))?
+        .succeeded()
+        .map_err(|code| Error::Exit("xdg-open".to_string(), code))
 }
 
 /// A package's URL on the AUR.
@@ -344,25 +732,38 @@ fn package_url(package: &str) -> String {
     format!("{}{}", AUR_PKG_URL, package)
 }
 
-fn package_date(epoch: u64) -> Result<ColoredString, Error> {
+/// The date of some AUR event, or with `relative`, how long before `now` it was.
+fn package_date(
+    fll: &FluentLanguageLoader,
+    relative: bool,
+    epoch: u64,
+    now: OffsetDateTime,
+) -> Result<ColoredString, Error> {
     // FIXME Thu May  5 22:11:40 2022
     //
     // There is a panic risk here with the u64->i64 conversion. In practice it
     // should never come up, as the timestamps passed in should never be
     // anywhere near the [`u64::MAX`] value.
-    let date = OffsetDateTime::from_unix_timestamp(epoch as i64)
-        .map_err(Error::DateConv)?
-        .date();
-    Ok(format!("{}", date).normal())
+    let date = OffsetDateTime::from_unix_timestamp(epoch as i64).map_err(Error::DateConv)?;
+
+    if relative {
+        Ok(crate::utils::ago(fll, date, now).normal())
+    } else {
+        Ok(format!("{}", date.date()).normal())
+    }
 }
 
 /// Clone the AUR repository of given packages.
-pub(crate) fn clone_aur_repos(fll: &FluentLanguageLoader, pkgs: &[String]) -> Result<(), Error> {
+pub(crate) fn clone_aur_repos(
+    fll: &FluentLanguageLoader,
+    retry: &aura_core::git::Retry,
+    pkgs: &[String],
+) -> Result<(), Error> {
     pkgs.par_iter()
         .map(|p| {
             let pkg = p.as_str();
             aura!(fll, "A-w", package = pkg);
-            aura_core::aur::clone_aur_repo(None, p).void()
+            aura_core::aur::clone_aur_repo(None, p, retry).void()
         })
         .collect::<Result<(), aura_core::git::Error>>()
         .map_err(Error::Git)?;
@@ -372,7 +773,11 @@ pub(crate) fn clone_aur_repos(fll: &FluentLanguageLoader, pkgs: &[String]) -> Re
 }
 
 /// Pull the latest commits from every clone in the `packages` directory.
-pub(crate) fn refresh(fll: &FluentLanguageLoader, clone_d: &Path) -> Result<(), Error> {
+pub(crate) fn refresh(
+    fll: &FluentLanguageLoader,
+    clone_d: &Path,
+    retry: &aura_core::git::Retry,
+) -> Result<(), Error> {
     aura!(fll, "A-y-refreshing");
 
     let uniques: HashSet<_> = clone_d
@@ -391,7 +796,7 @@ pub(crate) fn refresh(fll: &FluentLanguageLoader, clone_d: &Path) -> Result<(),
     if let Validated::Fail(errors) = uniques
         .into_par_iter()
         .map(|path| {
-            let res = aura_core::git::pull(&path);
+            let res = aura_core::git::pull(&path, retry);
             progress.lock().unwrap().inc_and_draw(&pull_bar, 1);
             res
         })
@@ -399,7 +804,7 @@ pub(crate) fn refresh(fll: &FluentLanguageLoader, clone_d: &Path) -> Result<(),
     {
         for error in errors {
             let msg = error.localise(fll);
-            aln!(msg.yellow());
+            aln!(msg.warning());
         }
     }
 
@@ -420,7 +825,7 @@ where
         .filter(|p| {
             // Prompt if the user specified packages that are marked "ignored".
             if env.aur.ignores.contains(*p) {
-                let pkg = p.bold().cyan().to_string();
+                let pkg = p.bold().highlight().to_string();
                 proceed!(fll, env, "A-install-ignored", file = pkg).is_some()
             } else {
                 true
@@ -463,6 +868,7 @@ fn install_work(
     pkgs: &HashSet<&str>,
 ) -> Result<(), Error> {
     let pool = env.alpm_pool().map_err(Error::Env)?;
+    let net = env.aur.network();
     aura!(fll, "A-install-deps");
 
     let rslv = if env.aur.skipdepcheck {
@@ -470,8 +876,9 @@ fn install_work(
     } else {
         aura_core::aur::dependencies::resolve(
             pool,
-            &crate::fetch::fetch_json,
+            &|u: &str| crate::fetch::fetch_json(&net, u),
             &env.aur.clones,
+            &net.git(),
             env.aur.nocheck,
             pkgs,
         )
@@ -501,6 +908,12 @@ fn install_work(
     aura!(fll, "A-install-aur-pkgs");
     to_build.iter().for_each(|p| println!(" {p}"));
 
+    // --- Catch conflicts before anything is built --- //
+    {
+        let alpm = env.alpm().map_err(Error::Env)?;
+        preflight(fll, alpm.as_ref(), &to_install, &rslv.identities)?;
+    }
+
     if env.aur.noconfirm.not() {
         // Proceed if the user accepts.
         proceed!(fll, env, "proceed").ok_or(Error::Cancelled)?;
@@ -508,13 +921,56 @@ fn install_work(
 
     if matches!(mode, Mode::Upgrade) && env.backups.automatic {
         let alpm = env.alpm().map_err(Error::Env)?;
-        crate::command::snapshot::save(fll, &alpm, env.backups.snapshots.as_path())
+        crate::command::snapshot::save(fll, &alpm, env.backups.snapshots.as_path(), None)
             .map_err(Error::Backup)?;
     }
 
+    // Remember what was installed, in case the transaction fails partway and
+    // must be undone.
+    let before = match env.aur.rollback {
+        false => None,
+        true => Some(Transaction::begin(&env.alpm().map_err(Error::Env)?)),
+    };
+
+    let r = transact(fll, env, mode, pkgs, &to_install, &to_build);
+
+    if let (Err(_), Some(tx)) = (&r, before) {
+        yellow!(fll, "A-rollback");
+        // Another handle must be opened, or else the changes made by the
+        // transaction won't be detected. Should the rollback fail too, it's
+        // reported here, and the failure that prompted it is what's returned.
+        match env.alpm() {
+            Err(e) => rollback_failed(fll, Error::Env(e)),
+            Ok(alpm) => {
+                if let Err(e) = crate::command::snapshot::rollback(env, fll, &alpm, &tx) {
+                    rollback_failed(fll, Error::Backup(e));
+                }
+            }
+        }
+    }
+
+    r
+}
+
+fn rollback_failed(fll: &FluentLanguageLoader, e: Error) {
+    e.nested();
+    aln!(e.localise(fll).error());
+    red!(fll, "A-rollback-failed");
+}
+
+/// Install the given repository dependencies, then build and install the given
+/// AUR packages.
+fn transact(
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    mode: Mode,
+    pkgs: &HashSet<&str>,
+    to_install: &[Official],
+    to_build: &[Buildable],
+) -> Result<(), Error> {
     // --- Determine the best build order --- //
     let order: Vec<Vec<&str>> =
-        aura_core::aur::dependencies::build_order(&to_build).map_err(Error::Deps)?;
+        aura_core::aur::dependencies::build_order(to_build).map_err(Error::Deps)?;
     debug!("Build order: {:?}", order);
 
     // --- Install repo dependencies --- //
@@ -531,17 +987,50 @@ fn install_work(
     let is_single = to_build.len() == 1;
     let caches = env.caches();
     let alpm = env.alpm().map_err(Error::Env)?;
+    let tools = build::Tools::new(fll, env);
+    let mut summary = Summary::default();
+    // The names of every package built, which for split packages needn't be
+    // those of their clones.
+    let mut produced = HashSet::new();
+    let mut halted = false;
     for raw_layer in order.into_iter().apply(Finished::new) {
         let done = raw_layer.is_last();
-        let layer = raw_layer.inner();
+        let mut layer = raw_layer.inner();
+
+        // Packages that depend on earlier failures can't be built either.
+        let blocked = aura_core::aur::dependencies::dependents_of(to_build, &summary.failed);
+        layer.retain(|pkg| {
+            let ok = blocked.contains(pkg).not();
+            if ok.not() {
+                summary.skipped.push(pkg.to_string());
+            }
+            ok
+        });
+
+        if halted {
+            summary
+                .skipped
+                .extend(layer.iter().map(|pkg| pkg.to_string()));
+            continue;
+        }
+
+        if layer.is_empty() {
+            continue;
+        }
+
         let clone_paths = layer.into_iter().map(|pkg| env.aur.clones.join(pkg));
 
-        let builts = build::build(
+        let build::Builds {
+            built: builts,
+            failed,
+            cancelled,
+            halted: stop,
+        } = build::build(
             fll,
             &caches,
             env,
             &alpm,
-            &env.general.editor,
+            &tools,
             is_single,
             pkgs,
             clone_paths,
@@ -570,14 +1059,115 @@ fn install_work(
             crate::pacman::pacman_install_from_tarball(env, flags, tarballs)
                 .map_err(Error::Pacman)?;
 
-            builts
-                .into_iter()
-                .try_for_each(|b| update_hash(&env.aur.hashes, &b.clone))?;
+            produced.extend(
+                builts
+                    .iter()
+                    .flat_map(|b| b.tarballs.iter())
+                    .map(|pp| pp.as_package().name.to_string()),
+            );
+
+            for b in builts {
+                update_hash(&env.aur.hashes, &b.clone)?;
+
+                // Only now that the install is known to have succeeded.
+                if env.aur.clean_after {
+                    build::clean_sources(&b.build_dir);
+                }
+
+                summary.built.extend(
+                    b.clone
+                        .file_name()
+                        .and_then(|s| s.to_str())
+                        .map(String::from),
+                );
+            }
         }
+
+        summary.failed.extend(
+            failed
+                .iter()
+                .filter_map(|p| p.file_name().and_then(|s| s.to_str()))
+                .map(String::from),
+        );
+
+        summary.skipped.extend(
+            cancelled
+                .iter()
+                .filter_map(|p| p.file_name().and_then(|s| s.to_str()))
+                .map(String::from),
+        );
+
+        halted = stop;
     }
 
-    green!(fll, "common-done");
-    Ok(())
+    if matches!(mode, Mode::Upgrade) {
+        let state = env.state_dir().map_err(Error::Env)?;
+        record_failed(&state, unbuilt(pkgs, &produced))?;
+    }
+
+    if summary.failed.is_empty() {
+        green!(fll, "common-done");
+        Ok(())
+    } else {
+        summary.report(fll);
+        Err(Error::BuildFailures(summary.failed.len()))
+    }
+}
+
+/// The outcome of building many AUR packages in one go.
+#[derive(Default)]
+struct Summary {
+    built: Vec<String>,
+    failed: HashSet<String>,
+    /// Packages not built because something they depend on failed, or because
+    /// a failed concurrent build halted the rest.
+    skipped: Vec<String>,
+}
+
+impl Summary {
+    fn report(&self, fll: &FluentLanguageLoader) {
+        let mut failed: Vec<_> = self.failed.iter().collect();
+        failed.sort();
+
+        aura!(fll, "A-summary");
+        let rows = [
+            (
+                fl!(fll, "A-summary-built"),
+                self.built.iter().collect::<Vec<_>>(),
+                crate::theme::color(Role::Success),
+            ),
+            (
+                fl!(fll, "A-summary-failed"),
+                failed,
+                crate::theme::color(Role::Error),
+            ),
+            (
+                fl!(fll, "A-summary-skipped"),
+                self.skipped.iter().collect(),
+                crate::theme::color(Role::Warning),
+            ),
+        ];
+        let longest = rows
+            .iter()
+            .map(|(l, _, _)| l.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        for (label, pkgs, color) in rows {
+            let names = pkgs
+                .iter()
+                .map(|p| p.as_str())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let label = format!("{:w$}", label, w = longest);
+            println!(
+                " {} ({}) {}",
+                label.as_str().color(color),
+                pkgs.len(),
+                names
+            );
+        }
+    }
 }
 
 fn update_hash(hashes: &Path, clone: &Path) -> Result<(), Error> {
@@ -593,13 +1183,76 @@ fn update_hash(hashes: &Path, clone: &Path) -> Result<(), Error> {
     Ok(())
 }
 
-/// Upgrade all installed AUR packages.
+/// The requested packages that no build produced, whether their own build
+/// failed or was skipped. These are package names, not the names of clones, so
+/// that they can be requested again as they are.
+fn unbuilt<'a>(
+    requested: &'a HashSet<&str>,
+    produced: &'a HashSet<String>,
+) -> impl Iterator<Item = &'a str> {
+    requested
+        .iter()
+        .copied()
+        .filter(|p| produced.contains(*p).not())
+}
+
+/// Remember which packages went unbuilt, so that `--retry-failed` can attempt
+/// them again. Nothing being left is recorded by removing the record entirely.
+fn record_failed<'a, I>(state: &Path, unbuilt: I) -> Result<(), Error>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let path = state.join(FAILED_BUILDS);
+    let unbuilt: BTreeSet<_> = unbuilt.into_iter().collect();
+
+    if unbuilt.is_empty() {
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(Error::FileWrite(path, e)),
+            _ => Ok(()),
+        }
+    } else {
+        let lines: String = unbuilt.into_iter().map(|p| format!("{p}\n")).collect();
+        std::fs::write(&path, lines).map_err(|e| Error::FileWrite(path, e))
+    }
+}
+
+/// The packages that went unbuilt during the last `-Au`, if any.
+fn failed_builds(state: &Path) -> Result<BTreeSet<String>, Error> {
+    let path = state.join(FAILED_BUILDS);
+
+    match std::fs::read_to_string(&path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeSet::new()),
+        Err(e) => Err(Error::FileOpen(path, e)),
+        Ok(s) => Ok(s
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| l.is_empty().not())
+            .map(String::from)
+            .collect()),
+    }
+}
+
+/// Attempt again only the packages that went unbuilt during the last `-Au`.
+pub(crate) fn retry_failed(fll: &FluentLanguageLoader, env: &Env) -> Result<(), Error> {
+    let state = env.state_dir().map_err(Error::Env)?;
+    let failed = failed_builds(&state)?;
+
+    if failed.is_empty() {
+        aura!(fll, "A-u-retry-none");
+        return Ok(());
+    }
+
+    aura!(fll, "A-u-retry", n = failed.len());
+    install(fll, env, Mode::Upgrade, failed.iter().map(|p| p.as_str()))
+}
+
+/// Upgrade all installed AUR packages, yielding how many were upgraded.
 pub(crate) fn upgrade<'a>(
     fll: &FluentLanguageLoader,
     alpm: &'a Alpm,
-    env: Env,
+    env: &Env,
     dryrun: bool,
-) -> Result<(), Error> {
+) -> Result<usize, Error> {
     info!("Upgrading all AUR packages.");
     debug!("Will ignore: {:?}", env.aur.ignores);
 
@@ -611,54 +1264,19 @@ pub(crate) fn upgrade<'a>(
     foreigns.retain(|p| env.aur.ignores.contains(p.name.as_ref()).not());
     debug!("After excluding ignores: {}", foreigns.len());
 
-    // --- Ensure they all have local clones --- //
-    if dryrun.not() {
-        aura!(fll, "A-u-fetch-info");
-    }
-    let clones: HashSet<PathBuf> = foreigns
-        .par_iter()
-        .map(|p| p.name.as_ref())
-        .filter_map(|p| {
-            let rpath = aura_core::aur::clone_path_of_pkgbase(
-                &env.aur.clones,
-                p,
-                &crate::fetch::fetch_json,
-            );
-
-            match rpath {
-                Ok(path) => Some(Ok(path)),
-                Err(aura_core::aur::Error::PackageDoesNotExist(p)) => {
-                    if dryrun.not() && env.aur.warn_unknowns {
-                        yellow!(fll, "faur-unknown", pkg = p);
-                    }
-                    None
-                }
-                Err(e) => Some(Err(e)),
-            }
-        })
-        .collect::<Result<HashSet<_>, aura_core::aur::Error>>()
-        .map_err(Error::Aur)?;
-    debug!("Unique clones: {}", clones.len());
-
-    // --- Compare versions to determine what to upgrade --- //
-    if dryrun.not() {
-        aura!(fll, "A-u-comparing");
-    }
-    info!("Reading .SRCINFO files...");
-    let srcinfos = clones
-        .into_par_iter()
-        .map(|path| {
-            let full = path.join(".SRCINFO");
-            Srcinfo::parse_file(&full).map_err(|e| Error::Srcinfo(full, e))
+    let from_api = if dryrun {
+        // A dry run mustn't clone anything, so the packages are looked up by
+        // name instead of by the `pkgbase` of their local clones.
+        info!("Pulling AUR data...");
+        aura_core::faur::info(foreigns.iter().map(|p| p.name.as_ref()), &|u: &str| {
+            crate::fetch::fetch_json(&env.aur.network(), u)
         })
-        .collect::<Result<Vec<_>, Error>>()?;
-    info!("Pulling AUR data...");
-    let from_api: Vec<aura_core::faur::Package> = aura_core::faur::info(
-        srcinfos.iter().map(|p| p.base.pkgbase.as_str()),
-        &crate::fetch::fetch_json,
-    )
-    .map_err(Error::Fetch)?;
+        .map_err(Error::Fetch)?
+    } else {
+        latest_versions(fll, env, &foreigns)?
+    };
     debug!("Packages pulled: {}", from_api.len());
+
     let db = alpm.alpm.localdb();
     let mut to_upgrade: Vec<(aura_core::Package<'_>, aura_core::Package<'_>)> = from_api
         .into_iter()
@@ -674,46 +1292,39 @@ pub(crate) fn upgrade<'a>(
     debug!("Packages to upgrade: {}", to_upgrade.len());
 
     // --- Account for VCS packages --- //
-    let vcs: Vec<_> = if env.aur.git {
-        foreigns
-            .iter()
-            .filter(|p| {
-                let n = p.name.as_ref();
-                n.ends_with("-git") || n.ends_with("-hg") || n.ends_with("-svn")
-            })
-            .filter(|p| to_upgrade.iter().all(|(old, _)| p.name != old.name))
-            .collect()
-    } else {
-        Vec::new()
-    };
+    let vcs: Vec<_> = if env.aur.git { vcs_rebuilds(&foreigns, &to_upgrade) } else { Vec::new() };
 
     debug!("VCS packages to consider: {:?}", vcs);
 
     // --- Report --- //
-    if to_upgrade.is_empty() && (env.aur.git.not() || (env.aur.git && vcs.is_empty())) {
+    if to_upgrade.is_empty() && vcs.is_empty() {
         if dryrun.not() {
             aura!(fll, "A-u-no-upgrades");
         }
     } else {
-        if dryrun.not() {
+        if dryrun.not() && to_upgrade.is_empty().not() {
             aura!(fll, "A-u-to-upgrade");
         }
         to_upgrade.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
         let longest_name = to_upgrade
             .iter()
-            .map(|(old, _)| old.name.chars().count())
+            .map(|(old, _)| &old.name)
+            .chain(vcs.iter().map(|p| &p.name))
+            .map(|n| n.chars().count())
             .max()
             .unwrap_or(0);
         let longest_version = to_upgrade
             .iter()
-            .map(|(old, _)| old.version.to_string().chars().count())
+            .map(|(old, _)| &old.version)
+            .chain(vcs.iter().map(|p| &p.version))
+            .map(|v| v.to_string().chars().count())
             .max()
             .unwrap_or(0);
 
         for (old, new) in to_upgrade.iter() {
             println!(
                 " {:n$} :: {:v$} -> {}",
-                old.name.cyan(),
+                old.name.highlight(),
                 old.version.to_string().truecolor(128, 128, 128),
                 new.version.to_string().bold(),
                 n = longest_name,
@@ -721,26 +1332,436 @@ pub(crate) fn upgrade<'a>(
             );
         }
 
-        // We've printed the packages that have available upgrades, so now bail
-        // early before anything else can happen.
-        if dryrun {
-            return Ok(());
-        }
+        if vcs.is_empty().not() {
+            if dryrun.not() {
+                aura!(fll, "A-u-git");
+            }
 
-        if env.aur.git && vcs.is_empty().not() {
-            aura!(fll, "A-u-git");
+            let latest = fl!(fll, "A-u-git-latest");
             for p in vcs.iter() {
-                println!(" {}", p.name.cyan());
+                println!(
+                    " {:n$} :: {:v$} -> {}",
+                    p.name.highlight(),
+                    p.version.to_string().truecolor(128, 128, 128),
+                    latest.bold(),
+                    n = longest_name,
+                    v = longest_version,
+                );
             }
         }
 
+        // We've printed the packages that have available upgrades, so now bail
+        // early before anything else can happen.
+        if dryrun {
+            return Ok(0);
+        }
+
         let names = to_upgrade
             .iter()
             .map(|(old, _)| old.name.as_ref())
             .chain(vcs.iter().map(|p| p.name.as_ref()));
 
-        install(fll, &env, Mode::Upgrade, names)?;
+        install(fll, env, Mode::Upgrade, names)?;
+
+        return Ok(to_upgrade.len() + vcs.len());
     }
 
-    Ok(())
+    Ok(0)
+}
+
+/// Ensure that every given package has a local clone, and look up the latest
+/// AUR data for each by their `pkgbase`.
+fn latest_versions(
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    foreigns: &[aura_core::Package<'_>],
+) -> Result<Vec<aura_core::faur::Package>, Error> {
+    let net = env.aur.network();
+    let retry = net.git();
+
+    // --- Ensure they all have local clones --- //
+    aura!(fll, "A-u-fetch-info");
+    let clones: HashSet<PathBuf> = foreigns
+        .par_iter()
+        .map(|p| p.name.as_ref())
+        .filter_map(|p| {
+            let rpath =
+                aura_core::aur::clone_path_of_pkgbase(&env.aur.clones, p, &retry, &|u: &str| {
+                    crate::fetch::fetch_json(&net, u)
+                });
+
+            match rpath {
+                Ok(path) => Some(Ok(path)),
+                Err(aura_core::aur::Error::PackageDoesNotExist(p)) => {
+                    if env.aur.warn_unknowns {
+                        yellow!(fll, "faur-unknown", pkg = p);
+                    }
+                    None
+                }
+                Err(e) => Some(Err(e)),
+            }
+        })
+        .collect::<Result<HashSet<_>, aura_core::aur::Error>>()
+        .map_err(Error::Aur)?;
+    debug!("Unique clones: {}", clones.len());
+
+    // --- Compare versions to determine what to upgrade --- //
+    aura!(fll, "A-u-comparing");
+    info!("Reading .SRCINFO files...");
+    let srcinfos = clones
+        .into_par_iter()
+        .map(|path| {
+            let full = path.join(".SRCINFO");
+            Srcinfo::parse_file(&full).map_err(|e| Error::Srcinfo(full, e))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    info!("Pulling AUR data...");
+    aura_core::faur::info(
+        srcinfos.iter().map(|p| p.base.pkgbase.as_str()),
+        &|u: &str| crate::fetch::fetch_json(&net, u),
+    )
+    .map_err(Error::Fetch)
+}
+
+/// Is this package built from a version control repository?
+fn is_vcs(name: &str) -> bool {
+    name.ends_with("-git") || name.ends_with("-hg") || name.ends_with("-svn")
+}
+
+/// VCS packages to rebuild, excluding those already due for a normal upgrade.
+fn vcs_rebuilds<'p, 'a>(
+    foreigns: &'p [aura_core::Package<'a>],
+    to_upgrade: &[(aura_core::Package<'_>, aura_core::Package<'_>)],
+) -> Vec<&'p aura_core::Package<'a>> {
+    foreigns
+        .iter()
+        .filter(|p| is_vcs(p.name.as_ref()))
+        .filter(|p| to_upgrade.iter().all(|(old, _)| p.name != old.name))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::TempDir;
+
+    struct MockIdentities;
+
+    impl Identities for MockIdentities {
+        fn repo(&self, pkg: &str) -> Option<Identity> {
+            let (provides, conflicts) = match pkg {
+                "jack2" => (vec!["jack"], vec![]),
+                "pipewire-jack" => (vec!["jack"], vec!["jack"]),
+                "qt6-base" => (vec![], vec![]),
+                _ => return None,
+            };
+
+            Some(Identity {
+                name: pkg.to_string(),
+                provides: provides.into_iter().map(String::from).collect(),
+                conflicts: conflicts.into_iter().map(String::from).collect(),
+            })
+        }
+
+        fn installed(&self) -> Vec<Identity> {
+            vec![Identity {
+                name: "jack2".to_string(),
+                provides: vec!["jack".to_string()],
+                ..Default::default()
+            }]
+        }
+    }
+
+    #[test]
+    fn conflicts_before_building() {
+        let fll = crate::localization::load(None).unwrap();
+        let built = |name: &str| Identity {
+            name: name.to_string(),
+            ..Default::default()
+        };
+
+        // Two AUR targets whose repo deps clash with each other.
+        let to_install = [Official::new("jack2"), Official::new("pipewire-jack")];
+        let res = preflight(
+            &fll,
+            &MockIdentities,
+            &to_install,
+            &[built("carla"), built("zrythm")],
+        );
+        assert!(matches!(
+            res,
+            Err(Error::Conflicts(cs)) if cs == vec![Conflict {
+                pkg: "jack2".to_string(),
+                with: "pipewire-jack".to_string(),
+            }]
+        ));
+
+        // A clash with what's already installed is left to Pacman.
+        let to_install = [Official::new("pipewire-jack"), Official::new("qt6-base")];
+        assert!(preflight(&fll, &MockIdentities, &to_install, &[built("carla")]).is_ok());
+
+        let to_install = [Official::new("qt6-base")];
+        assert!(preflight(&fll, &MockIdentities, &to_install, &[built("carla")]).is_ok());
+    }
+
+    #[test]
+    fn unbuilt_split_packages() {
+        // `vim-git` and `gvim-git` were asked for, but only `vim-git` built from
+        // their shared clone, `vim-git-runtime`. `qlot` never built at all.
+        let requested = HashSet::from(["vim-git", "gvim-git", "qlot"]);
+        let produced = HashSet::from(["vim-git-runtime".to_string(), "vim-git".to_string()]);
+        let found: BTreeSet<_> = unbuilt(&requested, &produced).collect();
+
+        assert_eq!(BTreeSet::from(["gvim-git", "qlot"]), found);
+    }
+
+    #[test]
+    fn failed_build_record() {
+        let state = TempDir::new("failed");
+
+        // Nothing has failed yet.
+        assert!(failed_builds(&state).ok().unwrap().is_empty());
+
+        record_failed(&state, ["qlot", "aura-git", "qlot"])
+            .ok()
+            .unwrap();
+        let failed = failed_builds(&state).ok().unwrap();
+        assert_eq!(
+            BTreeSet::from(["aura-git".to_string(), "qlot".to_string()]),
+            failed
+        );
+
+        // A retry in which only some succeed narrows the record.
+        record_failed(&state, ["qlot"]).ok().unwrap();
+        let failed = failed_builds(&state).ok().unwrap();
+        assert_eq!(vec!["qlot"], failed.iter().collect::<Vec<_>>());
+
+        // Full success clears it.
+        record_failed(&state, NOTHING).ok().unwrap();
+        assert!(state.join(FAILED_BUILDS).exists().not());
+        assert!(failed_builds(&state).ok().unwrap().is_empty());
+        record_failed(&state, NOTHING).ok().unwrap();
+    }
+
+    struct MockDbs {
+        local: HashSet<&'static str>,
+        repos: HashSet<&'static str>,
+    }
+
+    impl Satisfier for MockDbs {
+        fn installed(&self, dep: &str) -> bool {
+            self.local.contains(dep)
+        }
+
+        fn in_repos(&self, dep: &str) -> bool {
+            self.repos.contains(dep)
+        }
+    }
+
+    #[test]
+    fn dependency_statuses() {
+        let fll = crate::localization::load(None).unwrap();
+        let dbs = MockDbs {
+            local: HashSet::from(["sbcl", "git"]),
+            repos: HashSet::from(["sbcl", "git", "cmake"]),
+        };
+        let deps = ["sbcl", "cmake", "qlot", "git: for fetching sources"].map(String::from);
+
+        assert_eq!(DepStatus::Installed, DepStatus::of(&dbs, "sbcl"));
+        assert_eq!(DepStatus::Repo, DepStatus::of(&dbs, "cmake"));
+        assert_eq!(DepStatus::Missing, DepStatus::of(&dbs, "qlot"));
+        assert_eq!(
+            "sbcl (installed) cmake (repo) qlot (AUR) git (installed)",
+            plain(&annotated(&fll, &dbs, &deps).to_string())
+        );
+    }
+
+    /// The given text without its ANSI colour codes, whether or not colouring
+    /// was enabled when it was rendered.
+    fn plain(s: &str) -> String {
+        let mut out = String::new();
+        let mut chars = s.chars();
+
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|c| *c == 'm');
+            } else {
+                out.push(c);
+            }
+        }
+
+        out
+    }
+
+    #[test]
+    fn package_dates() {
+        let fll = crate::localization::load(None).unwrap();
+        // 2023-06-01, and some 3 months later.
+        let then = 1685577600;
+        let now = OffsetDateTime::from_unix_timestamp(1694736000).unwrap();
+        let date = |relative| {
+            package_date(&fll, relative, then, now)
+                .ok()
+                .unwrap()
+                .to_string()
+        };
+
+        assert!(date(false).contains("2023-06-01"));
+        assert!(date(true).contains("3 months ago"));
+    }
+
+    fn sorted(order: Order) -> Vec<String> {
+        let file = BufReader::new(File::open("tests/aur-search.json").unwrap());
+        let mut pkgs: Vec<aura_core::faur::Package> = serde_json::from_reader(file).unwrap();
+        order.sort(&mut pkgs);
+        pkgs.into_iter().map(|p| p.name).collect()
+    }
+
+    #[test]
+    fn installed_results() {
+        let file = BufReader::new(File::open("tests/aur-search.json").unwrap());
+        let pkgs: Vec<aura_core::faur::Package> = serde_json::from_reader(file).unwrap();
+        let walked = std::cell::Cell::new(0);
+        let installed = [
+            ("glibc", "2.40-1"),
+            ("aura-bin", "3.2.7-1"),
+            ("git", "2.46.0-1"),
+        ]
+        .into_iter()
+        .inspect(|_| walked.set(walked.get() + 1));
+
+        let names: Vec<_> = pkgs.iter().map(|p| p.name.as_str()).collect();
+        let versions = installed_versions(installed, &pkgs);
+
+        assert_eq!(
+            vec![
+                ("aura", None),
+                ("aura-bin", Some("3.2.7-1")),
+                ("aura-git", None)
+            ],
+            names.into_iter().zip(versions).collect::<Vec<_>>()
+        );
+        assert_eq!(3, walked.get());
+    }
+
+    #[test]
+    fn installed_filters() {
+        let installed = [("aura", "3.2.6-1"), ("aura-git", "3.2.7.r2.g8dd4d4d-1")];
+        let shown = |rev, limit, which| -> Vec<(String, Option<&str>)> {
+            let file = BufReader::new(File::open("tests/aur-search.json").unwrap());
+            let pkgs: Vec<aura_core::faur::Package> = serde_json::from_reader(file).unwrap();
+
+            arrange(installed, Order::Votes, rev, limit, which, pkgs)
+                .into_iter()
+                .map(|(p, v)| (p.name, v))
+                .collect()
+        };
+
+        assert_eq!(
+            vec![
+                ("aura-bin".to_string(), None),
+                ("aura".to_string(), Some("3.2.6-1")),
+                ("aura-git".to_string(), Some("3.2.7.r2.g8dd4d4d-1")),
+            ],
+            shown(false, None, Installed::Any)
+        );
+
+        // The limit counts only what's left after filtering.
+        assert_eq!(
+            vec![("aura".to_string(), Some("3.2.6-1"))],
+            shown(false, Some(1), Installed::Only)
+        );
+        assert_eq!(
+            vec![("aura-git".to_string(), Some("3.2.7.r2.g8dd4d4d-1"))],
+            shown(true, Some(1), Installed::Only)
+        );
+        assert_eq!(
+            vec![("aura-bin".to_string(), None)],
+            shown(false, None, Installed::Not)
+        );
+    }
+
+    #[test]
+    fn concatenated_pkgbuilds() {
+        let pkgs = vec!["aura-bin".to_string(), "aura-git".to_string()];
+        let located = std::cell::RefCell::new(Vec::new());
+        let mut out = Vec::new();
+
+        concat_pkgbuilds(&mut out, &pkgs, |p| {
+            located.borrow_mut().push(p.to_string());
+            Ok(PathBuf::from(format!("tests/{p}.PKGBUILD")))
+        })
+        .ok()
+        .unwrap();
+
+        let bin = std::fs::read_to_string("tests/aura-bin.PKGBUILD").unwrap();
+        let git = std::fs::read_to_string("tests/aura-git.PKGBUILD").unwrap();
+        let exp = format!("# ==> aura-bin <==\n{bin}\n# ==> aura-git <==\n{git}");
+
+        assert_eq!(pkgs, located.into_inner());
+        assert_eq!(exp, String::from_utf8(out).unwrap());
+
+        // Nothing more is located after a failure.
+        let calls = std::cell::Cell::new(0);
+        let res = concat_pkgbuilds(&mut Vec::new(), &pkgs, |p| {
+            calls.set(calls.get() + 1);
+            Err(Error::FileOpen(
+                PathBuf::from(p),
+                std::io::ErrorKind::NotFound.into(),
+            ))
+        });
+        assert!(matches!(res, Err(Error::FileOpen(p, _)) if p == Path::new("aura-bin")));
+        assert_eq!(1, calls.get());
+    }
+
+    #[test]
+    fn installed_flags() {
+        assert_eq!(Installed::Any, Installed::from_flags(false, false));
+        assert_eq!(Installed::Only, Installed::from_flags(true, false));
+        assert_eq!(Installed::Not, Installed::from_flags(false, true));
+    }
+
+    #[test]
+    fn search_orders() {
+        assert_eq!(vec!["aura", "aura-bin", "aura-git"], sorted(Order::Name));
+        assert_eq!(vec!["aura-bin", "aura", "aura-git"], sorted(Order::Votes));
+        assert_eq!(
+            vec!["aura-git", "aura", "aura-bin"],
+            sorted(Order::Popularity)
+        );
+        assert_eq!(vec!["aura-git", "aura-bin", "aura"], sorted(Order::Updated));
+    }
+
+    #[test]
+    fn order_flags() {
+        assert_eq!(Order::Votes, Order::from_flags(false, None));
+        assert_eq!(Order::Name, Order::from_flags(true, None));
+        assert_eq!(Order::Name, Order::from_flags(false, Some("name")));
+        assert_eq!(Order::Votes, Order::from_flags(false, Some("votes")));
+        assert_eq!(
+            Order::Popularity,
+            Order::from_flags(false, Some("popularity"))
+        );
+        assert_eq!(Order::Updated, Order::from_flags(false, Some("updated")));
+    }
+
+    #[test]
+    fn vcs_candidates() {
+        let pkg = |n: &str, v: &str| aura_core::Package::new(n.to_string(), v).unwrap();
+        let foreigns = vec![
+            pkg("aura-git", "4.0.0.r1-1"),
+            pkg("emacs-git", "30.0.50-1"),
+            pkg("foo-hg", "1.0-1"),
+            pkg("bar", "1.0-1"),
+        ];
+        let to_upgrade = vec![(pkg("foo-hg", "1.0-1"), pkg("foo-hg", "1.1-1"))];
+        let vcs: Vec<_> = vcs_rebuilds(&foreigns, &to_upgrade)
+            .into_iter()
+            .map(|p| p.name.to_string())
+            .collect();
+
+        assert_eq!(vec!["aura-git", "emacs-git"], vcs);
+        assert!(is_vcs("foo-svn"));
+        assert!(is_vcs("gitg").not());
+    }
 }
@@ -1,10 +1,12 @@
 use crate::aln;
 use crate::aura;
+use crate::command::misc::Program;
 use crate::env::Env;
 use crate::error::Nested;
 use crate::localization::Localised;
 use crate::proceed;
 use crate::red;
+use crate::theme::Themed;
 use crate::utils::PathStr;
 use crate::utils::ResultVoid;
 use crate::yellow;
@@ -16,16 +18,20 @@ use i18n_embed_fl::fl;
 use log::debug;
 use log::error;
 use log::warn;
-use nonempty_collections::NESet;
 use nonempty_collections::NEVec;
-use nonempty_collections::NonEmptyIterator;
 use r2d2_alpm::Alpm;
 use srcinfo::Srcinfo;
 use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::io::Write;
 use std::ops::Not;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Mutex;
 use validated::Validated;
 
 pub(crate) enum Error {
@@ -43,6 +49,9 @@ pub(crate) enum Error {
     PkgctlBuild,
     Cancelled,
     Permissions(PathBuf),
+    MissingKeys(String),
+    KeyImport(String),
+    Verify(String),
 }
 
 impl Nested for Error {
@@ -66,6 +75,9 @@ impl Nested for Error {
             Error::Cancelled => {}
             Error::PkgctlBuild => {}
             Error::Permissions(_) => {}
+            Error::MissingKeys(_) => {}
+            Error::KeyImport(_) => {}
+            Error::Verify(_) => {}
         }
     }
 }
@@ -87,6 +99,9 @@ impl Localised for Error {
             Error::ReadDir(p, _) => fl!(fll, "err-read-dir", dir = p.utf8()),
             Error::Pkglist(p, _) => fl!(fll, "A-build-pkglist", dir = p.utf8()),
             Error::Permissions(p) => fl!(fll, "A-build-e-perm", dir = p.utf8()),
+            Error::MissingKeys(p) => fl!(fll, "A-build-e-keys", pkg = p.as_str()),
+            Error::KeyImport(k) => fl!(fll, "A-build-e-import", key = k.as_str()),
+            Error::Verify(p) => fl!(fll, "A-build-e-verify", pkg = p.as_str()),
         }
     }
 }
@@ -94,59 +109,266 @@ impl Localised for Error {
 /// The results of a successful build.
 pub(crate) struct Built {
     pub(crate) clone: PathBuf,
+    pub(crate) build_dir: PathBuf,
     pub(crate) tarballs: Vec<PkgPath>,
 }
 
-// TODO Thu Jan 20 16:13:54 2022
-//
-// Consider parallel builds, but make it opt-in.
-//
-// 2024-06-12
-//
-// Really? Given that certain packages themselves build with multiple threads,
-// this sounds like a recipe for problems.
+/// The results of building a group of packages, some of which may have failed.
+pub(crate) struct Builds {
+    pub(crate) built: Vec<Built>,
+    /// The clones of packages that failed to build.
+    pub(crate) failed: Vec<PathBuf>,
+    /// The clones of packages whose builds never started, because another
+    /// concurrent build failed first.
+    pub(crate) cancelled: Vec<PathBuf>,
+    /// Should no further packages be built, because a concurrent build failed?
+    pub(crate) halted: bool,
+}
+
+/// A package whose build directory is ready, such that nothing remains to be
+/// asked of the user before building it.
+struct Prepared {
+    clone: PathBuf,
+    info: Srcinfo,
+    build_dir: PathBuf,
+    /// Locally built AUR dependencies to inject into an isolated chroot, if
+    /// the package is to be built in one.
+    chroot_deps: Option<Vec<PkgPath>>,
+}
+
+impl Prepared {
+    fn base(&self) -> &str {
+        self.info.base.pkgbase.as_str()
+    }
+}
+
+/// The programs chosen to review build files with, found once up front so that
+/// any that are missing are only warned about once.
+pub(crate) struct Tools {
+    /// Only sought with `--hotedit`.
+    editor: Option<Program>,
+    /// Without one, `git diff` shows the diffs itself.
+    difftool: Option<Program>,
+}
+
+impl Tools {
+    pub(crate) fn new(fll: &FluentLanguageLoader, env: &Env) -> Tools {
+        let editor = env.aur.hotedit.then(|| {
+            let editor = env.general.editor.as_str();
+            Program::find(editor).or_else(|| {
+                missing_tool(fll, editor, crate::env::DEFAULT_EDITOR);
+                Program::find(crate::env::DEFAULT_EDITOR)
+            })
+        });
+
+        // Choosing plain `diff` is the same as choosing nothing, since it can't
+        // read a diff from stdin as the others do.
+        let difftool = match env.aur.difftool.as_deref() {
+            Some(tool) if env.aur.diff && tool != "diff" => Program::find(tool).or_else(|| {
+                missing_tool(fll, tool, "git diff");
+                None
+            }),
+            _ => None,
+        };
+
+        Tools {
+            editor: editor.flatten(),
+            difftool,
+        }
+    }
+}
+
+fn missing_tool(fll: &FluentLanguageLoader, tool: &str, fallback: &str) {
+    let msg = fl!(
+        fll,
+        "A-build-tool-missing",
+        tool = tool,
+        fallback = fallback
+    );
+    aln!(msg.warning());
+}
+
+// NOTE 2026-10-16 Parallel builds are opt-in via `--jobs`, since certain
+// packages themselves build with multiple threads.
 /// Build the given packages and yield paths to their built tarballs.
+///
+/// The given packages must not depend on each other. With `--jobs` above 1,
+/// up to that many of them are built at once.
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn build<I>(
     fll: &FluentLanguageLoader,
     caches: &[&Path],
     env: &Env,
     alpm: &Alpm,
-    editor: &str,
+    tools: &Tools,
     // Was there only ever one package to be built? If so, we don't prompt the
     // user with a "will you continue?" message if the build fails.
     is_single: bool,
     requested: &HashSet<&str>,
     pkg_clones: I,
-) -> Result<Vec<Built>, Error>
+) -> Result<Builds, Error>
 where
     I: Iterator<Item = PathBuf>,
 {
     aura!(fll, "A-build-prep");
 
-    let to_install = pkg_clones
-        .map(|path| build_one(fll, caches, env, alpm, editor, requested, path))
-        .map(|r| build_check(fll, env, is_single, r))
-        .collect::<Result<Vec<Option<Built>>, Error>>()?
-        .into_iter()
-        .flatten()
-        .collect();
+    let mut builds = Builds {
+        built: Vec::new(),
+        failed: Vec::new(),
+        cancelled: Vec::new(),
+        halted: false,
+    };
+
+    let clones: Vec<PathBuf> = pkg_clones.collect();
 
-    Ok(to_install)
+    if env.aur.jobs < 2 || clones.len() < 2 {
+        for path in clones {
+            let r = prepare(fll, caches, env, alpm, tools, path.clone())
+                .and_then(|p| compile(env, requested, &p, None));
+
+            match build_check(fll, env, is_single, r)? {
+                Some(built) => builds.built.push(built),
+                None => builds.failed.push(path),
+            }
+        }
+
+        return Ok(builds);
+    }
+
+    // Anything that might prompt the user must happen before the concurrent
+    // builds begin, one package at a time.
+    let mut prepared = Vec::new();
+    for path in clones {
+        let r = prepare(fll, caches, env, alpm, tools, path.clone());
+
+        match build_check(fll, env, is_single, r)? {
+            Some(p) => prepared.push(p),
+            None => builds.failed.push(path),
+        }
+    }
+
+    let msg = fl!(fll, "A-build-jobs", n = prepared.len(), jobs = env.aur.jobs);
+    aln!(msg);
+
+    let mut failures = Vec::new();
+    let pending = schedule(
+        env.aur.jobs,
+        env.aur.keep_going,
+        prepared,
+        |p| {
+            let mut log = Vec::new();
+            let r = compile(env, requested, p, Some(&mut log));
+            (log, r)
+        },
+        |(_, r)| r.is_err(),
+        |p, (log, r)| {
+            aura!(
+                fll,
+                "A-build-log",
+                pkg = p.base().highlight().bold().to_string()
+            );
+            let mut out = std::io::stdout().lock();
+            let _ = out.write_all(&log);
+            let _ = out.flush();
+
+            match r {
+                Ok(built) => builds.built.push(built),
+                Err(e) => failures.push((p, e)),
+            }
+        },
+    );
+
+    if pending.is_empty().not() {
+        yellow!(fll, "A-build-cancelled", n = pending.len());
+        builds.cancelled = pending.into_iter().map(|p| p.clone).collect();
+    }
+
+    builds.halted = failures.is_empty().not() && env.aur.keep_going.not();
+
+    // All failures are reported together, after every in-flight build is done.
+    for (p, e) in failures {
+        red!(fll, "A-build-fail-pkg", pkg = p.base().to_string());
+        eprintln!("\n  {}\n", e.localise(fll));
+        builds.failed.push(p.clone);
+    }
+
+    Ok(builds)
 }
 
-fn build_one(
+/// Run `work` over `items` on up to `jobs` threads, handing each result to
+/// `done` in the order that the jobs finish. Unless `keep_going` is set, the
+/// first result considered `failed` stops any jobs that haven't yet started,
+/// and these are returned untouched.
+fn schedule<T, V, W, F, D>(
+    jobs: u32,
+    keep_going: bool,
+    items: Vec<T>,
+    work: W,
+    failed: F,
+    mut done: D,
+) -> Vec<T>
+where
+    T: Send,
+    V: Send,
+    W: Fn(&T) -> V + Sync,
+    F: Fn(&V) -> bool + Sync,
+    D: FnMut(T, V),
+{
+    let queue = Mutex::new(VecDeque::from(items));
+    let halted = AtomicBool::new(false);
+    let (tx, rx) = mpsc::channel();
+
+    std::thread::scope(|s| {
+        for _ in 0..jobs.max(1) {
+            let tx = tx.clone();
+            let queue = &queue;
+            let halted = &halted;
+            let work = &work;
+            let failed = &failed;
+
+            s.spawn(move || {
+                while halted.load(Ordering::SeqCst).not() {
+                    let Some(item) = queue.lock().ok().and_then(|mut q| q.pop_front()) else {
+                        break;
+                    };
+
+                    let v = work(&item);
+
+                    if keep_going.not() && failed(&v) {
+                        halted.store(true, Ordering::SeqCst);
+                    }
+
+                    if tx.send((item, v)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        // Otherwise the receiver would wait forever.
+        drop(tx);
+
+        for (item, v) in rx {
+            done(item, v);
+        }
+    });
+
+    queue.into_inner().map(Vec::from).unwrap_or_default()
+}
+
+/// Ready the build directory of a package, asking the user anything that needs
+/// to be asked along the way.
+fn prepare(
     fll: &FluentLanguageLoader,
     caches: &[&Path],
     env: &Env,
     alpm: &Alpm,
-    editor: &str,
-    requested: &HashSet<&str>,
+    tools: &Tools,
     clone: PathBuf,
-) -> Result<Built, Error> {
+) -> Result<Prepared, Error> {
     // Attempt a quick `git pull` to avoid the issue of building stale versions
     // of a package if the user forgot to `-Ay` recently.
-    if let Err(e) = aura_core::git::pull(&clone) {
+    if let Err(e) = aura_core::git::pull(&clone, &env.aur.network().git()) {
         warn!("{e}");
         yellow!(fll, "A-build-pull");
     }
@@ -156,7 +378,11 @@ fn build_one(
     let info = Srcinfo::parse_file(&path).map_err(|e| Error::Srcinfo(path, e))?;
     let base = info.base.pkgbase.as_str();
 
-    aura!(fll, "A-build-pkg", pkg = base.cyan().bold().to_string());
+    aura!(
+        fll,
+        "A-build-pkg",
+        pkg = base.highlight().bold().to_string()
+    );
 
     // --- Prepare the Build Directory --- //
     let build_dir = env.aur.build.join(base);
@@ -188,61 +414,84 @@ fn build_one(
         .map_err(Error::CopyBuildFiles)?;
 
     if env.aur.diff {
-        show_diffs(fll, env, &clone, base)?;
+        show_diffs(fll, env, tools.difftool.as_ref(), &clone, base)?;
     }
 
     if env.aur.hotedit {
-        overwrite_build_files(fll, env, editor, &build_dir, base)?;
+        overwrite_build_files(fll, env, tools.editor.as_ref(), &build_dir, base)?;
     }
 
     if env.aur.shellcheck {
         shellcheck(fll, env, &build_dir)?;
     }
 
-    let tarballs = {
-        // NOTE 2024-07-23 `pkgctl build` cannot be used as root, as it invokes
-        // `makepkg` internally. Nor can it be used by proxy through `nobody`,
-        // since the `$HOME` value of `nobody` is `/` and `pkgctl` attempts to
-        // `mkdir -p` from there, which it can't. Hacking `HOME=/tmp` also
-        // doesn't work, since the user of `pkgbuild build` needs to be a sudoer
-        // in order to download packages for the chroot.
-        let tarballs = if env.is_root.not() && env.aur.chroot.contains(base) {
-            let dbs = alpm.as_ref().syncdbs();
-            let aur_deps: Vec<_> = info
-                .base
-                .makedepends
-                .iter()
-                .flat_map(|av| av.vec.as_slice())
-                .chain(info.pkg.depends.iter().flat_map(|av| av.vec.as_slice()))
-                .filter(|s| dbs.find_satisfier(s.as_str()).is_none())
-                // `pop` fetches the last item in the Vec, which should be the
-                // most recent version of the package.
-                .filter_map(|p| aura_core::cache::matching(caches, p).pop())
-                .map(|(pp, _)| pp)
-                .collect();
-
-            pkgctl_build(&build_dir, &aur_deps)
+    // --- Check source signatures before anything is built --- //
+    if info.base.valid_pgp_keys.is_empty().not() {
+        if env.aur.verify {
+            verify_sources(fll, env, &info, &build_dir)?;
         } else {
-            makepkg(env, &build_dir)
+            yellow!(fll, "A-build-verify-skip", pkg = base.to_string());
+        }
+    }
+
+    // NOTE 2024-07-23 `pkgctl build` cannot be used as root, as it invokes
+    // `makepkg` internally. Nor can it be used by proxy through `nobody`,
+    // since the `$HOME` value of `nobody` is `/` and `pkgctl` attempts to
+    // `mkdir -p` from there, which it can't. Hacking `HOME=/tmp` also
+    // doesn't work, since the user of `pkgbuild build` needs to be a sudoer
+    // in order to download packages for the chroot.
+    let chroot_deps = (env.is_root.not() && env.aur.chroot.contains(base)).then(|| {
+        let dbs = alpm.as_ref().syncdbs();
+        info.base
+            .makedepends
+            .iter()
+            .flat_map(|av| av.vec.as_slice())
+            .chain(info.pkg.depends.iter().flat_map(|av| av.vec.as_slice()))
+            .filter(|s| dbs.find_satisfier(s.as_str()).is_none())
+            // `pop` fetches the last item in the Vec, which should be the
+            // most recent version of the package.
+            .filter_map(|p| aura_core::cache::matching(caches, p).pop())
+            .map(|(pp, _)| pp)
+            .collect()
+    });
+
+    Ok(Prepared {
+        clone,
+        info,
+        build_dir,
+        chroot_deps,
+    })
+}
+
+/// Build a prepared package and move its tarballs into the cache. If a `log`
+/// is given, the output of the build is captured there instead of being shown.
+fn compile(
+    env: &Env,
+    requested: &HashSet<&str>,
+    prepared: &Prepared,
+    mut log: Option<&mut Vec<u8>>,
+) -> Result<Built, Error> {
+    let Prepared {
+        clone,
+        info,
+        build_dir,
+        chroot_deps,
+    } = prepared;
+
+    let tarballs = {
+        let tarballs = match chroot_deps {
+            Some(deps) => pkgctl_build(build_dir, deps, log.as_deref_mut()),
+            None => makepkg(env, build_dir, log.as_deref_mut()),
         }?;
 
         for tb in tarballs.iter() {
             debug!("Built: {}", tb.as_path().display());
         }
 
-        // Filter according to the original packages asked for.
-        let interdeps = Interdeps::from_srcinfo(&info);
-
-        let special: Option<NESet<_>> = requested.iter().fold(None, |acc, req| {
-            let set = interdeps.transitive(req);
-
-            match (acc, set) {
-                (None, None) => None,
-                (None, Some(s)) => Some(s),
-                (Some(a), None) => Some(a),
-                (Some(a), Some(s)) => Some(a.union(&s).map(|x| *x).collect()),
-            }
-        });
+        // Filter according to the original packages asked for. Dependency
+        // resolution expects the same when checking for conflicts.
+        let interdeps = Interdeps::from_srcinfo(info);
+        let special = interdeps.wanted(requested);
 
         // FIXME 2024-07-06 I suspect this doesn't account for "debug" packages.
         let tars_to_copy = match special {
@@ -269,12 +518,35 @@ fn build_one(
         // NOTE 2024-07-27 As a matter of policy, this call failing should not
         // fail the entire rest of the build process, so we just catch it and
         // warn.
-        if let Err(e) = Command::new("rm").arg("-rf").arg(&build_dir).status() {
+        if let Err(e) = Command::new("rm").arg("-rf").arg(build_dir).status() {
             warn!("Removing build dir {} failed: {}", build_dir.display(), e);
         }
     }
 
-    Ok(Built { clone, tarballs })
+    Ok(Built {
+        clone: clone.clone(),
+        build_dir: build_dir.clone(),
+        tarballs,
+    })
+}
+
+/// Delete the `src/` and `pkg/` directories that `makepkg` left in a build
+/// directory, keeping the PKGBUILD and everything else.
+pub(crate) fn clean_sources(build_dir: &Path) {
+    for dir in ["src", "pkg"] {
+        let path = build_dir.join(dir);
+
+        // NOTE As with `clean`, this failing should not fail the rest of the
+        // installation, so we just warn. `rm` is used since these may belong
+        // to the build user.
+        if path.is_dir() {
+            match Command::new("rm").arg("-rf").arg(&path).status() {
+                Ok(s) if s.success() => {}
+                Ok(s) => warn!("Removing {} failed: {}", path.display(), s),
+                Err(e) => warn!("Removing {} failed: {}", path.display(), e),
+            }
+        }
+    }
 }
 
 /// The PKGBUILD author didn't specify any explicit in the `install` field, but
@@ -298,6 +570,7 @@ fn all_install_files(clone: &Path) -> Vec<PathBuf> {
 fn show_diffs(
     fll: &FluentLanguageLoader,
     env: &Env,
+    difftool: Option<&Program>,
     clone: &Path,
     pkgbase: &str,
 ) -> Result<(), Error> {
@@ -309,8 +582,16 @@ fn show_diffs(
     match hash_of_last_install(hashes, pkgbase) {
         Err(e) => warn!("Couldn't read latest hash of {}: {}", pkgbase, e),
         Ok(hash) => {
+            if env.aur.stat {
+                show_diff_stat(fll, clone, &hash)?;
+            }
+
             if proceed!(fll, env, "A-build-diff").is_some() {
-                aura_core::git::diff(clone, &hash).map_err(Error::GitDiff)?;
+                match difftool {
+                    Some(tool) => aura_core::git::diff_through(clone, &hash, &mut tool.command()),
+                    None => aura_core::git::diff(clone, &hash),
+                }
+                .map_err(Error::GitDiff)?;
                 proceed!(fll, env, "proceed").ok_or(Error::Cancelled)?;
             }
         }
@@ -319,6 +600,41 @@ fn show_diffs(
     Ok(())
 }
 
+/// Print a short summary of the lines changed in each build file.
+fn show_diff_stat(fll: &FluentLanguageLoader, clone: &Path, hash: &str) -> Result<(), Error> {
+    let stats = aura_core::git::diff_stat(clone, hash).map_err(Error::GitDiff)?;
+
+    if stats.is_empty() {
+        aura!(fll, "A-build-diff-none");
+    } else {
+        let (ins, del) = stats
+            .iter()
+            .fold((0, 0), |(i, d), s| (i + s.insertions, d + s.deletions));
+        let longest = stats.iter().map(|s| s.file.len()).max().unwrap_or(0);
+
+        for s in stats.iter() {
+            println!(
+                " {:w$} | {} {}",
+                s.file,
+                format!("+{}", s.insertions).success(),
+                format!("-{}", s.deletions).error(),
+                w = longest
+            );
+        }
+
+        let msg = fl!(
+            fll,
+            "A-build-diff-stat",
+            files = stats.len(),
+            ins = ins,
+            del = del
+        );
+        aln!(msg);
+    }
+
+    Ok(())
+}
+
 /// What AUR repo git hash is associated with the last time a given package was
 /// installed?
 fn hash_of_last_install(hashes: &Path, pkgbase: &str) -> Result<String, std::io::Error> {
@@ -326,6 +642,132 @@ fn hash_of_last_install(hashes: &Path, pkgbase: &str) -> Result<String, std::io:
     std::fs::read_to_string(path).map(|s| s.trim().to_string())
 }
 
+/// Make sure that every PGP key the PKGBUILD trusts is in the keyring, offering
+/// to import any that aren't, then have `makepkg` download the sources and
+/// check their signatures. Nothing is built if this fails.
+fn verify_sources(
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    info: &Srcinfo,
+    build_d: &Path,
+) -> Result<(), Error> {
+    let base = info.base.pkgbase.as_str();
+    let user = build_user(env);
+
+    let missing: Vec<_> = info
+        .base
+        .valid_pgp_keys
+        .iter()
+        .filter(|key| {
+            as_user(env, user, "gpg")
+                .arg("--list-keys")
+                .arg(key)
+                .output()
+                .map(|o| o.status.success().not())
+                .unwrap_or(true)
+        })
+        .collect();
+
+    if missing.is_empty().not() {
+        for key in missing.iter() {
+            let msg = fl!(fll, "A-build-key-missing", pkg = base, key = key.as_str());
+            aln!(msg.warning());
+        }
+
+        proceed!(fll, env, "A-build-key-import")
+            .ok_or_else(|| Error::MissingKeys(base.to_string()))?;
+
+        for key in missing {
+            as_user(env, user, "gpg")
+                .arg("--recv-keys")
+                .arg(key)
+                .status()
+                .map_err(|_| Error::KeyImport(key.clone()))?
+                .success()
+                .then_some(())
+                .ok_or_else(|| Error::KeyImport(key.clone()))?;
+        }
+    }
+
+    if let Some(u) = user {
+        user_permissions(build_d, u)?;
+    }
+
+    debug!(
+        "Verifying the sources of {} within: {}",
+        base,
+        build_d.display()
+    );
+
+    as_user(env, user, "makepkg")
+        .arg("--verifysource")
+        .current_dir(build_d)
+        .status()
+        .map_err(|_| Error::Verify(base.to_string()))?
+        .success()
+        .then_some(())
+        .ok_or_else(|| Error::Verify(base.to_string()))?;
+
+    let signed = signed_sources(info);
+
+    if signed.is_empty() {
+        yellow!(fll, "A-build-verified-none", pkg = base.to_string());
+    } else {
+        let msg = fl!(
+            fll,
+            "A-build-verified",
+            pkg = base,
+            files = signed.join(", ")
+        );
+        aln!(msg.success());
+    }
+
+    Ok(())
+}
+
+/// The sources whose PGP signatures `makepkg` checks: those with a detached
+/// `.sig`, `.sign`, or `.asc` file among the sources, and VCS sources marked
+/// with `?signed`.
+fn signed_sources(info: &Srcinfo) -> Vec<String> {
+    let mut signed: Vec<_> = info
+        .base
+        .source
+        .iter()
+        .flat_map(|av| av.vec.iter())
+        .filter_map(|s| signed_source(s))
+        .collect();
+
+    signed.sort();
+    signed.dedup();
+    signed
+}
+
+/// The local name of the source that a single `source` entry vouches for, if
+/// it's a signature.
+fn signed_source(source: &str) -> Option<String> {
+    let (name, url) = match source.split_once("::") {
+        Some((name, url)) => (Some(name), url),
+        None => (None, source),
+    };
+
+    let (url, fragment) = url.split_once('#').unwrap_or((url, ""));
+
+    if fragment.ends_with("?signed") || url.ends_with("?signed") {
+        let url = url.trim_end_matches("?signed");
+        let name = name.unwrap_or_else(|| url.rsplit('/').next().unwrap_or(url));
+
+        return Some(name.trim_end_matches(".git").to_string());
+    }
+
+    let name = name.unwrap_or_else(|| url.rsplit('/').next().unwrap_or(url));
+
+    [".sig", ".sign", ".asc"]
+        .into_iter()
+        .find_map(|ext| name.strip_suffix(ext))
+        .filter(|n| n.is_empty().not())
+        .map(String::from)
+}
+
 fn shellcheck(fll: &FluentLanguageLoader, env: &Env, build_d: &Path) -> Result<(), Error> {
     let status = Command::new("shellcheck")
         .current_dir(build_d)
@@ -339,7 +781,7 @@ fn shellcheck(fll: &FluentLanguageLoader, env: &Env, build_d: &Path) -> Result<(
             proceed!(fll, env, "proceed").ok_or(Error::Cancelled)?;
         }
         Err(e) => {
-            let msg = e.to_string().yellow();
+            let msg = e.to_string().warning();
             aln!(msg);
             proceed!(fll, env, "proceed").ok_or(Error::Cancelled)?;
         }
@@ -352,7 +794,7 @@ fn shellcheck(fll: &FluentLanguageLoader, env: &Env, build_d: &Path) -> Result<(
 fn overwrite_build_files(
     fll: &FluentLanguageLoader,
     env: &Env,
-    editor: &str,
+    editor: Option<&Program>,
     build_d: &Path,
     pkgbase: &str,
 ) -> Result<(), Error> {
@@ -385,8 +827,13 @@ fn overwrite_build_files(
     Ok(())
 }
 
-fn edit(editor: &str, file: PathBuf) -> Result<(), Error> {
-    Command::new(editor)
+fn edit(editor: Option<&Program>, file: PathBuf) -> Result<(), Error> {
+    let Some(editor) = editor else {
+        return Err(Error::EditFail(file));
+    };
+
+    editor
+        .command()
         .arg(&file)
         .status()
         .map_err(|_| Error::EditFail(file.clone()))?
@@ -397,7 +844,25 @@ fn edit(editor: &str, file: PathBuf) -> Result<(), Error> {
     Ok(())
 }
 
-fn pkgctl_build(within: &Path, deps: &[PkgPath]) -> Result<Vec<PkgPath>, Error> {
+/// Run a command to completion, reporting whether it succeeded. If a `log` is
+/// given, the command's output is captured there, its stderr following its
+/// stdout.
+fn run(cmd: &mut Command, log: Option<&mut Vec<u8>>) -> std::io::Result<bool> {
+    match log {
+        None => cmd.status().map(|s| s.success()),
+        Some(log) => cmd.output().map(|o| {
+            log.extend(o.stdout);
+            log.extend(o.stderr);
+            o.status.success()
+        }),
+    }
+}
+
+fn pkgctl_build(
+    within: &Path,
+    deps: &[PkgPath],
+    log: Option<&mut Vec<u8>>,
+) -> Result<Vec<PkgPath>, Error> {
     debug!("Running `pkgctl build` within {}", within.display());
     debug!("AUR deps to inject: {:?}", deps);
 
@@ -409,25 +874,40 @@ fn pkgctl_build(within: &Path, deps: &[PkgPath]) -> Result<Vec<PkgPath>, Error>
         cmd.arg(dep.as_path());
     }
 
-    cmd.current_dir(within)
-        .status()
+    run(cmd.current_dir(within), log)
         .map_err(|_| Error::PkgctlBuild)?
-        .success()
         .then_some(())
         .ok_or(Error::PkgctlBuild)?;
 
     tarball_paths(None, within)
 }
 
-/// Build each package specified by the `PKGBUILD` and yield a list of the built
-/// tarballs.
-fn makepkg(env: &Env, within: &Path) -> Result<Vec<PkgPath>, Error> {
-    let user = match env.aur.builduser.as_deref() {
+/// The user that `makepkg` should be run as, if not the current one.
+fn build_user(env: &Env) -> Option<&str> {
+    match env.aur.builduser.as_deref() {
         Some(u) => Some(u),
         // Assumption: The `nobody` user always exists.
         None if env.is_root => Some("nobody"),
         None => None,
-    };
+    }
+}
+
+/// A command that runs as the given user, if any.
+fn as_user(env: &Env, user: Option<&str>, program: &str) -> Command {
+    match user {
+        Some(u) => {
+            let mut c = Command::new(env.sudo());
+            c.arg("-u").arg(u).arg(program);
+            c
+        }
+        None => Command::new(program),
+    }
+}
+
+/// Build each package specified by the `PKGBUILD` and yield a list of the built
+/// tarballs.
+fn makepkg(env: &Env, within: &Path, log: Option<&mut Vec<u8>>) -> Result<Vec<PkgPath>, Error> {
+    let user = build_user(env);
 
     let mut cmd = if let Some(u) = user {
         user_permissions(within, u)?;
@@ -460,19 +940,17 @@ fn makepkg(env: &Env, within: &Path) -> Result<Vec<PkgPath>, Error> {
         cmd.arg("--skipinteg");
     }
 
-    if env.aur.skippgpcheck {
+    if env.aur.skippgpcheck || env.aur.verify.not() {
         cmd.arg("--skippgpcheck");
     }
 
     debug!("Running makepkg within: {}", within.display());
 
-    cmd.current_dir(within)
-        .status()
+    run(cmd.current_dir(within), log)
         // FIXME Tue Jun 21 14:00:15 2022
         //
         // This should probably collect the error.
         .map_err(|_| Error::Makepkg)?
-        .success()
         .then_some(())
         .ok_or(Error::Makepkg)?;
 
@@ -576,7 +1054,9 @@ fn build_check(
             red!(fll, "A-build-fail");
             eprintln!("\n  {}\n", e.localise(fll));
 
-            if is_single || proceed!(fll, env, "A-build-continue").is_none() {
+            if env.aur.keep_going {
+                Ok(None)
+            } else if is_single || proceed!(fll, env, "A-build-continue").is_none() {
                 Err(Error::Cancelled)
             } else {
                 Ok(None)
@@ -584,3 +1064,96 @@ fn build_check(
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::TempDir;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    #[test]
+    fn cleaned_sources() {
+        let build_dir = TempDir::new("clean");
+        std::fs::create_dir_all(build_dir.join("src/aura-4.0.2")).unwrap();
+        std::fs::create_dir_all(build_dir.join("pkg/aura")).unwrap();
+        std::fs::write(build_dir.join("PKGBUILD"), "pkgname=aura\n").unwrap();
+        std::fs::write(build_dir.join("aura-4.0.2-1-x86_64.pkg.tar.zst"), "").unwrap();
+
+        clean_sources(&build_dir);
+
+        assert!(build_dir.join("src").exists().not());
+        assert!(build_dir.join("pkg").exists().not());
+        assert!(build_dir.join("PKGBUILD").is_file());
+        assert!(build_dir.join("aura-4.0.2-1-x86_64.pkg.tar.zst").is_file());
+
+        // There being nothing to clean is fine.
+        clean_sources(&build_dir);
+    }
+
+    #[test]
+    fn signed_source_names() {
+        let info = Srcinfo::parse_file("tests/signed.SRCINFO").unwrap();
+        let expected = vec!["bin-x86_64.zst", "extra.tar.xz", "lib", "thing-1.0.tar.gz"];
+
+        assert_eq!(expected, signed_sources(&info));
+    }
+
+    #[test]
+    fn failure_cancels_pending() {
+        let mut seen = Vec::new();
+        let pending = schedule(
+            1,
+            false,
+            vec![1, 2, 3, 4],
+            |n| *n,
+            |n| *n == 2,
+            |n, _| seen.push(n),
+        );
+
+        assert_eq!(vec![1, 2], seen);
+        assert_eq!(vec![3, 4], pending);
+    }
+
+    #[test]
+    fn keep_going_runs_everything() {
+        let mut seen = Vec::new();
+        let pending = schedule(
+            2,
+            true,
+            vec![1, 2, 3, 4],
+            |n| *n,
+            |n| *n == 2,
+            |n, _| seen.push(n),
+        );
+        seen.sort();
+
+        assert_eq!(vec![1, 2, 3, 4], seen);
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn bounded_concurrency() {
+        let running = AtomicUsize::new(0);
+        let most = AtomicUsize::new(0);
+        let mut done = 0;
+
+        let pending = schedule(
+            3,
+            false,
+            (0..9).collect(),
+            |_: &u32| {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                most.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(20));
+                running.fetch_sub(1, Ordering::SeqCst);
+            },
+            |_| false,
+            |_, _| done += 1,
+        );
+
+        assert_eq!(9, done);
+        assert!(pending.is_empty());
+        assert!(most.load(Ordering::SeqCst) <= 3);
+    }
+}
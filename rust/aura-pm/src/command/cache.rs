@@ -1,16 +1,23 @@
 //! All functionality involving the `-C` command.
 
+use crate::aln;
 use crate::aura;
 use crate::env::Env;
+use crate::error::Exited;
 use crate::error::Nested;
 use crate::green;
 use crate::localization::Localised;
 use crate::proceed;
+use crate::theme::Role;
+use crate::theme::Themed;
 use crate::utils::PathStr;
 use crate::utils::NOTHING;
 use crate::yellow;
+use aura_core::cache::CacheInfo;
 use aura_core::cache::CacheSize;
 use aura_core::cache::PkgPath;
+use aura_core::cache::Verification;
+use aura_core::vercmp::vercmp;
 use colored::*;
 use i18n_embed::fluent::FluentLanguageLoader;
 use i18n_embed::LanguageLoader;
@@ -19,17 +26,22 @@ use itertools::Itertools;
 use linya::Progress;
 use log::debug;
 use log::error;
+use r2d2::Pool;
 use r2d2_alpm::Alpm;
+use r2d2_alpm::AlpmManager;
 use rayon::prelude::*;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::ffi::OsString;
 use std::io::BufWriter;
 use std::io::Write;
+use std::ops::Not;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::Duration;
+use std::time::SystemTime;
 use time::macros::format_description;
 use time::OffsetDateTime;
 use ubyte::ToByteUnit;
@@ -47,6 +59,10 @@ pub(crate) enum Error {
     CurrDir(std::io::Error),
     Mkdir(PathBuf, std::io::Error),
     Date(time::error::Format),
+    NoSpace(PathBuf, u64, u64),
+    Mismatch(Vec<PathBuf>),
+    /// Files that couldn't be copied, and why.
+    Copy(Vec<(PathBuf, std::io::Error)>),
 }
 
 impl Nested for Error {
@@ -64,6 +80,17 @@ impl Nested for Error {
             Error::CurrDir(e) => error!("{e}"),
             Error::Mkdir(_, e) => error!("{e}"),
             Error::Date(e) => error!("{e}"),
+            Error::NoSpace(_, _, _) => {}
+            Error::Mismatch(ps) => {
+                for p in ps {
+                    error!("{}", p.display());
+                }
+            }
+            Error::Copy(fs) => {
+                for (p, e) in fs {
+                    error!("{}: {e}", p.display());
+                }
+            }
         }
     }
 }
@@ -83,6 +110,37 @@ impl Localised for Error {
             Error::CurrDir(_) => fl!(fll, "C-b-curr"),
             Error::Mkdir(p, _) => fl!(fll, "dir-mkdir", dir = p.utf8()),
             Error::Date(_) => fl!(fll, "err-time-format"),
+            Error::NoSpace(p, needed, free) => fl!(
+                fll,
+                "C-b-space",
+                target = p.utf8(),
+                needed = format!("{}", needed.bytes()),
+                free = format!("{}", free.bytes())
+            ),
+            Error::Mismatch(ps) => fl!(fll, "C-b-mismatch", n = ps.len()),
+            Error::Copy(fs) => fl!(fll, "C-b-failed", n = fs.len()),
+        }
+    }
+}
+
+impl Exited for Error {
+    fn exit_code(&self) -> Option<u8> {
+        match self {
+            Error::Readline(_) => None,
+            Error::Pacman(e) => e.exit_code(),
+            Error::Cancelled => None,
+            Error::NoPackages => None,
+            Error::NothingToDo => None,
+            Error::AlreadyExists(_) => None,
+            Error::Delete(_) => None,
+            Error::ReadDir(_) => None,
+            Error::Stdout(_) => None,
+            Error::CurrDir(_) => None,
+            Error::Mkdir(_, _) => None,
+            Error::Date(_) => None,
+            Error::NoSpace(_, _, _) => None,
+            Error::Mismatch(_) => None,
+            Error::Copy(_) => None,
         }
     }
 }
@@ -133,7 +191,7 @@ fn downgrade_one(
     tarballs.sort_by(|a, b| b.as_package().cmp(a.as_package()));
     let digits = 1 + (tarballs.len() / 10);
 
-    let pkg = package.bold().cyan().to_string();
+    let pkg = package.bold().highlight().to_string();
     aura!(fll, "C-downgrade-which", pkg = pkg);
 
     for (i, pp) in tarballs.iter().enumerate() {
@@ -149,17 +207,26 @@ fn downgrade_one(
 pub(crate) fn invalid(
     env: &Env,
     fll: &FluentLanguageLoader,
-    alpm: &Alpm,
+    pool: &Pool<AlpmManager>,
     caches: &[&Path],
+    jobs: u32,
 ) -> Result<(), Error> {
     aura!(fll, "C-t-invalids");
 
     let elevation = env.sudo();
+    let tarballs: Vec<_> = aura_core::cache::package_paths(caches).collect();
 
     // FIXME Thu Jan 27 2022 Use `Validated` here.
-    aura_core::cache::package_paths(caches)
-        .filter(|pp| !aura_core::is_valid_package(alpm, pp.as_path()))
-        .for_each(|pp| {
+    let swept = sweep(jobs, tarballs, |pp| {
+        pool.get()
+            .ok()
+            .map(|alpm| aura_core::is_valid_package(&*alpm, pp.as_path()))
+    });
+
+    checked(fll, swept)
+        .into_iter()
+        .filter(|(_, valid)| !valid)
+        .for_each(|(pp, _)| {
             let _ = pp.sudo_remove_with_sig(elevation); // TODO Better handling.
         });
 
@@ -167,6 +234,209 @@ pub(crate) fn invalid(
     Ok(())
 }
 
+/// Run `check` over every item on a pool of `jobs` threads. The results keep
+/// the order of `items`, regardless of the level of parallelism.
+pub(crate) fn sweep<T, V, F>(jobs: u32, items: Vec<T>, check: F) -> Vec<(T, V)>
+where
+    T: Send,
+    V: Send,
+    F: Fn(&T) -> V + Sync,
+{
+    let run = || {
+        items
+            .into_par_iter()
+            .map(|t| {
+                let v = check(&t);
+                (t, v)
+            })
+            .collect()
+    };
+
+    match rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs as usize)
+        .build()
+    {
+        Ok(threads) => threads.install(run),
+        Err(_) => run(),
+    }
+}
+
+/// Name the tarballs that couldn't be checked at all, as when no ALPM handle
+/// became free in time, and yield the rest. Whatever is set aside here is never
+/// deleted.
+fn checked<V>(fll: &FluentLanguageLoader, swept: Vec<(PkgPath, Option<V>)>) -> Vec<(PkgPath, V)> {
+    let (checked, unchecked) = set_aside(swept);
+
+    if unchecked.is_empty().not() {
+        yellow!(fll, "C-unchecked", n = unchecked.len());
+
+        for pp in unchecked {
+            println!(" {}", pp.as_path().display());
+        }
+    }
+
+    checked
+}
+
+/// Split swept tarballs into those with a verdict, and those without.
+fn set_aside<V>(swept: Vec<(PkgPath, Option<V>)>) -> (Vec<(PkgPath, V)>, Vec<PkgPath>) {
+    let mut checked = Vec::new();
+    let mut unchecked = Vec::new();
+
+    for (pp, verdict) in swept {
+        match verdict {
+            Some(v) => checked.push((pp, v)),
+            None => unchecked.push(pp),
+        }
+    }
+
+    (checked, unchecked)
+}
+
+/// Totals of each [`Verification`] outcome across the caches.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Tally {
+    valid: usize,
+    corrupt: usize,
+    unsigned: usize,
+    bad_signature: usize,
+}
+
+impl Tally {
+    fn new<'a, I>(verdicts: I) -> Tally
+    where
+        I: IntoIterator<Item = &'a Verification>,
+    {
+        let mut tally = Tally::default();
+
+        for v in verdicts {
+            match v {
+                Verification::Valid => tally.valid += 1,
+                Verification::Corrupt => tally.corrupt += 1,
+                Verification::Unsigned => tally.unsigned += 1,
+                Verification::BadSignature => tally.bad_signature += 1,
+            }
+        }
+
+        tally
+    }
+}
+
+/// Check every tarball against its checksum and signature, reporting any that
+/// are corrupt, unsigned, or badly signed. Nothing is deleted unless `remove` is
+/// set, in which case corrupt and badly signed tarballs are removed.
+pub(crate) fn verify(
+    env: &Env,
+    fll: &FluentLanguageLoader,
+    pool: &Pool<AlpmManager>,
+    caches: &[&Path],
+    jobs: u32,
+    remove: bool,
+) -> Result<(), Error> {
+    let mut tarballs: Vec<_> = aura_core::cache::package_paths(caches).collect();
+    tarballs.sort();
+
+    aura!(fll, "C-v-checking", n = tarballs.len());
+
+    let swept = sweep(jobs, tarballs, |pp| {
+        pool.get()
+            .ok()
+            .map(|alpm| aura_core::cache::verify(&*alpm, pp))
+    });
+    let checked = checked(fll, swept);
+
+    report(fll, &checked).map_err(Error::Stdout)?;
+
+    if remove {
+        let invalid: Vec<_> = checked
+            .into_iter()
+            .filter(|(_, v)| v.is_invalid())
+            .map(|(pp, _)| pp)
+            .collect();
+
+        if invalid.is_empty() {
+            return Ok(());
+        }
+
+        proceed!(fll, env, "C-v-remove").ok_or(Error::Cancelled)?;
+
+        let elevation = env.sudo();
+
+        for pp in invalid {
+            let path = pp.as_path().to_path_buf();
+            pp.sudo_remove_with_sig(elevation)
+                .map_err(|_| Error::Delete(path))?;
+        }
+
+        green!(fll, "common-done");
+    }
+
+    Ok(())
+}
+
+fn report(
+    fll: &FluentLanguageLoader,
+    checked: &[(PkgPath, Verification)],
+) -> Result<(), std::io::Error> {
+    let mut w = BufWriter::new(std::io::stdout());
+    let flagged: Vec<_> = checked
+        .iter()
+        .filter(|(_, v)| *v != Verification::Valid)
+        .map(|(pp, v)| (pp, verification_label(fll, *v), verification_color(*v)))
+        .collect();
+    let width = flagged
+        .iter()
+        .map(|(_, l, _)| l.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    for (pp, label, color) in flagged {
+        // Pad before colouring, such that escape codes don't affect the width.
+        let padded = format!("{:w$}", label, w = width);
+        writeln!(w, "  [{}] {}", padded.color(color), pp.as_path().display())?;
+    }
+
+    let tally = Tally::new(checked.iter().map(|(_, v)| v));
+    let pairs = vec![
+        (
+            verification_label(fll, Verification::Valid),
+            tally.valid.to_string().success(),
+        ),
+        (
+            verification_label(fll, Verification::Corrupt),
+            tally.corrupt.to_string().error(),
+        ),
+        (
+            verification_label(fll, Verification::BadSignature),
+            tally.bad_signature.to_string().error(),
+        ),
+        (
+            verification_label(fll, Verification::Unsigned),
+            tally.unsigned.to_string().warning(),
+        ),
+    ];
+
+    crate::utils::info(&mut w, fll.current_language(), &pairs)
+}
+
+fn verification_label(fll: &FluentLanguageLoader, v: Verification) -> String {
+    match v {
+        Verification::Valid => fl!(fll, "C-v-valid"),
+        Verification::Corrupt => fl!(fll, "C-v-corrupt"),
+        Verification::Unsigned => fl!(fll, "C-v-unsigned"),
+        Verification::BadSignature => fl!(fll, "C-v-bad-sig"),
+    }
+}
+
+fn verification_color(v: Verification) -> Color {
+    match v {
+        Verification::Valid => crate::theme::color(Role::Success),
+        Verification::Corrupt => crate::theme::color(Role::Error),
+        Verification::Unsigned => crate::theme::color(Role::Warning),
+        Verification::BadSignature => crate::theme::color(Role::Error),
+    }
+}
+
 /// Print the contents of the package caches.
 pub(crate) fn list(caches: &[&Path]) -> Result<(), Error> {
     let rds = caches
@@ -190,6 +460,90 @@ pub(crate) fn list(caches: &[&Path]) -> Result<(), Error> {
     Ok(())
 }
 
+/// Print one tab-separated record per tarball in the cache, sorted by path.
+/// The fields, in this fixed order, are:
+///
+/// 1. Package name
+/// 2. Version
+/// 3. Size in bytes
+/// 4. Full path
+///
+/// Any future fields will only be appended, so that parsers can rely on these.
+pub(crate) fn list_records(caches: &[&Path]) -> Result<(), Error> {
+    let mut w = BufWriter::new(std::io::stdout());
+    let mut pps: Vec<_> = aura_core::cache::package_paths(caches).collect();
+    pps.sort_by(|a, b| a.as_path().cmp(b.as_path()));
+
+    for pp in pps {
+        let size = pp.as_path().metadata().map(|m| m.len()).unwrap_or(0);
+        writeln!(w, "{}", tarball_record(&pp, size)).map_err(Error::Stdout)?;
+    }
+
+    Ok(())
+}
+
+fn tarball_record(pp: &PkgPath, size: u64) -> String {
+    let p = pp.as_package();
+    format!(
+        "{}\t{}\t{}\t{}",
+        p.name,
+        p.version,
+        size,
+        pp.as_path().display()
+    )
+}
+
+/// Print one tab-separated record per given package that has tarballs in the
+/// cache. The fields, in this fixed order, are:
+///
+/// 1. Package name
+/// 2. Latest cached version
+/// 3. Installed version, or `-` if not installed
+/// 4. Creation time of the latest tarball, in seconds since the Unix epoch
+/// 5. Whether the latest tarball has a signature file: `true` or `false`
+/// 6. Size of the latest tarball in bytes
+/// 7. All cached versions, newest first, separated by commas
+///
+/// Any future fields will only be appended, so that parsers can rely on these.
+pub(crate) fn info_records(
+    alpm: &Alpm,
+    caches: &[&Path],
+    packages: Vec<String>,
+) -> Result<(), Error> {
+    let db = alpm.as_ref().localdb();
+    let mut w = BufWriter::new(std::io::stdout());
+
+    for ci in packages
+        .iter()
+        .filter_map(|p| aura_core::cache::info(caches, p).ok())
+        .flatten()
+    {
+        let installed = db.pkg(ci.name.as_str()).ok().map(|p| p.version().as_str());
+        writeln!(w, "{}", info_record(&ci, installed)).map_err(Error::Stdout)?;
+    }
+
+    Ok(())
+}
+
+fn info_record(ci: &CacheInfo, installed: Option<&str>) -> String {
+    let created = ci
+        .created
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        ci.name,
+        ci.version,
+        installed.unwrap_or("-"),
+        created,
+        ci.signature,
+        ci.size,
+        ci.available.join(",")
+    )
+}
+
 /// Print cache data for given packages.
 pub(crate) fn info(
     fll: &FluentLanguageLoader,
@@ -219,15 +573,15 @@ pub(crate) fn info(
             .format(&fmt)
             .map_err(Error::Date)?;
         let sig_yes_no = if ci.signature {
-            fl!(fll, "common-yes").green().bold()
+            fl!(fll, "common-yes").success().bold()
         } else {
-            fl!(fll, "common-no").yellow()
+            fl!(fll, "common-no").warning()
         };
         let is_in = if let Ok(pkg) = db.pkg(ci.name.as_str()) {
-            if ci.version == pkg.version().as_str() {
-                format!("[{}]", inst).cyan().bold()
+            if vercmp(&ci.version, pkg.version().as_str()).is_eq() {
+                format!("[{}]", inst).highlight().bold()
             } else {
-                format!("[{}: {}]", inst, pkg.version()).yellow().bold()
+                format!("[{}: {}]", inst, pkg.version()).warning().bold()
             }
         } else {
             "".normal()
@@ -249,14 +603,123 @@ pub(crate) fn info(
     Ok(())
 }
 
+/// A single cached version of some package, as listed by `--versions`.
+#[derive(Debug, PartialEq)]
+struct CachedVersion {
+    version: String,
+    size: u64,
+    /// Is this the version currently installed?
+    installed: bool,
+}
+
+/// Print every cached version of the given packages, newest first, marking the
+/// one that's installed. Nothing is downgraded.
+pub(crate) fn versions(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    caches: &[&Path],
+    packages: &[String],
+) -> Result<(), Error> {
+    let db = alpm.as_ref().localdb();
+    let inst = format!("[{}]", fl!(fll, "C-i-installed"));
+    let mut w = BufWriter::new(std::io::stdout());
+
+    for p in packages {
+        let tarballs = aura_core::cache::matching(caches, p)
+            .into_iter()
+            .map(|(pp, meta)| (pp, meta.len()));
+        let installed = db.pkg(p.as_str()).ok().map(|p| p.version().as_str());
+        let cached = cached_versions(tarballs, installed);
+
+        if cached.is_empty() {
+            w.flush().map_err(Error::Stdout)?;
+            yellow!(fll, "C-versions-none", pkg = p.as_str());
+            continue;
+        }
+
+        let sizes: Vec<_> = cached.iter().map(|c| c.size.bytes().to_string()).collect();
+        let longest = cached.iter().map(|c| c.version.len()).max().unwrap_or(0);
+        let widest = sizes.iter().map(|s| s.len()).max().unwrap_or(0);
+
+        writeln!(w, "{}", p.bold().highlight()).map_err(Error::Stdout)?;
+
+        for (c, size) in cached.iter().zip(sizes) {
+            let mark = if c.installed { inst.highlight().bold() } else { "".normal() };
+
+            writeln!(
+                w,
+                "  {:l$}  {:>s$}  {}",
+                c.version,
+                size,
+                mark,
+                l = longest,
+                s = widest
+            )
+            .map_err(Error::Stdout)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The distinct cached versions among some tarballs of a single package, newest
+/// first. The same version kept in several caches is only listed once.
+fn cached_versions<I>(tarballs: I, installed: Option<&str>) -> Vec<CachedVersion>
+where
+    I: IntoIterator<Item = (PkgPath, u64)>,
+{
+    let mut tarballs: Vec<_> = tarballs.into_iter().collect();
+    tarballs.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    tarballs
+        .into_iter()
+        .map(|(pp, size)| {
+            let version = pp.as_package().version.to_string();
+            let installed = installed.is_some_and(|i| vercmp(i, &version).is_eq());
+
+            CachedVersion {
+                version,
+                size,
+                installed,
+            }
+        })
+        .dedup_by(|a, b| a.version == b.version)
+        .collect()
+}
+
 /// Print all package filepaths from the cache that match some search term.
-pub(crate) fn search(caches: &[&Path], term: &str) -> Result<(), Error> {
-    for file in aura_core::cache::search(caches, term) {
-        println!("{}", file.display());
+pub(crate) fn search(caches: &[&Path], term: &str, fuzzy: bool) -> Result<(), Error> {
+    if fuzzy {
+        for file in fuzzy_search(caches, term) {
+            println!("{}", file.display());
+        }
+    } else {
+        for file in aura_core::cache::search(caches, term) {
+            println!("{}", file.display());
+        }
     }
+
     Ok(())
 }
 
+/// Cache files whose package names are close to the term, best matches first.
+fn fuzzy_search(caches: &[&Path], term: &str) -> Vec<PathBuf> {
+    aura_core::read_dirs(caches)
+        .filter_map(|r| r.ok())
+        .map(|de| de.path())
+        .filter_map(|path| {
+            let name = PkgPath::new(path.clone())
+                .map(|pp| pp.as_package().name.to_string())
+                .or_else(|| path.file_name().and_then(|s| s.to_str()).map(String::from))?;
+            let score = aura_core::fuzzy::score(term, &name)?;
+
+            Some((score, path))
+        })
+        .sorted()
+        .map(|(_, path)| path)
+        .collect()
+}
+
 /// Delete all but `keep`-many old tarballs for each package in the cache.
 pub(crate) fn clean(env: &Env, fll: &FluentLanguageLoader, keep: usize) -> Result<(), Error> {
     let caches = env.caches();
@@ -288,6 +751,58 @@ pub(crate) fn clean(env: &Env, fll: &FluentLanguageLoader, keep: usize) -> Resul
     Ok(())
 }
 
+/// Delete every tarball (and its signature) last modified more than `age` ago.
+/// With `dry_run`, only list what would be deleted.
+pub(crate) fn clean_older_than(
+    env: &Env,
+    fll: &FluentLanguageLoader,
+    age: Duration,
+    dry_run: bool,
+) -> Result<(), Error> {
+    let caches = env.caches();
+    let cutoff = SystemTime::now()
+        .checked_sub(age)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+
+    let mut old: Vec<(PkgPath, u64)> = aura_core::cache::package_paths(&caches)
+        .filter_map(|pp| {
+            let meta = pp.as_path().metadata().ok()?;
+            let modified = meta.modified().ok()?;
+            (modified < cutoff).then_some((pp, meta.len()))
+        })
+        .collect();
+    old.sort_by(|(p0, _), (p1, _)| p0.cmp(p1));
+
+    if old.is_empty() {
+        return Err(Error::NothingToDo);
+    }
+
+    for (pp, _) in old.iter() {
+        println!("{}", pp.as_path().display());
+    }
+
+    let bytes: u64 = old.iter().map(|(_, size)| size).sum();
+    let human = format!("{}", bytes.bytes());
+    aura!(fll, "C-o-total", n = old.len(), bytes = human);
+
+    if dry_run {
+        return Ok(());
+    }
+
+    proceed!(fll, env, "proceed").ok_or(Error::Cancelled)?;
+
+    let elevation = env.sudo();
+
+    for (pp, _) in old {
+        let path = pp.as_path().to_path_buf();
+        pp.sudo_remove_with_sig(elevation)
+            .map_err(|_| Error::Delete(path))?;
+    }
+
+    green!(fll, "C-c-freed", bytes = human);
+    Ok(())
+}
+
 /// Delete only those tarballs which aren't present in a snapshot.
 pub(crate) fn clean_not_saved(fll: &FluentLanguageLoader, env: &Env) -> Result<(), Error> {
     let caches = env.caches();
@@ -358,8 +873,15 @@ pub(crate) fn refresh(env: &Env, fll: &FluentLanguageLoader, alpm: &Alpm) -> Res
     Ok(())
 }
 
-/// Backup your package caches to a given directory.
-pub(crate) fn backup(fll: &FluentLanguageLoader, env: &Env, target: &Path) -> Result<(), Error> {
+/// Backup your package caches to a given directory. Tarballs already present
+/// there with a matching size and modification time are skipped, and with
+/// `verify`, every backed-up file is then compared against its original.
+pub(crate) fn backup(
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    target: &Path,
+    verify: bool,
+) -> Result<(), Error> {
     let sources = env.caches();
 
     // The full, absolute path to copy files to.
@@ -393,38 +915,158 @@ pub(crate) fn backup(fll: &FluentLanguageLoader, env: &Env, target: &Path) -> Re
     // Proceed if the user accepts.
     proceed!(fll, env, "proceed").ok_or(Error::Cancelled)?;
 
-    copy(&sources, &full, cache_size.files)
-}
-
-/// Copy all the cache files concurrently.
-fn copy(sources: &[&Path], target: &Path, file_count: usize) -> Result<(), Error> {
-    debug!("Begin cache copying.");
-
-    // TODO Change the bar style.
-    // A progress bar to display the copying progress.
-    let pb = Arc::new(Mutex::new(Progress::new()));
-    let bar = pb.lock().unwrap().bar(file_count, "File Copying"); // TODO Localize.
-
     // Silently succeeds if the directory already exists.
-    std::fs::create_dir_all(target).map_err(|e| Error::Mkdir(target.to_path_buf(), e))?;
+    std::fs::create_dir_all(&full).map_err(|e| Error::Mkdir(full.clone(), e))?;
 
-    aura_core::read_dirs(sources)
+    let pairs: Vec<(PathBuf, PathBuf)> = aura_core::read_dirs(&sources)
         .filter_map(|entry| entry.ok())
         .filter_map(|entry| {
             let from = entry.path();
-            entry.path().file_name().map(|name| {
-                let mut to = target.to_path_buf();
-                to.push(name);
+            from.file_name().map(|name| {
+                let to = full.join(name);
                 (from, to)
             })
         })
-        .par_bridge()
-        .for_each_with(pb, |p, (from, to)| {
-            if std::fs::copy(from, to).is_ok() {
+        .collect();
+
+    let (todo, skipped): (Vec<_>, Vec<_>) = pairs
+        .iter()
+        .partition(|(from, to)| is_current(stamp(from), stamp(to)).not());
+
+    // Check for room up front, so that we never leave a half-finished backup.
+    let needed: u64 = todo
+        .iter()
+        .filter_map(|(from, _)| stamp(from))
+        .map(|(bytes, _)| bytes)
+        .sum();
+
+    if let Some(free) = free_space(&full) {
+        if needed > free {
+            return Err(Error::NoSpace(full, needed, free));
+        }
+    }
+
+    let failed = copy(&todo);
+    let copied = todo.len() - failed.len();
+    let msg = fl!(fll, "C-b-done", copied = copied, skipped = skipped.len());
+    aln!(msg);
+
+    if failed.is_empty().not() {
+        return Err(Error::Copy(failed));
+    }
+
+    if verify {
+        verify_backup(fll, &pairs)?;
+    }
+
+    Ok(())
+}
+
+/// The size and modification time of a file, if it exists.
+fn stamp(path: &Path) -> Option<(u64, SystemTime)> {
+    let meta = path.metadata().ok()?;
+    let time = meta.modified().ok()?;
+
+    Some((meta.len(), time))
+}
+
+/// Does a backed-up file match its original in size and (to the second)
+/// modification time? Then it needn't be copied again.
+fn is_current(source: Option<(u64, SystemTime)>, target: Option<(u64, SystemTime)>) -> bool {
+    let secs = |t: SystemTime| {
+        t.duration_since(SystemTime::UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_secs())
+    };
+
+    match (source, target) {
+        (Some((sb, st)), Some((tb, tt))) => sb == tb && secs(st) == secs(tt),
+        _ => false,
+    }
+}
+
+/// How many bytes are free on the filesystem that holds the given directory?
+fn free_space(dir: &Path) -> Option<u64> {
+    let out = std::process::Command::new("df")
+        .arg("--output=avail")
+        .arg("-B1")
+        .arg(dir)
+        .output()
+        .ok()?;
+
+    std::str::from_utf8(&out.stdout).ok().and_then(parse_df)
+}
+
+/// The available byte count from the output of `df --output=avail -B1`.
+fn parse_df(out: &str) -> Option<u64> {
+    out.lines().nth(1)?.trim().parse().ok()
+}
+
+/// Copy cache files concurrently, keeping their modification times so that the
+/// next backup can skip them. Yields the files that couldn't be copied, sorted,
+/// along with why.
+///
+/// Files are always copied, never renamed, so the target may lie on another
+/// filesystem.
+fn copy(files: &[&(PathBuf, PathBuf)]) -> Vec<(PathBuf, std::io::Error)> {
+    debug!("Begin cache copying.");
+
+    // TODO Change the bar style.
+    // A progress bar to display the copying progress.
+    let pb = Arc::new(Mutex::new(Progress::new()));
+    let bar = pb.lock().unwrap().bar(files.len(), "File Copying"); // TODO Localize.
+
+    let mut failed: Vec<_> = files
+        .par_iter()
+        .map_with(pb, |p, (from, to)| match copy_one(from, to) {
+            Ok(()) => {
                 p.lock().unwrap().inc_and_draw(&bar, 1);
+                None
             }
-        });
-    Ok(())
+            Err(e) => Some((from.clone(), e)),
+        })
+        .flatten()
+        .collect();
+
+    failed.sort_by(|(a, _), (b, _)| a.cmp(b));
+    failed
+}
+
+/// Copy a single file, carrying over its modification time.
+fn copy_one(from: &Path, to: &Path) -> Result<(), std::io::Error> {
+    std::fs::copy(from, to)?;
+    let time = from.metadata()?.modified()?;
+    std::fs::File::options()
+        .write(true)
+        .open(to)?
+        .set_modified(time)
+}
+
+/// Compare the checksum of every backed-up file against its original.
+fn verify_backup(fll: &FluentLanguageLoader, pairs: &[(PathBuf, PathBuf)]) -> Result<(), Error> {
+    aura!(fll, "C-b-verifying", n = pairs.len());
+
+    let mut bad: Vec<PathBuf> = pairs
+        .par_iter()
+        .filter(|(from, to)| {
+            let original = aura_core::cache::sha256(from);
+            let copy = aura_core::cache::sha256(to);
+
+            match (original, copy) {
+                (Ok(a), Ok(b)) => a != b,
+                _ => true,
+            }
+        })
+        .map(|(_, to)| to.clone())
+        .collect();
+
+    if bad.is_empty() {
+        green!(fll, "C-b-verified");
+        Ok(())
+    } else {
+        bad.sort();
+        Err(Error::Mismatch(bad))
+    }
 }
 
 /// Display packages that don't have a tarball in any cache.
@@ -433,3 +1075,188 @@ pub(crate) fn missing(alpm: &Alpm, caches: &[&Path]) {
         println!("{} {}", pkg.name(), pkg.version());
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn copy_failures_reported() {
+        let pairs = [
+            (
+                PathBuf::from("tests/no-such-b.pkg.tar.zst"),
+                PathBuf::from("tests/no-such-dir/b.pkg.tar.zst"),
+            ),
+            (
+                PathBuf::from("tests/no-such-a.pkg.tar.zst"),
+                PathBuf::from("tests/no-such-dir/a.pkg.tar.zst"),
+            ),
+        ];
+        let files: Vec<_> = pairs.iter().collect();
+        let failed = copy(&files);
+        let paths: Vec<_> = failed.iter().map(|(p, _)| p.clone()).collect();
+
+        assert_eq!(vec![pairs[1].0.clone(), pairs[0].0.clone()], paths);
+        assert!(failed
+            .iter()
+            .all(|(_, e)| e.kind() == std::io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn current_backups() {
+        let t = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let later = t + Duration::from_millis(300);
+
+        assert!(is_current(Some((100, t)), Some((100, t))));
+        assert!(is_current(Some((100, t)), Some((100, later))));
+        assert!(is_current(Some((100, t)), Some((99, t))).not());
+        assert!(is_current(Some((100, t)), Some((100, t + Duration::from_secs(5)))).not());
+        assert!(is_current(Some((100, t)), None).not());
+        assert!(is_current(None, None).not());
+    }
+
+    #[test]
+    fn machine_records() {
+        let pp = PkgPath::new(PathBuf::from(
+            "/var/cache/pacman/pkg/aura-bin-4.0.2-1-x86_64.pkg.tar.zst",
+        ))
+        .unwrap();
+        assert_eq!(
+            "aura-bin\t4.0.2-1\t2048\t/var/cache/pacman/pkg/aura-bin-4.0.2-1-x86_64.pkg.tar.zst",
+            tarball_record(&pp, 2048)
+        );
+
+        let ci = CacheInfo {
+            name: "gcc".to_string(),
+            version: "14.2.1-1".to_string(),
+            created: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            signature: true,
+            size: 4096,
+            available: vec!["14.2.1-1".to_string(), "14.1.1-1".to_string()],
+        };
+        assert_eq!(
+            "gcc\t14.2.1-1\t14.1.1-1\t1700000000\ttrue\t4096\t14.2.1-1,14.1.1-1",
+            info_record(&ci, Some("14.1.1-1"))
+        );
+        assert_eq!(Some("-"), info_record(&ci, None).split('\t').nth(2));
+    }
+
+    #[test]
+    fn newest_versions_first() {
+        let fixture = std::fs::read_to_string("tests/cache-versions.txt").unwrap();
+        let tarballs = fixture.lines().filter(|l| !l.starts_with('#')).map(|l| {
+            let mut words = l.split_whitespace();
+            let path = PathBuf::from(words.next().unwrap());
+            let size = words.next().unwrap().parse().unwrap();
+
+            (PkgPath::new(path).unwrap(), size)
+        });
+
+        let cached = |version: &str, size, installed| CachedVersion {
+            version: version.to_string(),
+            size,
+            installed,
+        };
+        let expected = vec![
+            cached("1:14.2.1-1", 52_000_000, false),
+            cached("14.10.0-1", 51_000_000, true),
+            cached("14.2.1-2", 50_500_000, false),
+            cached("14.2.1-1", 50_000_000, false),
+            cached("13.2.1-5", 48_000_000, false),
+        ];
+
+        assert_eq!(expected, cached_versions(tarballs, Some("14.10.0-1")));
+        assert!(cached_versions(Vec::new(), Some("14.10.0-1")).is_empty());
+    }
+
+    #[test]
+    fn df_output() {
+        assert_eq!(
+            Some(52_613_349_376),
+            parse_df("       Avail\n 52613349376\n")
+        );
+        assert_eq!(None, parse_df("Avail\n"));
+        assert_eq!(None, parse_df(""));
+    }
+
+    /// Tarballs paired with the verdict their recorded check results imply.
+    fn fixture() -> Vec<(PkgPath, Verification)> {
+        std::fs::read_to_string("tests/verify-cache.txt")
+            .unwrap()
+            .lines()
+            .filter(|l| !l.starts_with('#'))
+            .map(|l| {
+                let mut words = l.split_whitespace();
+                let pp = PkgPath::new(PathBuf::from(words.next().unwrap())).unwrap();
+                let intact = words.next() == Some("yes");
+                let signature = match words.next() {
+                    Some("good") => Some(true),
+                    Some("bad") => Some(false),
+                    _ => None,
+                };
+
+                (pp, Verification::new(intact, signature))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn verification_tally() {
+        let checked = fixture();
+        let expected = Tally {
+            valid: 2,
+            corrupt: 2,
+            unsigned: 2,
+            bad_signature: 1,
+        };
+
+        assert_eq!(expected, Tally::new(checked.iter().map(|(_, v)| v)));
+
+        let invalid: Vec<_> = checked
+            .iter()
+            .filter(|(_, v)| v.is_invalid())
+            .map(|(pp, _)| pp.as_package().name.as_ref())
+            .collect();
+
+        assert_eq!(vec!["git", "linux", "aura"], invalid);
+    }
+
+    #[test]
+    fn parallel_sweep() {
+        let expected = fixture();
+        let verdicts: HashMap<PathBuf, Verification> = expected
+            .iter()
+            .map(|(pp, v)| (pp.as_path().to_path_buf(), *v))
+            .collect();
+
+        for jobs in [1, 2, 8] {
+            let tarballs: Vec<_> = fixture().into_iter().map(|(pp, _)| pp).collect();
+            let checked = sweep(jobs, tarballs, |pp| verdicts[pp.as_path()]);
+
+            assert_eq!(expected, checked);
+            assert_eq!(
+                Tally::new(expected.iter().map(|(_, v)| v)),
+                Tally::new(checked.iter().map(|(_, v)| v))
+            );
+        }
+    }
+
+    #[test]
+    fn unchecked_set_aside() {
+        // Every tarball but the first and last could be checked.
+        let expected = fixture();
+        let last = expected.len() - 1;
+        let swept: Vec<_> = fixture()
+            .into_iter()
+            .enumerate()
+            .map(|(i, (pp, v))| (pp, (i != 0 && i != last).then_some(v)))
+            .collect();
+        let (checked, unchecked) = set_aside(swept);
+
+        assert_eq!(&expected[1..last], checked.as_slice());
+        assert_eq!(
+            vec![expected[0].0.as_path(), expected[last].0.as_path()],
+            unchecked.iter().map(|pp| pp.as_path()).collect::<Vec<_>>()
+        );
+    }
+}
@@ -1,14 +1,15 @@
 //! Analyze many aspects of your installation for validity.
 
+use crate::aln;
 use crate::aura;
 use crate::env::Aur;
 use crate::env::Env;
 use crate::error::Nested;
-use crate::executable;
 use crate::green;
 use crate::localization::code_and_country;
 use crate::localization::identifier_from_locale;
 use crate::localization::Localised;
+use crate::theme::Themed;
 use crate::utils::PathStr;
 use alpm::PackageReason;
 use colored::*;
@@ -19,10 +20,11 @@ use r2d2_alpm::Alpm;
 use r2d2_alpm::AlpmManager;
 use rayon::prelude::*;
 use std::collections::HashSet;
+use std::ffi::OsStr;
 use std::ops::Not;
+use std::os::unix::ffi::OsStrExt;
 use std::path::Path;
 use std::path::PathBuf;
-use std::process::Command;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
@@ -33,14 +35,22 @@ pub(crate) const CANCEL: &str = "⊘";
 
 const SECS_IN_DAY: u64 = 60 * 60 * 24;
 
+/// The list of mirrors Pacman downloads from.
+const MIRRORLIST: &str = "/etc/pacman.d/mirrorlist";
+
+/// After how many days a mirrorlist is considered stale.
+const MIRRORLIST_DAYS: u64 = 90;
+
 pub(crate) enum Error {
     Env(crate::env::Error),
+    Failed(usize),
 }
 
 impl Nested for Error {
     fn nested(&self) {
         match self {
             Error::Env(e) => e.nested(),
+            Error::Failed(_) => {}
         }
     }
 }
@@ -49,103 +59,249 @@ impl Localised for Error {
     fn localise(&self, fll: &FluentLanguageLoader) -> String {
         match self {
             Error::Env(e) => e.localise(fll),
+            Error::Failed(n) => fl!(fll, "check-failed", n = *n),
         }
     }
 }
 
-/// Validate the system.
-pub(crate) fn check(fll: &FluentLanguageLoader, env: &Env) -> Result<(), Error> {
+/// How a single check turned out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    Pass,
+    /// Something worth knowing about, but not broken.
+    Warn,
+    Fail,
+    /// The check itself couldn't be performed.
+    Skip,
+}
+
+impl Verdict {
+    fn symbol(&self) -> ColoredString {
+        match self {
+            Verdict::Pass => GOOD.success(),
+            Verdict::Warn => WARN.warning(),
+            Verdict::Fail => BAD.error(),
+            Verdict::Skip => CANCEL.truecolor(128, 128, 128),
+        }
+    }
+}
+
+/// The result of a single check, along with any details or fixes.
+struct Outcome {
+    verdict: Verdict,
+    label: String,
+    notes: Vec<String>,
+}
+
+impl Outcome {
+    fn new(verdict: Verdict, label: String) -> Outcome {
+        Outcome {
+            verdict,
+            label,
+            notes: Vec::new(),
+        }
+    }
+
+    /// A check that passes if `good`, and otherwise yields the given verdict.
+    fn judge(good: bool, otherwise: Verdict, label: String) -> Outcome {
+        let verdict = if good { Verdict::Pass } else { otherwise };
+        Outcome::new(verdict, label)
+    }
+
+    fn passed(&self) -> bool {
+        self.verdict == Verdict::Pass
+    }
+
+    fn note(mut self, note: String) -> Outcome {
+        self.notes.push(note);
+        self
+    }
+
+    fn notes<I>(mut self, notes: I) -> Outcome
+    where
+        I: IntoIterator<Item = String>,
+    {
+        self.notes.extend(notes);
+        self
+    }
+
+    fn print(&self) {
+        println!("  [{}] {}", self.verdict.symbol(), self.label);
+
+        let len = self.notes.len();
+        for (i, note) in self.notes.iter().enumerate() {
+            let arrow = if i + 1 == len { "└─" } else { "├─" };
+            println!("      {} {}", arrow, note);
+        }
+    }
+}
+
+/// A titled group of related checks. New checks belong within the function
+/// that forms the relevant `Section`.
+struct Section {
+    title: String,
+    outcomes: Vec<Outcome>,
+}
+
+impl Section {
+    /// The outcomes worth printing. When `quiet`, only failures are.
+    fn shown(&self, quiet: bool) -> impl Iterator<Item = &Outcome> {
+        self.outcomes
+            .iter()
+            .filter(move |o| quiet.not() || o.verdict == Verdict::Fail)
+    }
+
+    fn failures(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|o| o.verdict == Verdict::Fail)
+            .count()
+    }
+
+    /// Print the section, yielding how many of its checks failed.
+    fn report(&self, quiet: bool) -> usize {
+        let mut shown = self.shown(quiet).peekable();
+
+        if shown.peek().is_some() {
+            aln!(self.title);
+            shown.for_each(|o| o.print());
+        }
+
+        self.failures()
+    }
+}
+
+/// Validate the system, failing if any check fails. With `quiet`, only failing
+/// checks are printed.
+pub(crate) fn check(fll: &FluentLanguageLoader, env: &Env, quiet: bool) -> Result<(), Error> {
     let caches = env.caches();
     let alpm = env.alpm().map_err(Error::Env)?;
     let pool = env.alpm_pool().map_err(Error::Env)?;
 
-    aura!(fll, "check-start");
-    environment(fll);
-    aura_config(fll);
-    pacman_config(fll, &env.pacman, &env.aur);
-    makepkg_config(fll, env);
-    snapshots(fll, &env.backups.snapshots, &caches);
-    cache(fll, &alpm, pool, &caches);
-    packages(fll, env, &alpm);
-    green!(fll, "common-done");
-
-    Ok(())
-}
-
-fn environment(fll: &FluentLanguageLoader) {
-    aura!(fll, "check-env");
-    lang(fll);
-    editor(fll);
-    java(fll);
-    executable!(fll, "bat", "check-env-exec", exec = "bat");
-    executable!(fll, "dot", "check-env-exec", exec = "dot");
-    executable!(fll, "fd", "check-env-exec", exec = "fd");
-    executable!(fll, "git", "check-env-exec", exec = "git");
-    executable!(fll, "rg", "check-env-exec", exec = "rg");
-    executable!(fll, "shellcheck", "check-env-exec", exec = "shellcheck");
-    executable!(fll, "xdg-open", "check-env-exec", exec = "xdg-open");
-}
-
-fn java(fll: &FluentLanguageLoader) {
+    if quiet.not() {
+        aura!(fll, "check-start");
+    }
+
+    // Each section is reported as soon as it's ready, since some are slow.
+    let failures = environment(fll).report(quiet)
+        + aura_config(fll).report(quiet)
+        + pacman_config(fll, &env.pacman, &env.aur).report(quiet)
+        + makepkg_config(fll, env).report(quiet)
+        + snapshots(fll, &env.backups.snapshots, &caches).report(quiet)
+        + cache(fll, &alpm, pool, &caches).report(quiet)
+        + packages(fll, env, &alpm).report(quiet);
+
+    conclude(fll, quiet, failures)
+}
+
+/// Report only how many `.pacnew` and `.pacsave` files await review.
+pub(crate) fn pacnew(fll: &FluentLanguageLoader, quiet: bool) -> Result<(), Error> {
+    let files = crate::command::conf::pending(Path::new(crate::command::conf::PACNEW_ROOT));
+    let section = Section {
+        title: fl!(fll, "check-pconf"),
+        outcomes: vec![pending(fll, &files)],
+    };
+
+    conclude(fll, quiet, section.report(quiet))
+}
+
+fn conclude(fll: &FluentLanguageLoader, quiet: bool, failures: usize) -> Result<(), Error> {
+    match failures {
+        0 => {
+            if quiet.not() {
+                green!(fll, "common-done");
+            }
+            Ok(())
+        }
+        n => Err(Error::Failed(n)),
+    }
+}
+
+fn environment(fll: &FluentLanguageLoader) -> Section {
+    let mut outcomes = lang(fll);
+    outcomes.extend(editor(fll));
+    outcomes.push(java(fll));
+    outcomes.extend(
+        ["bat", "dot", "fd", "git", "rg", "shellcheck", "xdg-open"]
+            .into_iter()
+            .map(|exec| executable(fll, exec, fl!(fll, "check-env-exec", exec = exec))),
+    );
+
+    Section {
+        title: fl!(fll, "check-env"),
+        outcomes,
+    }
+}
+
+/// Is the given program installed?
+fn executable(fll: &FluentLanguageLoader, exec: &str, label: String) -> Outcome {
+    let good = which::which(exec).is_ok();
+    let o = Outcome::judge(good, Verdict::Warn, label);
+
+    if good {
+        o
+    } else {
+        let exec = exec.highlight().bold().to_string();
+        o.note(fl!(fll, "check-missing-exec", exec = exec))
+    }
+}
+
+fn java(fll: &FluentLanguageLoader) -> Outcome {
     match which::which("archlinux-java") {
         Err(_) => {
-            let msg = fl!(fll, "check-env-java-bin");
-            println!("  [{}] {}", WARN.yellow(), msg);
-            let pkg = "jdk-openjdk".cyan().to_string();
-            let msg = fl!(fll, "check-env-java-bin-fix", pkg = pkg);
-            println!("      └─ {}", msg);
+            let pkg = "jdk-openjdk".highlight().to_string();
+            Outcome::new(Verdict::Warn, fl!(fll, "check-env-java-bin")).note(fl!(
+                fll,
+                "check-env-java-bin-fix",
+                pkg = pkg
+            ))
         }
         Ok(_) => {
             let good = crate::utils::cmd_lines("archlinux-java", &["status"])
                 .map(|lines| lines.last().starts_with("No Java environment").not())
                 .unwrap_or(false);
-            let symb = if good { GOOD.green() } else { BAD.red() };
-            println!("  [{}] {}", symb, fl!(fll, "check-env-java-set"));
+            let o = Outcome::judge(good, Verdict::Warn, fl!(fll, "check-env-java-set"));
 
-            if !good {
-                let cmd = "archlinux-java --help".cyan().to_string();
-                let msg = fl!(fll, "check-env-java-set-fix", cmd = cmd);
-                println!("      └─ {}", msg);
+            if good {
+                o
+            } else {
+                let cmd = "archlinux-java --help".highlight().to_string();
+                o.note(fl!(fll, "check-env-java-set-fix", cmd = cmd))
             }
         }
     }
 }
 
-fn lang(fll: &FluentLanguageLoader) {
+fn lang(fll: &FluentLanguageLoader) -> Vec<Outcome> {
     match std::env::var("LANG") {
         Err(_) => {
-            let cmd = "locale -a".cyan().to_string();
+            let cmd = "locale -a".highlight().to_string();
             let msg = fl!(fll, "check-env-lang", cmd = cmd, lang = "???");
-            println!("  [{}] {}", BAD.red(), msg);
-            let msg = fl!(fll, "check-env-lang-fix2");
-            println!("      └─ {}", msg);
+            vec![Outcome::new(Verdict::Warn, msg).note(fl!(fll, "check-env-lang-fix2"))]
         }
         Ok(lang) => {
             let good = crate::utils::cmd_lines("locale", &["-a"])
                 .map(|lines| lines.into_iter().any(|line| same_lang(&lang, &line)))
                 .unwrap_or(false);
 
-            let symb = if good { GOOD.green() } else { BAD.red() };
-            let cmd = "locale -a".cyan().to_string();
+            let cmd = "locale -a".highlight().to_string();
             let msg = fl!(fll, "check-env-lang", cmd = cmd, lang = lang.clone());
-            println!("  [{}] {}", symb, msg);
+            let mut o = Outcome::judge(good, Verdict::Warn, msg);
 
             if !good {
-                let file = "/etc/locale.gen".cyan().to_string();
-                let lnge = lang.cyan().to_string();
-                let msg = fl!(fll, "check-env-lang-fix", file = file, lang = lnge);
-                println!("      └─ {}", msg);
+                let file = "/etc/locale.gen".highlight().to_string();
+                let lnge = lang.highlight().to_string();
+                o = o.note(fl!(fll, "check-env-lang-fix", file = file, lang = lnge));
             }
 
-            aura_knows_lang(fll, &lang);
+            vec![o, aura_knows_lang(fll, &lang)]
         }
     }
 }
 
-fn aura_knows_lang(fll: &FluentLanguageLoader, lang: &str) {
+fn aura_knows_lang(fll: &FluentLanguageLoader, lang: &str) -> Outcome {
     let good = identifier_from_locale(lang).is_some();
-    let symb = if good { GOOD.green() } else { WARN.yellow() };
-    println!("  [{}] {}", symb, fl!(fll, "check-env-lang-known"));
+    Outcome::judge(good, Verdict::Warn, fl!(fll, "check-env-lang-known"))
 }
 
 /// Whether the LANG variable content can be considered the same as a given line
@@ -156,210 +312,227 @@ fn same_lang(lang: &str, locale: &str) -> bool {
     l0 == l1
 }
 
-fn editor(fll: &FluentLanguageLoader) {
+fn editor(fll: &FluentLanguageLoader) -> Vec<Outcome> {
     let edit = std::env::var("EDITOR");
     let good = edit.is_ok();
-    let symb = if good { GOOD.green() } else { WARN.yellow() };
-    println!("  [{}] {}", symb, fl!(fll, "check-env-editor"));
+    let set = Outcome::judge(good, Verdict::Warn, fl!(fll, "check-env-editor"));
 
-    if let Ok(e) = edit.as_deref() {
-        let exec = e.cyan().to_string();
-        executable!(fll, e, "check-env-editor-exec", exec = exec);
+    let exec = if let Ok(e) = edit.as_deref() {
+        let exec = e.highlight().to_string();
+        executable(fll, e, fl!(fll, "check-env-editor-exec", exec = exec))
     } else {
-        executable!(fll, "vi", "check-env-editor-vi");
-    }
+        executable(fll, "vi", fl!(fll, "check-env-editor-vi"))
+    };
+
+    vec![set, exec]
 }
 
-fn pacman_config(fll: &FluentLanguageLoader, c: &pacmanconf::Config, a: &Aur) {
-    aura!(fll, "check-pconf");
-    parallel_downloads(fll, c);
-    duplicate_ignores(fll, c, a);
-    pacnews(fll);
+fn pacman_config(fll: &FluentLanguageLoader, c: &pacmanconf::Config, a: &Aur) -> Section {
+    Section {
+        title: fl!(fll, "check-pconf"),
+        outcomes: vec![
+            parallel_downloads(fll, c),
+            duplicate_ignores(fll, c, a),
+            pacnews(fll),
+            pacsaves(fll),
+            mirrorlist(fll),
+        ],
+    }
 }
 
-fn makepkg_config(fll: &FluentLanguageLoader, env: &Env) {
+fn makepkg_config(fll: &FluentLanguageLoader, env: &Env) -> Section {
     let path = env
         .makepkg
         .as_ref()
         .map(|m| m.path.as_path().utf8())
         .unwrap_or_else(|| "ERROR".to_string());
 
-    aura!(fll, "check-mconf", path = path.clone());
-    packager_set(fll, env, &path);
+    Section {
+        title: fl!(fll, "check-mconf", path = path.clone()),
+        outcomes: vec![packager_set(fll, env, &path)],
+    }
 }
 
-fn aura_config(fll: &FluentLanguageLoader) {
-    aura!(fll, "check-aconf");
-    parsable_aura_toml(fll);
-    old_aura_dirs(fll);
-    old_aura_conf(fll);
+fn aura_config(fll: &FluentLanguageLoader) -> Section {
+    let mut outcomes = parsable_aura_toml(fll);
+    outcomes.push(old_aura_dirs(fll));
+    outcomes.extend(old_aura_conf(fll));
+
+    Section {
+        title: fl!(fll, "check-aconf"),
+        outcomes,
+    }
 }
 
-fn old_aura_dirs(fll: &FluentLanguageLoader) {
+fn old_aura_dirs(fll: &FluentLanguageLoader) -> Outcome {
     let good = Path::new("/var/cache/aura").is_dir().not();
-    let symbol = if good { GOOD.green() } else { WARN.yellow() };
-    println!("  [{}] {}", symbol, fl!(fll, "check-aconf-old-dirs"));
-
-    if !good {
-        if let Ok(cache) = crate::dirs::aura_xdg_cache() {
-            let old = "/var/cache/aura".bold().yellow().to_string();
-            let new = cache.display().to_string().bold().cyan().to_string();
-            let msg = fl!(fll, "common-replace", old = old, new = new);
-            println!("      └─ {}", msg);
+    let o = Outcome::judge(good, Verdict::Warn, fl!(fll, "check-aconf-old-dirs"));
+
+    match crate::dirs::aura_xdg_cache() {
+        Ok(cache) if !good => {
+            let old = "/var/cache/aura".bold().warning().to_string();
+            let new = cache.display().to_string().bold().highlight().to_string();
+            o.note(fl!(fll, "common-replace", old = old, new = new))
         }
+        _ => o,
     }
 }
 
-fn old_aura_conf(fll: &FluentLanguageLoader) {
-    if let Ok(xdg) = crate::dirs::xdg_config() {
-        let user = xdg.join("aura").join("aura.conf");
-        let files = [Path::new("/etc/aura.conf"), &user];
-        let exists: Vec<_> = files.into_iter().filter(|p| p.is_file()).collect();
-        let good = exists.is_empty();
-        let symbol = if good { GOOD.green() } else { WARN.yellow() };
-        println!("  [{}] {}", symbol, fl!(fll, "check-aconf-old-conf"));
-
-        if let Ok(aura) = crate::dirs::aura_config() {
-            let new = aura.display().to_string().bold().cyan().to_string();
-
-            let len = exists.len();
-            for (i, file) in exists.into_iter().enumerate() {
-                let old = file.display().to_string().bold().yellow().to_string();
-                let msg = fl!(fll, "common-replace", old = old, new = new.as_str());
-                let arrow = if i + 1 == len { "└─" } else { "├─" };
-                println!("      {} {}", arrow, msg);
-            }
+fn old_aura_conf(fll: &FluentLanguageLoader) -> Option<Outcome> {
+    let xdg = crate::dirs::xdg_config().ok()?;
+    let user = xdg.join("aura").join("aura.conf");
+    let files = [Path::new("/etc/aura.conf"), &user];
+    let exists: Vec<_> = files.into_iter().filter(|p| p.is_file()).collect();
+    let good = exists.is_empty();
+    let o = Outcome::judge(good, Verdict::Warn, fl!(fll, "check-aconf-old-conf"));
+
+    match crate::dirs::aura_config() {
+        Err(_) => Some(o),
+        Ok(aura) => {
+            let new = aura.display().to_string().bold().highlight().to_string();
+            let notes = exists.into_iter().map(|file| {
+                let old = file.display().to_string().bold().warning().to_string();
+                fl!(fll, "common-replace", old = old, new = new.as_str())
+            });
+
+            Some(o.notes(notes))
         }
     }
 }
 
-fn parsable_aura_toml(fll: &FluentLanguageLoader) {
+fn parsable_aura_toml(fll: &FluentLanguageLoader) -> Vec<Outcome> {
     let exists = crate::dirs::aura_config()
         .map(|file| file.is_file())
         .unwrap_or(false);
-    let symbol = if exists { GOOD.green() } else { WARN.yellow() };
-    println!("  [{}] {}", symbol, fl!(fll, "check-aconf-aura-exists"));
+    let o = Outcome::judge(exists, Verdict::Warn, fl!(fll, "check-aconf-aura-exists"));
 
     if exists {
         let parsable = crate::env::parsable_env();
-        let symbol = if parsable { GOOD.green() } else { BAD.red() };
-        println!("  [{}] {}", symbol, fl!(fll, "check-aconf-aura-parse"));
+        let p = Outcome::judge(parsable, Verdict::Fail, fl!(fll, "check-aconf-aura-parse"));
+        vec![o, p]
     } else {
         let cmd = "aura conf --gen > ~/.config/aura/config.toml"
             .bold()
-            .cyan()
+            .highlight()
             .to_string();
-        let msg = fl!(fll, "check-aconf-aura-exists-fix", cmd = cmd);
-        println!("      └─ {}", msg);
+        vec![o.note(fl!(fll, "check-aconf-aura-exists-fix", cmd = cmd))]
     }
 }
 
-fn packager_set(fll: &FluentLanguageLoader, env: &Env, path: &str) {
+fn packager_set(fll: &FluentLanguageLoader, env: &Env, path: &str) -> Outcome {
     let good = env
         .makepkg
         .as_ref()
         .and_then(|m| m.packager.as_deref())
         .is_some();
+    let o = Outcome::judge(good, Verdict::Warn, fl!(fll, "check-mconf-packager"));
 
-    let symbol = if good { GOOD.green() } else { BAD.red() };
-    println!("  [{}] {}", symbol, fl!(fll, "check-mconf-packager"));
-
-    if !good {
-        let cmd = "PACKAGER=\"You <you@foo.com>\"".cyan().to_string();
-        let msg = fl!(fll, "check-mconf-packager-fix", cmd = cmd, path = path);
-        println!("      └─ {}", msg);
+    if good {
+        o
+    } else {
+        let cmd = "PACKAGER=\"You <you@foo.com>\"".highlight().to_string();
+        o.note(fl!(fll, "check-mconf-packager-fix", cmd = cmd, path = path))
     }
 }
 
-fn parallel_downloads(fll: &FluentLanguageLoader, c: &pacmanconf::Config) {
+fn parallel_downloads(fll: &FluentLanguageLoader, c: &pacmanconf::Config) -> Outcome {
     let good = c.parallel_downloads > 1;
-    let symbol = if good { GOOD.green() } else { BAD.red() };
-    println!("  [{}] {}", symbol, fl!(fll, "check-pconf-par"));
+    let o = Outcome::judge(good, Verdict::Warn, fl!(fll, "check-pconf-par"));
 
-    if !good {
-        let cmd = "ParallelDownloads".bold().cyan().to_string();
+    if good {
+        o
+    } else {
+        let cmd = "ParallelDownloads".bold().highlight().to_string();
         let fix = format!("ParallelDownloads = {}", num_cpus::get())
             .bold()
-            .cyan()
+            .highlight()
             .to_string();
-        let msg = fl!(fll, "check-pconf-par-fix", setting = cmd, set = fix);
-        println!("      └─ {}", msg);
+        o.note(fl!(fll, "check-pconf-par-fix", setting = cmd, set = fix))
     }
 }
 
-fn duplicate_ignores(fll: &FluentLanguageLoader, c: &pacmanconf::Config, a: &Aur) {
+fn duplicate_ignores(fll: &FluentLanguageLoader, c: &pacmanconf::Config, a: &Aur) -> Outcome {
     let pi: HashSet<_> = c.ignore_pkg.iter().map(|s| s.as_str()).collect();
     let ai: HashSet<_> = a.ignores.iter().map(|s| s.as_str()).collect();
     let mut ix = pi.intersection(&ai).copied().collect::<Vec<_>>();
 
     let good = ix.is_empty();
-    let symbol = if good { GOOD.green() } else { BAD.red() };
-    println!("  [{}] {}", symbol, fl!(fll, "check-pconf-ignores"));
+    let o = Outcome::judge(good, Verdict::Warn, fl!(fll, "check-pconf-ignores"));
 
-    if !good {
+    if good {
+        o
+    } else {
         ix.sort_unstable();
         let ps = ix.join(", ");
-        let msg = fl!(fll, "check-pconf-ignores-fix", pkgs = ps);
-        println!("      └─ {}", msg);
+        o.note(fl!(fll, "check-pconf-ignores-fix", pkgs = ps))
     }
 }
 
-fn snapshots(fll: &FluentLanguageLoader, s_path: &Path, t_path: &[&Path]) {
-    aura!(fll, "check-snapshots");
-    usable_snapshots(fll, s_path, t_path);
+fn snapshots(fll: &FluentLanguageLoader, s_path: &Path, t_path: &[&Path]) -> Section {
+    Section {
+        title: fl!(fll, "check-snapshots"),
+        outcomes: vec![usable_snapshots(fll, s_path, t_path)],
+    }
 }
 
-fn usable_snapshots(fll: &FluentLanguageLoader, s_path: &Path, t_path: &[&Path]) {
+fn usable_snapshots(fll: &FluentLanguageLoader, s_path: &Path, t_path: &[&Path]) -> Outcome {
     let ss = aura_core::snapshot::snapshots(s_path);
     let vs = aura_core::cache::all_versions(t_path);
     let (goods, bads): (Vec<_>, Vec<_>) = ss.partition(|s| s.usable(&vs));
     let good = bads.is_empty();
 
-    let symbol = if good { GOOD.green() } else { BAD.red() };
-    println!(
-        "  [{}] {} ({}/{})",
-        symbol,
+    let label = format!(
+        "{} ({}/{})",
         fl!(fll, "check-snapshot-usable"),
         goods.len(),
         goods.len() + bads.len()
     );
+    let o = Outcome::judge(good, Verdict::Warn, label);
 
-    if !good {
-        let cmd = "aura -Bc".bold().cyan().to_string();
-        let msg = fl!(fll, "check-snapshot-usable-fix", command = cmd);
-        println!("      └─ {}", msg);
+    if good {
+        o
+    } else {
+        let cmd = "aura -Bc".bold().highlight().to_string();
+        o.note(fl!(fll, "check-snapshot-usable-fix", command = cmd))
     }
 }
 
-fn cache(fll: &FluentLanguageLoader, alpm: &Alpm, pool: Pool<AlpmManager>, caches: &[&Path]) {
-    aura!(fll, "check-cache");
-    caches_exist(fll, caches);
-    official_packages_have_tarballs(fll, alpm, caches);
-    foreign_packages_have_tarballs(fll, alpm, caches);
-    valid_tarballs(fll, pool, caches);
+fn cache(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    pool: Pool<AlpmManager>,
+    caches: &[&Path],
+) -> Section {
+    Section {
+        title: fl!(fll, "check-cache"),
+        outcomes: vec![
+            caches_exist(fll, caches),
+            official_packages_have_tarballs(fll, alpm, caches),
+            foreign_packages_have_tarballs(fll, alpm, caches),
+            valid_tarballs(fll, pool, caches),
+        ],
+    }
 }
 
-fn caches_exist(fll: &FluentLanguageLoader, caches: &[&Path]) {
-    let (goods, bads): (Vec<&Path>, _) = caches.iter().partition(|p| p.is_dir());
-    let good = bads.is_empty();
-    let symbol = if good { GOOD.green() } else { BAD.red() };
-    println!(
-        "  [{}] {} ({}/{})",
-        symbol,
+fn caches_exist(fll: &FluentLanguageLoader, caches: &[&Path]) -> Outcome {
+    let (goods, bads): (Vec<&Path>, Vec<&Path>) = caches.iter().partition(|p| p.is_dir());
+    let label = format!(
+        "{} ({}/{})",
         fl!(fll, "check-cache-exists"),
         goods.len(),
         goods.len() + bads.len()
     );
 
-    if !good {
-        for bad in bads {
-            println!("      └─ {}", bad.display());
-        }
-    }
+    Outcome::judge(bads.is_empty(), Verdict::Fail, label)
+        .notes(bads.into_iter().map(|bad| bad.display().to_string()))
 }
 
 /// Is every tarball in the cache valid and loadable by ALPM?
-fn valid_tarballs(fll: &FluentLanguageLoader, pool: Pool<AlpmManager>, caches: &[&Path]) {
+fn valid_tarballs(
+    fll: &FluentLanguageLoader,
+    pool: Pool<AlpmManager>,
+    caches: &[&Path],
+) -> Outcome {
     let (goods, bads): (Vec<_>, Vec<_>) = aura_core::cache::package_paths(caches)
         .par_bridge()
         .partition(|pp| {
@@ -368,126 +541,173 @@ fn valid_tarballs(fll: &FluentLanguageLoader, pool: Pool<AlpmManager>, caches: &
                 .unwrap_or(false)
         });
     let good = bads.is_empty();
-    let symbol = if good { GOOD.green() } else { BAD.red() };
-    println!(
-        "  [{}] {} ({}/{})",
-        symbol,
+    let label = format!(
+        "{} ({}/{})",
         fl!(fll, "check-cache-tarballs"),
         goods.len(),
         goods.len() + bads.len()
     );
+    let o = Outcome::judge(good, Verdict::Fail, label);
 
-    if !good {
-        let cmd = "aura -Ct".bold().cyan().to_string();
-        let msg = fl!(fll, "check-cache-tarballs-fix", command = cmd);
-        println!("      └─ {}", msg);
+    if good {
+        o
+    } else {
+        let cmd = "aura -Ct".bold().highlight().to_string();
+        o.note(fl!(fll, "check-cache-tarballs-fix", command = cmd))
     }
 }
 
 /// Does every installed package have a tarball in the cache?
-fn official_packages_have_tarballs(fll: &FluentLanguageLoader, alpm: &Alpm, caches: &[&Path]) {
+fn official_packages_have_tarballs(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    caches: &[&Path],
+) -> Outcome {
     let all_installed = aura_core::native_packages(alpm).count();
     let bads: Vec<_> = aura_core::cache::officials_missing_tarballs(alpm, caches).collect();
-    let is_bad = bads.is_empty().not();
-    let symbol = if is_bad { BAD.red() } else { GOOD.green() };
-    println!(
-        "  [{}] {} ({}/{})",
-        symbol,
+    let good = bads.is_empty();
+    let label = format!(
+        "{} ({}/{})",
         fl!(fll, "check-cache-missing"),
         all_installed - bads.len(),
         all_installed
     );
+    let o = Outcome::judge(good, Verdict::Warn, label);
 
-    if is_bad {
-        let cmd = "aura -Cy".bold().cyan().to_string();
-        let msg = fl!(fll, "check-cache-missing-fix", command = cmd);
-        println!("      └─ {}", msg);
+    if good {
+        o
+    } else {
+        let cmd = "aura -Cy".bold().highlight().to_string();
+        o.note(fl!(fll, "check-cache-missing-fix", command = cmd))
     }
 }
 
 /// Does every installed foreign package have a tarball in the cache?
-fn foreign_packages_have_tarballs(fll: &FluentLanguageLoader, alpm: &Alpm, caches: &[&Path]) {
+fn foreign_packages_have_tarballs(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    caches: &[&Path],
+) -> Outcome {
     let all_installed = aura_core::foreign_packages(alpm).count();
     let bads: Vec<_> = aura_core::cache::foreigns_missing_tarballs(alpm, caches).collect();
-    let is_bad = bads.is_empty().not();
-    let symbol = if is_bad { BAD.red() } else { GOOD.green() };
-    println!(
-        "  [{}] {} ({}/{})",
-        symbol,
+    let good = bads.is_empty();
+    let label = format!(
+        "{} ({}/{})",
         fl!(fll, "check-cache-missing-for"),
         all_installed - bads.len(),
         all_installed
     );
+    let o = Outcome::judge(good, Verdict::Warn, label);
 
-    if is_bad {
-        let cmd = "aura -Cm".bold().cyan().to_string();
-        let msg = fl!(fll, "check-cache-missing-for-fix", cmd = cmd);
-        println!("      └─ {}", msg);
+    if good {
+        o
+    } else {
+        let cmd = "aura -Cm".bold().highlight().to_string();
+        o.note(fl!(fll, "check-cache-missing-for-fix", cmd = cmd))
     }
 }
 
-fn pacnews(fll: &FluentLanguageLoader) {
-    match pacnew_work() {
-        None => {
-            println!(
-                "  [{}] {}",
-                CANCEL.truecolor(128, 128, 128),
-                fl!(fll, "check-pconf-pacnew")
-            );
-            println!(
-                "      └─ {}",
-                fl!(
+fn pacnews(fll: &FluentLanguageLoader) -> Outcome {
+    let bads = pacnew_work();
+    let o = Outcome::judge(
+        bads.is_empty(),
+        Verdict::Warn,
+        fl!(fll, "check-pconf-pacnew"),
+    );
+    let notes = bads.into_iter().map(|(path, days)| {
+        fl!(
+            fll,
+            "check-pconf-pacnew-old",
+            path = path.utf8().highlight().to_string(),
+            days = days.to_string().error().to_string(),
+        )
+    });
+
+    o.notes(notes)
+}
+
+/// Are there any `.pacsave` files, i.e. configs left behind by removed
+/// packages, that might need review?
+fn pacsaves(fll: &FluentLanguageLoader) -> Outcome {
+    let saves = etc_files("pacsave");
+
+    Outcome::judge(
+        saves.is_empty(),
+        Verdict::Warn,
+        fl!(fll, "check-pconf-pacsave"),
+    )
+    .notes(saves.into_iter().map(|p| p.utf8().highlight().to_string()))
+}
+
+/// How many config files await review? Never a failure, since merging them is
+/// often a matter of taste.
+fn pending(fll: &FluentLanguageLoader, files: &[PathBuf]) -> Outcome {
+    let o = Outcome::judge(
+        files.is_empty(),
+        Verdict::Warn,
+        fl!(fll, "check-pconf-pending"),
+    );
+
+    if files.is_empty() {
+        o
+    } else {
+        let cmd = "aura conf --new".bold().highlight().to_string();
+        o.note(fl!(
+            fll,
+            "check-pconf-pending-fix",
+            count = files.len(),
+            cmd = cmd
+        ))
+    }
+}
+
+/// Has the mirrorlist been updated recently?
+fn mirrorlist(fll: &FluentLanguageLoader) -> Outcome {
+    let days = Path::new(MIRRORLIST)
+        .metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| days_old(modified, SystemTime::now()));
+
+    match days {
+        None => Outcome::new(Verdict::Skip, fl!(fll, "check-pconf-mirrorlist")),
+        Some(days) => {
+            let good = days <= MIRRORLIST_DAYS;
+            let o = Outcome::judge(good, Verdict::Warn, fl!(fll, "check-pconf-mirrorlist"));
+
+            if good {
+                o
+            } else {
+                let path = MIRRORLIST.highlight().to_string();
+                o.note(fl!(
                     fll,
-                    "check-pconf-pacnew-broken",
-                    fd = "fd".cyan().to_string()
-                )
-            );
-        }
-        Some(bads) => {
-            let good = bads.is_empty();
-            let sym = if good { GOOD.green() } else { BAD.red() };
-            println!("  [{}] {}", sym, fl!(fll, "check-pconf-pacnew"));
-
-            let len = bads.len();
-            for (i, (path, days)) in bads.into_iter().enumerate() {
-                let arrow = if i + 1 == len { "└─" } else { "├─" };
-
-                println!(
-                    "      {} {}",
-                    arrow,
-                    fl!(
-                        fll,
-                        "check-pconf-pacnew-old",
-                        path = path.utf8().cyan().to_string(),
-                        days = days.to_string().red().to_string(),
-                    ),
-                );
+                    "check-pconf-mirrorlist-old",
+                    path = path,
+                    days = days
+                ))
             }
         }
     }
 }
 
+/// How many whole days before `now` a file was last modified.
+pub(crate) fn days_old(modified: SystemTime, now: SystemTime) -> Option<u64> {
+    now.duration_since(modified)
+        .ok()
+        .map(|d| d.as_secs() / SECS_IN_DAY)
+}
+
+/// All files under `/etc` with the given extension.
+fn etc_files(ext: &str) -> Vec<PathBuf> {
+    crate::command::conf::with_extensions(Path::new(crate::command::conf::PACNEW_ROOT), &[ext])
+}
+
 /// Attempt to produce a list of paths for which the current in-use config file
 /// is older than its associated `.pacnew`. For each such path, also include how
 /// many days out-of-date it is.
-fn pacnew_work() -> Option<Vec<(PathBuf, u64)>> {
-    let mut cmd = if Path::new("/bin/fd").exists() {
-        let mut c = Command::new("fd");
-        c.args([".pacnew", "/etc"]);
-        c
-    } else {
-        let mut c = Command::new("find");
-        c.arg("/etc").arg("-name").arg("*.pacnew");
-        c
-    };
-
-    let outp = cmd.output().ok()?;
-    let stdo = std::str::from_utf8(&outp.stdout).ok()?;
-    let bads = stdo
-        .trim()
-        .lines()
-        .map(Path::new)
-        .filter(|p| p.extension() == Some("pacnew".as_ref()))
+fn pacnew_work() -> Vec<(PathBuf, u64)> {
+    etc_files("pacnew")
+        .into_iter()
         .map(|new| (new.with_extension(""), new))
         .filter_map(|(orig, new)| {
             orig.metadata()
@@ -500,97 +720,248 @@ fn pacnew_work() -> Option<Vec<(PathBuf, u64)>> {
                 .and_then(|o_mod| n_m.modified().ok().map(|n_mod| (orig, o_mod, n_mod)))
         })
         .filter(|(_, o_m, n_m)| o_m < n_m)
-        .filter_map(|(orig, o_m, n_m)| {
-            n_m.duration_since(o_m)
-                .ok()
-                .map(|d| (orig, d.as_secs() / SECS_IN_DAY))
+        .filter_map(|(orig, o_m, n_m)| days_old(o_m, n_m).map(|d| (orig, d)))
+        .collect()
+}
+
+fn packages(fll: &FluentLanguageLoader, env: &Env, alpm: &Alpm) -> Section {
+    let outcomes = [
+        old_packages(fll, alpm),
+        Some(sync_databases(fll, env)),
+        orphans(fll, alpm),
+        Some(broken_symlinks(fll, alpm)),
+        empty_directories(fll, env),
+    ];
+
+    Section {
+        title: fl!(fll, "check-pkgs"),
+        outcomes: outcomes.into_iter().flatten().collect(),
+    }
+}
+
+fn old_packages(fll: &FluentLanguageLoader, alpm: &Alpm) -> Option<Outcome> {
+    let now = SystemTime::now();
+    let sec = now.duration_since(UNIX_EPOCH).ok()?.as_secs();
+
+    let old: Vec<_> = alpm
+        .as_ref()
+        .localdb()
+        .pkgs()
+        .into_iter()
+        // Only consider packages that you explicitly installed...
+        .filter(|p| p.reason() == PackageReason::Explicit)
+        // ...and aren't required by anything.
+        .filter(|p| p.required_by().is_empty())
+        .filter(|p| p.optional_for().is_empty())
+        .filter_map(|p| {
+            p.install_date().and_then(|id| {
+                let diff = (sec - id as u64) / SECS_IN_DAY;
+                if diff > 365 {
+                    Some((p, diff))
+                } else {
+                    None
+                }
+            })
         })
         .collect();
 
-    Some(bads)
+    let o = Outcome::judge(old.is_empty(), Verdict::Warn, fl!(fll, "check-pkgs-old"));
+    let notes = old.into_iter().map(|(p, diff)| {
+        let pkg = p.name().highlight().to_string();
+
+        let day = if diff < 365 * 2 {
+            diff.to_string().warning().to_string()
+        } else {
+            diff.to_string().error().to_string()
+        };
+
+        fl!(fll, "check-pkgs-old-warn", pkg = pkg, days = day)
+    });
+
+    Some(o.notes(notes))
 }
 
-fn packages(fll: &FluentLanguageLoader, env: &Env, alpm: &Alpm) {
-    aura!(fll, "check-pkgs");
-    old_packages(fll, alpm);
-    empty_directories(fll, env);
+/// Have the sync databases been changed by their mirrors recently? If not,
+/// the mirrors have likely stopped syncing.
+fn sync_databases(fll: &FluentLanguageLoader, env: &Env) -> Outcome {
+    let label = fl!(fll, "check-pkgs-sync-dbs");
+    let threshold = env.general.stale_db_days;
+
+    match crate::command::sync::last_synced(env) {
+        None => Outcome::new(Verdict::Skip, label),
+        Some(newest) => match crate::command::sync::staleness(newest, SystemTime::now(), threshold)
+        {
+            None => Outcome::new(Verdict::Pass, label),
+            Some(days) => {
+                let cmd = "aura -Syy".bold().highlight().to_string();
+                Outcome::new(Verdict::Warn, label).note(fl!(
+                    fll,
+                    "check-pkgs-sync-dbs-old",
+                    days = days,
+                    cmd = cmd
+                ))
+            }
+        },
+    }
 }
 
-fn old_packages(fll: &FluentLanguageLoader, alpm: &Alpm) {
-    let now = SystemTime::now();
+/// Are any packages installed as dependencies that nothing depends on?
+fn orphans(fll: &FluentLanguageLoader, alpm: &Alpm) -> Option<Outcome> {
+    let n = aura_core::orphans(alpm).count();
+    let o = Outcome::judge(n == 0, Verdict::Warn, fl!(fll, "check-pkgs-orphans"));
 
-    if let Ok(dur) = now.duration_since(UNIX_EPOCH) {
-        let sec = dur.as_secs();
+    if o.passed() {
+        Some(o)
+    } else {
+        let cmd = "aura -Oj".bold().highlight().to_string();
+        Some(o.note(fl!(fll, "check-pkgs-orphans-fix", n = n, cmd = cmd)))
+    }
+}
 
-        let old: Vec<_> = alpm
-            .as_ref()
-            .localdb()
-            .pkgs()
+/// Do any installed packages own symlinks that point nowhere?
+fn broken_symlinks(fll: &FluentLanguageLoader, alpm: &Alpm) -> Outcome {
+    let root = Path::new(alpm.as_ref().root());
+    let mut broken: Vec<_> = alpm
+        .as_ref()
+        .localdb()
+        .pkgs()
+        .iter()
+        .flat_map(|p| {
+            p.files()
+                .files()
+                .iter()
+                .map(|f| root.join(OsStr::from_bytes(f.name().as_ref())))
+                .filter(|path| is_broken_symlink(path))
+                .map(move |path| (p.name(), path))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    broken.sort();
+
+    Outcome::judge(
+        broken.is_empty(),
+        Verdict::Fail,
+        fl!(fll, "check-pkgs-symlinks"),
+    )
+    .notes(
+        broken
             .into_iter()
-            // Only consider packages that you explicitly installed...
-            .filter(|p| p.reason() == PackageReason::Explicit)
-            // ...and aren't required by anything.
-            .filter(|p| p.required_by().is_empty())
-            .filter(|p| p.optional_for().is_empty())
-            .filter_map(|p| {
-                p.install_date().and_then(|id| {
-                    let diff = (sec - id as u64) / SECS_IN_DAY;
-                    if diff > 365 {
-                        Some((p, diff))
-                    } else {
-                        None
-                    }
-                })
-            })
-            .collect();
+            .map(|(pkg, path)| format!("{} {}", pkg.highlight(), path.display())),
+    )
+}
 
-        let good = old.is_empty();
-        let symb = if good { GOOD.green() } else { WARN.yellow() };
-        println!("  [{}] {}", symb, fl!(fll, "check-pkgs-old"));
+fn is_broken_symlink(path: &Path) -> bool {
+    path.symlink_metadata()
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false)
+        && path.metadata().is_err()
+}
 
-        let len = old.len();
-        for (i, (p, diff)) in old.into_iter().enumerate() {
-            let pkg = p.name().cyan().to_string();
+fn empty_directories(fll: &FluentLanguageLoader, env: &Env) -> Option<Outcome> {
+    let dir = env.aur.clones.read_dir().ok()?;
+    let mut empties: Vec<_> = dir
+        .filter_map(|de| de.ok())
+        .map(|de| de.path())
+        .par_bridge()
+        .filter_map(|p| {
+            p.read_dir()
+                .ok()
+                .map(|mut dir| dir.next().is_none())
+                .unwrap_or(true)
+                .then_some(p)
+        })
+        .collect();
+    empties.sort();
 
-            let day = if diff < 365 * 2 {
-                diff.to_string().yellow().to_string()
-            } else {
-                diff.to_string().red().to_string()
-            };
+    let o = Outcome::judge(
+        empties.is_empty(),
+        Verdict::Warn,
+        fl!(fll, "check-pkgs-empty"),
+    );
 
-            let msg = fl!(fll, "check-pkgs-old-warn", pkg = pkg, days = day);
-            let arrow = if i + 1 == len { "└─" } else { "├─" };
-            println!("      {} {}", arrow, msg);
-        }
+    if o.passed() {
+        Some(o)
+    } else {
+        let fix = fl!(fll, "check-pkgs-empty-fix");
+        Some(
+            o.note(fix)
+                .notes(empties.into_iter().map(|e| e.display().to_string())),
+        )
     }
 }
 
-fn empty_directories(fll: &FluentLanguageLoader, env: &Env) {
-    if let Ok(dir) = env.aur.clones.read_dir() {
-        let mut empties: Vec<_> = dir
-            .filter_map(|de| de.ok())
-            .map(|de| de.path())
-            .par_bridge()
-            .filter_map(|p| {
-                p.read_dir()
-                    .ok()
-                    .map(|mut dir| dir.next().is_none())
-                    .unwrap_or(true)
-                    .then_some(p)
-            })
-            .collect();
-        empties.sort();
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::TempDir;
+    use std::time::Duration;
+
+    fn section(verdicts: &[Verdict]) -> Section {
+        Section {
+            title: "Test".to_string(),
+            outcomes: verdicts
+                .iter()
+                .map(|v| Outcome::new(*v, format!("{v:?}")))
+                .collect(),
+        }
+    }
 
-        let symbol = if empties.is_empty() { GOOD.green() } else { BAD.red() };
-        println!("  [{}] {}", symbol, fl!(fll, "check-pkgs-empty"),);
+    #[test]
+    fn quiet_shows_failures() {
+        let s = section(&[Verdict::Pass, Verdict::Fail, Verdict::Warn, Verdict::Skip]);
+
+        assert_eq!(4, s.shown(false).count());
+        assert_eq!(
+            vec!["Fail"],
+            s.shown(true).map(|o| o.label.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(1, s.failures());
+        assert_eq!(0, section(&[Verdict::Pass, Verdict::Warn]).failures());
+    }
 
-        if empties.is_empty().not() {
-            let msg = fl!(fll, "check-pkgs-empty-fix");
-            println!("      └─ {}", msg);
+    #[test]
+    fn pending_count() {
+        let fll = crate::localization::load(None).unwrap();
+        let files = crate::command::conf::pending(Path::new("tests/pacnew"));
+        let o = pending(&fll, &files);
 
-            for empty in empties {
-                println!("         - {}", empty.display());
-            }
-        }
+        assert_eq!(2, files.len());
+        assert_eq!(Verdict::Warn, o.verdict);
+        assert!(o.notes[0].contains('2'));
+        assert!(pending(&fll, &[]).passed());
+    }
+
+    #[test]
+    fn judgement() {
+        assert!(Outcome::judge(true, Verdict::Fail, String::new()).passed());
+        assert_eq!(
+            Verdict::Warn,
+            Outcome::judge(false, Verdict::Warn, String::new()).verdict
+        );
+    }
+
+    #[test]
+    fn staleness() {
+        let now = UNIX_EPOCH + Duration::from_secs(100 * SECS_IN_DAY);
+        let then = UNIX_EPOCH + Duration::from_secs(9 * SECS_IN_DAY + 60);
+
+        assert_eq!(Some(90), days_old(then, now));
+        assert_eq!(Some(0), days_old(now, now));
+        assert_eq!(None, days_old(now, then));
+    }
+
+    #[test]
+    fn broken_links() {
+        let dir = TempDir::new("symlinks");
+
+        let target = dir.join("target");
+        std::fs::write(&target, "").unwrap();
+        std::os::unix::fs::symlink(&target, dir.join("good")).unwrap();
+        std::os::unix::fs::symlink(dir.join("missing"), dir.join("bad")).unwrap();
+
+        assert!(is_broken_symlink(&dir.join("bad")));
+        assert!(is_broken_symlink(&dir.join("good")).not());
+        assert!(is_broken_symlink(&target).not());
+        assert!(is_broken_symlink(&dir.join("nothing")).not());
     }
 }
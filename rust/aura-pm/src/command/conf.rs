@@ -2,12 +2,20 @@
 
 use crate::command::misc;
 use crate::env::Env;
+use crate::error::Exited;
 use crate::error::Nested;
+use crate::green;
 use crate::localization::Localised;
+use crate::proceed;
 use crate::utils::PathStr;
 use crate::utils::ResultVoid;
+use crate::utils::Succeeded;
+use i18n_embed::fluent::FluentLanguageLoader;
 use i18n_embed_fl::fl;
 use log::error;
+use std::fs::File;
+use std::io::Write;
+use std::ops::Not;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
@@ -15,10 +23,22 @@ use std::process::Command;
 /// The default filepath of the Pacman configuration.
 const DEFAULT_PAC_CONF: &str = "/etc/pacman.conf";
 
+/// Where Pacman leaves the `.pacnew` and `.pacsave` files that await review.
+pub(crate) const PACNEW_ROOT: &str = "/etc";
+
 pub(crate) enum Error {
     PathToAuraConfig(crate::dirs::Error),
     SerializeEnv(basic_toml::Error),
     CouldntOpen(PathBuf, std::io::Error),
+    Env(crate::env::Error),
+    NoViewer,
+    Write(PathBuf, std::io::Error),
+    Read(PathBuf, std::io::Error),
+    Invalid(PathBuf, basic_toml::Error),
+    Unreadable(basic_toml::Error),
+    Exists(PathBuf),
+    Cancelled,
+    Exit(String, u8),
 }
 
 impl Nested for Error {
@@ -27,16 +47,55 @@ impl Nested for Error {
             Error::PathToAuraConfig(e) => e.nested(),
             Error::SerializeEnv(e) => error!("{e}"),
             Error::CouldntOpen(_, e) => error!("{e}"),
+            Error::Env(e) => e.nested(),
+            Error::NoViewer => {}
+            Error::Write(_, e) => error!("{e}"),
+            Error::Read(_, e) => error!("{e}"),
+            Error::Invalid(_, _) => {}
+            Error::Unreadable(e) => error!("{e}"),
+            Error::Exists(_) => {}
+            Error::Cancelled => {}
+            Error::Exit(_, _) => {}
         }
     }
 }
 
 impl Localised for Error {
-    fn localise(&self, fll: &i18n_embed::fluent::FluentLanguageLoader) -> String {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
         match self {
             Error::PathToAuraConfig(_) => fl!(fll, "err-config-path"),
             Error::SerializeEnv(_) => fl!(fll, "conf-toml-err"),
             Error::CouldntOpen(p, _) => fl!(fll, "open-err", url = p.utf8()),
+            Error::Env(e) => e.localise(fll),
+            Error::NoViewer => fl!(fll, "conf-no-viewer"),
+            Error::Write(p, _) => fl!(fll, "err-file-write", file = p.utf8()),
+            Error::Read(p, _) => fl!(fll, "err-file-open", file = p.utf8()),
+            Error::Invalid(p, e) => {
+                fl!(fll, "conf-invalid", file = p.utf8(), err = e.to_string())
+            }
+            Error::Unreadable(_) => fl!(fll, "conf-gen-unreadable"),
+            Error::Exists(p) => fl!(fll, "conf-gen-exists", file = p.utf8()),
+            Error::Cancelled => fl!(fll, "common-cancelled"),
+            Error::Exit(cmd, code) => fl!(fll, "err-exit", cmd = cmd.as_str(), code = *code),
+        }
+    }
+}
+
+impl Exited for Error {
+    fn exit_code(&self) -> Option<u8> {
+        match self {
+            Error::PathToAuraConfig(_) => None,
+            Error::SerializeEnv(_) => None,
+            Error::CouldntOpen(_, _) => None,
+            Error::Env(_) => None,
+            Error::NoViewer => None,
+            Error::Write(_, _) => None,
+            Error::Read(_, _) => None,
+            Error::Invalid(_, _) => None,
+            Error::Unreadable(_) => None,
+            Error::Exists(_) => None,
+            Error::Cancelled => None,
+            Error::Exit(_, code) => Some(*code),
         }
     }
 }
@@ -46,45 +105,242 @@ pub(crate) fn general(env: &Env) {
     println!("{:#?}", env);
 }
 
-/// Output your current, full Aura config as legal TOML.
-pub(crate) fn gen(env: &Env) -> Result<(), Error> {
+/// How to treat the file that generated config is to be written to.
+#[derive(Debug, PartialEq, Eq)]
+enum Clobber {
+    Write,
+    /// It's Aura's live config, so ask first.
+    Confirm,
+    Refuse,
+}
+
+/// Existing files are only overwritten with `force`, or for Aura's own live
+/// config, after confirmation.
+fn clobber(exists: bool, live: bool, force: bool) -> Clobber {
+    match () {
+        _ if exists.not() || force => Clobber::Write,
+        _ if live => Clobber::Confirm,
+        _ => Clobber::Refuse,
+    }
+}
+
+/// Output your current, full Aura config as legal TOML, either to stdout or to
+/// the given file.
+pub(crate) fn gen(
+    fll: &FluentLanguageLoader,
+    env: &Env,
+    output: Option<&Path>,
+    force: bool,
+) -> Result<(), Error> {
     let s = basic_toml::to_string(env).map_err(Error::SerializeEnv)?;
-    println!("{s}");
+
+    // Never emit a config that Aura itself couldn't read back.
+    crate::env::validate(&s).map_err(Error::Unreadable)?;
+
+    let Some(path) = output else {
+        println!("{s}");
+        return Ok(());
+    };
+
+    let live = crate::dirs::aura_config().map_err(Error::PathToAuraConfig)?;
+    let is_live = path
+        .canonicalize()
+        .is_ok_and(|p| live.canonicalize().is_ok_and(|l| p == l));
+
+    match clobber(path.exists(), is_live, force) {
+        Clobber::Write => {}
+        Clobber::Confirm => {
+            proceed!(fll, env, "conf-gen-overwrite", file = path.utf8()).ok_or(Error::Cancelled)?
+        }
+        Clobber::Refuse => return Err(Error::Exists(path.to_path_buf())),
+    }
+
+    std::fs::write(path, s).map_err(|e| Error::Write(path.to_path_buf(), e))?;
+    green!(fll, "conf-gen-written", file = path.utf8());
     Ok(())
 }
 
-/// Open the `$XDG_HOME/aura/config.toml` in `bat` or `less`.
-pub(crate) fn open_aura_conf() -> Result<(), Error> {
+/// Output a commented config template, with every setting at its default.
+pub(crate) fn template() -> Result<(), Error> {
+    let s = crate::env::template().map_err(Error::Env)?;
+    print!("{s}");
+    Ok(())
+}
+
+/// List the `.pacnew` and `.pacsave` files that await review.
+pub(crate) fn new(fll: &FluentLanguageLoader) {
+    let files = pending(Path::new(PACNEW_ROOT));
+
+    if files.is_empty() {
+        green!(fll, "conf-new-none");
+    } else {
+        files.iter().for_each(|p| println!("{}", p.display()));
+    }
+}
+
+/// All `.pacnew` and `.pacsave` files beneath some directory, sorted.
+pub(crate) fn pending(dir: &Path) -> Vec<PathBuf> {
+    with_extensions(dir, &["pacnew", "pacsave"])
+}
+
+/// All files beneath some directory with any of the given extensions, sorted.
+/// Subdirectories that can't be read are skipped.
+pub(crate) fn with_extensions(dir: &Path, exts: &[&str]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    with_extensions_work(dir, exts, &mut files);
+    files.sort();
+    files
+}
+
+fn with_extensions_work(dir: &Path, exts: &[&str], files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => with_extensions_work(&path, exts, files),
+            Ok(t) if t.is_file() && has_extension(&path, exts) => files.push(path),
+            _ => {}
+        }
+    }
+}
+
+fn has_extension(path: &Path, exts: &[&str]) -> bool {
+    path.extension()
+        .map(|ext| exts.iter().any(|e| ext == *e))
+        .unwrap_or(false)
+}
+
+/// Open the `$XDG_HOME/aura/config.toml` in a file viewer.
+pub(crate) fn open_aura_conf(env: &Env) -> Result<(), Error> {
+    let path = crate::dirs::aura_config().map_err(Error::PathToAuraConfig)?;
+    view(env, &path)
+}
+
+/// Edit Aura's own config, creating it from the template first if necessary.
+/// The result is checked afterward, so that mistakes surface immediately and
+/// not during some later, unrelated command.
+pub(crate) fn edit(fll: &FluentLanguageLoader, env: &Env) -> Result<(), Error> {
     let path = crate::dirs::aura_config().map_err(Error::PathToAuraConfig)?;
-    let prog = misc::viewer();
 
-    Command::new(prog)
+    if path.is_file().not() {
+        let s = crate::env::template().map_err(Error::Env)?;
+        std::fs::write(&path, s).map_err(|e| Error::Write(path.clone(), e))?;
+        let file = path.utf8();
+        green!(fll, "conf-created", file = file);
+    }
+
+    let prog = editor(&env.general.editor);
+    Command::new(&prog)
         .arg(&path)
         .status()
-        .map_err(|e| Error::CouldntOpen(path, e))
-        .void()
+        .map_err(|e| Error::CouldntOpen(path.clone(), e))?
+        .succeeded()
+        .map_err(|code| Error::Exit(prog.utf8(), code))?;
+
+    let content = std::fs::read_to_string(&path).map_err(|e| Error::Read(path.clone(), e))?;
+    crate::env::validate(&content).map_err(|e| Error::Invalid(path.clone(), e))?;
+
+    green!(fll, "conf-valid");
+    Ok(())
 }
 
-/// Open the `pacman.conf` in `bat` or `less`.
-pub(crate) fn open_pacman_conf() -> Result<(), Error> {
-    let conf = Path::new(DEFAULT_PAC_CONF);
-    let prog = misc::viewer();
+/// The program to edit a file with: the configured editor (or `$EDITOR`) if it
+/// exists, then a file viewer, then `vi`.
+fn editor(configured: &str) -> PathBuf {
+    which::which(configured)
+        .ok()
+        .or_else(misc::viewer)
+        .unwrap_or_else(|| PathBuf::from("vi"))
+}
 
-    Command::new(prog)
-        .arg(conf)
-        .status()
-        .map_err(|e| Error::CouldntOpen(conf.to_path_buf(), e))
-        .void()
+/// Open the `pacman.conf` in a file viewer.
+pub(crate) fn open_pacman_conf(env: &Env) -> Result<(), Error> {
+    view(env, Path::new(DEFAULT_PAC_CONF))
 }
 
-/// Open the `makepkg.conf` in `bat` or `less`.
-pub(crate) fn open_makepkg_conf() -> Result<(), Error> {
-    let path = crate::makepkg::conf_location();
-    let prog = misc::viewer();
+/// Open the `makepkg.conf` in a file viewer.
+pub(crate) fn open_makepkg_conf(env: &Env) -> Result<(), Error> {
+    view(env, &crate::makepkg::conf_location())
+}
 
-    Command::new(prog)
-        .arg(&path)
+/// Show a file in a viewer, or when nobody is there to scroll through it, write
+/// it straight to stdout.
+fn view(env: &Env, path: &Path) -> Result<(), Error> {
+    if env.general.interactive.not() {
+        return dump(path, &mut std::io::stdout().lock());
+    }
+
+    let prog = misc::viewer().ok_or(Error::NoViewer)?;
+
+    Command::new(&prog)
+        .arg(path)
         .status()
-        .map_err(|e| Error::CouldntOpen(path, e))
+        .map_err(|e| Error::CouldntOpen(path.to_path_buf(), e))?
+        .succeeded()
+        .map_err(|code| Error::Exit(prog.utf8(), code))
+}
+
+fn dump<W>(path: &Path, w: &mut W) -> Result<(), Error>
+where
+    W: Write,
+{
+    let mut file = File::open(path).map_err(|e| Error::CouldntOpen(path.to_path_buf(), e))?;
+    std::io::copy(&mut file, w)
+        .map_err(|e| Error::CouldntOpen(path.to_path_buf(), e))
         .void()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dumped_without_pager() {
+        let path = Path::new("tests/simple-config.toml");
+        let mut out = Vec::new();
+        dump(path, &mut out).ok().unwrap();
+
+        assert_eq!(std::fs::read(path).unwrap(), out);
+    }
+
+    #[test]
+    fn pending_files() {
+        let files = pending(Path::new("tests/pacnew"));
+        let expected = vec![
+            PathBuf::from("tests/pacnew/pacman.conf.pacnew"),
+            PathBuf::from("tests/pacnew/pacman.d/mirrorlist.pacsave"),
+        ];
+
+        assert_eq!(expected, files);
+        assert!(pending(Path::new("tests/no-such-dir")).is_empty());
+        assert_eq!(
+            vec![PathBuf::from("tests/pacnew/pacman.d/mirrorlist.pacsave")],
+            with_extensions(Path::new("tests/pacnew"), &["pacsave"])
+        );
+    }
+
+    #[test]
+    fn clobbering() {
+        assert_eq!(Clobber::Write, clobber(false, false, false));
+        assert_eq!(Clobber::Write, clobber(false, true, false));
+        assert_eq!(Clobber::Refuse, clobber(true, false, false));
+        assert_eq!(Clobber::Write, clobber(true, false, true));
+        assert_eq!(Clobber::Confirm, clobber(true, true, false));
+        assert_eq!(Clobber::Write, clobber(true, true, true));
+    }
+
+    #[test]
+    fn validation() {
+        let good = std::fs::read_to_string("tests/simple-config.toml").unwrap();
+        assert!(crate::env::validate(&good).is_ok());
+        assert!(crate::env::validate("").is_ok());
+
+        let bad = "[general]\nnoconfirm = true\n\n[aur]\ngit = yes\n";
+        let err = crate::env::validate(bad).unwrap_err().to_string();
+        assert!(err.contains("line 5"), "{err}");
+    }
+}
@@ -1,14 +1,23 @@
 //! Output a dependency graph in DOT format.
 
+use crate::a;
+use crate::aln;
+use crate::error::Exited;
 use crate::error::Nested;
 use crate::localization::Localised;
+use crate::theme::Themed;
+use crate::utils::PathStr;
+use crate::utils::Succeeded;
 use applying::Apply;
 use aura_core::deps;
 use aura_core::deps::PkgGraph;
 use aura_core::Dbs;
+use colored::Colorize;
+use i18n_embed::fluent::FluentLanguageLoader;
 use i18n_embed_fl::fl;
 use log::error;
 use r2d2_alpm::Alpm;
+use std::io::ErrorKind;
 use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
@@ -18,6 +27,10 @@ use std::process::Stdio;
 pub(crate) enum Error {
     Io(std::io::Error),
     Stdin,
+    NoGraphviz,
+    Format(PathBuf),
+    Write(PathBuf, std::io::Error),
+    Exit(String, u8),
 }
 
 impl Nested for Error {
@@ -25,6 +38,10 @@ impl Nested for Error {
         match self {
             Error::Io(e) => error!("{e}"),
             Error::Stdin => {}
+            Error::NoGraphviz => {}
+            Error::Format(_) => {}
+            Error::Write(_, e) => error!("{e}"),
+            Error::Exit(_, _) => {}
         }
     }
 }
@@ -34,18 +51,75 @@ impl Localised for Error {
         match self {
             Error::Io(_) => fl!(fll, "deps-io"),
             Error::Stdin => fl!(fll, "deps-io"),
+            Error::NoGraphviz => fl!(fll, "deps-no-graphviz"),
+            Error::Format(p) => fl!(fll, "deps-format", file = p.utf8()),
+            Error::Write(p, _) => fl!(fll, "err-file-write", file = p.utf8()),
+            Error::Exit(cmd, code) => fl!(fll, "err-exit", cmd = cmd.as_str(), code = *code),
+        }
+    }
+}
+
+impl Exited for Error {
+    fn exit_code(&self) -> Option<u8> {
+        match self {
+            Error::Io(_) => None,
+            Error::Stdin => None,
+            Error::NoGraphviz => None,
+            Error::Format(_) => None,
+            Error::Write(_, _) => None,
+            Error::Exit(_, code) => Some(*code),
+        }
+    }
+}
+
+/// The format of a rendered graph, as determined by a file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// Raw DOT, which needs no rendering.
+    Dot,
+    Png,
+    Svg,
+    Pdf,
+}
+
+impl Format {
+    /// A path without an extension is written as raw DOT. Unrecognized
+    /// extensions yield `None`.
+    fn from_path(path: &Path) -> Option<Format> {
+        match path.extension() {
+            None => Some(Format::Dot),
+            Some(ext) => match ext.to_str()?.to_lowercase().as_str() {
+                "dot" | "gv" => Some(Format::Dot),
+                "png" => Some(Format::Png),
+                "svg" => Some(Format::Svg),
+                "pdf" => Some(Format::Pdf),
+                _ => None,
+            },
+        }
+    }
+
+    /// The `-T` flag to pass to Graphviz.
+    fn flag(self) -> &'static str {
+        match self {
+            Format::Dot => "-Tdot",
+            Format::Png => "-Tpng",
+            Format::Svg => "-Tsvg",
+            Format::Pdf => "-Tpdf",
         }
     }
 }
 
 /// Given some packages to focus on, output their combined dependency graph as a
 /// PNG, unless requested to be printed in raw DOT format.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn graph(
+    fll: &FluentLanguageLoader,
     alpm: &Alpm,
     limit: Option<u8>,
     optional: bool,
     raw: bool,
     open: bool,
+    output: Option<PathBuf>,
     packages: Vec<String>,
 ) -> Result<(), Error> {
     let db = Dbs::from_alpm(alpm);
@@ -55,23 +129,20 @@ pub(crate) fn graph(
         .collect();
     let graph = deps::PkgGraph::by_deps(&db, limit, optional, &foreigns, &pkgs);
 
-    if raw {
-        println!("{}", graph);
-    } else {
-        render(graph, &packages, open)?;
-    }
-
-    Ok(())
+    emit(fll, graph, &packages, raw, open, output)
 }
 
 /// Like [`graph`], but display all packages that depend on the given ones
 /// instead.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn reverse(
+    fll: &FluentLanguageLoader,
     alpm: &Alpm,
     limit: Option<u8>,
     optional: bool,
     raw: bool,
     open: bool,
+    output: Option<PathBuf>,
     packages: Vec<String>,
 ) -> Result<(), Error> {
     let db = Dbs::from_alpm(alpm);
@@ -81,16 +152,101 @@ pub(crate) fn reverse(
         .collect();
     let graph = deps::PkgGraph::by_parents(&db, limit, optional, &foreigns, &pkgs);
 
+    emit(fll, graph, &packages, raw, open, output)
+}
+
+/// Instead of the whole graph, print the packages at its top (`roots`) and
+/// bottom (`leaves`), one per line. When both are asked for, each list is given
+/// a heading.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn ends(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    reverse: bool,
+    limit: Option<u8>,
+    optional: bool,
+    roots: bool,
+    leaves: bool,
+    packages: Vec<String>,
+) {
+    let db = Dbs::from_alpm(alpm);
+    let pkgs: Vec<_> = packages.iter().map(|p| p.as_ref()).collect();
+    let foreigns: Vec<_> = aura_core::foreign_packages(alpm)
+        .map(|p| p.name())
+        .collect();
+    let graph = if reverse {
+        deps::PkgGraph::by_parents(&db, limit, optional, &foreigns, &pkgs)
+    } else {
+        deps::PkgGraph::by_deps(&db, limit, optional, &foreigns, &pkgs)
+    };
+
+    warn_cycles(fll, &graph);
+
+    let both = roots && leaves;
+    let lists = [
+        (roots, fl!(fll, "deps-roots"), graph.roots()),
+        (leaves, fl!(fll, "deps-leaves"), graph.leaves()),
+    ];
+
+    for (_, heading, names) in lists.iter().filter(|(wanted, _, _)| *wanted) {
+        if both {
+            aln!(heading);
+        }
+
+        for name in names {
+            println!("{name}");
+        }
+    }
+}
+
+/// Written to stderr, so as not to disturb any output on stdout.
+fn warn_cycles(fll: &FluentLanguageLoader, graph: &PkgGraph) {
+    for cycle in graph.cycles() {
+        let msg = fl!(fll, "deps-cycle", pkgs = cycle.join(", "));
+        eprintln!("{}", a!(msg.warning()));
+    }
+}
+
+fn emit(
+    fll: &FluentLanguageLoader,
+    graph: PkgGraph,
+    pkgs: &[String],
+    raw: bool,
+    open: bool,
+    output: Option<PathBuf>,
+) -> Result<(), Error> {
+    warn_cycles(fll, &graph);
+
     if raw {
         println!("{}", graph);
-    } else {
-        render(graph, &packages, open)?;
+        return Ok(());
+    }
+
+    let (path, format) = match output {
+        None => (default_path(pkgs, open), Format::Png),
+        Some(path) => match Format::from_path(&path) {
+            Some(format) => (path, format),
+            None => return Err(Error::Format(path)),
+        },
+    };
+
+    write(graph, &path, format)?;
+
+    if open {
+        Command::new("xdg-open")
+            .arg(path)
+            .status()
+            .map_err(Error::Io)?
+            .succeeded()
+            .map_err(|code| Error::Exit("xdg-open".to_string(), code))?;
     }
 
     Ok(())
 }
 
-fn render(graph: PkgGraph, pkgs: &[String], open: bool) -> Result<(), Error> {
+/// An image named after the focused packages, placed in `/tmp` if it's only
+/// going to be opened.
+fn default_path(pkgs: &[String], open: bool) -> PathBuf {
     let name: PathBuf = if pkgs.is_empty() {
         PathBuf::from("deps.png")
     } else {
@@ -100,27 +256,54 @@ fn render(graph: PkgGraph, pkgs: &[String], open: bool) -> Result<(), Error> {
             .apply(|p| p.with_extension("png"))
     };
 
-    let path = if open { Path::new("/tmp").join(name) } else { name };
+    if open {
+        Path::new("/tmp").join(name)
+    } else {
+        name
+    }
+}
+
+/// Write the graph to a file, rendering it with Graphviz if necessary.
+fn write(graph: PkgGraph, path: &Path, format: Format) -> Result<(), Error> {
+    if format == Format::Dot {
+        return std::fs::write(path, graph.to_string())
+            .map_err(|e| Error::Write(path.to_path_buf(), e));
+    }
 
     let mut child = Command::new("dot")
-        .arg("-Tpng")
+        .arg(format.flag())
         .arg("-o")
-        .arg(&path)
+        .arg(path)
         .stdin(Stdio::piped())
         .spawn()
-        .map_err(Error::Io)?;
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => Error::NoGraphviz,
+            _ => Error::Io(e),
+        })?;
 
     let g_string = graph.to_string();
     let mut stdin = child.stdin.take().ok_or(Error::Stdin)?;
     std::thread::spawn(move || stdin.write_all(g_string.as_bytes()));
-    child.wait().map_err(Error::Io)?;
+    child
+        .wait()
+        .map_err(Error::Io)?
+        .succeeded()
+        .map_err(|code| Error::Exit("dot".to_string(), code))
+}
 
-    if open {
-        Command::new("xdg-open")
-            .arg(path)
-            .status()
-            .map_err(Error::Io)?;
-    }
+#[cfg(test)]
+mod test {
+    use super::*;
 
-    Ok(())
+    #[test]
+    fn formats_by_extension() {
+        let format = |p: &str| Format::from_path(Path::new(p));
+
+        assert_eq!(Some(Format::Svg), format("rev.svg"));
+        assert_eq!(Some(Format::Png), format("/tmp/gcc.PNG"));
+        assert_eq!(Some(Format::Pdf), format("deps.pdf"));
+        assert_eq!(Some(Format::Dot), format("deps.dot"));
+        assert_eq!(Some(Format::Dot), format("deps"));
+        assert_eq!(None, format("deps.jpg"));
+    }
 }
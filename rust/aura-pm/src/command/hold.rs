@@ -0,0 +1,272 @@
+//! Holding packages back from sysupgrades.
+//!
+//! Held packages are recorded as the `holds` setting of the `[general]` section
+//! of `aura.toml`, and are passed as `--ignore` to every `-Syu` and `-Au` until
+//! they're released again.
+
+use crate::aura;
+use crate::env::Env;
+use crate::error::Nested;
+use crate::green;
+use crate::localization::Localised;
+use crate::utils::PathStr;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::error;
+use std::collections::BTreeSet;
+use std::ops::Not;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub(crate) enum Error {
+    Dirs(crate::dirs::Error),
+    FileOpen(PathBuf, std::io::Error),
+    FileWrite(PathBuf, std::io::Error),
+    Invalid(PathBuf, basic_toml::Error),
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Dirs(e) => e.nested(),
+            Error::FileOpen(_, e) => error!("{e}"),
+            Error::FileWrite(_, e) => error!("{e}"),
+            Error::Invalid(_, _) => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Dirs(e) => e.localise(fll),
+            Error::FileOpen(p, _) => fl!(fll, "err-file-open", file = p.utf8()),
+            Error::FileWrite(p, _) => fl!(fll, "err-file-write", file = p.utf8()),
+            Error::Invalid(p, e) => fl!(fll, "conf-invalid", file = p.utf8(), err = e.to_string()),
+        }
+    }
+}
+
+/// Hold the given packages, such that sysupgrades leave them alone.
+pub(crate) fn add(fll: &FluentLanguageLoader, packages: &[String]) -> Result<(), Error> {
+    let path = crate::dirs::aura_config().map_err(Error::Dirs)?;
+    let added = hold(&path, packages)?;
+
+    if added.is_empty() {
+        aura!(fll, "hold-already");
+    } else {
+        green!(fll, "hold-added", pkgs = added.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Release the given packages, such that sysupgrades consider them again.
+pub(crate) fn remove(fll: &FluentLanguageLoader, packages: &[String]) -> Result<(), Error> {
+    let path = crate::dirs::aura_config().map_err(Error::Dirs)?;
+    let released = release(&path, packages)?;
+
+    if released.is_empty() {
+        aura!(fll, "hold-not-held");
+    } else {
+        green!(fll, "hold-removed", pkgs = released.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Print the held packages, one per line.
+pub(crate) fn list(env: &Env) {
+    for p in env.general.holds.iter() {
+        println!("{p}");
+    }
+}
+
+/// The contents of the given config, along with the packages it holds. A
+/// missing config holds nothing.
+fn held(path: &Path) -> Result<(String, BTreeSet<String>), Error> {
+    let conf = match std::fs::read_to_string(path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(Error::FileOpen(path.to_path_buf(), e)),
+        Ok(s) => s,
+    };
+    let held = crate::env::holds(&conf).map_err(|e| Error::Invalid(path.to_path_buf(), e))?;
+
+    Ok((conf, held))
+}
+
+/// Add to the held packages, yielding those that weren't already held.
+fn hold(path: &Path, packages: &[String]) -> Result<Vec<String>, Error> {
+    let (conf, mut held) = held(path)?;
+    let added: Vec<_> = packages
+        .iter()
+        .filter(|p| held.insert(p.to_string()))
+        .cloned()
+        .collect();
+
+    if added.is_empty().not() {
+        record(path, &conf, &held)?;
+    }
+
+    Ok(added)
+}
+
+/// Remove from the held packages, yielding those that actually were held.
+fn release(path: &Path, packages: &[String]) -> Result<Vec<String>, Error> {
+    let (conf, mut held) = held(path)?;
+    let released: Vec<_> = packages
+        .iter()
+        .filter(|p| held.remove(p.as_str()))
+        .cloned()
+        .collect();
+
+    if released.is_empty().not() {
+        record(path, &conf, &held)?;
+    }
+
+    Ok(released)
+}
+
+/// Overwrite the held packages, leaving the rest of the config as it was.
+fn record(path: &Path, conf: &str, held: &BTreeSet<String>) -> Result<(), Error> {
+    let new = with_holds(conf, held);
+
+    // Never leave behind a config that Aura itself can't read.
+    crate::env::validate(&new).map_err(|e| Error::Invalid(path.to_path_buf(), e))?;
+    std::fs::write(path, new).map_err(|e| Error::FileWrite(path.to_path_buf(), e))
+}
+
+/// The given config with its `holds` setting replaced, or added to the
+/// `[general]` section if it had none. Comments and all other settings are kept
+/// as they were.
+fn with_holds(conf: &str, held: &BTreeSet<String>) -> String {
+    let setting = format!("holds = {:?}", held.iter().collect::<Vec<_>>());
+    let mut lines: Vec<&str> = conf.lines().collect();
+
+    match lines.iter().position(|l| l.trim() == "[general]") {
+        None => {
+            if lines.last().is_some_and(|l| l.trim().is_empty().not()) {
+                lines.push("");
+            }
+            lines.push("[general]");
+            lines.push(&setting);
+        }
+        Some(header) => {
+            let body = header + 1;
+            let end = lines[body..]
+                .iter()
+                .position(|l| l.trim_start().starts_with('['))
+                .map_or(lines.len(), |n| body + n);
+
+            match lines[body..end].iter().position(|l| is_holds(l)) {
+                None => lines.insert(body, &setting),
+                Some(n) => {
+                    let start = body + n;
+                    // The array may have been spread across several lines.
+                    let stop = lines[start..end]
+                        .iter()
+                        .position(|l| l.contains(']'))
+                        .map_or(start, |m| start + m);
+                    lines.drain(start..=stop);
+                    lines.insert(start, &setting);
+                }
+            }
+        }
+    }
+
+    let mut new = lines.join("\n");
+    new.push('\n');
+    new
+}
+
+/// Is this the line that begins the `holds` setting?
+fn is_holds(line: &str) -> bool {
+    line.trim_start()
+        .strip_prefix("holds")
+        .is_some_and(|rest| rest.trim_start().starts_with('='))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::TempDir;
+
+    fn pkgs(ps: &[&str]) -> Vec<String> {
+        ps.iter().map(|p| p.to_string()).collect()
+    }
+
+    fn set(ps: &[&str]) -> BTreeSet<String> {
+        ps.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn hold_add_remove_list() {
+        let dir = TempDir::new("holds");
+        let path = dir.join("config.toml");
+        let listed = |path: &Path| held(path).ok().unwrap().1;
+
+        // Nothing is held yet, and there's no config at all.
+        assert!(listed(&path).is_empty());
+
+        let added = hold(&path, &pkgs(&["linux", "nvidia", "linux"]))
+            .ok()
+            .unwrap();
+        assert_eq!(pkgs(&["linux", "nvidia"]), added);
+        assert_eq!(set(&["linux", "nvidia"]), listed(&path));
+
+        // Packages already held are passed over.
+        let added = hold(&path, &pkgs(&["nvidia", "mesa"])).ok().unwrap();
+        assert_eq!(pkgs(&["mesa"]), added);
+        assert_eq!(3, listed(&path).len());
+
+        // Only what was actually held is reported as released.
+        let released = release(&path, &pkgs(&["mesa", "firefox"])).ok().unwrap();
+        assert_eq!(pkgs(&["mesa"]), released);
+        assert_eq!(set(&["linux", "nvidia"]), listed(&path));
+
+        // Releasing everything leaves nothing held.
+        release(&path, &pkgs(&["linux", "nvidia"])).ok().unwrap();
+        assert!(listed(&path).is_empty());
+        assert!(release(&path, &pkgs(&["linux"])).ok().unwrap().is_empty());
+
+        // A config that can't be read is left alone.
+        std::fs::write(&path, "[general\n").unwrap();
+        assert!(hold(&path, &pkgs(&["linux"])).is_err());
+        assert_eq!("[general\n", std::fs::read_to_string(&path).unwrap());
+    }
+
+    #[test]
+    fn holds_rewritten_in_place() {
+        let held = set(&["linux", "nvidia"]);
+        let conf = "# Mine.\n[general]\n# Held.\nholds = [\n  \"mesa\",\n]\ndoas = true\n\n[aur]\ngit = true\n";
+        let exp = "# Mine.\n[general]\n# Held.\nholds = [\"linux\", \"nvidia\"]\ndoas = true\n\n[aur]\ngit = true\n";
+        assert_eq!(exp, with_holds(conf, &held));
+
+        // Added to an existing section.
+        let conf = "[aur]\ngit = true\n\n[general]\ndoas = true\n";
+        let exp = "[aur]\ngit = true\n\n[general]\nholds = [\"linux\", \"nvidia\"]\ndoas = true\n";
+        assert_eq!(exp, with_holds(conf, &held));
+
+        // A `holds` of some other section is left alone.
+        let conf = "[general]\ndoas = true\n\n[other]\nholds = []\n";
+        let exp = "[general]\nholds = []\ndoas = true\n\n[other]\nholds = []\n";
+        assert_eq!(exp, with_holds(conf, &BTreeSet::new()));
+
+        // Or with no section at all.
+        let exp = "[aur]\ngit = true\n\n[general]\nholds = [\"linux\", \"nvidia\"]\n";
+        assert_eq!(exp, with_holds("[aur]\ngit = true\n", &held));
+        assert_eq!("[general]\nholds = []\n", with_holds("", &BTreeSet::new()));
+
+        for conf in [exp, "[general]\nholds = []\n"] {
+            assert!(crate::env::validate(conf).is_ok());
+        }
+    }
+
+    #[test]
+    fn holds_line() {
+        assert!(is_holds("holds = []"));
+        assert!(is_holds("  holds=[\"linux\"]"));
+        assert!(is_holds("holds_extra = 1").not());
+        assert!(is_holds("# holds = []").not());
+    }
+}
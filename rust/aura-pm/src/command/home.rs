@@ -0,0 +1,308 @@
+//! Declarative management of the explicitly installed package set.
+//!
+//! The packages a user wants are listed in `~/.config/aura/home.toml`:
+//!
+//! ```toml
+//! packages = [
+//!     "base",
+//!     "git",
+//! ]
+//! ```
+
+use crate::aura;
+use crate::command::aur;
+use crate::command::aur::Mode;
+use crate::command::orphans;
+use crate::env::Env;
+use crate::error::Exited;
+use crate::error::Nested;
+use crate::green;
+use crate::localization::Localised;
+use crate::proceed;
+use crate::theme::Themed;
+use crate::utils::PathStr;
+use alpm::PackageReason;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::error;
+use r2d2_alpm::Alpm;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::ops::Not;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub(crate) enum Error {
+    Dirs(crate::dirs::Error),
+    NoHome(PathBuf),
+    Read(PathBuf, std::io::Error),
+    Parse(PathBuf, basic_toml::Error),
+    Write(PathBuf, std::io::Error),
+    Pacman(crate::pacman::Error),
+    Aur(Box<aur::Error>),
+    Orphans(orphans::Error),
+    Drift(usize),
+    Cancelled,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Dirs(e) => e.nested(),
+            Error::NoHome(_) => {}
+            Error::Read(_, e) => error!("{e}"),
+            Error::Parse(_, e) => error!("{e}"),
+            Error::Write(_, e) => error!("{e}"),
+            Error::Pacman(e) => e.nested(),
+            Error::Aur(e) => e.nested(),
+            Error::Orphans(e) => e.nested(),
+            Error::Drift(_) => {}
+            Error::Cancelled => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Dirs(e) => e.localise(fll),
+            Error::NoHome(p) => fl!(fll, "home-none", path = p.utf8()),
+            Error::Read(p, _) => fl!(fll, "err-file-open", file = p.utf8()),
+            Error::Parse(p, _) => fl!(fll, "home-parse", path = p.utf8()),
+            Error::Write(p, _) => fl!(fll, "err-file-write", file = p.utf8()),
+            Error::Pacman(e) => e.localise(fll),
+            Error::Aur(e) => e.localise(fll),
+            Error::Orphans(e) => e.localise(fll),
+            Error::Drift(n) => fl!(fll, "home-drift", n = *n),
+            Error::Cancelled => fl!(fll, "common-cancelled"),
+        }
+    }
+}
+
+impl Exited for Error {
+    fn exit_code(&self) -> Option<u8> {
+        match self {
+            Error::Dirs(_) => None,
+            Error::NoHome(_) => None,
+            Error::Read(_, _) => None,
+            Error::Parse(_, _) => None,
+            Error::Write(_, _) => None,
+            Error::Pacman(e) => e.exit_code(),
+            Error::Aur(e) => e.exit_code(),
+            Error::Orphans(e) => e.exit_code(),
+            Error::Drift(_) => None,
+            Error::Cancelled => None,
+        }
+    }
+}
+
+/// The contents of `home.toml`.
+///
+/// Unknown keys are ignored, such that configs written for newer versions of
+/// Aura remain readable by older ones.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Declared {
+    /// Packages that should be explicitly installed.
+    packages: BTreeSet<String>,
+}
+
+/// The difference between `home.toml` and the live system.
+#[derive(Debug, PartialEq, Eq)]
+struct Drift {
+    /// Declared, but not explicitly installed.
+    missing: Vec<String>,
+    /// Explicitly installed, but not declared.
+    undeclared: Vec<String>,
+}
+
+impl Drift {
+    fn new(declared: &BTreeSet<String>, explicit: &BTreeSet<String>) -> Drift {
+        Drift {
+            missing: declared.difference(explicit).cloned().collect(),
+            undeclared: explicit.difference(declared).cloned().collect(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.missing.len() + self.undeclared.len()
+    }
+}
+
+/// Report any drift between `home.toml` and the system.
+pub(crate) fn status(fll: &FluentLanguageLoader, alpm: &Alpm) -> Result<(), Error> {
+    let drift = drift(alpm)?;
+    report(fll, &drift);
+    Ok(())
+}
+
+/// Like [`status`], but fails if there is any drift.
+pub(crate) fn check(fll: &FluentLanguageLoader, alpm: &Alpm) -> Result<(), Error> {
+    let drift = drift(alpm)?;
+    report(fll, &drift);
+
+    match drift.len() {
+        0 => Ok(()),
+        n => Err(Error::Drift(n)),
+    }
+}
+
+/// Bring the system in line with `home.toml`: missing packages are installed
+/// (or marked as explicit, if already present as dependencies) and undeclared
+/// ones are marked as dependencies.
+pub(crate) fn sync(env: &Env, fll: &FluentLanguageLoader, alpm: &Alpm) -> Result<(), Error> {
+    let drift = drift(alpm)?;
+    report(fll, &drift);
+
+    if drift.len() == 0 {
+        return Ok(());
+    }
+
+    proceed!(fll, env, "proceed").ok_or(Error::Cancelled)?;
+
+    let db = alpm.as_ref().localdb();
+    let syncs = alpm.as_ref().syncdbs();
+    let (installed, absent): (Vec<_>, Vec<_>) = drift
+        .missing
+        .into_iter()
+        .partition(|p| db.pkg(p.as_str()).is_ok());
+    let (repo, foreign): (Vec<_>, Vec<_>) = absent
+        .into_iter()
+        .partition(|p| syncs.pkg(p.as_str()).is_ok());
+
+    if installed.is_empty().not() {
+        orphans::adopt(env, alpm, fll, installed).map_err(Error::Orphans)?;
+    }
+
+    if repo.is_empty().not() {
+        crate::pacman::pacman_install_from_repos(env, ["--asexplicit"], &repo)
+            .map_err(Error::Pacman)?;
+    }
+
+    if foreign.is_empty().not() {
+        aur::install(fll, env, Mode::Install, foreign.iter().map(|p| p.as_str()))
+            .map_err(|e| Error::Aur(Box::new(e)))?;
+    }
+
+    if drift.undeclared.is_empty().not() {
+        orphans::disown(env, alpm, fll, drift.undeclared).map_err(Error::Orphans)?;
+    }
+
+    green!(fll, "common-done");
+    Ok(())
+}
+
+/// Write the current set of explicitly installed packages to `home.toml`.
+pub(crate) fn dump(env: &Env, fll: &FluentLanguageLoader, alpm: &Alpm) -> Result<(), Error> {
+    let path = crate::dirs::home_toml().map_err(Error::Dirs)?;
+
+    if path.is_file() {
+        let file = path.utf8();
+        proceed!(fll, env, "home-overwrite", file = file).ok_or(Error::Cancelled)?;
+    }
+
+    let content = render(&explicits(alpm));
+    std::fs::write(&path, content).map_err(|e| Error::Write(path.clone(), e))?;
+
+    let file = path.utf8();
+    green!(fll, "home-dumped", file = file);
+    Ok(())
+}
+
+fn drift(alpm: &Alpm) -> Result<Drift, Error> {
+    let path = crate::dirs::home_toml().map_err(Error::Dirs)?;
+    let declared = read(&path)?;
+
+    Ok(Drift::new(&declared.packages, &explicits(alpm)))
+}
+
+fn read(path: &Path) -> Result<Declared, Error> {
+    if path.is_file().not() {
+        return Err(Error::NoHome(path.to_path_buf()));
+    }
+
+    let content = std::fs::read_to_string(path).map_err(|e| Error::Read(path.to_path_buf(), e))?;
+    basic_toml::from_str(&content).map_err(|e| Error::Parse(path.to_path_buf(), e))
+}
+
+/// The names of all explicitly installed packages.
+fn explicits(alpm: &Alpm) -> BTreeSet<String> {
+    alpm.as_ref()
+        .localdb()
+        .pkgs()
+        .into_iter()
+        .filter(|p| p.reason() == PackageReason::Explicit)
+        .map(|p| p.name().to_string())
+        .collect()
+}
+
+fn report(fll: &FluentLanguageLoader, drift: &Drift) {
+    if drift.len() == 0 {
+        green!(fll, "home-ok");
+        return;
+    }
+
+    if drift.missing.is_empty().not() {
+        aura!(fll, "home-missing");
+        drift
+            .missing
+            .iter()
+            .for_each(|p| println!("  {}", p.success()));
+    }
+
+    if drift.undeclared.is_empty().not() {
+        aura!(fll, "home-undeclared");
+        drift
+            .undeclared
+            .iter()
+            .for_each(|p| println!("  {}", p.warning()));
+    }
+}
+
+/// A fresh `home.toml`, with one package per line.
+fn render(packages: &BTreeSet<String>) -> String {
+    let mut s = String::from("# Packages that should be explicitly installed.\npackages = [\n");
+
+    for p in packages {
+        s.push_str(&format!("    \"{p}\",\n"));
+    }
+
+    s.push_str("]\n");
+    s
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn set(ps: &[&str]) -> BTreeSet<String> {
+        ps.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn drift() {
+        let declared = set(&["base", "git", "emacs"]);
+        let explicit = set(&["base", "git", "firefox"]);
+        let expected = Drift {
+            missing: vec!["emacs".to_string()],
+            undeclared: vec!["firefox".to_string()],
+        };
+
+        assert_eq!(expected, Drift::new(&declared, &explicit));
+    }
+
+    #[test]
+    fn unknown_keys() {
+        let toml = "packages = [\"base\"]\nhooks = [\"foo\"]\n\n[services]\nsshd = true\n";
+        let declared: Declared = basic_toml::from_str(toml).unwrap();
+        assert_eq!(set(&["base"]), declared.packages);
+    }
+
+    #[test]
+    fn render_round_trip() {
+        let packages = set(&["base", "git"]);
+        let declared: Declared = basic_toml::from_str(&render(&packages)).unwrap();
+        assert_eq!(packages, declared.packages);
+    }
+}
@@ -3,15 +3,21 @@
 #![allow(clippy::many_single_char_names)]
 
 use crate::command::misc;
+use crate::error::Exited;
 use crate::error::Nested;
 use crate::localization::Localised;
-use crate::utils::ResultVoid;
+use crate::theme::Themed;
+use crate::utils::Succeeded;
+use crate::yellow;
+use aura_core::logs::Event;
 use aura_pm::Date;
 use colored::*;
 use i18n_embed::fluent::FluentLanguageLoader;
 use i18n_embed::LanguageLoader;
 use i18n_embed_fl::fl;
 use log::error;
+use r2d2_alpm::Alpm;
+use std::borrow::Cow;
 use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
@@ -20,11 +26,14 @@ use std::io::Write;
 use std::ops::Deref;
 use std::path::Path;
 use std::process::Command;
+use time::macros::format_description;
+use time::PrimitiveDateTime;
 
 pub(crate) enum Error {
     Search(&'static str, std::io::Error),
     View(std::io::Error),
     Info(std::io::Error),
+    Exit(String, u8),
 }
 
 impl Nested for Error {
@@ -33,6 +42,7 @@ impl Nested for Error {
             Error::Search(_, e) => error!("{e}"),
             Error::View(e) => error!("{e}"),
             Error::Info(e) => error!("{e}"),
+            Error::Exit(_, _) => {}
         }
     }
 }
@@ -44,33 +54,63 @@ impl Localised for Error {
             Error::Search(s, _) => fl!(fll, "L-search-err", cmd = s.deref()),
             Error::View(_) => fl!(fll, "L-view-err"),
             Error::Info(_) => fl!(fll, "err-write"),
+            Error::Exit(cmd, code) => fl!(fll, "err-exit", cmd = cmd.as_str(), code = *code),
+        }
+    }
+}
+
+impl Exited for Error {
+    fn exit_code(&self) -> Option<u8> {
+        match self {
+            Error::Search(_, _) => None,
+            Error::View(_) => None,
+            Error::Info(_) => None,
+            Error::Exit(_, code) => Some(*code),
         }
     }
 }
 
 /// Search the Pacman log for a matching string.
-pub(crate) fn search(path: &Path, term: String) -> Result<(), Error> {
+pub(crate) fn search(fll: &FluentLanguageLoader, path: &Path, term: String) -> Result<(), Error> {
     let (search, args) = misc::searcher();
-    Command::new(search)
+    let status = Command::new(search)
         .args(args)
         .arg(term)
         .arg(path)
         .status()
-        .map_err(|e| Error::Search(search, e))
-        .void()
+        .map_err(|e| Error::Search(search, e))?;
+
+    // Both `rg` and `grep` exit with 1 when nothing matched, which isn't an
+    // error in itself.
+    match status.succeeded() {
+        Err(1) => {
+            yellow!(fll, "L-search-none");
+            Ok(())
+        }
+        r => r.map_err(|code| Error::Exit(search.to_string(), code)),
+    }
 }
 
 /// Display install/upgrade history for the given packages.
-pub(crate) fn info(fll: &FluentLanguageLoader, path: &Path, pks: Vec<String>) -> Result<(), Error> {
-    info_work(fll, path, pks).map_err(Error::Info)
+pub(crate) fn info(
+    fll: &FluentLanguageLoader,
+    path: &Path,
+    relative: bool,
+    pks: Vec<String>,
+) -> Result<(), Error> {
+    info_work(fll, path, relative, pks).map_err(Error::Info)
 }
 
 fn info_work(
     fll: &FluentLanguageLoader,
     path: &Path,
+    relative: bool,
     pks: Vec<String>,
 ) -> Result<(), std::io::Error> {
     let mut w = BufWriter::new(std::io::stdout());
+    // Pacman stamps its log in local time.
+    let now = aura_pm::now_local();
+    let now = PrimitiveDateTime::new(now.date(), now.time());
 
     let p = fl!(fll, "common-name");
     let f = fl!(fll, "L-first");
@@ -83,7 +123,7 @@ fn info_work(
     {
         let pairs: Vec<(&str, ColoredString)> = vec![
             (&p, e.package.normal()),
-            (&f, e.installed.normal()),
+            (&f, installed(fll, relative, &e.installed, now).normal()),
             (&u, format!("{}", e.upgrades).normal()),
             (&r, "".normal()),
         ];
@@ -98,6 +138,138 @@ fn info_work(
     Ok(())
 }
 
+/// When a package was first installed, or with `relative`, how long before
+/// `now` it was. Stamps that can't be read are shown as they are.
+fn installed(
+    fll: &FluentLanguageLoader,
+    relative: bool,
+    stamp: &str,
+    now: PrimitiveDateTime,
+) -> String {
+    // Older logs separate the date and time with a space.
+    let format = format_description!("[year]-[month]-[day]T[hour]:[minute]");
+
+    match PrimitiveDateTime::parse(&stamp.replacen(' ', "T", 1), format) {
+        Ok(then) if relative => crate::utils::ago(fll, then, now),
+        _ => stamp.to_string(),
+    }
+}
+
+/// Display the changelogs of every package changed by the most recent upgrade.
+pub(crate) fn changelogs(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    path: &Path,
+) -> Result<(), Error> {
+    let pkgs = aura_core::logs::last_upgrade(path).map_err(Error::View)?;
+
+    if pkgs.is_empty() {
+        yellow!(fll, "L-no-upgrade");
+        return Ok(());
+    }
+
+    changelogs_work(fll, alpm, pkgs).map_err(Error::Info)
+}
+
+fn changelogs_work(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    pkgs: Vec<String>,
+) -> Result<(), std::io::Error> {
+    let db = alpm.as_ref().localdb();
+    let mut w = BufWriter::new(std::io::stdout());
+
+    // Packages removed since the upgrade have nothing to show.
+    for pkg in pkgs.iter().filter_map(|p| db.pkg(p.as_str()).ok()) {
+        writeln!(w, "{}", pkg.name().bold().highlight())?;
+
+        match pkg.changelog() {
+            Ok(mut log) => {
+                std::io::copy(&mut log, &mut w)?;
+            }
+            Err(_) => writeln!(w, "{}", fl!(fll, "L-no-changelog").dimmed())?,
+        }
+
+        writeln!(w)?;
+    }
+
+    Ok(())
+}
+
+/// The machine-readable formats of `-L` history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Format {
+    Csv,
+    Json,
+}
+
+/// Output every recorded package change as one record each, possibly narrowed
+/// to certain packages, a search term, or dates.
+pub(crate) fn history(
+    path: &Path,
+    pks: &[String],
+    search: Option<&str>,
+    before: Option<Date>,
+    after: Option<Date>,
+    format: Format,
+) -> Result<(), Error> {
+    let events = aura_core::logs::events(path, pks, search).map_err(Error::View)?;
+    let events = dated(events, before.as_ref(), after.as_ref());
+    let mut w = BufWriter::new(std::io::stdout());
+
+    match format {
+        Format::Csv => write_csv(&mut w, &events),
+        Format::Json => serde_json::to_writer_pretty(&mut w, &events)
+            .map_err(std::io::Error::from)
+            .and_then(|_| writeln!(w)),
+    }
+    .map_err(Error::Info)
+}
+
+/// Only the events that fall between the given dates.
+fn dated(events: Vec<Event>, before: Option<&Date>, after: Option<&Date>) -> Vec<Event> {
+    events
+        .into_iter()
+        .filter(|e| stamped_within(&e.timestamp, before, after))
+        .collect()
+}
+
+/// Does the date of a log timestamp fall between the given dates?
+fn stamped_within(timestamp: &str, before: Option<&Date>, after: Option<&Date>) -> bool {
+    timestamp
+        .get(..10)
+        .and_then(|d| d.parse::<Date>().ok())
+        .is_some_and(|d| within(&d, before, after))
+}
+
+fn write_csv<W>(w: &mut W, events: &[Event]) -> Result<(), std::io::Error>
+where
+    W: Write,
+{
+    writeln!(w, "package,action,version,timestamp")?;
+
+    for e in events {
+        let fields = [&e.package, &e.action, &e.version, &e.timestamp].map(|f| csv_field(f));
+        writeln!(w, "{}", fields.join(","))?;
+    }
+
+    Ok(())
+}
+
+/// Quote a CSV field if it must be, doubling any quotes within it.
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Is the date on or after `after`, and before `before`?
+fn within(date: &Date, before: Option<&Date>, after: Option<&Date>) -> bool {
+    after.map(|a| date.0 >= a.0).unwrap_or(true) && before.map(|b| date.0 < b.0).unwrap_or(true)
+}
+
 /// Output the content of the Pacman/ALPM log, possibly filtered by date.
 pub(crate) fn view(path: &Path, before: Option<Date>, after: Option<Date>) -> Result<(), Error> {
     view_work(path, before, after).map_err(Error::View)
@@ -118,15 +290,100 @@ fn view_work(path: &Path, before: Option<Date>, after: Option<Date>) -> Result<(
             .collect::<String>()
             .parse::<Date>()
         {
-            match (&after, &before) {
-                (Some(a), Some(b)) if date.0 >= a.0 && date.0 < b.0 => writeln!(out, "{}", line)?,
-                (Some(a), None) if date.0 >= a.0 => writeln!(out, "{}", line)?,
-                (None, Some(b)) if date.0 < b.0 => writeln!(out, "{}", line)?,
-                (None, None) => writeln!(out, "{}", line)?,
-                (_, _) => {}
+            if within(&date, before.as_ref(), after.as_ref()) {
+                writeln!(out, "{}", line)?;
             }
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use time::macros::datetime;
+
+    fn event(package: &str, version: &str, timestamp: &str) -> Event {
+        Event {
+            package: package.to_string(),
+            action: "upgraded".to_string(),
+            version: version.to_string(),
+            from_version: Some("2.45.1-1".to_string()),
+            to_version: Some(version.to_string()),
+            timestamp: timestamp.to_string(),
+        }
+    }
+
+    #[test]
+    fn csv_history() {
+        let events = [
+            event("git", "2.46.0-1", "2024-08-01T09:12:59+0900"),
+            event("odd,name", "1:2.0-1", "2024-08-02T10:00:00+0900"),
+            event("quoted\"name", "1.0-1", "2024-08-03T10:00:00+0900"),
+        ];
+        let mut out = Vec::new();
+        write_csv(&mut out, &events).unwrap();
+
+        let exp = "\
+package,action,version,timestamp
+git,upgraded,2.46.0-1,2024-08-01T09:12:59+0900
+\"odd,name\",upgraded,1:2.0-1,2024-08-02T10:00:00+0900
+\"quoted\"\"name\",upgraded,1.0-1,2024-08-03T10:00:00+0900
+";
+        assert_eq!(exp, String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn dated_history() {
+        let events = || {
+            vec![
+                event("git", "2.45.2-1", "2024-07-31T23:59:00+0900"),
+                event("git", "2.46.0-1", "2024-08-01T09:12:59+0900"),
+                event("git", "2.46.1-1", "2024-08-15T09:12:59+0900"),
+                event("git", "2.47.0-1", "garbage"),
+            ]
+        };
+        let versions = |es: Vec<Event>| es.into_iter().map(|e| e.version).collect::<Vec<_>>();
+        let date = |s: &str| s.parse::<Date>().unwrap();
+
+        assert_eq!(3, dated(events(), None, None).len());
+        assert_eq!(
+            vec!["2.46.0-1"],
+            versions(dated(
+                events(),
+                Some(&date("2024-08-15")),
+                Some(&date("2024-08-01"))
+            ))
+        );
+        assert_eq!(
+            vec!["2.45.2-1"],
+            versions(dated(events(), Some(&date("2024-08-01")), None))
+        );
+
+        let json = serde_json::to_value(&events()[..1]).unwrap();
+        assert_eq!("git", json[0]["package"]);
+        assert_eq!("upgraded", json[0]["action"]);
+        assert_eq!("2.45.2-1", json[0]["version"]);
+        assert_eq!("2.45.1-1", json[0]["from_version"]);
+        assert_eq!("2.45.2-1", json[0]["to_version"]);
+        assert_eq!("2024-07-31T23:59:00+0900", json[0]["timestamp"]);
+    }
+
+    #[test]
+    fn install_stamps() {
+        let fll = crate::localization::load(None).unwrap();
+        let now = datetime!(2023-06-03 12:00);
+
+        assert_eq!(
+            "2023-06-01T12:00",
+            installed(&fll, false, "2023-06-01T12:00", now)
+        );
+        assert_eq!("2 days ago", installed(&fll, true, "2023-06-01T12:00", now));
+        assert_eq!(
+            "3 hours ago",
+            installed(&fll, true, "2023-06-03 09:00", now)
+        );
+        assert_eq!("garbage", installed(&fll, true, "garbage", now));
+    }
+}
@@ -1,11 +1,10 @@
 //! Miscellaneous functionality.
 
 use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
 
-/// Expected location of the `bat` executable if installed from official repos.
-const BAT: &str = "/bin/bat";
-
-/// Expected location of the `less` executable.
+/// Expected location of the `less` executable, if all else fails.
 const LESS: &str = "/bin/less";
 
 /// Expected location of the `ripgrep` executable.
@@ -14,16 +13,54 @@ const RIPGREP: &str = "/bin/rg";
 /// Expected location of the `grep` executable.
 const GREP: &str = "/bin/grep";
 
-/// A complete path to a file viewer program like `less`.
-pub(crate) fn viewer() -> &'static str {
-    let bat = Path::new(BAT);
-    if bat.exists() {
-        BAT
-    } else {
-        LESS
+/// A program chosen by the user, like `delta --side-by-side`, found on the
+/// `$PATH`.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Program {
+    pub(crate) path: PathBuf,
+    /// Any extra arguments given along with the program's name.
+    pub(crate) args: Vec<String>,
+}
+
+impl Program {
+    /// Find a chosen program, if it exists.
+    pub(crate) fn find(chosen: &str) -> Option<Program> {
+        let mut words = chosen.split_whitespace();
+        let path = words.next().and_then(|p| which::which(p).ok())?;
+        let args = words.map(|w| w.to_string()).collect();
+
+        Some(Program { path, args })
+    }
+
+    /// A call to this program, ready for more arguments.
+    pub(crate) fn command(&self) -> Command {
+        let mut cmd = Command::new(&self.path);
+        cmd.args(&self.args);
+        cmd
     }
 }
 
+/// A complete path to a file viewer program like `less`.
+///
+/// In order of preference: whatever `$PAGER` names, `bat`, then `less`. Only the
+/// program itself is taken from `$PAGER`, not any extra arguments it contains.
+pub(crate) fn viewer() -> Option<PathBuf> {
+    std::env::var("PAGER")
+        .ok()
+        .and_then(|pager| {
+            pager
+                .split_whitespace()
+                .next()
+                .and_then(|p| which::which(p).ok())
+        })
+        .or_else(|| which::which("bat").ok())
+        .or_else(|| which::which("less").ok())
+        .or_else(|| {
+            let less = Path::new(LESS);
+            less.exists().then(|| less.to_path_buf())
+        })
+}
+
 /// A complete path to a file searcher program like `grep`, along with any extra
 /// arguments needed to affect the exact output.
 pub(crate) fn searcher() -> (&'static str, &'static [&'static str]) {
@@ -34,3 +71,18 @@ pub(crate) fn searcher() -> (&'static str, &'static [&'static str]) {
         (GREP, &[])
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn found_programs() {
+        let sh = Program::find("sh -e -u").unwrap();
+        assert!(sh.path.is_absolute());
+        assert_eq!(vec!["-e", "-u"], sh.args);
+
+        assert_eq!(None, Program::find("aura-no-such-program --flag"));
+        assert_eq!(None, Program::find("  "));
+    }
+}
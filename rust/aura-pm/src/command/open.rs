@@ -0,0 +1,268 @@
+//! Opening webpages related to Aura.
+
+use crate::command::misc::Program;
+use crate::env::Env;
+use crate::error::Exited;
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::utils::Succeeded;
+use aura_pm::flags::Open;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::error;
+use std::io::Write;
+use std::process::Command;
+
+/// The program that opens webpages when no other has been chosen.
+const XDG_OPEN: &str = "xdg-open";
+
+pub(crate) enum Error {
+    CouldntOpen(String, std::io::Error),
+    /// `xdg-open` isn't installed, and no other browser was chosen.
+    NoBrowser,
+    /// The chosen browser isn't on the `$PATH`.
+    NoSuchBrowser(String),
+    Stdout(std::io::Error),
+    Exit(String, u8),
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::CouldntOpen(_, e) => error!("{e}"),
+            Error::NoBrowser => {}
+            Error::NoSuchBrowser(_) => {}
+            Error::Stdout(e) => error!("{e}"),
+            Error::Exit(_, _) => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::CouldntOpen(url, _) => fl!(fll, "open-err", url = url.as_str()),
+            Error::NoBrowser => fl!(fll, "open-no-browser"),
+            Error::NoSuchBrowser(b) => fl!(fll, "open-no-such-browser", prog = b.as_str()),
+            Error::Stdout(_) => fl!(fll, "err-write"),
+            Error::Exit(cmd, code) => fl!(fll, "err-exit", cmd = cmd.as_str(), code = *code),
+        }
+    }
+}
+
+impl Exited for Error {
+    fn exit_code(&self) -> Option<u8> {
+        match self {
+            Error::CouldntOpen(_, _) => None,
+            Error::NoBrowser => None,
+            Error::NoSuchBrowser(_) => None,
+            Error::Stdout(_) => None,
+            Error::Exit(_, code) => Some(*code),
+        }
+    }
+}
+
+/// A webpage that `aura open` knows about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Page {
+    Docs,
+    Repo,
+    Bug,
+    License,
+    Aur,
+}
+
+impl Page {
+    /// The page selected on the command line. Defaults to the documentation.
+    fn from_flags(o: &Open) -> Page {
+        match () {
+            _ if o.repo => Page::Repo,
+            _ if o.bug => Page::Bug,
+            _ if o.license => Page::License,
+            _ if o.aur => Page::Aur,
+            _ => Page::Docs,
+        }
+    }
+
+    /// The URL of this page, respecting any overrides in the `[open]` config.
+    fn url(self, urls: &crate::env::Open) -> &str {
+        match self {
+            Page::Docs => &urls.docs_url,
+            Page::Repo => &urls.repo_url,
+            Page::Bug => &urls.bug_url,
+            Page::License => &urls.license_url,
+            Page::Aur => &urls.aur_url,
+        }
+    }
+}
+
+/// Open the requested webpage in a browser, or just print its URL. Aura's man
+/// page is read with `man` instead.
+pub(crate) fn open(env: &Env, o: &Open) -> Result<(), Error> {
+    if o.man {
+        return man();
+    }
+
+    let url = Page::from_flags(o).url(&env.open);
+    let mut stdout = std::io::stdout().lock();
+    let chosen = o.browser.as_deref().or(env.open.browser.as_deref());
+
+    open_with(&mut stdout, url, o.print, |url| browse(chosen, url))
+}
+
+fn open_with<W, F>(w: &mut W, url: &str, print: bool, launch: F) -> Result<(), Error>
+where
+    W: Write,
+    F: FnOnce(&str) -> Result<(), Error>,
+{
+    if print {
+        writeln!(w, "{url}").map_err(Error::Stdout)
+    } else {
+        launch(url)
+    }
+}
+
+/// The program to open pages with: the one chosen on the command line or in
+/// config, otherwise `xdg-open`.
+fn browser(chosen: Option<&str>) -> Result<Program, Error> {
+    match chosen {
+        Some(b) => Program::find(b).ok_or_else(|| Error::NoSuchBrowser(b.to_string())),
+        None => Program::find(XDG_OPEN).ok_or(Error::NoBrowser),
+    }
+}
+
+fn browse(chosen: Option<&str>, url: &str) -> Result<(), Error> {
+    let browser = browser(chosen)?;
+
+    browser
+        .command()
+        .arg(url)
+        .status()
+        .map_err(|e| Error::CouldntOpen(url.to_string(), e))?
+        .succeeded()
+        .map_err(|code| Error::Exit(browser.path.display().to_string(), code))
+}
+
+fn man() -> Result<(), Error> {
+    Command::new("man")
+        .arg("aura")
+        .status()
+        .map_err(|e| Error::CouldntOpen("man aura".to_string(), e))?
+        .succeeded()
+        .map_err(|code| Error::Exit("man".to_string(), code))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PAGES: &[Page] = &[Page::Docs, Page::Repo, Page::Bug, Page::License, Page::Aur];
+
+    #[test]
+    fn overridden_urls() {
+        let urls = crate::env::Open {
+            docs_url: "https://mirror.example.com/docs".to_string(),
+            repo_url: "https://mirror.example.com/repo".to_string(),
+            bug_url: "https://mirror.example.com/bug".to_string(),
+            license_url: "https://mirror.example.com/license".to_string(),
+            aur_url: "https://mirror.example.com/aur".to_string(),
+            browser: None,
+        };
+        let expected = ["docs", "repo", "bug", "license", "aur"];
+
+        for (page, exp) in PAGES.iter().zip(expected) {
+            assert_eq!(format!("https://mirror.example.com/{exp}"), page.url(&urls));
+        }
+    }
+
+    #[test]
+    fn default_urls() {
+        let urls = crate::env::Open {
+            repo_url: "https://mirror.example.com/repo".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!("https://mirror.example.com/repo", Page::Repo.url(&urls));
+        assert_eq!(crate::env::DOCS_URL, Page::Docs.url(&urls));
+        assert_eq!(crate::env::BUG_URL, Page::Bug.url(&urls));
+        assert_eq!(crate::env::LICENSE_URL, Page::License.url(&urls));
+        assert_eq!(crate::env::AUR_URL, Page::Aur.url(&urls));
+    }
+
+    #[test]
+    fn print_only() {
+        use aura_pm::flags::Args;
+        use aura_pm::flags::SubCmd;
+        use clap::Parser;
+
+        let urls = crate::env::Open {
+            bug_url: "https://mirror.example.com/bug".to_string(),
+            ..Default::default()
+        };
+        let cases = [
+            ("--docs", crate::env::DOCS_URL),
+            ("--repo", crate::env::REPO_URL),
+            ("--bug", "https://mirror.example.com/bug"),
+            ("--license", crate::env::LICENSE_URL),
+            ("--aur", crate::env::AUR_URL),
+        ];
+
+        for (flag, expected) in cases {
+            let o = match Args::try_parse_from(["aura", "open", flag, "--print"])
+                .unwrap()
+                .subcmd
+            {
+                SubCmd::Open(o) => o,
+                _ => panic!("Expected open"),
+            };
+            let mut out = Vec::new();
+            let url = Page::from_flags(&o).url(&urls);
+
+            open_with(&mut out, url, o.print, |_| {
+                panic!("Nothing should be opened")
+            })
+            .ok()
+            .unwrap();
+            assert_eq!(format!("{expected}\n"), String::from_utf8(out).unwrap());
+        }
+    }
+
+    #[test]
+    fn chosen_browsers() {
+        let fll = crate::localization::load(None).unwrap();
+
+        let sh = browser(Some("sh -c")).ok().unwrap();
+        assert!(sh.path.is_absolute());
+        assert_eq!(vec!["-c"], sh.args);
+
+        let msg = match browser(Some("aura-no-such-browser --new-window")) {
+            Err(e @ Error::NoSuchBrowser(_)) => e.localise(&fll),
+            _ => panic!("Expected the browser to be missing"),
+        };
+        assert!(msg.contains("aura-no-such-browser --new-window"));
+
+        // Without a choice, the failure says how to make one.
+        let msg = Error::NoBrowser.localise(&fll);
+        assert!(msg.contains("--browser"));
+        assert!(msg.contains("[open]"));
+    }
+
+    #[test]
+    fn man_page() {
+        use aura_pm::flags::Args;
+        use aura_pm::flags::SubCmd;
+        use clap::Parser;
+
+        match Args::try_parse_from(["aura", "open", "--man"])
+            .unwrap()
+            .subcmd
+        {
+            SubCmd::Open(o) => assert!(o.man),
+            _ => panic!("Expected open"),
+        }
+
+        assert!(Args::try_parse_from(["aura", "open", "--man", "--print"]).is_err());
+        assert!(Args::try_parse_from(["aura", "open", "--man", "--bug"]).is_err());
+        assert!(Args::try_parse_from(["aura", "open", "--man", "--browser", "w3m"]).is_err());
+    }
+}
@@ -1,33 +1,46 @@
 //! All functionality involving the `-O` command.
 
+use crate::aura;
+use crate::command::query;
 use crate::env::Env;
+use crate::error::Exited;
 use crate::error::Nested;
 use crate::green;
 use crate::localization::Localised;
-use crate::utils::NOTHING;
+use crate::proceed;
 use alpm::PackageReason;
 use applying::Apply;
+use aura_core::deps::PkgGraph;
+use aura_core::Dbs;
+use aura_pm::flags::QueryFilters;
 use colored::*;
 use i18n_embed::fluent::FluentLanguageLoader;
 use i18n_embed_fl::fl;
 use log::error;
 use r2d2_alpm::Alpm;
+use serde::Deserialize;
+use std::collections::HashSet;
 use std::ops::Not;
+use ubyte::ToByteUnit;
 
 pub(crate) enum Error {
     SetExplicit(String, alpm::Error),
+    SetDepend(String, alpm::Error),
     Sudo(crate::utils::SudoError),
     NoneExist,
     Removal(crate::pacman::Error),
+    Cancelled,
 }
 
 impl Nested for Error {
     fn nested(&self) {
         match self {
             Error::SetExplicit(_, e) => error!("{e}"),
+            Error::SetDepend(_, e) => error!("{e}"),
             Error::Sudo(e) => e.nested(),
             Error::NoneExist => {}
             Error::Removal(e) => e.nested(),
+            Error::Cancelled => {}
         }
     }
 }
@@ -38,7 +51,22 @@ impl Localised for Error {
             Error::Sudo(e) => e.localise(fll),
             Error::NoneExist => fl!(fll, "err-none-exist"),
             Error::SetExplicit(p, _) => fl!(fll, "O-explicit-err", pkg = p.as_str()),
+            Error::SetDepend(p, _) => fl!(fll, "O-depend-err", pkg = p.as_str()),
             Error::Removal(e) => e.localise(fll),
+            Error::Cancelled => fl!(fll, "common-cancelled"),
+        }
+    }
+}
+
+impl Exited for Error {
+    fn exit_code(&self) -> Option<u8> {
+        match self {
+            Error::SetExplicit(_, _) => None,
+            Error::SetDepend(_, _) => None,
+            Error::Sudo(_) => None,
+            Error::NoneExist => None,
+            Error::Removal(e) => e.exit_code(),
+            Error::Cancelled => None,
         }
     }
 }
@@ -51,8 +79,94 @@ pub(crate) fn list(alpm: &Alpm) {
 /// Print the name of each "elderly" package. In theory these are all explicitly
 /// installed applications, but occasionally packages are installed by mistake
 /// or forgotten. We want to identify such packages for removal.
-pub(crate) fn elderly(alpm: &Alpm) {
-    aura_core::elderly(alpm).for_each(|o| println!("{} {}", o.name(), o.version()))
+///
+/// As a `tree`, each is shown with the dependencies it drags in, down to some
+/// `depth`. Dependencies shared with an earlier package are only expanded once.
+///
+/// These are also `-Q --leaves`, which can be narrowed by the other `-Q`
+/// filters as usual.
+pub(crate) fn elderly(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    filters: &QueryFilters,
+    quiet: bool,
+    tree: bool,
+    depth: Option<u8>,
+) {
+    let elders = query::filtered(alpm, filters, aura_core::elderly(alpm));
+
+    if tree.not() {
+        for p in elders {
+            if quiet {
+                println!("{}", p.name());
+            } else {
+                println!("{} {}", p.name(), p.version());
+            }
+        }
+        return;
+    }
+
+    let db = Dbs::from_alpm(alpm);
+    let focii: Vec<_> = elders.iter().map(|p| p.name()).collect();
+    // The graph must reach one layer past the deepest one shown.
+    let limit = depth.map(|d| d.saturating_add(1));
+    let graph = PkgGraph::by_deps(&db, limit, false, &[], &focii);
+    let seen = fl!(fll, "O-elderly-seen");
+
+    for b in graph.tree(depth) {
+        let indent = "  ".repeat(usize::from(b.depth));
+
+        match () {
+            _ if b.depth == 0 => println!("{}", b.name.bold()),
+            _ if b.seen => println!("{indent}{} {}", b.name, seen.dimmed()),
+            _ => println!("{indent}{}", b.name),
+        }
+    }
+}
+
+/// Print every package not (optionally) required by another, along with those
+/// that would become so were the others removed. A recursive `-Qt`, which can
+/// be narrowed by the other `-Q` filters as usual.
+pub(crate) fn unrequired(alpm: &Alpm, filters: &QueryFilters, quiet: bool) {
+    let reason = match () {
+        _ if filters.deps => Some(PackageReason::Depend),
+        _ if filters.explicit => Some(PackageReason::Explicit),
+        _ => None,
+    };
+
+    // Most of the cluster is still required by the rest of it, which is the
+    // point, so `-t` mustn't narrow it any further.
+    let filters = QueryFilters {
+        unrequired: false,
+        ..*filters
+    };
+
+    for p in query::filtered(alpm, &filters, aura_core::unrequired(alpm, reason)) {
+        if quiet {
+            println!("{}", p.name());
+        } else {
+            println!("{} {}", p.name(), p.version());
+        }
+    }
+}
+
+/// A package database in which install reasons can be altered.
+trait Reasons {
+    /// Is the given package installed?
+    fn installed(&self, pkg: &str) -> bool;
+
+    /// Change the install reason of some installed package.
+    fn set_reason(&self, pkg: &str, reason: PackageReason) -> Result<(), alpm::Error>;
+}
+
+impl Reasons for alpm::Db {
+    fn installed(&self, pkg: &str) -> bool {
+        self.pkg(pkg).is_ok()
+    }
+
+    fn set_reason(&self, pkg: &str, reason: PackageReason) -> Result<(), alpm::Error> {
+        self.pkg(pkg)?.set_reason(reason)
+    }
 }
 
 /// Sets a package's install reason to "as explicit". An alias for `-D --asexplicit`.
@@ -66,41 +180,258 @@ pub(crate) fn adopt(
     crate::utils::sudo(env).map_err(Error::Sudo)?;
 
     let db = alpm.as_ref().localdb();
-    let reals: Vec<_> = packages
-        .into_iter()
-        .filter_map(|p| db.pkg(p).ok())
-        .collect();
+
+    for p in set_reasons(db, packages, PackageReason::Explicit)? {
+        green!(fll, "O-adopt", pkg = p);
+    }
+
+    Ok(())
+}
+
+/// Sets a package's install reason to "as a dependency", such that it will
+/// become an orphan if nothing else requires it. An alias for `-D --asdeps`.
+pub(crate) fn disown(
+    env: &Env,
+    alpm: &Alpm,
+    fll: &FluentLanguageLoader,
+    packages: Vec<String>,
+) -> Result<(), Error> {
+    crate::utils::sudo(env).map_err(Error::Sudo)?;
+
+    let db = alpm.as_ref().localdb();
+
+    for p in set_reasons(db, packages, PackageReason::Depend)? {
+        green!(fll, "O-disown", pkg = p);
+    }
+
+    Ok(())
+}
+
+/// Set the install reason of every given package that actually exists, yielding
+/// the names of those that were altered.
+fn set_reasons<R>(
+    db: &R,
+    packages: Vec<String>,
+    reason: PackageReason,
+) -> Result<Vec<String>, Error>
+where
+    R: Reasons,
+{
+    let reals: Vec<_> = packages.into_iter().filter(|p| db.installed(p)).collect();
 
     // Exit early if no real packages were given.
     if reals.is_empty() {
         return Err(Error::NoneExist);
     }
 
-    for p in reals {
-        p.set_reason(PackageReason::Explicit)
-            .map_err(|e| Error::SetExplicit(p.name().to_string(), e))?;
-        green!(fll, "O-adopt", pkg = p.name());
+    for p in reals.iter() {
+        db.set_reason(p, reason).map_err(|e| match reason {
+            PackageReason::Explicit => Error::SetExplicit(p.clone(), e),
+            PackageReason::Depend => Error::SetDepend(p.clone(), e),
+        })?;
     }
 
-    Ok(())
+    Ok(reals)
 }
 
-/// Uninstall all orphan packages.
+/// The facts about an installed package needed to plan the removal of orphans.
+#[derive(Debug, Deserialize)]
+struct Installed {
+    name: String,
+    version: String,
+    size: i64,
+    explicit: bool,
+    /// The packages that (optionally) require this one.
+    #[serde(default)]
+    required_by: Vec<String>,
+}
+
+impl Installed {
+    fn from_alpm(p: &alpm::Package) -> Installed {
+        Installed {
+            name: p.name().to_string(),
+            version: p.version().as_str().to_string(),
+            size: p.isize(),
+            explicit: p.reason() == PackageReason::Explicit,
+            required_by: p
+                .required_by()
+                .into_iter()
+                .chain(p.optional_for())
+                .collect(),
+        }
+    }
+}
+
+/// Uninstall all orphan packages, along with the dependencies that would
+/// become orphans once they're gone. The plan is shown first, and with
+/// `dry_run`, nothing else happens.
 ///
 /// Will fail if the process does not have permission to create the lockfile,
 /// which usually lives in a root-owned directory.
-pub(crate) fn remove(env: &Env, alpm: &Alpm, fll: &FluentLanguageLoader) -> Result<(), Error> {
-    let orphans: Vec<_> = aura_core::orphans(alpm).collect();
+pub(crate) fn remove(
+    env: &Env,
+    alpm: &Alpm,
+    fll: &FluentLanguageLoader,
+    dry_run: bool,
+) -> Result<(), Error> {
+    let installed: Vec<_> = alpm
+        .as_ref()
+        .localdb()
+        .pkgs()
+        .iter()
+        .map(Installed::from_alpm)
+        .collect();
+    let orphans = plan(&installed);
 
-    if orphans.is_empty().not() {
-        orphans
-            .iter()
-            .map(|p| p.name())
-            .apply(|names| crate::pacman::sudo_pacman(env, "-Rsu", NOTHING, names))
-            .map_err(Error::Removal)?;
+    if orphans.is_empty() {
+        return Ok(());
+    }
+
+    show_plan(fll, &orphans);
 
-        green!(fll, "common-done");
+    if dry_run {
+        return Ok(());
     }
 
+    proceed!(fll, env, "proceed").ok_or(Error::Cancelled)?;
+
+    // Exactly the plan, having already been confirmed. `-s` would also take
+    // dependencies that something outside of it still optionally needs.
+    orphans
+        .iter()
+        .map(|p| p.name.as_str())
+        .apply(|names| crate::pacman::sudo_pacman(env, "-Ru", ["--noconfirm"], names))
+        .map_err(Error::Removal)?;
+
+    green!(fll, "common-done");
     Ok(())
 }
+
+/// Every orphan, followed by the dependencies that would become orphans once
+/// those are gone, and so on, until nothing more would be freed.
+fn plan(installed: &[Installed]) -> Vec<&Installed> {
+    let mut removed: Vec<&Installed> = Vec::new();
+    let mut names: HashSet<&str> = HashSet::new();
+
+    loop {
+        let tier: Vec<&Installed> = installed
+            .iter()
+            .filter(|i| i.explicit.not() && names.contains(i.name.as_str()).not())
+            .filter(|i| i.required_by.iter().all(|r| names.contains(r.as_str())))
+            .collect();
+
+        if tier.is_empty() {
+            break removed;
+        }
+
+        names.extend(tier.iter().map(|i| i.name.as_str()));
+        removed.extend(tier);
+    }
+}
+
+/// Print each package of a removal plan with its installed size. Those that are
+/// only orphans because of the others also name the packages that pulled them
+/// in.
+fn show_plan(fll: &FluentLanguageLoader, orphans: &[&Installed]) {
+    let longest = orphans
+        .iter()
+        .map(|p| p.name.chars().count() + p.version.chars().count() + 1)
+        .max()
+        .unwrap_or(0);
+
+    // Rendered ahead of time, so that they can be right-aligned.
+    let human: Vec<_> = orphans.iter().map(|p| p.size.bytes().to_string()).collect();
+    let widest = human.iter().map(|s| s.chars().count()).max().unwrap_or(0);
+
+    for (p, size) in orphans.iter().zip(human) {
+        let label = format!("{} {}", p.name, p.version);
+        let line = format!("{:w$} {:>s$}", label, size, w = longest, s = widest);
+
+        if p.required_by.is_empty() {
+            println!("{line}");
+        } else {
+            let via = fl!(fll, "O-abandon-via", pkgs = p.required_by.join(", "));
+            println!("{line}  {}", via.dimmed());
+        }
+    }
+
+    let bytes: i64 = orphans.iter().map(|p| p.size).sum();
+    let total = bytes.bytes().to_string();
+    aura!(fll, "O-abandon-total", n = orphans.len(), bytes = total);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    struct MockDb(RefCell<HashMap<String, PackageReason>>);
+
+    impl Reasons for MockDb {
+        fn installed(&self, pkg: &str) -> bool {
+            self.0.borrow().contains_key(pkg)
+        }
+
+        fn set_reason(&self, pkg: &str, reason: PackageReason) -> Result<(), alpm::Error> {
+            self.0.borrow_mut().insert(pkg.to_string(), reason);
+            Ok(())
+        }
+    }
+
+    fn mock() -> MockDb {
+        let pkgs = [
+            ("firefox".to_string(), PackageReason::Explicit),
+            ("gimp".to_string(), PackageReason::Explicit),
+            ("gtk3".to_string(), PackageReason::Depend),
+        ];
+
+        MockDb(RefCell::new(pkgs.into_iter().collect()))
+    }
+
+    #[test]
+    fn disowning() {
+        let db = mock();
+        let pkgs = vec!["gimp".to_string(), "nonexistent".to_string()];
+        let changed = set_reasons(&db, pkgs, PackageReason::Depend).ok().unwrap();
+        assert_eq!(vec!["gimp".to_string()], changed);
+
+        let reasons = db.0.borrow();
+        assert_eq!(Some(&PackageReason::Depend), reasons.get("gimp"));
+        assert_eq!(Some(&PackageReason::Explicit), reasons.get("firefox"));
+        assert!(reasons.get("nonexistent").is_none());
+    }
+
+    #[test]
+    fn adopting() {
+        let db = mock();
+        let pkgs = vec!["gtk3".to_string()];
+        set_reasons(&db, pkgs, PackageReason::Explicit)
+            .ok()
+            .unwrap();
+        assert_eq!(Some(&PackageReason::Explicit), db.0.borrow().get("gtk3"));
+    }
+
+    #[test]
+    fn transitive_orphans() {
+        let file = std::fs::File::open("tests/orphans.json").unwrap();
+        let installed: Vec<Installed> = serde_json::from_reader(file).unwrap();
+        let names: Vec<_> = plan(&installed)
+            .into_iter()
+            .map(|p| p.name.as_str())
+            .collect();
+
+        // `python-lxml` is an orphan outright, while `libxslt` and then
+        // `libxml2` are only freed once what needs them is gone. `gnupg` still
+        // needs `libgcrypt` and `zlib`.
+        assert_eq!(vec!["python-lxml", "libxslt", "libxml2"], names);
+    }
+
+    #[test]
+    fn none_exist() {
+        let db = mock();
+        let pkgs = vec!["nonexistent".to_string()];
+        let res = set_reasons(&db, pkgs, PackageReason::Depend);
+        assert!(matches!(res, Err(Error::NoneExist)));
+    }
+}
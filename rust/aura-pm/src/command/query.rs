@@ -0,0 +1,588 @@
+//! Extensions to the `-Q` command.
+
+use crate::aln;
+use crate::error::Nested;
+use crate::green;
+use crate::localization::Localised;
+use crate::pacman::Extra;
+use crate::theme::Themed;
+use alpm::PackageReason;
+use aura_core::vercmp::vercmp;
+use aura_pm::flags::QueryFilters;
+use aura_pm::flags::FILE_COUNT;
+use aura_pm::flags::PULLED_IN_BY;
+use colored::*;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::warn;
+use r2d2_alpm::Alpm;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::io::ErrorKind;
+use std::ops::Not;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::path::PathBuf;
+
+pub(crate) enum Error {
+    /// Some of the given files have no owner.
+    Unowned(usize),
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Unowned(_) => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Unowned(n) => fl!(fll, "Q-owns-unowned", n = *n),
+        }
+    }
+}
+
+/// Run `pacman -Qi`, adding with `file_count` how many files each package owns,
+/// and with `pulled_in_by`, what pulled in each package that was installed as a
+/// dependency.
+pub(crate) fn info(
+    alpm: &Alpm,
+    args: Vec<String>,
+    file_count: bool,
+    pulled_in_by: bool,
+) -> Result<(), crate::pacman::Error> {
+    let mut extras = Vec::new();
+
+    if file_count {
+        extras.push(Extra::file_count(FILE_COUNT, |p| files_owned(alpm, p)));
+    }
+    if pulled_in_by {
+        extras.push(Extra::pulled_in_by(PULLED_IN_BY, |p| {
+            pulled_in(alpm, "None", p)
+        }));
+    }
+
+    crate::pacman::pacman_info(args, &extras)
+}
+
+/// How many files and directories an installed package owns, according to the
+/// local database. This is the length of its `-Ql` listing.
+pub(crate) fn files_owned(alpm: &Alpm, pkg: &str) -> Option<String> {
+    let p = alpm.as_ref().localdb().pkg(pkg).ok()?;
+    Some(p.files().files().len().to_string())
+}
+
+/// The explicitly installed packages that the given one was pulled in by,
+/// joined as Pacman joins lists. `none` for explicitly installed packages, such
+/// that every package has the field.
+pub(crate) fn pulled_in(alpm: &Alpm, none: &str, pkg: &str) -> Option<String> {
+    let db = alpm.as_ref().localdb();
+    db.pkg(pkg).ok()?;
+
+    let by = pulled_in_by(pkg, |name| {
+        let p = db.pkg(name).ok()?;
+        let explicit = p.reason() == PackageReason::Explicit;
+        let required_by = p
+            .required_by()
+            .into_iter()
+            .chain(p.optional_for())
+            .collect();
+
+        Some((explicit, required_by))
+    })
+    .unwrap_or_default();
+
+    if by.is_empty() {
+        Some(none.to_string())
+    } else {
+        Some(by.join("  "))
+    }
+}
+
+/// For a package installed as a dependency, the nearest explicitly installed
+/// packages that (optionally) require it, directly or through other
+/// dependencies. These are empty for orphans. `lookup` gives whether a package
+/// was installed explicitly, and what requires it.
+fn pulled_in_by<F>(pkg: &str, lookup: F) -> Option<Vec<String>>
+where
+    F: Fn(&str) -> Option<(bool, Vec<String>)>,
+{
+    let (explicit, mut layer) = lookup(pkg)?;
+
+    if explicit {
+        return None;
+    }
+
+    let mut seen: HashSet<String> = HashSet::from([pkg.to_string()]);
+
+    while layer.is_empty().not() {
+        layer.retain(|p| seen.insert(p.clone()));
+
+        let mut found = Vec::new();
+        let mut next = Vec::new();
+
+        for p in layer {
+            match lookup(&p) {
+                Some((true, _)) => found.push(p),
+                Some((false, by)) => next.extend(by),
+                None => {}
+            }
+        }
+
+        if found.is_empty().not() {
+            found.sort();
+            return Some(found);
+        }
+
+        layer = next;
+    }
+
+    Some(Vec::new())
+}
+
+/// What the `-Q` filters need to know about an installed package.
+#[derive(Debug)]
+struct Facts {
+    explicit: bool,
+    /// Not found in any sync database.
+    foreign: bool,
+    /// Some other package (optionally) requires it.
+    required: bool,
+    /// A newer version is available in some sync database.
+    outdated: bool,
+}
+
+impl Facts {
+    fn from_alpm(alpm: &Alpm, p: &alpm::Package) -> Facts {
+        let sync = alpm.as_ref().syncdbs().pkg(p.name()).ok();
+
+        Facts {
+            explicit: p.reason() == PackageReason::Explicit,
+            foreign: sync.is_none(),
+            required: p.required_by().is_empty().not() || p.optional_for().is_empty().not(),
+            outdated: sync.is_some_and(|s| {
+                vercmp(s.version().as_str(), p.version().as_str()) == Ordering::Greater
+            }),
+        }
+    }
+}
+
+/// Does a package pass every given filter? As with Pacman, filters that
+/// contradict each other, like `-Qde` or `-Qmn`, let nothing through.
+fn passes(filters: &QueryFilters, facts: &Facts) -> bool {
+    (filters.explicit.not() || facts.explicit)
+        && (filters.deps.not() || facts.explicit.not())
+        && (filters.foreign.not() || facts.foreign)
+        && (filters.native.not() || facts.foreign.not())
+        && (filters.unrequired.not() || facts.required.not())
+        && (filters.upgrades.not() || facts.outdated)
+}
+
+/// Narrow some installed packages to those passing every given filter.
+pub(crate) fn filtered<'a, I>(
+    alpm: &Alpm,
+    filters: &QueryFilters,
+    pkgs: I,
+) -> Vec<&'a alpm::Package>
+where
+    I: IntoIterator<Item = &'a alpm::Package>,
+{
+    pkgs.into_iter()
+        .filter(|p| passes(filters, &Facts::from_alpm(alpm, p)))
+        .collect()
+}
+
+/// Print the files owned by the given packages (or by every installed package)
+/// that no longer exist on disk. A lighter-weight `-Qk`.
+pub(crate) fn missing(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    filters: &QueryFilters,
+    quiet: bool,
+    packages: &[String],
+) {
+    let db = alpm.as_ref().localdb();
+    let root = Path::new(alpm.as_ref().root());
+
+    let pkgs: Vec<_> = if packages.is_empty() {
+        db.pkgs().iter().collect()
+    } else {
+        packages
+            .iter()
+            .filter_map(|p| match db.pkg(p.as_str()) {
+                Ok(pkg) => Some(pkg),
+                Err(_) => {
+                    let msg = fl!(fll, "Q-unknown", pkg = p.as_str());
+                    aln!(msg.warning());
+                    None
+                }
+            })
+            .collect()
+    };
+
+    let mut flagged = 0;
+
+    for p in filtered(alpm, filters, pkgs) {
+        let owned = p
+            .files()
+            .files()
+            .iter()
+            .map(|f| root.join(OsStr::from_bytes(f.name().as_ref())));
+        let gone = missing_files(owned);
+
+        if gone.is_empty() {
+            continue;
+        }
+
+        flagged += 1;
+
+        for path in gone {
+            if quiet {
+                println!("{}", path.display());
+            } else {
+                println!("{} {}", p.name().bold(), path.display());
+            }
+        }
+    }
+
+    if quiet.not() {
+        if flagged == 0 {
+            green!(fll, "Q-missing-none");
+        } else {
+            let msg = fl!(fll, "Q-missing", n = flagged);
+            aln!(msg.warning());
+        }
+    }
+}
+
+/// Installed packages whose names or descriptions are close to every given
+/// term, best matches first. A forgiving `-Qs`.
+pub(crate) fn fuzzy_search(alpm: &Alpm, terms: &[String], quiet: bool) {
+    let mut matches: Vec<_> = alpm
+        .as_ref()
+        .localdb()
+        .pkgs()
+        .iter()
+        .filter_map(|p| {
+            terms
+                .iter()
+                .map(|term| {
+                    let name = aura_core::fuzzy::score(term, p.name());
+                    let desc = p.desc().and_then(|d| aura_core::fuzzy::score(term, d));
+
+                    // A close name outranks a close description.
+                    name.or(desc.map(|d| d + 1))
+                })
+                .sum::<Option<usize>>()
+                .map(|score| (score, p))
+        })
+        .collect();
+
+    matches.sort_by(|(a, p), (b, q)| a.cmp(b).then_with(|| p.name().cmp(q.name())));
+
+    for (_, p) in matches {
+        if quiet {
+            println!("{}", p.name());
+        } else {
+            println!(
+                "{}{} {}",
+                "local/".bold(),
+                p.name().bold(),
+                p.version().as_str().success().bold()
+            );
+
+            if let Some(d) = p.desc() {
+                println!("    {d}");
+            }
+        }
+    }
+}
+
+/// Print the package that owns each given file, reading every package's file
+/// list only once. A faster `-Qo` for many files. As with Pacman, it's an error
+/// if any file has no owner.
+pub(crate) fn owns(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    quiet: bool,
+    files: &[String],
+) -> Result<(), Error> {
+    let db = alpm.as_ref().localdb();
+    let root = Path::new(alpm.as_ref().root());
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
+
+    let paths: Vec<_> = files.iter().map(|f| absolute(&cwd, f)).collect();
+    let pkgs = db.pkgs().iter().map(|p| {
+        let files = p
+            .files()
+            .files()
+            .iter()
+            .map(|f| <[u8]>::to_vec(f.name().as_ref()));
+        (p.name().to_string(), files.collect::<Vec<_>>())
+    });
+
+    let mut unowned = 0;
+
+    for (path, owners) in paths.iter().zip(owners(&paths, root, pkgs)) {
+        if owners.is_empty() {
+            let msg = fl!(fll, "Q-owns-none", file = path.display().to_string());
+            aln!(msg.warning());
+            unowned += 1;
+        } else if quiet {
+            println!("{}", owners.join(" "));
+        } else {
+            println!("{} -> {}", path.display(), owners.join(" ").bold());
+        }
+    }
+
+    if unowned == 0 {
+        Ok(())
+    } else {
+        Err(Error::Unowned(unowned))
+    }
+}
+
+/// A file path made absolute against the current directory, with any symlinks
+/// leading up to it resolved. As with Pacman, a symlink named directly is
+/// itself what gets looked up, not what it points to, and a bare name like `ls`
+/// is a program found on the `$PATH`.
+fn absolute(cwd: &Path, file: &str) -> PathBuf {
+    let path = if file.contains('/') {
+        cwd.join(file)
+    } else {
+        which::which(file).unwrap_or_else(|_| cwd.join(file))
+    };
+
+    match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) => dir
+            .canonicalize()
+            .map(|d| d.join(name))
+            .unwrap_or_else(|_| path.clone()),
+        _ => path,
+    }
+}
+
+/// The owners of each given path, in order, found in a single pass over the
+/// file lists of the given packages. File lists are relative to `root`.
+fn owners<S, P, F, N>(paths: &[PathBuf], root: &Path, pkgs: P) -> Vec<Vec<S>>
+where
+    S: Clone,
+    P: IntoIterator<Item = (S, F)>,
+    F: IntoIterator<Item = N>,
+    N: AsRef<[u8]>,
+{
+    let mut wanted: HashMap<&Path, Vec<usize>> = HashMap::new();
+    for (ix, path) in paths.iter().enumerate() {
+        wanted.entry(path.as_path()).or_default().push(ix);
+    }
+
+    let mut owners = vec![Vec::new(); paths.len()];
+
+    for (name, files) in pkgs {
+        for file in files {
+            let full = root.join(OsStr::from_bytes(file.as_ref()));
+
+            if let Some(ixs) = wanted.get(full.as_path()) {
+                for ix in ixs {
+                    owners[*ix].push(name.clone());
+                }
+            }
+        }
+    }
+
+    owners
+}
+
+/// Those paths which no longer exist. Symlinks count as present even if what
+/// they point to doesn't, just as with `-Qk`. A path that can't be checked at
+/// all, say for lack of permission, isn't known to be missing.
+fn missing_files<I>(paths: I) -> Vec<PathBuf>
+where
+    I: IntoIterator<Item = PathBuf>,
+{
+    paths
+        .into_iter()
+        .filter(|path| match path.symlink_metadata() {
+            Ok(_) => false,
+            Err(e) if e.kind() == ErrorKind::NotFound => true,
+            Err(e) => {
+                warn!("Couldn't check {}: {e}", path.display());
+                false
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn combined_filters() {
+        let fixture = std::fs::read_to_string("tests/query-filters.txt").unwrap();
+        let pkgs: Vec<_> = fixture
+            .lines()
+            .filter(|l| l.starts_with('#').not())
+            .map(|l| {
+                let words: Vec<_> = l.split_whitespace().collect();
+                let facts = Facts {
+                    explicit: words[1] == "explicit",
+                    foreign: words[2] == "foreign",
+                    required: words[3] == "required",
+                    outdated: words[4] == "outdated",
+                };
+
+                (words[0], facts)
+            })
+            .collect();
+
+        let listed = |filters: QueryFilters| -> Vec<&str> {
+            pkgs.iter()
+                .filter(|(_, facts)| passes(&filters, facts))
+                .map(|(name, _)| *name)
+                .collect()
+        };
+
+        assert_eq!(8, listed(QueryFilters::default()).len());
+        assert_eq!(
+            vec!["aura-bin", "paru"],
+            listed(QueryFilters {
+                explicit: true,
+                foreign: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            vec!["libfoo"],
+            listed(QueryFilters {
+                deps: true,
+                foreign: true,
+                unrequired: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            vec!["firefox"],
+            listed(QueryFilters {
+                explicit: true,
+                native: true,
+                upgrades: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            vec!["glibc", "gtk2"],
+            listed(QueryFilters {
+                deps: true,
+                upgrades: true,
+                ..Default::default()
+            })
+        );
+
+        // Contradictory filters let nothing through.
+        assert!(listed(QueryFilters {
+            deps: true,
+            explicit: true,
+            ..Default::default()
+        })
+        .is_empty());
+        assert!(listed(QueryFilters {
+            foreign: true,
+            native: true,
+            ..Default::default()
+        })
+        .is_empty());
+    }
+
+    #[test]
+    fn only_missing_files() {
+        let root = Path::new("tests/owned");
+        let owned = [
+            "etc/",
+            "etc/gone.conf",
+            "etc/present.conf",
+            "usr/",
+            "usr/bin/",
+            "usr/bin/gone",
+            "usr/bin/present",
+        ]
+        .map(|f| root.join(f));
+        let expected = vec![root.join("etc/gone.conf"), root.join("usr/bin/gone")];
+
+        assert_eq!(expected, missing_files(owned));
+    }
+
+    #[test]
+    fn pulled_in_by_explicit() {
+        #[derive(serde::Deserialize)]
+        struct Reason {
+            explicit: bool,
+            #[serde(default)]
+            required_by: Vec<String>,
+        }
+
+        let file = std::fs::File::open("tests/reasons.json").unwrap();
+        let reasons: HashMap<String, Reason> = serde_json::from_reader(file).unwrap();
+        let by = |pkg: &str| {
+            pulled_in_by(pkg, |p| {
+                reasons.get(p).map(|r| (r.explicit, r.required_by.clone()))
+            })
+        };
+
+        // Through both `libxslt` and `python-lxml`.
+        assert_eq!(Some(vec!["calibre".to_string()]), by("libxml2"));
+        // The nearest explicit package wins.
+        assert_eq!(Some(vec!["gnupg".to_string()]), by("zlib"));
+        // Orphans, even those in cycles, were pulled in by nothing left.
+        assert_eq!(Some(Vec::new()), by("python-cssselect"));
+        assert_eq!(Some(Vec::new()), by("icu"));
+        assert_eq!(None, by("calibre"));
+        assert_eq!(None, by("nonexistent"));
+    }
+
+    #[test]
+    fn bulk_owners() {
+        let root = Path::new("/");
+        let pkgs = [
+            ("filesystem", vec!["etc/", "usr/", "usr/bin/"]),
+            ("coreutils", vec!["usr/", "usr/bin/", "usr/bin/ls"]),
+            ("pacman", vec!["etc/", "etc/pacman.conf", "usr/bin/pacman"]),
+        ];
+        let paths = [
+            "/usr/bin/ls",
+            "/etc/pacman.conf",
+            "/usr/bin/nonexistent",
+            "/usr/bin",
+            "/usr/bin/ls",
+        ]
+        .map(PathBuf::from);
+
+        let found = owners(&paths, root, pkgs);
+        assert_eq!(vec!["coreutils"], found[0]);
+        assert_eq!(vec!["pacman"], found[1]);
+        assert!(found[2].is_empty());
+        assert_eq!(vec!["filesystem", "coreutils"], found[3]);
+        assert_eq!(found[0], found[4]);
+    }
+
+    #[test]
+    fn absolute_paths() {
+        let cwd = Path::new("tests/owned").canonicalize().unwrap();
+        let present = cwd.join("etc/present.conf");
+
+        assert_eq!(present, absolute(&cwd, "etc/present.conf"));
+        assert_eq!(present, absolute(&cwd, "usr/../etc/./present.conf"));
+        assert_eq!(present, absolute(Path::new("/"), present.to_str().unwrap()));
+        assert_eq!(cwd.join("etc/gone.conf"), absolute(&cwd, "etc/gone.conf"));
+
+        let sh = absolute(&cwd, "sh");
+        assert!(sh.is_absolute());
+        assert!(sh.starts_with(&cwd).not());
+        assert_eq!(cwd.join("not-a-program"), absolute(&cwd, "not-a-program"));
+    }
+}
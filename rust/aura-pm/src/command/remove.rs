@@ -0,0 +1,287 @@
+//! Extensions to the `-R` command.
+
+use crate::error::Nested;
+use crate::localization::Localised;
+use alpm::PackageReason;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use r2d2_alpm::Alpm;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::ops::Not;
+
+pub(crate) enum Error {
+    NotFound(Vec<String>),
+    Stdout,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::NotFound(_) => {}
+            Error::Stdout => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::NotFound(ps) => fl!(fll, "R-not-found", pkgs = ps.join(", ")),
+            Error::Stdout => fl!(fll, "err-write"),
+        }
+    }
+}
+
+/// The facts about an installed package needed to plan its removal.
+#[derive(Debug, Deserialize)]
+struct Installed {
+    name: String,
+    version: String,
+    size: i64,
+    explicit: bool,
+    #[serde(default)]
+    depends: Vec<String>,
+    #[serde(default)]
+    provides: Vec<String>,
+}
+
+impl Installed {
+    fn from_alpm(p: &alpm::Package) -> Installed {
+        Installed {
+            name: p.name().to_string(),
+            version: p.version().as_str().to_string(),
+            size: p.isize(),
+            explicit: p.reason() == PackageReason::Explicit,
+            depends: p.depends().iter().map(|d| d.name().to_string()).collect(),
+            provides: p.provides().iter().map(|d| d.name().to_string()).collect(),
+        }
+    }
+
+    /// Does this package fulfill the given dependency?
+    fn satisfies(&self, dep: &str) -> bool {
+        self.name == dep || self.provides.iter().any(|p| p == dep)
+    }
+
+    fn target(&self) -> Target<'_> {
+        Target {
+            name: &self.name,
+            version: &self.version,
+            size_bytes: self.size,
+        }
+    }
+}
+
+/// A single package to be removed, as rendered by `--json`.
+#[derive(Debug, Serialize)]
+struct Target<'a> {
+    name: &'a str,
+    version: &'a str,
+    size_bytes: i64,
+}
+
+/// Everything a `-R` would remove, and why, as rendered by `--json`.
+#[derive(Debug, Serialize)]
+struct Plan<'a> {
+    /// The packages asked for by name.
+    requested: Vec<Target<'a>>,
+    /// With `-c`, the packages that depend on those being removed.
+    cascaded: Vec<Target<'a>>,
+    /// With `-s`, the dependencies that nothing else would need anymore.
+    orphaned: Vec<Target<'a>>,
+    reclaimed_bytes: i64,
+}
+
+/// Print what a `-R` with the given options would remove, as JSON. Nothing is
+/// actually removed.
+pub(crate) fn print_json(
+    alpm: &Alpm,
+    packages: &[String],
+    cascade: bool,
+    recursive: u8,
+) -> Result<(), Error> {
+    let installed: Vec<_> = alpm
+        .as_ref()
+        .localdb()
+        .pkgs()
+        .iter()
+        .map(Installed::from_alpm)
+        .collect();
+    let plan = plan(&installed, packages, cascade, recursive)?;
+
+    let mut w = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut w, &plan).map_err(|_| Error::Stdout)?;
+    writeln!(w).map_err(|_| Error::Stdout)
+}
+
+/// Work out the removal as Pacman would: the requested packages, then with
+/// `cascade`, everything left that depends on them, then with `recursive`, the
+/// dependencies that would no longer be needed. At `recursive` levels beyond 1,
+/// explicitly installed dependencies are removed too.
+fn plan<'a>(
+    installed: &'a [Installed],
+    packages: &[String],
+    cascade: bool,
+    recursive: u8,
+) -> Result<Plan<'a>, Error> {
+    let missing: Vec<_> = packages
+        .iter()
+        .filter(|p| installed.iter().any(|i| &i.name == *p).not())
+        .cloned()
+        .collect();
+
+    if missing.is_empty().not() {
+        return Err(Error::NotFound(missing));
+    }
+
+    let mut removed: BTreeSet<usize> = installed
+        .iter()
+        .enumerate()
+        .filter(|(_, i)| packages.contains(&i.name))
+        .map(|(ix, _)| ix)
+        .collect();
+    let requested = removed.clone();
+
+    if cascade {
+        grow(installed, &mut removed, |ix, removed| {
+            installed[ix]
+                .depends
+                .iter()
+                .any(|d| broken(installed, removed, d))
+        });
+    }
+    let cascaded = removed
+        .difference(&requested)
+        .copied()
+        .collect::<BTreeSet<_>>();
+
+    if recursive > 0 {
+        grow(installed, &mut removed, |ix, removed| {
+            let pkg = &installed[ix];
+
+            (recursive > 1 || pkg.explicit.not())
+                && needed_by(installed, removed, pkg, true)
+                && needed_by(installed, removed, pkg, false).not()
+        });
+    }
+
+    let targets = |set: &BTreeSet<usize>| -> Vec<Target<'a>> {
+        set.iter().map(|ix| installed[*ix].target()).collect()
+    };
+    let orphaned: BTreeSet<usize> = removed
+        .iter()
+        .filter(|ix| requested.contains(ix).not() && cascaded.contains(ix).not())
+        .copied()
+        .collect();
+
+    Ok(Plan {
+        requested: targets(&requested),
+        cascaded: targets(&cascaded),
+        orphaned: targets(&orphaned),
+        reclaimed_bytes: removed.iter().map(|ix| installed[*ix].size).sum(),
+    })
+}
+
+/// Keep marking packages for removal while `doomed` picks out new ones.
+fn grow<F>(installed: &[Installed], removed: &mut BTreeSet<usize>, doomed: F)
+where
+    F: Fn(usize, &BTreeSet<usize>) -> bool,
+{
+    loop {
+        let new: Vec<_> = (0..installed.len())
+            .filter(|ix| removed.contains(ix).not())
+            .filter(|ix| doomed(*ix, removed))
+            .collect();
+
+        if new.is_empty() {
+            break;
+        }
+
+        removed.extend(new);
+    }
+}
+
+/// Would the given dependency be left unsatisfied by the removal?
+fn broken(installed: &[Installed], removed: &BTreeSet<usize>, dep: &str) -> bool {
+    let mut providers = installed
+        .iter()
+        .enumerate()
+        .filter(|(_, i)| i.satisfies(dep))
+        .peekable();
+
+    providers.peek().is_some() && providers.all(|(ix, _)| removed.contains(&ix))
+}
+
+/// Does some package in (or, if not `gone`, outside of) the removal depend on
+/// the given one?
+fn needed_by(
+    installed: &[Installed],
+    removed: &BTreeSet<usize>,
+    pkg: &Installed,
+    gone: bool,
+) -> bool {
+    installed
+        .iter()
+        .enumerate()
+        .filter(|(ix, _)| removed.contains(ix) == gone)
+        .any(|(_, i)| i.depends.iter().any(|d| pkg.satisfies(d)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn names(targets: &[Target]) -> Vec<String> {
+        targets.iter().map(|t| t.name.to_string()).collect()
+    }
+
+    #[test]
+    fn cascading_recursive_plan() {
+        let file = std::fs::File::open("tests/removal.json").unwrap();
+        let installed: Vec<Installed> = serde_json::from_reader(file).unwrap();
+        let p = plan(&installed, &["gtk3".to_string()], true, 1)
+            .ok()
+            .unwrap();
+
+        assert_eq!(vec!["gtk3"], names(&p.requested));
+        assert_eq!(vec!["gimp", "gimp-plugin-gmic"], names(&p.cascaded));
+        assert_eq!(vec!["atk", "gegl", "babl"], names(&p.orphaned));
+        assert_eq!(1 + 2 + 4 + 8 + 16 + 32, p.reclaimed_bytes);
+
+        let json = serde_json::to_value(&p).unwrap();
+        assert_eq!("gimp", json["cascaded"][0]["name"]);
+        assert_eq!("2.10.38-1", json["cascaded"][0]["version"]);
+        assert_eq!(2, json["cascaded"][0]["size_bytes"]);
+    }
+
+    #[test]
+    fn plan_options() {
+        let file = std::fs::File::open("tests/removal.json").unwrap();
+        let installed: Vec<Installed> = serde_json::from_reader(file).unwrap();
+
+        // Without -c or -s, only the requested packages go.
+        let p = plan(&installed, &["gimp".to_string()], false, 0)
+            .ok()
+            .unwrap();
+        assert!(p.cascaded.is_empty());
+        assert!(p.orphaned.is_empty());
+        assert_eq!(2, p.reclaimed_bytes);
+
+        // `glib2` is still needed by `gtk3`. `mesa` is only needed through
+        // what it provides, and `pango` was explicitly installed, so only -ss
+        // removes it.
+        let p = plan(&installed, &["firefox".to_string()], false, 1)
+            .ok()
+            .unwrap();
+        assert_eq!(vec!["mesa"], names(&p.orphaned));
+        let p = plan(&installed, &["firefox".to_string()], false, 2)
+            .ok()
+            .unwrap();
+        assert_eq!(vec!["pango", "mesa"], names(&p.orphaned));
+
+        let res = plan(&installed, &["nonexistent".to_string()], false, 0);
+        assert!(matches!(res, Err(Error::NotFound(ps)) if ps == vec!["nonexistent"]));
+    }
+}
@@ -2,19 +2,25 @@
 
 use crate::aura;
 use crate::env::Env;
+use crate::error::Exited;
 use crate::error::Nested;
 use crate::green;
 use crate::localization::Localised;
 use crate::proceed;
+use crate::theme::Themed;
 use crate::utils::PathStr;
 use crate::utils::NOTHING;
 use aura_core::snapshot::Snapshot;
+use aura_core::vercmp::vercmp;
 use colored::*;
 use i18n_embed::fluent::FluentLanguageLoader;
 use i18n_embed_fl::fl;
+use itertools::Itertools;
 use log::error;
 use r2d2_alpm::Alpm;
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fs::File;
@@ -24,6 +30,9 @@ use std::path::Path;
 use std::path::PathBuf;
 use time::macros::format_description;
 
+/// The longest name a snapshot may be given.
+const MAX_NAME: usize = 64;
+
 pub(crate) enum Error {
     Pacman(crate::pacman::Error),
     Readline(std::io::Error),
@@ -32,7 +41,10 @@ pub(crate) enum Error {
     OpenFile(PathBuf, std::io::Error),
     TimeFormat(time::error::Format),
     Cancelled,
+    BadName(String),
     NoSnapshots,
+    UnknownSnapshot(String),
+    MissingTarballs(Vec<String>),
 }
 
 impl Nested for Error {
@@ -44,7 +56,10 @@ impl Nested for Error {
             Error::DeleteFile(_, e) => error!("{e}"),
             Error::OpenFile(_, e) => error!("{e}"),
             Error::Cancelled => {}
+            Error::BadName(_) => {}
             Error::NoSnapshots => {}
+            Error::UnknownSnapshot(_) => {}
+            Error::MissingTarballs(_) => {}
             Error::TimeFormat(e) => error!("{e}"),
         }
     }
@@ -57,7 +72,10 @@ impl Localised for Error {
             Error::Readline(_) => fl!(fll, "err-user-input"),
             Error::JsonWrite(p, _) => fl!(fll, "err-json-write", file = p.utf8()),
             Error::Cancelled => fl!(fll, "common-cancelled"),
+            Error::BadName(name) => fl!(fll, "B-bad-name", name = name.as_str()),
             Error::NoSnapshots => fl!(fll, "B-none"),
+            Error::UnknownSnapshot(id) => fl!(fll, "B-unknown", id = id.as_str()),
+            Error::MissingTarballs(ps) => fl!(fll, "B-missing", pkgs = ps.join(", ")),
             Error::DeleteFile(p, _) => fl!(fll, "err-file-del", file = p.utf8()),
             Error::OpenFile(p, _) => fl!(fll, "err-file-open", file = p.utf8()),
             Error::TimeFormat(_) => fl!(fll, "err-time-format"),
@@ -65,34 +83,173 @@ impl Localised for Error {
     }
 }
 
-/// During a `-Br`, the packages to update and/or remove.
-#[derive(Debug)]
+impl Exited for Error {
+    fn exit_code(&self) -> Option<u8> {
+        match self {
+            Error::Pacman(e) => e.exit_code(),
+            Error::Readline(_) => None,
+            Error::JsonWrite(_, _) => None,
+            Error::DeleteFile(_, _) => None,
+            Error::OpenFile(_, _) => None,
+            Error::TimeFormat(_) => None,
+            Error::Cancelled => None,
+            Error::BadName(_) => None,
+            Error::NoSnapshots => None,
+            Error::UnknownSnapshot(_) => None,
+            Error::MissingTarballs(_) => None,
+        }
+    }
+}
+
+/// During a `-Br`, the packages to install, alter, and/or remove.
+#[derive(Debug, PartialEq, Eq)]
 struct StateDiff<'a> {
-    /// Packages that need to be reinstalled, upgraded, or downgraded.
-    to_add_or_alter: HashMap<&'a str, &'a str>,
+    /// Packages in the snapshot that aren't installed anymore, with the
+    /// version to install.
+    to_install: BTreeMap<&'a str, &'a str>,
+
+    /// Installed packages whose version differs from the snapshot, with their
+    /// current and snapshotted versions.
+    to_alter: BTreeMap<&'a str, (&'a str, &'a str)>,
 
     /// Packages that are installed now, but weren't when the snapshot was
     /// taken.
-    to_remove: HashSet<&'a str>,
+    to_remove: BTreeSet<&'a str>,
+}
+
+impl<'a> StateDiff<'a> {
+    fn is_empty(&self) -> bool {
+        self.to_install.is_empty() && self.to_alter.is_empty() && self.to_remove.is_empty()
+    }
+
+    /// The version that a given package must be set to, if any.
+    fn target(&self, name: &str) -> Option<&'a str> {
+        self.to_install
+            .get(name)
+            .copied()
+            .or_else(|| self.to_alter.get(name).map(|(_, v)| *v))
+    }
+
+    /// The `name-version` of each package to be installed or altered that has
+    /// no tarball in the cache.
+    ///
+    /// Accepts a `HashMap` assumed to have come from
+    /// [`aura_core::cache::all_versions`].
+    fn missing_tarballs(&self, versions: &HashMap<String, HashSet<String>>) -> Vec<String> {
+        self.to_install
+            .iter()
+            .map(|(n, v)| (*n, *v))
+            .chain(self.to_alter.iter().map(|(n, (_, v))| (*n, *v)))
+            .filter(|(n, v)| {
+                versions
+                    .get(*n)
+                    .map(|set| set.contains(*v))
+                    .unwrap_or(false)
+                    .not()
+            })
+            .map(|(n, v)| format!("{n}-{v}"))
+            .sorted()
+            .collect()
+    }
+}
+
+/// The packages installed before some transaction, such that it can be undone
+/// if it fails partway.
+pub(crate) struct Transaction {
+    before: Snapshot,
+}
+
+impl Transaction {
+    /// Record the currently installed packages.
+    pub(crate) fn begin(alpm: &Alpm) -> Transaction {
+        Transaction::from_installed(&installed(alpm))
+    }
+
+    fn from_installed(installed: &HashMap<&str, &str>) -> Transaction {
+        let before = Snapshot {
+            time: time::OffsetDateTime::now_utc(),
+            pinned: false,
+            name: None,
+            packages: installed
+                .iter()
+                .map(|(n, v)| (n.to_string(), v.to_string()))
+                .collect(),
+        };
+
+        Transaction { before }
+    }
+
+    /// What must change to return to the recorded state. Packages the
+    /// transaction never touched are left alone.
+    fn plan<'a>(&'a self, installed: &'a HashMap<&'a str, &'a str>) -> StateDiff<'a> {
+        package_diff(&self.before, installed)
+    }
 }
 
-pub(crate) fn save(fll: &FluentLanguageLoader, alpm: &Alpm, snapshots: &Path) -> Result<(), Error> {
-    let snap = Snapshot::from_alpm(alpm);
+/// Save the current package state, optionally labelled with a `name`. A name
+/// already held by another snapshot gets a numbered suffix.
+pub(crate) fn save(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    snapshots: &Path,
+    name: Option<&str>,
+) -> Result<(), Error> {
+    let mut snap = Snapshot::from_alpm(alpm);
+
+    if let Some(name) = name {
+        if legal_name(name).not() {
+            return Err(Error::BadName(name.to_string()));
+        }
+
+        let taken: HashSet<String> = aura_core::snapshot::snapshots(snapshots)
+            .filter_map(|ss| ss.name)
+            .collect();
+        snap.name = Some(unique_name(name, &taken));
+    }
+
     let form =
         format_description!("[year].[month]([month repr:short]).[day].[hour].[minute].[second]");
-    let name = format!(
-        "{}.json",
-        snap.time.format(form).map_err(Error::TimeFormat)?
-    );
-    let path = snapshots.join(name);
+    let time = snap.time.format(form).map_err(Error::TimeFormat)?;
+    let file = match snap.name.as_deref() {
+        None => format!("{time}.json"),
+        Some(name) => format!("{time}.{name}.json"),
+    };
+    let path = snapshots.join(file);
 
     let file = BufWriter::new(File::create(&path).map_err(|e| Error::OpenFile(path.clone(), e))?);
     serde_json::to_writer(file, &snap).map_err(|e| Error::JsonWrite(path, e))?;
-    green!(fll, "B-saved");
+
+    match snap.name.as_deref() {
+        None => green!(fll, "B-saved"),
+        Some(name) => green!(fll, "B-saved-as", name = name),
+    }
 
     Ok(())
 }
 
+/// Can a snapshot be given this name? Names become part of filenames, so only
+/// ASCII letters, digits, `-`, and `_` are allowed.
+fn legal_name(name: &str) -> bool {
+    name.is_empty().not()
+        && name.len() <= MAX_NAME
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The given name, or if another snapshot already has it, the name with the
+/// first free numbered suffix.
+fn unique_name(name: &str, taken: &HashSet<String>) -> String {
+    if taken.contains(name).not() {
+        return name.to_string();
+    }
+
+    (2..)
+        .map(|n| format!("{name}-{n}"))
+        .find(|candidate| taken.contains(candidate).not())
+        .unwrap_or_else(|| name.to_string())
+}
+
 /// Remove all saveds snapshots that don't have tarballs in the cache.
 pub(crate) fn clean(fll: &FluentLanguageLoader, env: &Env) -> Result<(), Error> {
     proceed!(fll, env, "B-clean").ok_or(Error::Cancelled)?;
@@ -110,74 +267,212 @@ pub(crate) fn clean(fll: &FluentLanguageLoader, env: &Env) -> Result<(), Error>
     Ok(())
 }
 
-/// Show all saved package snapshot filenames.
+/// Show all saved package snapshot filenames, along with their names.
 pub(crate) fn list(snapshots: &Path) -> Result<(), Error> {
-    let mut paths: Vec<_> = aura_core::snapshot::snapshots_with_paths(snapshots)
-        .map(|(path, _)| path)
-        .collect();
-    paths.sort();
+    let mut shots: Vec<_> = aura_core::snapshot::snapshots_with_paths(snapshots).collect();
+    shots.sort_by(|(a, _), (b, _)| a.cmp(b));
 
-    for path in paths {
-        println!("{}", path.display());
+    for (path, ss) in shots {
+        match ss.name {
+            None => println!("{}", path.display()),
+            Some(name) => println!("{} {}", path.display(), name.bold()),
+        }
     }
 
     Ok(())
 }
 
-pub(crate) fn restore(env: &Env, fll: &FluentLanguageLoader, alpm: &Alpm) -> Result<(), Error> {
+/// Restore the system to a previous snapshot. The snapshot is either given by
+/// `id` (its filename with or without `.json`, its date, or its name), or
+/// selected interactively.
+pub(crate) fn restore(
+    env: &Env,
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    id: Option<&str>,
+) -> Result<(), Error> {
     let caches = env.caches();
-    let snapshots = &env.backups.snapshots;
-    let vers = aura_core::cache::all_versions(&caches);
 
-    let mut shots: Vec<_> = aura_core::snapshot::snapshots(snapshots)
-        .filter(|ss| ss.usable(&vers))
-        .collect();
-    shots.sort_by_key(|ss| ss.time);
-    let digits = 1 + (shots.len() / 10);
+    let mut shots: Vec<_> =
+        aura_core::snapshot::snapshots_with_paths(&env.backups.snapshots).collect();
+    shots.sort_by_key(|(_, ss)| ss.time);
 
     if shots.is_empty() {
         return Err(Error::NoSnapshots);
     }
 
-    aura!(fll, "B-select");
-    for (i, ss) in shots.iter().enumerate() {
-        let form = format_description!("[year]-[month]-[day] [hour]-[minute]-[second]");
-        let time = ss.time.format(form).map_err(Error::TimeFormat)?;
-        let pinned = ss.pinned.then(|| "[pinned]".cyan()).unwrap_or_default();
-        println!(" {:w$}) {} {}", i, time, pinned, w = digits);
+    let snapshot = match id {
+        Some(id) => by_id(shots, id).ok_or_else(|| Error::UnknownSnapshot(id.to_string()))?,
+        None => select(fll, shots)?,
+    };
+
+    let installed = installed(alpm);
+    let diff = package_diff(&snapshot, &installed);
+
+    if diff.is_empty() {
+        green!(fll, "B-current");
+        return Ok(());
     }
 
-    let index = crate::utils::select(">>> ", shots.len() - 1).map_err(Error::Readline)?;
-    restore_snapshot(env, alpm, &caches, shots.remove(index))?;
+    // Abort before touching anything if the restoration couldn't complete.
+    let missing = diff.missing_tarballs(&aura_core::cache::all_versions(&caches));
+    if missing.is_empty().not() {
+        return Err(Error::MissingTarballs(missing));
+    }
+
+    report(fll, &diff);
+    proceed!(fll, env, "proceed").ok_or(Error::Cancelled)?;
+    restore_snapshot(env, &caches, &diff)?;
 
     green!(fll, "common-done");
     Ok(())
 }
 
-fn restore_snapshot(
+/// Offer to return every package affected by a failed transaction to the
+/// version it had beforehand, using tarballs from the cache.
+pub(crate) fn rollback(
     env: &Env,
+    fll: &FluentLanguageLoader,
     alpm: &Alpm,
-    caches: &[&Path],
-    snapshot: Snapshot,
+    tx: &Transaction,
 ) -> Result<(), Error> {
-    let installed: HashMap<&str, &str> = alpm
-        .as_ref()
+    let caches = env.caches();
+    let installed = installed(alpm);
+    let plan = tx.plan(&installed);
+
+    if plan.is_empty() {
+        green!(fll, "B-rollback-none");
+        return Ok(());
+    }
+
+    let missing = plan.missing_tarballs(&aura_core::cache::all_versions(&caches));
+    if missing.is_empty().not() {
+        return Err(Error::MissingTarballs(missing));
+    }
+
+    report(fll, &plan);
+    proceed!(fll, env, "B-rollback").ok_or(Error::Cancelled)?;
+    restore_snapshot(env, &caches, &plan)?;
+
+    green!(fll, "common-done");
+    Ok(())
+}
+
+/// The name and version of every installed package.
+fn installed(alpm: &Alpm) -> HashMap<&str, &str> {
+    alpm.as_ref()
         .localdb()
         .pkgs()
         .iter()
         .map(|p| (p.name(), p.version().as_str()))
-        .collect();
-    let diff = package_diff(&snapshot, &installed);
+        .collect()
+}
+
+/// The snapshot whose filename, date, or name matches the given identifier.
+fn by_id(shots: Vec<(PathBuf, Snapshot)>, id: &str) -> Option<Snapshot> {
+    let id = id.strip_suffix(".json").unwrap_or(id);
+
+    shots
+        .into_iter()
+        .find(|(path, ss)| identified_by(path, ss, id))
+        .map(|(_, ss)| ss)
+}
+
+fn identified_by(path: &Path, ss: &Snapshot, id: &str) -> bool {
+    let stem = path.file_stem().and_then(|s| s.to_str());
+    let name = ss.name.as_deref();
+    // The date is whatever of the filename precedes the name.
+    let date = match name {
+        None => stem,
+        Some(n) => stem
+            .and_then(|s| s.strip_suffix(n))
+            .and_then(|s| s.strip_suffix('.')),
+    };
+
+    stem == Some(id) || date == Some(id) || name == Some(id)
+}
+
+/// Let the user pick from a numbered list of snapshots, oldest first, either by
+/// number or by name.
+fn select(
+    fll: &FluentLanguageLoader,
+    mut shots: Vec<(PathBuf, Snapshot)>,
+) -> Result<Snapshot, Error> {
+    let digits = 1 + (shots.len() / 10);
+
+    aura!(fll, "B-select");
+    for (i, (path, ss)) in shots.iter().enumerate() {
+        let form = format_description!("[year]-[month]-[day] [hour]-[minute]-[second]");
+        let time = ss.time.format(form).map_err(Error::TimeFormat)?;
+        let pinned = ss
+            .pinned
+            .then(|| "[pinned]".highlight())
+            .unwrap_or_default();
+        let name = ss.name.as_deref().unwrap_or_default().bold();
+        let id = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .dimmed();
+        println!(" {:w$}) {} {} {} {}", i, time, name, id, pinned, w = digits);
+    }
+
+    let named = |answer: &str| {
+        shots
+            .iter()
+            .position(|(_, ss)| ss.name.as_deref() == Some(answer))
+    };
+    let index =
+        crate::utils::select_named(">>> ", shots.len() - 1, named).map_err(Error::Readline)?;
+    Ok(shots.remove(index).1)
+}
+
+/// Show what a restoration would change.
+fn report(fll: &FluentLanguageLoader, diff: &StateDiff) {
+    let (downgrades, upgrades): (Vec<_>, Vec<_>) = diff
+        .to_alter
+        .iter()
+        .partition(|(_, (now, then))| vercmp(then, now) == Ordering::Less);
+
+    if diff.to_install.is_empty().not() {
+        aura!(fll, "B-r-install");
+        for (name, ver) in diff.to_install.iter() {
+            println!("  {} {}", name.success(), ver);
+        }
+    }
+
+    if downgrades.is_empty().not() {
+        aura!(fll, "B-r-downgrade");
+        for (name, (now, then)) in downgrades {
+            println!("  {} {} -> {}", name.warning(), now, then);
+        }
+    }
+
+    if upgrades.is_empty().not() {
+        aura!(fll, "B-r-upgrade");
+        for (name, (now, then)) in upgrades {
+            println!("  {} {} -> {}", name.highlight(), now, then);
+        }
+    }
+
+    if diff.to_remove.is_empty().not() {
+        aura!(fll, "B-r-remove");
+        for name in diff.to_remove.iter() {
+            println!("  {}", name.error());
+        }
+    }
+}
 
+fn restore_snapshot(env: &Env, caches: &[&Path], diff: &StateDiff) -> Result<(), Error> {
     // Alter packages first to avoid potential breakage from the later removal
     // step.
-    if diff.to_add_or_alter.is_empty().not() {
+    if diff.to_install.is_empty().not() || diff.to_alter.is_empty().not() {
         let tarballs = aura_core::cache::package_paths(caches)
             .filter(|pp| {
                 let p = pp.as_package();
-                match diff.to_add_or_alter.get(p.name.as_ref()) {
-                    Some(v) if p.same_version(v) => true,
-                    Some(_) | None => false,
+                match diff.target(p.name.as_ref()) {
+                    Some(v) => p.same_version(v),
+                    None => false,
                 }
             })
             .map(|pp| pp.into_pathbuf().into_os_string());
@@ -187,7 +482,8 @@ fn restore_snapshot(
 
     // Remove packages that weren't installed within the chosen snapshot.
     if diff.to_remove.is_empty().not() {
-        crate::pacman::sudo_pacman(env, "-R", NOTHING, diff.to_remove).map_err(Error::Pacman)?;
+        crate::pacman::sudo_pacman(env, "-R", NOTHING, diff.to_remove.iter())
+            .map_err(Error::Pacman)?;
     }
 
     Ok(())
@@ -198,14 +494,15 @@ fn package_diff<'a>(
     snapshot: &'a Snapshot,
     installed: &'a HashMap<&'a str, &'a str>,
 ) -> StateDiff<'a> {
-    let mut to_add_or_alter: HashMap<&'a str, &'a str> = HashMap::new();
-    let mut to_remove: HashSet<&'a str> = HashSet::new();
+    let mut to_install: BTreeMap<&'a str, &'a str> = BTreeMap::new();
+    let mut to_alter: BTreeMap<&'a str, (&'a str, &'a str)> = BTreeMap::new();
+    let mut to_remove: BTreeSet<&'a str> = BTreeSet::new();
 
     for (name, ver) in snapshot.packages.iter() {
         // If a package saved in the snapshot isn't installed at all anymore, it
         // needs to be reinstalled.
         if installed.contains_key(name.as_str()).not() {
-            to_add_or_alter.insert(name, ver);
+            to_install.insert(name, ver);
         }
     }
 
@@ -216,20 +513,178 @@ fn package_diff<'a>(
             None => {
                 to_remove.insert(name);
             }
-            Some(v) => match alpm::vercmp(v.as_str(), ver) {
+            Some(v) => match vercmp(v, ver) {
                 // The installed version is the same as the snapshot; no action
                 // necessary.
                 Ordering::Equal => {}
                 // Otherwise, the version in the snapshot must be installed.
                 Ordering::Less | Ordering::Greater => {
-                    to_add_or_alter.insert(name, v);
+                    to_alter.insert(name, (*ver, v.as_str()));
                 }
             },
         }
     }
 
     StateDiff {
-        to_add_or_alter,
+        to_install,
+        to_alter,
         to_remove,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn snapshot(packages: &[(&str, &str)]) -> Snapshot {
+        Snapshot {
+            time: time::OffsetDateTime::UNIX_EPOCH,
+            pinned: false,
+            name: None,
+            packages: packages
+                .iter()
+                .map(|(n, v)| (n.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn restore_diff() {
+        let snap = snapshot(&[
+            ("gcc", "14.1.1-1"),
+            ("git", "2.46.0-1"),
+            ("emacs", "29.4-1"),
+        ]);
+        let installed: HashMap<&str, &str> = [
+            ("gcc", "14.2.1-1"),
+            ("git", "2.46.0-1"),
+            ("firefox", "129.0-1"),
+        ]
+        .into();
+        let diff = package_diff(&snap, &installed);
+
+        assert_eq!(BTreeMap::from([("emacs", "29.4-1")]), diff.to_install);
+        assert_eq!(
+            BTreeMap::from([("gcc", ("14.2.1-1", "14.1.1-1"))]),
+            diff.to_alter
+        );
+        assert_eq!(BTreeSet::from(["firefox"]), diff.to_remove);
+        assert_eq!(Some("14.1.1-1"), diff.target("gcc"));
+        assert_eq!(None, diff.target("git"));
+    }
+
+    #[test]
+    fn restore_missing_tarballs() {
+        let snap = snapshot(&[("gcc", "14.1.1-1"), ("emacs", "29.4-1")]);
+        let installed: HashMap<&str, &str> = [("gcc", "14.2.1-1")].into();
+        let diff = package_diff(&snap, &installed);
+
+        let mut versions: HashMap<String, HashSet<String>> = HashMap::new();
+        versions.insert("gcc".into(), ["14.1.1-1".into(), "14.2.1-1".into()].into());
+        assert_eq!(vec!["emacs-29.4-1"], diff.missing_tarballs(&versions));
+
+        versions.insert("emacs".into(), ["29.3-1".into()].into());
+        assert_eq!(vec!["emacs-29.4-1"], diff.missing_tarballs(&versions));
+
+        versions.insert("emacs".into(), ["29.4-1".into()].into());
+        assert!(diff.missing_tarballs(&versions).is_empty());
+    }
+
+    #[test]
+    fn rollback_plan() {
+        let before: HashMap<&str, &str> = [
+            ("glibc", "2.40-1"),
+            ("foo", "1.0-1"),
+            ("bar", "2.0-1"),
+            ("baz", "3.0-1"),
+        ]
+        .into();
+        let tx = Transaction::from_installed(&before);
+
+        assert_eq!(4, tx.before.packages.len());
+        assert_eq!(
+            Some("1.0-1"),
+            tx.before.packages.get("foo").map(|v| v.as_str())
+        );
+
+        // `foo` was upgraded and `qux` was pulled in as a new dependency, but
+        // then `bar` failed to build. `baz` was never reached.
+        let after: HashMap<&str, &str> = [
+            ("glibc", "2.40-1"),
+            ("foo", "1.1-1"),
+            ("bar", "2.0-1"),
+            ("baz", "3.0-1"),
+            ("qux", "0.5-1"),
+        ]
+        .into();
+        let plan = tx.plan(&after);
+
+        assert!(plan.to_install.is_empty());
+        assert_eq!(BTreeMap::from([("foo", ("1.1-1", "1.0-1"))]), plan.to_alter);
+        assert_eq!(BTreeSet::from(["qux"]), plan.to_remove);
+        assert!(tx.plan(&before).is_empty());
+    }
+
+    #[test]
+    fn snapshot_ids() {
+        let shots = || {
+            vec![
+                (
+                    PathBuf::from("/s/2024.07(Jul).09.21.07.02.json"),
+                    snapshot(&[("a", "1")]),
+                ),
+                (
+                    PathBuf::from("/s/2024.07(Jul).12.22.56.43.json"),
+                    snapshot(&[("b", "1")]),
+                ),
+            ]
+        };
+
+        let found = by_id(shots(), "2024.07(Jul).12.22.56.43").unwrap();
+        assert!(found.packages.contains_key("b"));
+        let found = by_id(shots(), "2024.07(Jul).09.21.07.02.json").unwrap();
+        assert!(found.packages.contains_key("a"));
+        assert!(by_id(shots(), "2024.07(Jul).10").is_none());
+    }
+
+    #[test]
+    fn named_snapshots() {
+        let named = || Snapshot {
+            name: Some("before-kde".to_string()),
+            ..snapshot(&[("c", "1")])
+        };
+        let shots = || {
+            vec![
+                (
+                    PathBuf::from("/s/2024.07(Jul).09.21.07.02.json"),
+                    snapshot(&[("a", "1")]),
+                ),
+                (
+                    PathBuf::from("/s/2024.07(Jul).12.22.56.43.before-kde.json"),
+                    named(),
+                ),
+            ]
+        };
+
+        let found = by_id(shots(), "before-kde").unwrap();
+        assert!(found.packages.contains_key("c"));
+        let found = by_id(shots(), "2024.07(Jul).12.22.56.43").unwrap();
+        assert!(found.packages.contains_key("c"));
+        let found = by_id(shots(), "2024.07(Jul).12.22.56.43.before-kde.json").unwrap();
+        assert!(found.packages.contains_key("c"));
+        assert!(by_id(shots(), "after-kde").is_none());
+    }
+
+    #[test]
+    fn snapshot_names() {
+        assert!(legal_name("before-kde_6"));
+        assert!(legal_name("").not());
+        assert!(legal_name("../etc").not());
+        assert!(legal_name("two words").not());
+        assert!(legal_name(&"a".repeat(MAX_NAME + 1)).not());
+
+        let taken: HashSet<String> = ["work".to_string(), "work-2".to_string()].into();
+        assert_eq!("home", unique_name("home", &taken));
+        assert_eq!("work-3", unique_name("work", &taken));
+    }
+}
@@ -4,15 +4,20 @@ use crate::env::Env;
 use crate::error::Nested;
 use crate::localization::Localised;
 use crate::localization::{self};
+use crate::theme::Themed;
+use alpm::PackageReason;
+use aura_core::cache::PkgPath;
 use colored::*;
 use i18n_embed::fluent::FluentLanguageLoader;
 use i18n_embed::LanguageLoader;
 use i18n_embed_fl::fl;
+use itertools::Itertools;
 use log::error;
 use r2d2_alpm::Alpm;
+use serde::Serialize;
 use std::collections::HashMap;
-use std::collections::HashSet;
 use std::io::BufWriter;
+use std::io::Write;
 use ubyte::ToByteUnit;
 use unic_langid::langid;
 use unic_langid::LanguageIdentifier;
@@ -43,8 +48,83 @@ impl Localised for Error {
     }
 }
 
+/// The translation coverage of a single locale, as rendered by `--json`.
+#[derive(Serialize)]
+struct Coverage {
+    locale: String,
+    language: String,
+    messages: usize,
+    total: usize,
+    percent: f64,
+}
+
+/// Basic facts about the machine, as rendered by `--json`.
+#[derive(Serialize)]
+struct Machine<'a> {
+    host: String,
+    user: String,
+    distro: String,
+    editor: &'a str,
+    installed_packages: usize,
+    pacman_cache_bytes: u64,
+    aura_cache_bytes: u64,
+    aura_build_bytes: u64,
+    tmp_bytes: u64,
+}
+
+/// A single package's installation footprint, as rendered by `--json`.
+#[derive(Serialize)]
+struct Heavy<'a> {
+    name: &'a str,
+    installed_size_bytes: i64,
+}
+
+/// A package group's installation footprint, as rendered by `--json`. Packages
+/// without a group are totalled under a `null` group.
+#[derive(Serialize)]
+struct HeavyGroup<'a> {
+    group: Option<&'a str>,
+    installed_size_bytes: i64,
+}
+
+/// An installed package group, as rendered by `--json`.
+#[derive(Serialize, Debug, PartialEq)]
+struct Group<'a> {
+    name: &'a str,
+    /// The installed packages that belong to it.
+    members: usize,
+    /// The total installed size of those members.
+    installed_size_bytes: i64,
+    /// Members that were explicitly installed.
+    explicit: usize,
+    /// Members that were pulled in as dependencies.
+    dependencies: usize,
+}
+
+/// A summary of the package cache, as rendered by `--json`.
+#[derive(Serialize, Debug, PartialEq)]
+struct CacheSummary {
+    total_bytes: u64,
+    tarballs: usize,
+    keep: usize,
+    /// Packages with more than `keep` cached versions.
+    downgradable: usize,
+    /// What `aura -Cc <keep>` would free.
+    reclaimable_bytes: u64,
+}
+
+/// Write some value to stdout as pretty JSON.
+fn print_json<T>(value: &T) -> Result<(), Error>
+where
+    T: Serialize,
+{
+    let mut w = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut w, value).map_err(|_| Error::Stdout)?;
+    writeln!(w).map_err(|_| Error::Stdout)
+}
+
 /// Raw contents of loaded localizations.
-pub(crate) fn localization() -> Result<(), Error> {
+pub(crate) fn localization(json: bool) -> Result<(), Error> {
     let stats: HashMap<LanguageIdentifier, (String, usize)> = localization::load_all()
         .map_err(Error::LangLoad)?
         .into_iter()
@@ -59,6 +139,22 @@ pub(crate) fn localization() -> Result<(), Error> {
     let mut sorted: Vec<_> = stats.into_iter().collect();
     sorted.sort_by_key(|(_, (_, count))| *count);
     sorted.reverse();
+
+    if json {
+        let coverage: Vec<_> = sorted
+            .into_iter()
+            .map(|(lang, (language, messages))| Coverage {
+                locale: lang.to_string(),
+                language,
+                messages,
+                total: max,
+                percent: 100.0 * messages as f64 / max as f64,
+            })
+            .collect();
+
+        return print_json(&coverage);
+    }
+
     let long = sorted
         .iter()
         .map(|(_, (n, _))| n.chars().count())
@@ -69,11 +165,11 @@ pub(crate) fn localization() -> Result<(), Error> {
     for (lang, (n, c)) in sorted {
         let perc = 100.0 * c as f64 / max as f64;
         let l = if perc < 50.0 {
-            format!("{}", lang).red()
+            format!("{}", lang).error()
         } else if perc < 100.0 {
-            format!("{}", lang).yellow()
+            format!("{}", lang).warning()
         } else {
-            format!("{}", lang).green()
+            format!("{}", lang).success()
         };
         let pad = long - visual_len(&lang, &n);
         println!(
@@ -100,7 +196,7 @@ fn visual_len(lang: &LanguageIdentifier, msg: &str) -> usize {
 }
 
 /// Basic stats about the current machine.
-pub(crate) fn stats(env: &Env, fll: &FluentLanguageLoader) -> Result<(), Error> {
+pub(crate) fn stats(env: &Env, fll: &FluentLanguageLoader, json: bool) -> Result<(), Error> {
     let alpm = env.alpm().map_err(Error::Env)?;
     let mut w = BufWriter::new(std::io::stdout());
 
@@ -114,14 +210,24 @@ pub(crate) fn stats(env: &Env, fll: &FluentLanguageLoader) -> Result<(), Error>
         .sum();
     let aura_build_bytes = aura_core::recursive_dir_size(&env.aur.build);
     let tmp_bytes = aura_core::recursive_dir_size("/tmp");
+    let host = whoami::fallible::hostname().unwrap_or_else(|_| "Unknown".to_string());
+
+    if json {
+        return print_json(&Machine {
+            host,
+            user: whoami::username(),
+            distro: whoami::distro(),
+            editor: &env.general.editor,
+            installed_packages: pkgs,
+            pacman_cache_bytes,
+            aura_cache_bytes,
+            aura_build_bytes,
+            tmp_bytes,
+        });
+    }
 
     let pairs = vec![
-        (
-            fl!(fll, "stats-host"),
-            whoami::fallible::hostname()
-                .unwrap_or_else(|_| "Unknown".to_string())
-                .normal(),
-        ),
+        (fl!(fll, "stats-host"), host.normal()),
         (fl!(fll, "stats-user"), whoami::username().normal()),
         (fl!(fll, "stats-distro"), whoami::distro().normal()),
         (fl!(fll, "stats-editor"), env.general.editor.normal()),
@@ -147,44 +253,454 @@ pub(crate) fn stats(env: &Env, fll: &FluentLanguageLoader) -> Result<(), Error>
     crate::utils::info(&mut w, fll.current_language(), &pairs).map_err(|_| Error::Stdout)
 }
 
+/// Statistics about the package caches, as if cleaned to `keep` versions.
+pub(crate) fn cache(
+    env: &Env,
+    fll: &FluentLanguageLoader,
+    keep: usize,
+    json: bool,
+) -> Result<(), Error> {
+    let tarballs = aura_core::cache::package_paths(&env.caches()).filter_map(|pp| {
+        let bytes = pp.as_path().metadata().ok()?.len();
+        Some((pp, bytes))
+    });
+    let summary = cache_summary(tarballs, keep);
+
+    if json {
+        return print_json(&summary);
+    }
+
+    let pairs = vec![
+        (
+            fl!(fll, "stats-cache-size"),
+            format!("{}", summary.total_bytes.bytes()).normal(),
+        ),
+        (
+            fl!(fll, "stats-cache-tarballs"),
+            summary.tarballs.to_string().normal(),
+        ),
+        (
+            fl!(fll, "stats-cache-old", keep = keep),
+            summary.downgradable.to_string().normal(),
+        ),
+        (
+            fl!(fll, "stats-cache-reclaim", keep = keep),
+            format!("{}", summary.reclaimable_bytes.bytes()).success(),
+        ),
+    ];
+
+    let mut w = BufWriter::new(std::io::stdout());
+    crate::utils::info(&mut w, fll.current_language(), &pairs).map_err(|_| Error::Stdout)
+}
+
+/// Tally tarballs and their sizes. Like `-Cc`, the newest `keep` versions of
+/// each package are the ones that would survive a cleaning.
+fn cache_summary<I>(tarballs: I, keep: usize) -> CacheSummary
+where
+    I: IntoIterator<Item = (PkgPath, u64)>,
+{
+    let mut summary = CacheSummary {
+        total_bytes: 0,
+        tarballs: 0,
+        keep,
+        downgradable: 0,
+        reclaimable_bytes: 0,
+    };
+
+    tarballs
+        .into_iter()
+        .sorted_by(|(p0, _), (p1, _)| p1.cmp(p0))
+        .chunk_by(|(pp, _)| pp.as_package().name.clone())
+        .into_iter()
+        .for_each(|(_, group)| {
+            let sizes: Vec<u64> = group.map(|(_, bytes)| bytes).collect();
+            let extra: u64 = sizes.iter().skip(keep).sum();
+
+            summary.total_bytes += sizes.iter().sum::<u64>();
+            summary.tarballs += sizes.len();
+            summary.reclaimable_bytes += extra;
+
+            if sizes.len() > keep {
+                summary.downgradable += 1;
+            }
+        });
+
+    summary
+}
+
 /// The number of packages installed on the system.
 fn installed_packages(alpm: &Alpm) -> usize {
     alpm.as_ref().localdb().pkgs().iter().count()
 }
 
-/// Display the Top 10 packages with the biggest installation footprint.
-pub(crate) fn heavy_packages(alpm: &Alpm) {
+/// Display the Top `count` packages with the biggest installation footprint,
+/// or the smallest if `reverse` is set. A `count` of `None` displays them all.
+pub(crate) fn heavy_packages(
+    alpm: &Alpm,
+    count: Option<usize>,
+    reverse: bool,
+    json: bool,
+) -> Result<(), Error> {
     let db = alpm.as_ref().localdb();
-    let mut sizes: Vec<(&str, i64)> = db.pkgs().iter().map(|p| (p.name(), p.isize())).collect();
-    sizes.sort_by_key(|(_, size)| *size);
-    sizes.reverse();
+    let sizes = heaviest(
+        db.pkgs().iter().map(|p| (p.name(), p.isize())),
+        count,
+        reverse,
+    );
+
+    if json {
+        let heavy: Vec<_> = sizes
+            .into_iter()
+            .map(|(name, installed_size_bytes)| Heavy {
+                name,
+                installed_size_bytes,
+            })
+            .collect();
+
+        return print_json(&heavy);
+    }
+
     let longest = sizes
         .iter()
-        .take(10)
         .map(|(p, _)| p.chars().count())
         .max()
         .unwrap_or(0);
 
-    for (pkg, size) in sizes.into_iter().take(10) {
-        println!("{:w$} {}", pkg, size.bytes(), w = longest);
+    // Rendered ahead of time, so that they can be right-aligned.
+    let human: Vec<_> = sizes
+        .iter()
+        .map(|(_, size)| size.bytes().to_string())
+        .collect();
+    let widest = human.iter().map(|s| s.chars().count()).max().unwrap_or(0);
+
+    for ((pkg, _), size) in sizes.iter().zip(human) {
+        println!("{:w$} {:>s$}", pkg, size, w = longest, s = widest);
+    }
+
+    Ok(())
+}
+
+/// The `n` heaviest packages, heaviest first. With `reverse`, the `n` lightest
+/// instead, lightest first. Packages of equal size are ordered by name, and an
+/// `n` of `None` yields every package.
+fn heaviest<'a, I>(pkgs: I, n: Option<usize>, reverse: bool) -> Vec<(&'a str, i64)>
+where
+    I: Iterator<Item = (&'a str, i64)>,
+{
+    let mut sizes: Vec<_> = pkgs.collect();
+
+    if reverse {
+        sizes.sort_by(|(n0, s0), (n1, s1)| s0.cmp(s1).then_with(|| n0.cmp(n1)));
+    } else {
+        sizes.sort_by(|(n0, s0), (n1, s1)| s1.cmp(s0).then_with(|| n0.cmp(n1)));
+    }
+
+    if let Some(n) = n {
+        sizes.truncate(n);
+    }
+
+    sizes
+}
+
+/// Display the installation footprint of each package group, heaviest first
+/// (or lightest first, if `reverse` is set).
+pub(crate) fn heavy_groups(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    reverse: bool,
+    json: bool,
+) -> Result<(), Error> {
+    let db = alpm.as_ref().localdb();
+    let mut sizes = group_sizes(
+        db.pkgs()
+            .iter()
+            .map(|p| (p.groups().iter().collect(), p.isize())),
+    );
+
+    if reverse {
+        sizes.reverse();
+    }
+
+    if json {
+        let heavy: Vec<_> = sizes
+            .into_iter()
+            .map(|(group, installed_size_bytes)| HeavyGroup {
+                group,
+                installed_size_bytes,
+            })
+            .collect();
+
+        return print_json(&heavy);
+    }
+
+    let ungrouped = fl!(fll, "stats-ungrouped");
+    let named: Vec<_> = sizes
+        .into_iter()
+        .map(|(g, size)| (g.unwrap_or(ungrouped.as_str()), size))
+        .collect();
+
+    let longest = named
+        .iter()
+        .map(|(g, _)| g.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    for (group, size) in named {
+        println!("{:w$} {}", group, size.bytes(), w = longest);
     }
+
+    Ok(())
 }
 
-/// Display the unique groups found installed on the system.
-pub(crate) fn groups(alpm: &Alpm) {
+/// Total the installed sizes of packages by group, heaviest first. A package
+/// that belongs to several groups counts toward each of them, and those that
+/// belong to none are gathered under `None`.
+fn group_sizes<'a, I>(pkgs: I) -> Vec<(Option<&'a str>, i64)>
+where
+    I: Iterator<Item = (Vec<&'a str>, i64)>,
+{
+    let mut totals: HashMap<Option<&str>, i64> = HashMap::new();
+
+    for (groups, size) in pkgs {
+        if groups.is_empty() {
+            *totals.entry(None).or_default() += size;
+        }
+
+        for g in groups {
+            *totals.entry(Some(g)).or_default() += size;
+        }
+    }
+
+    let mut sizes: Vec<_> = totals.into_iter().collect();
+    sizes.sort_by(|(g0, s0), (g1, s1)| s1.cmp(s0).then(g0.cmp(g1)));
+    sizes
+}
+
+/// Display the unique groups found installed on the system, heaviest first,
+/// along with how their members came to be installed.
+pub(crate) fn groups(
+    fll: &FluentLanguageLoader,
+    alpm: &Alpm,
+    quiet: bool,
+    json: bool,
+) -> Result<(), Error> {
     let db = alpm.as_ref().localdb();
-    let mut groups = HashSet::new();
+    let groups = group_summaries(db.pkgs().iter().map(|p| {
+        let explicit = p.reason() == PackageReason::Explicit;
+        (p.groups().iter().collect(), p.isize(), explicit)
+    }));
 
-    for p in db.pkgs() {
-        for g in p.groups() {
-            groups.insert(g);
+    if json {
+        return print_json(&groups);
+    }
+
+    if quiet {
+        for g in groups {
+            println!("{}", g.name);
         }
+
+        return Ok(());
+    }
+
+    let longest = groups
+        .iter()
+        .map(|g| g.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let sizes: Vec<_> = groups
+        .iter()
+        .map(|g| g.installed_size_bytes.bytes().to_string())
+        .collect();
+    let widest = sizes.iter().map(|s| s.chars().count()).max().unwrap_or(0);
+
+    for (g, size) in groups.iter().zip(sizes) {
+        let origin = fl!(
+            fll,
+            "stats-group-origin",
+            explicit = g.explicit,
+            deps = g.dependencies
+        );
+
+        println!(
+            "{:w$} {:>3} {:>s$}  {}",
+            g.name.bold(),
+            g.members,
+            size,
+            origin,
+            w = longest,
+            s = widest
+        );
     }
 
-    let mut v: Vec<_> = groups.into_iter().collect();
-    v.sort_unstable();
+    Ok(())
+}
+
+/// Summarize each package group from its members' groups, installed sizes, and
+/// whether they were explicitly installed. Heaviest groups come first.
+fn group_summaries<'a, I>(pkgs: I) -> Vec<Group<'a>>
+where
+    I: Iterator<Item = (Vec<&'a str>, i64, bool)>,
+{
+    let mut groups: HashMap<&str, Group> = HashMap::new();
+
+    for (names, size, explicit) in pkgs {
+        for name in names {
+            let g = groups.entry(name).or_insert(Group {
+                name,
+                members: 0,
+                installed_size_bytes: 0,
+                explicit: 0,
+                dependencies: 0,
+            });
+
+            g.members += 1;
+            g.installed_size_bytes += size;
+
+            if explicit {
+                g.explicit += 1;
+            } else {
+                g.dependencies += 1;
+            }
+        }
+    }
+
+    let mut v: Vec<_> = groups.into_values().collect();
+    v.sort_by(|a, b| {
+        b.installed_size_bytes
+            .cmp(&a.installed_size_bytes)
+            .then_with(|| a.name.cmp(b.name))
+    });
+    v
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SIZES: &[(&str, i64)] = &[
+        ("gcc", 203_423_744),
+        ("firefox", 264_241_152),
+        ("which", 30_720),
+        ("emacs", 140_509_184),
+        ("iana-etc", 3_932_160),
+        ("tzdata", 1_622_016),
+    ];
+
+    #[test]
+    fn heaviest_packages() {
+        let expected = vec![("firefox", 264_241_152), ("gcc", 203_423_744)];
+        assert_eq!(expected, heaviest(SIZES.iter().copied(), Some(2), false));
+    }
+
+    #[test]
+    fn lightest_packages() {
+        let expected = vec![
+            ("which", 30_720),
+            ("tzdata", 1_622_016),
+            ("iana-etc", 3_932_160),
+        ];
+        assert_eq!(expected, heaviest(SIZES.iter().copied(), Some(3), true));
+    }
+
+    #[test]
+    fn count_exceeds_packages() {
+        assert_eq!(
+            SIZES.len(),
+            heaviest(SIZES.iter().copied(), Some(100), true).len()
+        );
+    }
+
+    #[test]
+    fn all_packages_with_ties() {
+        let sizes = [("zsh", 10), ("bash", 10), ("dash", 5), ("fish", 20)];
+        let expected = vec![("fish", 20), ("bash", 10), ("zsh", 10), ("dash", 5)];
+        assert_eq!(expected, heaviest(sizes.iter().copied(), None, false));
+
+        let expected = vec![("dash", 5), ("bash", 10), ("zsh", 10), ("fish", 20)];
+        assert_eq!(expected, heaviest(sizes.iter().copied(), None, true));
+    }
+
+    #[test]
+    fn json_schema() {
+        let heavy = Heavy {
+            name: "gcc",
+            installed_size_bytes: 203_423_744,
+        };
+        let json = serde_json::to_string(&heavy).unwrap();
+        assert_eq!(r#"{"name":"gcc","installed_size_bytes":203423744}"#, json);
+
+        let group = HeavyGroup {
+            group: None,
+            installed_size_bytes: 1024,
+        };
+        let json = serde_json::to_string(&group).unwrap();
+        assert_eq!(r#"{"group":null,"installed_size_bytes":1024}"#, json);
+    }
+
+    #[test]
+    fn group_totals() {
+        let fixture = std::fs::read_to_string("tests/group-sizes.txt").unwrap();
+        let pkgs = fixture.lines().filter(|l| !l.starts_with('#')).map(|l| {
+            let mut words = l.split_whitespace().skip(1);
+            let size = words.next().unwrap().parse().unwrap();
+            (words.collect(), size)
+        });
+
+        let expected = vec![
+            (None, 432_013_312),
+            (Some("base-devel"), 209_486_848),
+            (Some("plasma"), 58_720_256),
+            (Some("xorg"), 3_932_160),
+            (Some("xorg-server"), 3_932_160),
+        ];
+
+        assert_eq!(expected, group_sizes(pkgs));
+    }
+
+    #[test]
+    fn group_members() {
+        let fixture = std::fs::read_to_string("tests/group-members.txt").unwrap();
+        let pkgs = fixture.lines().filter(|l| !l.starts_with('#')).map(|l| {
+            let mut words = l.split_whitespace().skip(1);
+            let size = words.next().unwrap().parse().unwrap();
+            let explicit = words.next() == Some("explicit");
+            (words.collect(), size, explicit)
+        });
+
+        let group = |name, members, installed_size_bytes, explicit, dependencies| Group {
+            name,
+            members,
+            installed_size_bytes,
+            explicit,
+            dependencies,
+        };
+        let expected = vec![
+            group("base-devel", 4, 209_486_848, 1, 3),
+            group("plasma", 2, 58_720_256, 2, 0),
+            group("xorg", 1, 3_932_160, 0, 1),
+            group("xorg-server", 1, 3_932_160, 0, 1),
+        ];
+
+        assert_eq!(expected, group_summaries(pkgs));
+    }
+
+    #[test]
+    fn cache_figures() {
+        let fixture = std::fs::read_to_string("tests/cache-sizes.txt").unwrap();
+        let tarballs = fixture.lines().filter(|l| !l.starts_with('#')).map(|l| {
+            let (path, bytes) = l.split_once(' ').unwrap();
+            let pp = PkgPath::new(path.into()).unwrap();
+            (pp, bytes.trim().parse().unwrap())
+        });
+
+        let expected = CacheSummary {
+            total_bytes: 444_100_000,
+            tarballs: 11,
+            keep: 2,
+            downgradable: 2,
+            reclaimable_bytes: 108_000_000,
+        };
 
-    for p in v {
-        println!("{}", p);
+        assert_eq!(expected, cache_summary(tarballs, 2));
     }
 }
@@ -0,0 +1,848 @@
+//! Extensions to the `-S` command.
+
+use crate::env::Env;
+use crate::error::Exited;
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::utils::Succeeded;
+use aura_core::cache::PkgPath;
+use colored::Colorize;
+use curl::easy::Easy;
+use curl::easy::TimeCondition;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::debug;
+use log::error;
+use log::warn;
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::ops::Not;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+/// The HTTP status of a file that hasn't changed since we last fetched it.
+const NOT_MODIFIED: u32 = 304;
+
+/// The HTTP status of a file that a mirror doesn't have.
+const NOT_FOUND: u32 = 404;
+
+/// How long to wait for a mirror to answer at all.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub(crate) enum Error {
+    Pacman(crate::pacman::Error),
+    NotDownloaded(String),
+    Unparseable(String),
+    Read(PathBuf, std::io::Error),
+    Write(PathBuf, std::io::Error),
+    Stdout,
+    Env(crate::env::Error),
+    Locked(PathBuf),
+    External(std::io::Error),
+    Install(u8),
+    RefreshFailed(Vec<String>),
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Pacman(e) => e.nested(),
+            Error::NotDownloaded(_) => {}
+            Error::Unparseable(_) => {}
+            Error::Read(_, e) => error!("{e}"),
+            Error::Write(_, e) => error!("{e}"),
+            Error::Stdout => {}
+            Error::Env(e) => e.nested(),
+            Error::Locked(_) => {}
+            Error::External(e) => error!("{e}"),
+            Error::Install(_) => {}
+            Error::RefreshFailed(_) => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Pacman(e) => e.localise(fll),
+            Error::NotDownloaded(f) => fl!(fll, "S-not-downloaded", file = f.as_str()),
+            Error::Unparseable(f) => fl!(fll, "U-unparseable", target = f.as_str()),
+            Error::Read(p, _) => fl!(fll, "err-file-open", file = p.display().to_string()),
+            Error::Write(p, _) => fl!(fll, "err-file-write", file = p.display().to_string()),
+            Error::Stdout => fl!(fll, "err-write"),
+            Error::Env(e) => e.localise(fll),
+            Error::Locked(p) => fl!(fll, "S-locked", file = p.display().to_string()),
+            Error::External(_) => fl!(fll, "err-sudo"),
+            Error::Install(_) => fl!(fll, "S-install"),
+            Error::RefreshFailed(rs) => fl!(fll, "S-refresh-failed", repos = rs.join(", ")),
+        }
+    }
+}
+
+impl Exited for Error {
+    fn exit_code(&self) -> Option<u8> {
+        match self {
+            Error::Pacman(e) => e.exit_code(),
+            Error::NotDownloaded(_) => None,
+            Error::Unparseable(_) => None,
+            Error::Read(_, _) => None,
+            Error::Write(_, _) => None,
+            Error::Stdout => None,
+            Error::Env(_) => None,
+            Error::Locked(_) => None,
+            Error::External(_) => None,
+            Error::Install(code) => Some(*code),
+            Error::RefreshFailed(_) => None,
+        }
+    }
+}
+
+/// A single downloaded tarball, as rendered by `-Sw --json`. A list of these
+/// is enough to verify and install the same packages on an offline machine.
+#[derive(Debug, Serialize)]
+struct Entry {
+    name: String,
+    version: String,
+    /// Where Pacman reports the tarball as coming from.
+    url: String,
+    /// In bytes.
+    size: u64,
+    sha256: String,
+    signature_present: bool,
+}
+
+/// Something that can fetch the targets of a `-Sw` into the package cache.
+pub(crate) trait Downloader {
+    /// Fetch every target.
+    fn download(&self) -> Result<(), Error>;
+
+    /// The URL of every target, whose last segment is its file name.
+    fn urls(&self) -> Result<Vec<String>, Error>;
+}
+
+/// Downloads by way of `pacman -Sw` itself.
+pub(crate) struct Pacman<'a> {
+    env: &'a Env,
+    args: Vec<String>,
+}
+
+impl<'a> Pacman<'a> {
+    /// Given the arguments of the original `-Sw` call, as Pacman should see
+    /// them.
+    pub(crate) fn new(env: &'a Env, args: Vec<String>) -> Self {
+        Self { env, args }
+    }
+}
+
+impl Downloader for Pacman<'_> {
+    fn download(&self) -> Result<(), Error> {
+        crate::pacman::sudo_pacman_to_stderr(self.env, &self.args).map_err(Error::Pacman)
+    }
+
+    fn urls(&self) -> Result<Vec<String>, Error> {
+        let print = ["--print", "--print-format", "%l"].map(String::from);
+        let args = unrefreshed(&self.args).into_iter().chain(print);
+
+        crate::pacman::pacman_lines(args).map_err(Error::Pacman)
+    }
+}
+
+/// Download the targets of a `-Sw`, then print a manifest of them as JSON.
+/// Pacman's own output goes to stderr.
+pub(crate) fn print_manifest<D>(downloader: &D, caches: &[&Path]) -> Result<(), Error>
+where
+    D: Downloader,
+{
+    let entries = manifest(downloader, caches)?;
+
+    let mut w = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut w, &entries).map_err(|_| Error::Stdout)?;
+    writeln!(w).map_err(|_| Error::Stdout)
+}
+
+fn manifest<D>(downloader: &D, caches: &[&Path]) -> Result<Vec<Entry>, Error>
+where
+    D: Downloader,
+{
+    downloader.download()?;
+    downloader
+        .urls()?
+        .into_iter()
+        .map(|url| entry(caches, url))
+        .collect()
+}
+
+/// Describe a downloaded tarball by finding it in one of the caches.
+fn entry(caches: &[&Path], url: String) -> Result<Entry, Error> {
+    let file = url.rsplit('/').next().unwrap_or(&url).to_string();
+    let path = caches
+        .iter()
+        .map(|cache| cache.join(&file))
+        .find(|path| path.is_file())
+        .ok_or_else(|| Error::NotDownloaded(file.clone()))?;
+    let pp = PkgPath::new(path).ok_or(Error::Unparseable(file))?;
+    let size = pp
+        .as_path()
+        .metadata()
+        .map_err(|e| Error::Read(pp.as_path().to_path_buf(), e))?
+        .len();
+    let sha256 = aura_core::cache::sha256(pp.as_path())
+        .map_err(|e| Error::Read(pp.as_path().to_path_buf(), e))?;
+    let pkg = pp.as_package();
+
+    Ok(Entry {
+        name: pkg.name.to_string(),
+        version: pkg.version.to_string(),
+        url,
+        size,
+        sha256,
+        signature_present: pp.sig_file().is_file(),
+    })
+}
+
+/// A sync database, and the mirrors it can be downloaded from.
+struct Repo<'a> {
+    name: &'a str,
+    servers: &'a [String],
+}
+
+impl Repo<'_> {
+    /// The name of the database file, both locally and on each mirror.
+    fn file(&self) -> String {
+        format!("{}.db", self.name)
+    }
+}
+
+/// A downloaded file, and when the mirror last changed it, if it said.
+#[derive(Debug, PartialEq, Eq)]
+struct Fetched {
+    bytes: Vec<u8>,
+    modified: Option<SystemTime>,
+}
+
+/// What became of a single database during a refresh.
+#[derive(Debug, PartialEq, Eq)]
+enum Refreshed {
+    /// A newer database, and its signature if the mirror has one.
+    Updated(Fetched, Option<Vec<u8>>),
+    /// The local copy is as new as the mirror's.
+    Current,
+    /// None of the repo's mirrors could provide it.
+    Failed,
+}
+
+/// Refresh the database of every repository enabled for syncing, downloading
+/// as many at once as `refresh_jobs` allows. Unless `force`d, databases that
+/// are already up to date are left alone. A repo that fails doesn't stop the
+/// others, but the failure is reported once they've all finished.
+pub(crate) fn refresh(fll: &FluentLanguageLoader, env: &Env, force: bool) -> Result<(), Error> {
+    let db_path = Path::new(&env.pacman.db_path);
+
+    // Pacman mustn't touch the databases until they've all been replaced.
+    let _lock = DbLock::acquire(env, db_path.join("db.lck"))?;
+
+    let sync_dir = db_path.join("sync");
+    let repos: Vec<_> = env
+        .pacman
+        .repos
+        .iter()
+        .filter(|r| syncable(&r.usage))
+        .map(|r| Repo {
+            name: &r.name,
+            servers: &r.servers,
+        })
+        .collect();
+
+    crate::aura!(fll, "S-refreshing", n = repos.len());
+
+    let results = crate::command::cache::sweep(env.general.refresh_jobs, repos, |repo| {
+        let since = if force { None } else { modified(&sync_dir.join(repo.file())) };
+        let result = fetch(repo, since, download);
+        report(fll, repo.name, &result);
+        result
+    });
+
+    let staging = env.cache_dir().map_err(Error::Env)?.join("sync");
+    install(env, &sync_dir, &staging, &results)?;
+
+    match failures(&results) {
+        failed if failed.is_empty() => Ok(()),
+        failed => Err(Error::RefreshFailed(failed)),
+    }
+}
+
+/// Pacman's lock on its databases, held by Aura while it refreshes them in
+/// Pacman's place. It's released when dropped.
+struct DbLock {
+    sudo: &'static str,
+    path: PathBuf,
+}
+
+impl DbLock {
+    /// Create the lock file, as Pacman would, failing if it already exists.
+    fn acquire(env: &Env, path: PathBuf) -> Result<DbLock, Error> {
+        // Pacman itself is busy with the databases.
+        if path.exists() {
+            return Err(Error::Locked(path));
+        }
+
+        // With `noclobber`, the shell opens the file exclusively, so only one
+        // of any racing processes can create it.
+        let status = Command::new(env.sudo())
+            .args(["sh", "-c", "set -C && : > \"$1\"", "sh"])
+            .arg(&path)
+            .stderr(Stdio::null())
+            .status()
+            .map_err(Error::External)?;
+
+        match status.succeeded() {
+            Ok(()) => Ok(DbLock {
+                sudo: env.sudo(),
+                path,
+            }),
+            Err(_) if path.exists() => Err(Error::Locked(path)),
+            Err(code) => Err(Error::Install(code)),
+        }
+    }
+}
+
+impl Drop for DbLock {
+    fn drop(&mut self) {
+        let removed = Command::new(self.sudo)
+            .args(["rm", "-f"])
+            .arg(&self.path)
+            .status()
+            .is_ok_and(|s| s.success());
+
+        if removed.not() {
+            error!("Failed to remove {}", self.path.display());
+        }
+    }
+}
+
+/// Does Pacman refresh a repo with the given `Usage` during `-Sy`?
+fn syncable(usage: &[String]) -> bool {
+    usage.is_empty() || usage.iter().any(|u| u == "Sync" || u == "All")
+}
+
+/// When a local database was last written, in seconds since the epoch.
+fn modified(path: &Path) -> Option<i64> {
+    let time = path.metadata().and_then(|m| m.modified()).ok()?;
+    let secs = time.duration_since(SystemTime::UNIX_EPOCH).ok()?.as_secs();
+
+    i64::try_from(secs).ok()
+}
+
+/// Why a single download failed.
+#[derive(Debug)]
+enum Fault {
+    /// The mirror answered that it doesn't have the file.
+    Missing,
+    /// Anything else, which may well be temporary.
+    Curl(curl::Error),
+}
+
+impl From<curl::Error> for Fault {
+    fn from(v: curl::Error) -> Self {
+        Self::Curl(v)
+    }
+}
+
+impl std::fmt::Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Fault::Missing => write!(f, "not found"),
+            Fault::Curl(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+/// Download a repo's database from the first of its mirrors that answers,
+/// along with a signature if the mirror has one. A mirror that can't say for
+/// sure whether it has a signature is passed over like one that fails outright.
+fn fetch<F>(repo: &Repo, since: Option<i64>, download: F) -> Refreshed
+where
+    F: Fn(&str, Option<i64>) -> Result<Option<Fetched>, Fault>,
+{
+    for server in repo.servers {
+        let url = format!("{}/{}", server.trim_end_matches('/'), repo.file());
+
+        match download(&url, since) {
+            Ok(None) => return Refreshed::Current,
+            Ok(Some(db)) => match download(&format!("{url}.sig"), None) {
+                Ok(Some(sig)) => return Refreshed::Updated(db, Some(sig.bytes)),
+                Err(Fault::Missing) => return Refreshed::Updated(db, None),
+                Ok(None) => warn!("{url}.sig: empty response"),
+                Err(e) => warn!("{url}.sig: {e}"),
+            },
+            Err(e) => warn!("{url}: {e}"),
+        }
+    }
+
+    Refreshed::Failed
+}
+
+/// Download a single file. Given a time, in seconds since the epoch, yields
+/// nothing if the file hasn't changed since then.
+fn download(url: &str, since: Option<i64>) -> Result<Option<Fetched>, Fault> {
+    debug!("CURL calling {url}");
+
+    let mut handle = Easy::new();
+    let mut data = Vec::new();
+    handle.url(url)?;
+    handle.fail_on_error(true)?;
+    handle.follow_location(true)?;
+    handle.connect_timeout(CONNECT_TIMEOUT)?;
+    handle.fetch_filetime(true)?;
+
+    if let Some(time) = since {
+        handle.time_condition(TimeCondition::IfModifiedSince)?;
+        handle.time_value(time)?;
+    }
+
+    // Blocked off to allow `data` and `handle` to be borrowed again down below.
+    let performed = {
+        let mut tx = handle.transfer();
+        tx.write_function(|bytes| {
+            data.extend_from_slice(bytes);
+            Ok(bytes.len())
+        })?;
+        tx.perform()
+    };
+
+    // Local `file://` mirrors have no HTTP status to give.
+    match performed {
+        Err(e) if e.is_file_couldnt_read_file() => return Err(Fault::Missing),
+        Err(_) if handle.response_code()? == NOT_FOUND => return Err(Fault::Missing),
+        Err(e) => return Err(Fault::Curl(e)),
+        Ok(()) => {}
+    }
+
+    if handle.time_condition_unmet()? || handle.response_code()? == NOT_MODIFIED {
+        Ok(None)
+    } else {
+        // Pacman likewise dates a database by when its mirror last changed it.
+        let modified = handle
+            .filetime()?
+            .and_then(|secs| u64::try_from(secs).ok())
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+
+        Ok(Some(Fetched {
+            bytes: data,
+            modified,
+        }))
+    }
+}
+
+/// Announce what became of a repo as soon as it's known.
+fn report(fll: &FluentLanguageLoader, repo: &str, result: &Refreshed) {
+    match result {
+        Refreshed::Updated(_, _) => crate::green!(fll, "S-refreshed", repo = repo),
+        Refreshed::Current => crate::aura!(fll, "S-current", repo = repo),
+        Refreshed::Failed => crate::red!(fll, "S-failed", repo = repo),
+    }
+}
+
+/// Move newly downloaded databases into place, alongside their signatures. The
+/// old signature of a database that no longer has one is removed, lest Pacman
+/// check the new database against it.
+fn install(
+    env: &Env,
+    sync_dir: &Path,
+    staging: &Path,
+    results: &[(Repo, Refreshed)],
+) -> Result<(), Error> {
+    let mut staged = Vec::new();
+    let mut stale = Vec::new();
+
+    std::fs::create_dir_all(staging).map_err(|e| Error::Write(staging.to_path_buf(), e))?;
+
+    for (repo, result) in results {
+        if let Refreshed::Updated(db, sig) = result {
+            let sig_file = format!("{}.sig", repo.file());
+            staged.push(stage(staging, &repo.file(), &db.bytes, db.modified)?);
+
+            match sig {
+                Some(s) => staged.push(stage(staging, &sig_file, s, None)?),
+                None => stale.push(sync_dir.join(sig_file)),
+            }
+        }
+    }
+
+    let moved = if staged.is_empty() {
+        Ok(())
+    } else {
+        run(Command::new(env.sudo())
+            .args(["install", "-p", "-m", "644", "-t"])
+            .arg(sync_dir)
+            .args(&staged))
+    };
+
+    // The staged files are no longer needed either way.
+    let _ = std::fs::remove_dir_all(staging);
+    moved?;
+
+    stale.retain(|p| p.exists());
+
+    if stale.is_empty().not() {
+        run(Command::new(env.sudo()).args(["rm", "-f"]).args(&stale))?;
+    }
+
+    Ok(())
+}
+
+/// Run an elevated command to completion.
+fn run(command: &mut Command) -> Result<(), Error> {
+    command
+        .status()
+        .map_err(Error::External)?
+        .succeeded()
+        .map_err(Error::Install)
+}
+
+/// Write a downloaded file into the staging area, dated as its mirror dated it.
+fn stage(
+    staging: &Path,
+    file: &str,
+    bytes: &[u8],
+    modified: Option<SystemTime>,
+) -> Result<PathBuf, Error> {
+    let path = staging.join(file);
+    let write = || -> std::io::Result<()> {
+        let mut f = File::create(&path)?;
+        f.write_all(bytes)?;
+
+        match modified {
+            Some(time) => f.set_modified(time),
+            None => Ok(()),
+        }
+    };
+
+    write().map_err(|e| Error::Write(path.clone(), e))?;
+    Ok(path)
+}
+
+/// The names of the repos that couldn't be refreshed.
+fn failures(results: &[(Repo, Refreshed)]) -> Vec<String> {
+    results
+        .iter()
+        .filter(|(_, r)| matches!(r, Refreshed::Failed))
+        .map(|(repo, _)| repo.name.to_string())
+        .collect()
+}
+
+/// Warn if the sync databases have gone stale, since upgrading from a mirror
+/// that has stopped syncing can pull in a partial set of updates.
+pub(crate) fn warn_stale(fll: &FluentLanguageLoader, env: &Env) {
+    let stale = last_synced(env)
+        .and_then(|newest| staleness(newest, SystemTime::now(), env.general.stale_db_days));
+
+    if let Some(days) = stale {
+        let cmd = "aura -Syy".bold().to_string();
+        crate::yellow!(fll, "S-stale", days = days, cmd = cmd);
+    }
+}
+
+/// When the newest of the sync databases was last changed by its mirror. Both
+/// Pacman and Aura date the database files that way when refreshing them.
+/// Nothing if there are no databases yet.
+pub(crate) fn last_synced(env: &Env) -> Option<SystemTime> {
+    let sync_dir = Path::new(&env.pacman.db_path).join("sync");
+
+    env.pacman
+        .repos
+        .iter()
+        .filter(|r| syncable(&r.usage))
+        .filter_map(|r| {
+            let repo = Repo {
+                name: &r.name,
+                servers: &r.servers,
+            };
+
+            sync_dir
+                .join(repo.file())
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+        })
+        .max()
+}
+
+/// How many whole days before `now` the sync databases were last changed, but
+/// only if that's more than the `threshold`.
+pub(crate) fn staleness(newest: SystemTime, now: SystemTime, threshold: u64) -> Option<u64> {
+    crate::command::check::days_old(newest, now).filter(|days| *days > threshold)
+}
+
+/// The same arguments, minus any database refresh, which would otherwise
+/// require root.
+pub(crate) fn unrefreshed(args: &[String]) -> Vec<String> {
+    args.iter()
+        .filter(|a| a.as_str() != "--refresh")
+        .map(|a| match a.strip_prefix('-') {
+            Some(shorts) if shorts.starts_with('-').not() => a.replace('y', ""),
+            _ => a.clone(),
+        })
+        .filter(|a| a != "-")
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::TempDir;
+    use std::collections::BTreeSet;
+
+    const MIRROR: &str = "https://mirror.example.org/core/os/x86_64";
+
+    fn fetched(bytes: &[u8]) -> Fetched {
+        Fetched {
+            bytes: bytes.to_vec(),
+            modified: None,
+        }
+    }
+
+    /// Pretends to download by writing tarballs straight into a cache.
+    struct MockDownloader<'a> {
+        cache: &'a Path,
+        /// File names, their contents, and whether they are signed.
+        files: Vec<(&'a str, &'a str, bool)>,
+    }
+
+    impl Downloader for MockDownloader<'_> {
+        fn download(&self) -> Result<(), Error> {
+            for (file, contents, signed) in self.files.iter() {
+                let path = self.cache.join(file);
+                std::fs::write(&path, contents).unwrap();
+
+                if *signed {
+                    std::fs::write(path.with_extension("zst.sig"), "").unwrap();
+                }
+            }
+
+            Ok(())
+        }
+
+        fn urls(&self) -> Result<Vec<String>, Error> {
+            Ok(self
+                .files
+                .iter()
+                .map(|(file, _, _)| format!("{MIRROR}/{file}"))
+                .collect())
+        }
+    }
+
+    #[test]
+    fn manifest_matches_downloads() {
+        let cache = TempDir::new("manifest");
+
+        let downloader = MockDownloader {
+            cache: &cache,
+            files: vec![
+                ("glibc-2.40-1-x86_64.pkg.tar.zst", "abc", true),
+                ("aura-bin-4.0.2-1-x86_64.pkg.tar.zst", "", false),
+            ],
+        };
+        let entries = manifest(&downloader, &[Path::new("/nonexistent"), &cache])
+            .ok()
+            .unwrap();
+
+        let downloaded: BTreeSet<_> = std::fs::read_dir(&cache)
+            .unwrap()
+            .filter_map(|de| de.ok())
+            .filter_map(|de| PkgPath::new(de.path()))
+            .map(|pp| pp.as_package().name.to_string())
+            .collect();
+        let listed: BTreeSet<_> = entries.iter().map(|e| e.name.clone()).collect();
+        assert_eq!(downloaded, listed);
+
+        let glibc = &entries[0];
+        assert_eq!("glibc", glibc.name);
+        assert_eq!("2.40-1", glibc.version);
+        assert_eq!(
+            format!("{MIRROR}/glibc-2.40-1-x86_64.pkg.tar.zst"),
+            glibc.url
+        );
+        assert_eq!(3, glibc.size);
+        assert_eq!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            glibc.sha256
+        );
+        assert!(glibc.signature_present);
+
+        let aura = &entries[1];
+        assert_eq!(0, aura.size);
+        assert_eq!(
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            aura.sha256
+        );
+        assert!(aura.signature_present.not());
+
+        // Pacman claiming a target that never arrived is an error.
+        let res = entry(
+            &[&cache],
+            format!("{MIRROR}/zlib-1.3.1-2-x86_64.pkg.tar.zst"),
+        );
+        assert!(
+            matches!(res, Err(Error::NotDownloaded(f)) if f == "zlib-1.3.1-2-x86_64.pkg.tar.zst")
+        );
+    }
+
+    #[test]
+    fn mirror_fallback() {
+        let servers = vec![
+            "https://down.example.org/core/os/x86_64".to_string(),
+            "https://up.example.org/core/os/x86_64/".to_string(),
+        ];
+        let core = Repo {
+            name: "core",
+            servers: &servers,
+        };
+        let seen = std::cell::RefCell::new(Vec::new());
+        let download = |url: &str, since: Option<i64>| {
+            seen.borrow_mut().push(url.to_string());
+            match url {
+                u if u.starts_with("https://down") => Err(Fault::Curl(curl::Error::new(7))),
+                u if u.ends_with(".sig") => Ok(Some(fetched(b"sig"))),
+                _ if since.is_some() => Ok(None),
+                _ => Ok(Some(fetched(b"db"))),
+            }
+        };
+
+        assert_eq!(
+            Refreshed::Updated(fetched(b"db"), Some(b"sig".to_vec())),
+            fetch(&core, None, download)
+        );
+        assert_eq!(
+            vec![
+                "https://down.example.org/core/os/x86_64/core.db",
+                "https://up.example.org/core/os/x86_64/core.db",
+                "https://up.example.org/core/os/x86_64/core.db.sig",
+            ],
+            seen.take()
+        );
+
+        // Up to date, so there's nothing to download.
+        assert_eq!(Refreshed::Current, fetch(&core, Some(0), download));
+
+        let broken = Repo {
+            name: "core",
+            servers: &servers[..1],
+        };
+        assert_eq!(Refreshed::Failed, fetch(&broken, None, download));
+    }
+
+    #[test]
+    fn signature_failures() {
+        let servers = vec![
+            "https://flaky.example.org/core/os/x86_64".to_string(),
+            "https://unsigned.example.org/core/os/x86_64".to_string(),
+        ];
+        let core = Repo {
+            name: "core",
+            servers: &servers,
+        };
+        let download = |url: &str, _| match url {
+            u if u.ends_with(".db") => Ok(Some(fetched(b"db"))),
+            u if u.starts_with("https://flaky") => Err(Fault::Curl(curl::Error::new(28))),
+            _ => Err(Fault::Missing),
+        };
+
+        // Only a mirror that definitely has no signature is believed, such that
+        // the old one isn't removed because of a timeout.
+        assert_eq!(
+            Refreshed::Updated(fetched(b"db"), None),
+            fetch(&core, None, download)
+        );
+
+        let flaky = Repo {
+            name: "core",
+            servers: &servers[..1],
+        };
+        assert_eq!(Refreshed::Failed, fetch(&flaky, None, download));
+    }
+
+    #[test]
+    fn failures_reported_together() {
+        let servers = ["https://mirror.example.org".to_string()];
+        let names = ["core", "extra", "multilib", "chaotic"];
+
+        for jobs in [1, 2, 4] {
+            let repos = names
+                .iter()
+                .map(|name| Repo {
+                    name,
+                    servers: &servers,
+                })
+                .collect();
+            // The failure of one repo doesn't affect any other.
+            let results = crate::command::cache::sweep(jobs, repos, |repo| {
+                fetch(repo, None, |url: &str, _| match url {
+                    u if u.contains("multilib") || u.contains("chaotic") => {
+                        Err(Fault::Curl(curl::Error::new(28)))
+                    }
+                    u if u.ends_with(".sig") => Err(Fault::Missing),
+                    _ => Ok(Some(fetched(b""))),
+                })
+            });
+
+            let order: Vec<_> = results.iter().map(|(r, _)| r.name).collect();
+            assert_eq!(names.to_vec(), order);
+            assert_eq!(Refreshed::Updated(fetched(b""), None), results[0].1);
+            assert_eq!(vec!["multilib", "chaotic"], failures(&results));
+        }
+    }
+
+    #[test]
+    fn staged_with_mirror_date() {
+        let staging = TempDir::new("staging");
+        let then = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let modified = |p: &Path| p.metadata().unwrap().modified().unwrap();
+
+        let db = stage(&staging, "core.db", b"db", Some(then)).ok().unwrap();
+        assert_eq!(then, modified(&db));
+        assert_eq!(b"db".to_vec(), std::fs::read(&db).unwrap());
+
+        // Without a date from the mirror, the file is as new as the download.
+        let sig = stage(&staging, "core.db.sig", b"sig", None).ok().unwrap();
+        assert!(modified(&sig) > then);
+    }
+
+    #[test]
+    fn sync_usage() {
+        let usage = |us: &[&str]| syncable(&us.iter().map(|u| u.to_string()).collect::<Vec<_>>());
+
+        assert!(usage(&[]));
+        assert!(usage(&["All"]));
+        assert!(usage(&["Search", "Sync"]));
+        assert!(usage(&["Install", "Upgrade"]).not());
+    }
+
+    #[test]
+    fn refresh_stripped() {
+        let args = ["-Syyw", "--refresh", "-y", "--dbpath", "/tmp/db", "glibc"].map(String::from);
+        let expected = vec!["-Sw", "--dbpath", "/tmp/db", "glibc"];
+
+        assert_eq!(expected, unrefreshed(&args));
+    }
+
+    #[test]
+    fn stale_databases() {
+        let day = Duration::from_secs(60 * 60 * 24);
+        let now = UNIX_EPOCH + day * 100;
+
+        assert_eq!(None, staleness(now - day * 3, now, 7));
+        assert_eq!(None, staleness(now - day * 7, now, 7));
+        assert_eq!(Some(8), staleness(now - day * 8, now, 7));
+        assert_eq!(Some(8), staleness(now - day * 8 - day / 2, now, 7));
+        assert_eq!(Some(1), staleness(now - day, now, 0));
+        // A clock that's behind the mirror isn't stale.
+        assert_eq!(None, staleness(now + day, now, 7));
+    }
+}
@@ -0,0 +1,178 @@
+//! Extensions to the `-U` command.
+
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::satisfy::DepStatus;
+use crate::satisfy::Satisfier;
+use alpm::SigLevel;
+use aura_core::Package;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use r2d2_alpm::Alpm;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+
+pub(crate) enum Error {
+    Unparseable(String),
+    Stdout,
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Unparseable(_) => {}
+            Error::Stdout => {}
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Unparseable(t) => fl!(fll, "U-unparseable", target = t.as_str()),
+            Error::Stdout => fl!(fll, "err-write"),
+        }
+    }
+}
+
+/// A single tarball to be installed, as rendered by `--json`.
+#[derive(Debug, Serialize)]
+struct Target {
+    name: String,
+    version: String,
+    /// In bytes, when the tarball is already on disk.
+    size: Option<u64>,
+    /// The path or URL as given.
+    source: String,
+    /// Only known for tarballs already on disk.
+    depends: Vec<Dependency>,
+}
+
+/// A dependency of some tarball, as rendered by `--json`.
+#[derive(Debug, Serialize)]
+struct Dependency {
+    name: String,
+    status: DepStatus,
+}
+
+/// Print what a `-U` of the given tarballs or URLs would install, as JSON.
+/// Nothing is actually installed or downloaded.
+pub(crate) fn print_json(alpm: &Alpm, packages: &[String]) -> Result<(), Error> {
+    let a = alpm.as_ref();
+
+    let targets = packages
+        .iter()
+        .map(|source| {
+            let size = Path::new(source)
+                .metadata()
+                .ok()
+                .filter(|m| m.is_file())
+                .map(|m| m.len());
+            let depends: Vec<String> = match size {
+                None => Vec::new(),
+                Some(_) => a
+                    .pkg_load(source.as_str(), false, SigLevel::NONE)
+                    .map(|p| p.depends().iter().map(|d| d.name().to_string()).collect())
+                    .unwrap_or_default(),
+            };
+
+            target(a, source, size, &depends).ok_or_else(|| Error::Unparseable(source.clone()))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut w = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut w, &targets).map_err(|_| Error::Stdout)?;
+    writeln!(w).map_err(|_| Error::Stdout)
+}
+
+/// Describe a tarball from its path or URL, which must be named as `makepkg`
+/// names them.
+fn target<D>(dbs: &D, source: &str, size: Option<u64>, depends: &[String]) -> Option<Target>
+where
+    D: Satisfier,
+{
+    let pkg = Package::from_path(Path::new(source))?;
+    let depends = depends
+        .iter()
+        .map(|d| Dependency {
+            name: d.clone(),
+            status: DepStatus::of(dbs, d),
+        })
+        .collect();
+
+    Some(Target {
+        name: pkg.name.into_owned(),
+        version: pkg.version.to_string(),
+        size,
+        source: source.to_string(),
+        depends,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    struct MockDbs;
+
+    impl Satisfier for MockDbs {
+        fn installed(&self, dep: &str) -> bool {
+            dep == "glibc"
+        }
+
+        fn in_repos(&self, dep: &str) -> bool {
+            dep == "glibc" || dep == "pacman"
+        }
+    }
+
+    #[test]
+    fn tarball_targets() {
+        let deps = vec![
+            "glibc".to_string(),
+            "pacman".to_string(),
+            "aura-extras".to_string(),
+        ];
+        let local = target(
+            &MockDbs,
+            "/var/cache/pacman/pkg/aura-bin-4.0.2-1-x86_64.pkg.tar.zst",
+            Some(4096),
+            &deps,
+        )
+        .unwrap();
+        let remote = target(
+            &MockDbs,
+            "https://example.org/pkgs/gcc-libs-14.2.1-1-x86_64.pkg.tar.zst",
+            None,
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(
+            json!([
+                {
+                    "name": "aura-bin",
+                    "version": "4.0.2-1",
+                    "size": 4096,
+                    "source": "/var/cache/pacman/pkg/aura-bin-4.0.2-1-x86_64.pkg.tar.zst",
+                    "depends": [
+                        { "name": "glibc", "status": "installed" },
+                        { "name": "pacman", "status": "repo" },
+                        { "name": "aura-extras", "status": "missing" },
+                    ],
+                },
+                {
+                    "name": "gcc-libs",
+                    "version": "14.2.1-1",
+                    "size": null,
+                    "source": "https://example.org/pkgs/gcc-libs-14.2.1-1-x86_64.pkg.tar.zst",
+                    "depends": [],
+                },
+            ]),
+            serde_json::to_value([local, remote]).unwrap()
+        );
+
+        assert!(target(&MockDbs, "tarball", None, &[]).is_none());
+    }
+}
@@ -0,0 +1,192 @@
+//! Upgrading repository and AUR packages in a single invocation.
+
+use crate::aura;
+use crate::command::aur;
+use crate::env::Env;
+use crate::error::Exited;
+use crate::error::Nested;
+use crate::localization::Localised;
+use crate::theme::Themed;
+use colored::Colorize;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed_fl::fl;
+use log::error;
+use r2d2_alpm::Alpm;
+use std::collections::HashMap;
+
+pub(crate) enum Error {
+    Env(crate::env::Error),
+    Pacman(crate::pacman::Error),
+    Aur(Box<aur::Error>),
+    Stdout(std::io::Error),
+}
+
+impl Nested for Error {
+    fn nested(&self) {
+        match self {
+            Error::Env(e) => e.nested(),
+            Error::Pacman(e) => e.nested(),
+            Error::Aur(e) => e.nested(),
+            Error::Stdout(e) => error!("{e}"),
+        }
+    }
+}
+
+impl Localised for Error {
+    fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Error::Env(e) => e.localise(fll),
+            Error::Pacman(e) => e.localise(fll),
+            Error::Aur(e) => e.localise(fll),
+            Error::Stdout(_) => fl!(fll, "err-write"),
+        }
+    }
+}
+
+impl Exited for Error {
+    fn exit_code(&self) -> Option<u8> {
+        match self {
+            Error::Env(_) => None,
+            Error::Pacman(e) => e.exit_code(),
+            Error::Aur(e) => e.exit_code(),
+            Error::Stdout(_) => None,
+        }
+    }
+}
+
+/// How many packages each phase of an upgrade changed.
+#[derive(Debug, PartialEq, Eq)]
+struct Summary {
+    repo: usize,
+    aur: usize,
+}
+
+impl Summary {
+    fn total(&self) -> usize {
+        self.repo + self.aur
+    }
+}
+
+/// A repository upgrade followed by an AUR one. The AUR phase only begins once
+/// the first has succeeded, so `sudo` is likely to still have the user's
+/// credentials.
+pub(crate) fn sysupgrade<F>(fll: &FluentLanguageLoader, env: &Env, repo: F) -> Result<(), Error>
+where
+    F: FnOnce() -> Result<(), crate::pacman::Error>,
+{
+    let summary = phases(
+        || {
+            let before = versions(&env.alpm().map_err(Error::Env)?);
+            repo().map_err(Error::Pacman)?;
+            let after = versions(&env.alpm().map_err(Error::Env)?);
+            Ok(changed(&before, &after))
+        },
+        || {
+            let alpm = env.alpm().map_err(Error::Env)?;
+            aur::upgrade(fll, &alpm, env, false).map_err(|e| Error::Aur(Box::new(e)))
+        },
+    )?;
+
+    report(fll, env, &summary)
+}
+
+/// Run each phase in order, tallying how many packages they changed.
+fn phases<R, A>(repo: R, aur: A) -> Result<Summary, Error>
+where
+    R: FnOnce() -> Result<usize, Error>,
+    A: FnOnce() -> Result<usize, Error>,
+{
+    let repo = repo()?;
+    let aur = aur()?;
+
+    Ok(Summary { repo, aur })
+}
+
+/// The versions of all installed packages.
+fn versions(alpm: &Alpm) -> HashMap<String, String> {
+    alpm.as_ref()
+        .localdb()
+        .pkgs()
+        .into_iter()
+        .map(|p| (p.name().to_string(), p.version().to_string()))
+        .collect()
+}
+
+/// How many packages were upgraded or newly installed.
+fn changed(before: &HashMap<String, String>, after: &HashMap<String, String>) -> usize {
+    after
+        .iter()
+        .filter(|(name, version)| before.get(*name) != Some(*version))
+        .count()
+}
+
+fn report(fll: &FluentLanguageLoader, env: &Env, summary: &Summary) -> Result<(), Error> {
+    let pairs = [
+        (fl!(fll, "Su-repo"), summary.repo.to_string().highlight()),
+        (fl!(fll, "Su-aur"), summary.aur.to_string().highlight()),
+        (fl!(fll, "Su-total"), summary.total().to_string().bold()),
+    ];
+
+    aura!(fll, "Su-summary");
+    crate::utils::info(
+        &mut std::io::stdout().lock(),
+        env.general.language.clone(),
+        &pairs,
+    )
+    .map_err(Error::Stdout)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn both_phases() {
+        let ran = RefCell::new(Vec::new());
+        let summary = phases(
+            || {
+                ran.borrow_mut().push("repo");
+                Ok(12)
+            },
+            || {
+                ran.borrow_mut().push("aur");
+                Ok(3)
+            },
+        )
+        .ok()
+        .unwrap();
+
+        assert_eq!(vec!["repo", "aur"], ran.into_inner());
+        assert_eq!(Summary { repo: 12, aur: 3 }, summary);
+        assert_eq!(15, summary.total());
+    }
+
+    #[test]
+    fn aur_waits_for_repo() {
+        let result = phases(
+            || Err(Error::Pacman(crate::pacman::Error::Exit(1))),
+            || panic!("The AUR phase shouldn't run"),
+        );
+
+        assert!(matches!(result, Err(Error::Pacman(_))));
+    }
+
+    #[test]
+    fn changed_packages() {
+        let map = |ps: &[(&str, &str)]| -> HashMap<String, String> {
+            ps.iter()
+                .map(|(n, v)| (n.to_string(), v.to_string()))
+                .collect()
+        };
+        let before = map(&[("linux", "6.9.1-1"), ("git", "2.45.0-1"), ("gcc", "14.1-1")]);
+        let after = map(&[
+            ("linux", "6.9.2-1"),
+            ("git", "2.45.0-1"),
+            ("gcc", "14.1-2"),
+            ("libfoo", "1.0-1"),
+        ]);
+
+        assert_eq!(3, changed(&before, &after));
+    }
+}
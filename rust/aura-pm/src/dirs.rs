@@ -5,7 +5,9 @@ use crate::localization::Localised;
 use crate::utils::PathStr;
 use i18n_embed_fl::fl;
 use log::error;
+use std::env::VarError;
 use std::ops::Not;
+use std::path::Path;
 use std::path::PathBuf;
 
 #[derive(Debug)]
@@ -13,6 +15,7 @@ pub enum Error {
     Mkdir(PathBuf, std::io::Error),
     XdgHome(std::env::VarError),
     XdgCache(std::env::VarError),
+    XdgState(std::env::VarError),
 }
 
 impl Nested for Error {
@@ -21,6 +24,7 @@ impl Nested for Error {
             Error::Mkdir(_, e) => error!("{e}"),
             Error::XdgHome(e) => error!("{e}"),
             Error::XdgCache(e) => error!("{e}"),
+            Error::XdgState(e) => error!("{e}"),
         }
     }
 }
@@ -31,16 +35,34 @@ impl Localised for Error {
             Error::Mkdir(p, _) => fl!(fll, "dir-mkdir", dir = p.utf8()),
             Error::XdgHome(_) => fl!(fll, "dir-home"),
             Error::XdgCache(_) => fl!(fll, "dir-cache"),
+            Error::XdgState(_) => fl!(fll, "dir-state"),
         }
     }
 }
 
 /// Like [`xdg_cache`], but for `XDG_CONFIG_HOME`.
 pub(crate) fn xdg_config() -> Result<PathBuf, Error> {
-    std::env::var("XDG_CONFIG_HOME")
-        .map(PathBuf::from)
-        .or_else(|_| std::env::var("HOME").map(|h| [&h, ".config"].iter().collect()))
-        .map_err(Error::XdgHome)
+    xdg_base(var, "XDG_CONFIG_HOME", &[".config"]).map_err(Error::XdgHome)
+}
+
+/// Some XDG base directory: the value of the given variable, or otherwise its
+/// `default` path within `$HOME`. Variables are read via `lookup`.
+fn xdg_base<F>(lookup: F, name: &str, default: &[&str]) -> Result<PathBuf, VarError>
+where
+    F: Fn(&str) -> Result<String, VarError>,
+{
+    lookup(name).map(PathBuf::from).or_else(|_| {
+        lookup("HOME").map(|home| {
+            let mut path = PathBuf::from(home);
+            path.extend(default);
+            path
+        })
+    })
+}
+
+/// A variable from the real environment of the process.
+fn var(name: &str) -> Result<String, VarError> {
+    std::env::var(name)
 }
 
 /// The location of Aura's config file.
@@ -57,6 +79,11 @@ pub(crate) fn aura_config() -> Result<PathBuf, Error> {
     Ok(dir.join("config.toml"))
 }
 
+/// The location of the declarative package list used by `aura home`.
+pub(crate) fn home_toml() -> Result<PathBuf, Error> {
+    aura_config().map(|c| c.with_file_name("home.toml"))
+}
+
 /// Fetch the path value of `$XDG_CACHE_HOME` or provide its default according
 /// to the specification:
 ///
@@ -64,10 +91,7 @@ pub(crate) fn aura_config() -> Result<PathBuf, Error> {
 /// > non-essential data files should be stored. If `$XDG_CACHE_HOME` is either not
 /// > set or empty, a default equal to `$HOME/.cache` should be used.
 fn xdg_cache() -> Result<PathBuf, Error> {
-    std::env::var("XDG_CACHE_HOME")
-        .map(PathBuf::from)
-        .or_else(|_| std::env::var("HOME").map(|h| [&h, ".cache"].iter().collect()))
-        .map_err(Error::XdgCache)
+    xdg_base(var, "XDG_CACHE_HOME", &[".cache"]).map_err(Error::XdgCache)
 }
 
 /// The full path to the Aura cache.
@@ -76,12 +100,37 @@ pub(crate) fn aura_xdg_cache() -> Result<PathBuf, Error> {
     Ok(cache)
 }
 
-/// The full path to the package snapshot directory.
+/// Like [`xdg_cache`], but for `XDG_STATE_HOME`, whose default is
+/// `$HOME/.local/state`.
+fn xdg_state() -> Result<PathBuf, Error> {
+    xdg_base(var, "XDG_STATE_HOME", &[".local", "state"]).map_err(Error::XdgState)
+}
+
+/// The full path to Aura's cache directory.
 ///
-/// Creates the directory if it doesn't exist.
-pub(crate) fn snapshot() -> Result<PathBuf, Error> {
-    let path = aura_xdg_cache()?.join("snapshots");
+/// A `cache_dir` setting from `aura.toml` takes precedence over
+/// `$XDG_CACHE_HOME`. The directory itself is not created here; see [`mkdir`].
+pub(crate) fn cache_dir(config: Option<&Path>) -> Result<PathBuf, Error> {
+    match config {
+        Some(p) => Ok(p.to_path_buf()),
+        None => aura_xdg_cache(),
+    }
+}
+
+/// The full path to Aura's state directory, for persistent data that isn't
+/// important enough to live in `~/.config/`.
+///
+/// A `state_dir` setting from `aura.toml` takes precedence over
+/// `$XDG_STATE_HOME`. The directory itself is not created here; see [`mkdir`].
+pub(crate) fn state_dir(config: Option<&Path>) -> Result<PathBuf, Error> {
+    match config {
+        Some(p) => Ok(p.to_path_buf()),
+        None => xdg_state().map(|p| p.join("aura")),
+    }
+}
 
+/// Create the given directory if it doesn't already exist.
+pub(crate) fn mkdir(path: PathBuf) -> Result<PathBuf, Error> {
     if path.is_dir().not() {
         std::fs::create_dir_all(&path).map_err(|e| Error::Mkdir(path.clone(), e))?;
     }
@@ -89,57 +138,84 @@ pub(crate) fn snapshot() -> Result<PathBuf, Error> {
     Ok(path)
 }
 
+/// The full path to the package snapshot directory.
+///
+/// Creates the directory if it doesn't exist.
+pub(crate) fn snapshot(cache: &Path) -> Result<PathBuf, Error> {
+    mkdir(cache.join("snapshots"))
+}
+
 /// The full path to the directory of AUR package `git` clones.
 ///
 /// Creates the directory if it doesn't exist.
-pub(crate) fn clones() -> Result<PathBuf, Error> {
+pub(crate) fn clones(cache: &Path) -> Result<PathBuf, Error> {
     let path = std::env::var("AURDEST")
         .map(PathBuf::from)
-        .or_else(|_| aura_xdg_cache().map(|p| p.join("packages")))?;
+        .unwrap_or_else(|_| cache.join("packages"));
 
-    if path.is_dir().not() {
-        std::fs::create_dir_all(&path).map_err(|e| Error::Mkdir(path.clone(), e))?;
-    }
-
-    Ok(path)
+    mkdir(path)
 }
 
 /// The full path to the build directory.
 ///
 /// Creates the directory if it doesn't exist.
-pub(crate) fn builds() -> Result<PathBuf, Error> {
-    let path = aura_xdg_cache()?.join("builds");
-
-    if path.is_dir().not() {
-        std::fs::create_dir_all(&path).map_err(|e| Error::Mkdir(path.clone(), e))?;
-    }
-
-    Ok(path)
+pub(crate) fn builds(cache: &Path) -> Result<PathBuf, Error> {
+    mkdir(cache.join("builds"))
 }
 
 /// The full path to the Aura-specific tarball cache.
 ///
 /// Creates the directory if it doesn't exist.
-pub(crate) fn tarballs() -> Result<PathBuf, Error> {
-    let path = aura_xdg_cache()?.join("cache");
-
-    if path.is_dir().not() {
-        std::fs::create_dir_all(&path).map_err(|e| Error::Mkdir(path.clone(), e))?;
-    }
-
-    Ok(path)
+pub(crate) fn tarballs(cache: &Path) -> Result<PathBuf, Error> {
+    mkdir(cache.join("cache"))
 }
 
 /// The full path to the directory of git hashes that indicate the last time an
 /// AUR package was built and installed.
 ///
 /// Creates the directory if it doesn't exist.
-pub(crate) fn hashes() -> Result<PathBuf, Error> {
-    let path = aura_xdg_cache()?.join("hashes");
+pub(crate) fn hashes(cache: &Path) -> Result<PathBuf, Error> {
+    mkdir(cache.join("hashes"))
+}
 
-    if path.is_dir().not() {
-        std::fs::create_dir_all(&path).map_err(|e| Error::Mkdir(path.clone(), e))?;
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::TempDir;
+    use std::collections::HashMap;
+
+    #[test]
+    fn cache_and_state_precedence() {
+        let vars = HashMap::from([("HOME", "/home/aura"), ("XDG_CACHE_HOME", "/xdg/cache")]);
+        let lookup = |name: &str| {
+            vars.get(name)
+                .map(|v| v.to_string())
+                .ok_or(VarError::NotPresent)
+        };
+
+        // XDG variables are honoured...
+        assert_eq!(
+            Ok(PathBuf::from("/xdg/cache")),
+            xdg_base(lookup, "XDG_CACHE_HOME", &[".cache"])
+        );
+
+        // ...but without them, we fall back to the spec's defaults.
+        assert_eq!(
+            Ok(PathBuf::from("/home/aura/.local/state")),
+            xdg_base(lookup, "XDG_STATE_HOME", &[".local", "state"])
+        );
+        assert!(xdg_base(|_| Err(VarError::NotPresent), "XDG_STATE_HOME", &[".local"]).is_err());
+
+        // Config overrides beat them all.
+        let over = PathBuf::from("/over");
+        assert_eq!(over, cache_dir(Some(&over)).unwrap());
+        assert_eq!(over, state_dir(Some(&over)).unwrap());
+
+        // Resolving a path never creates it; only `mkdir` does.
+        let dir = TempDir::new("dirs");
+        let state = state_dir(Some(&dir.join("state"))).unwrap();
+        assert!(state.exists().not());
+        assert_eq!(state, mkdir(state.clone()).unwrap());
+        assert!(state.is_dir());
     }
-
-    Ok(path)
 }
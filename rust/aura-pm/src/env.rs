@@ -1,10 +1,15 @@
 //! Aura runtime settings.
 
+use crate::command::misc::Program;
 use crate::dirs;
 use crate::error::Nested;
+use crate::localization::env_language;
 use crate::localization::identifier_from_locale;
 use crate::localization::Localised;
 use crate::makepkg::Makepkg;
+use crate::theme::Colors;
+use crate::utils::PathStr;
+use aura_core::faur::FAUR_URL;
 use i18n_embed_fl::fl;
 use log::debug;
 use log::error;
@@ -14,13 +19,34 @@ use r2d2_alpm::Alpm;
 use r2d2_alpm::AlpmManager;
 use serde::Deserialize;
 use serde::Serialize;
+use std::collections::BTreeSet;
 use std::collections::HashSet;
 use std::ops::Not;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 use unic_langid::LanguageIdentifier;
 
-const DEFAULT_EDITOR: &str = "vi";
+/// The editor used when no other can be found.
+pub(crate) const DEFAULT_EDITOR: &str = "vi";
+
+/// How many sync databases are downloaded at once, by default.
+const REFRESH_JOBS: u32 = 4;
+
+/// After how many days without a new package the sync databases are considered
+/// stale.
+const STALE_DB_DAYS: u64 = 7;
+
+/// Aura's documentation.
+pub(crate) const DOCS_URL: &str = "https://fosskers.github.io/aura/";
+/// Aura's source code repository.
+pub(crate) const REPO_URL: &str = "https://github.com/fosskers/aura";
+/// The page for filing a new bug report.
+pub(crate) const BUG_URL: &str = "https://github.com/fosskers/aura/issues/new";
+/// Aura's license.
+pub(crate) const LICENSE_URL: &str = "https://github.com/fosskers/aura/blob/master/LICENSE";
+/// Aura's own AUR page.
+pub(crate) const AUR_URL: &str = "https://aur.archlinux.org/packages/aura";
 
 pub(crate) enum Error {
     Dirs(crate::dirs::Error),
@@ -28,6 +54,9 @@ pub(crate) enum Error {
     Alpm(alpm::Error),
     R2d2(r2d2::Error),
     MissingEditor,
+    Endpoint(String),
+    Color(String),
+    PackagesFrom(PathBuf, std::io::Error),
 }
 
 impl Nested for Error {
@@ -36,8 +65,11 @@ impl Nested for Error {
             Error::Dirs(e) => e.nested(),
             Error::PConf(e) => error!("{e}"),
             Error::MissingEditor => {}
+            Error::Endpoint(_) => {}
+            Error::Color(_) => {}
             Error::Alpm(e) => error!("{e}"),
             Error::R2d2(e) => error!("{e}"),
+            Error::PackagesFrom(_, e) => error!("{e}"),
         }
     }
 }
@@ -48,8 +80,11 @@ impl Localised for Error {
             Error::Dirs(e) => e.localise(fll),
             Error::PConf(_) => fl!(fll, "env-pconf"),
             Error::MissingEditor => fl!(fll, "env-missing-editor"),
+            Error::Endpoint(url) => fl!(fll, "env-endpoint", url = url.as_str()),
+            Error::Color(c) => fl!(fll, "env-color", color = c.as_str()),
             Error::Alpm(_) => fl!(fll, "err-alpm"),
             Error::R2d2(_) => fl!(fll, "err-pool-create"),
+            Error::PackagesFrom(p, _) => fl!(fll, "err-file-open", file = p.utf8()),
         }
     }
 }
@@ -59,6 +94,8 @@ struct RawEnv {
     general: Option<RawGeneral>,
     aur: Option<RawAur>,
     backups: Option<RawBackups>,
+    open: Option<RawOpen>,
+    colors: Option<RawColors>,
 }
 
 impl RawEnv {
@@ -76,6 +113,186 @@ pub(crate) fn parsable_env() -> bool {
     RawEnv::try_new().is_some()
 }
 
+/// Parse the contents of an `aura.toml`, to see if they're legal.
+pub(crate) fn validate(content: &str) -> Result<(), basic_toml::Error> {
+    basic_toml::from_str::<RawEnv>(content).map(|_| ())
+}
+
+/// The packages held by the given contents of an `aura.toml`.
+pub(crate) fn holds(content: &str) -> Result<BTreeSet<String>, basic_toml::Error> {
+    basic_toml::from_str::<RawEnv>(content).map(|r| r.general.map(|g| g.holds).unwrap_or_default())
+}
+
+/// A fully-commented `aura.toml` in which every setting is given its default
+/// value.
+pub(crate) fn template() -> Result<String, Error> {
+    let cache = dirs::cache_dir(None).map_err(Error::Dirs)?;
+    let state = dirs::state_dir(None).map_err(Error::Dirs)?;
+    let general = General::default();
+    let aur = Aur::try_default(&cache).map_err(Error::Dirs)?;
+    let backups = Backups::try_default(&cache).map_err(Error::Dirs)?;
+    let open = Open::default();
+    let colors = Colors::default();
+
+    Ok(render_template(
+        &general, &aur, &backups, &open, &colors, &cache, &state,
+    ))
+}
+
+fn render_template(
+    general: &General,
+    aur: &Aur,
+    backups: &Backups,
+    open: &Open,
+    colors: &Colors,
+    cache: &Path,
+    state: &Path,
+) -> String {
+    format!(
+        r#"# Aura's configuration file, usually found at ~/.config/aura/config.toml.
+# Every setting below is shown with its default value.
+
+[general]
+# Affects parallelism in various algorithms.
+cpus = {cpus}
+# How many repository databases `-Sy` downloads at once.
+refresh_jobs = {refresh_jobs}
+# The editor opened with `--hotedit`, etc. Defaults to $EDITOR.
+editor = {editor:?}
+# Raise privileges via `doas` instead of `sudo`.
+doas = {doas}
+# The human language of Aura's output. See `aura stats --lang`.
+language = "{language}"
+# Automatically accept all prompts.
+noconfirm = {noconfirm}
+# Show dates like "3 months ago" in `-Ai` and `-Li`.
+relative_dates = {relative_dates}
+# Warn before `-Su` if the sync databases have gone this many days without a new
+# package, as they do when the mirrors have stopped syncing.
+stale_db_days = {stale_db_days}
+# Aura's cache directory. Defaults to $XDG_CACHE_HOME/aura.
+cache_dir = {cache:?}
+# Aura's state directory. Defaults to $XDG_STATE_HOME/aura.
+state_dir = {state:?}
+# Packages held back from every `-Syu` and `-Au`. Managed by `aura hold`.
+holds = []
+
+[aur]
+# The path in which to build packages.
+build = {build:?}
+# The path in which to store built package tarballs.
+cache = {tarballs:?}
+# The path in which to clone package metadata. Defaults to $AURDEST if set.
+clones = {clones:?}
+# The path in which to store the git hash of the latest build of each package.
+hashes = {hashes:?}
+# An alternate user to build as. Unset by default.
+# builduser = "nobody"
+# Packages to build with `pkgctl build` in a chroot.
+chroot = []
+# Packages to never upgrade.
+ignores = []
+# Always rebuild VCS packages during `-Au`.
+git = {git}
+# Prompt to edit build files (PKGBUILD, etc.) before building.
+hotedit = {hotedit}
+# Run `shellcheck` over PKGBUILDs before building.
+shellcheck = {shellcheck}
+# Display diffs of build files during upgrades.
+diff = {diff}
+# A program to review those diffs with, like "delta". Unset by default, which
+# shows git's own diff output.
+# difftool = "delta"
+# Remove makedeps after building.
+delmakedeps = {delmakedeps}
+# Delete a package's build directory after building.
+clean = {clean}
+# Delete the `src/` and `pkg/` directories left in a package's build directory,
+# once it's been installed.
+clean_after = {clean_after}
+# If false, suppress warnings about unknown packages.
+warn_unknowns = {warn_unknowns}
+# Don't run the `check()` function while building.
+nocheck = {nocheck}
+# Always reverse the results of `-As`.
+reverse = {reverse}
+# faur instances to query for package data, tried in order until one answers.
+endpoints = {endpoints:?}
+# How many times to retry a failed RPC request, git clone, or git pull, waiting
+# a little longer before each attempt.
+retries = {retries}
+# Seconds to wait for an RPC request, clone, or pull to connect, and for a
+# stalled one to receive anything. Unset by default, which waits as long as
+# curl and git themselves do.
+# connect_timeout = 30
+# read_timeout = 60
+
+[backups]
+# The path in which to store snapshot files.
+snapshots = {snapshots:?}
+# Automatically save a snapshot during `-Au`.
+automatic = {automatic}
+
+[open]
+# The pages opened by `aura open`. Override these to point at a fork or mirror.
+docs_url = {docs_url:?}
+repo_url = {repo_url:?}
+bug_url = {bug_url:?}
+license_url = {license_url:?}
+aur_url = {aur_url:?}
+# The program to open them with. Unset by default, which uses `xdg-open`.
+# browser = "firefox"
+
+[colors]
+# The colours of Aura's output, by role. Each is a name like "red" or
+# "bright blue", or a "#rrggbb" code. `--color never` disables them all.
+header = {header:?}
+success = {success:?}
+warning = {warning:?}
+error = {error:?}
+highlight = {highlight:?}
+"#,
+        cpus = general.cpus,
+        refresh_jobs = general.refresh_jobs,
+        editor = general.editor,
+        doas = general.doas,
+        language = general.language,
+        noconfirm = general.noconfirm,
+        relative_dates = general.relative_dates,
+        stale_db_days = general.stale_db_days,
+        cache = cache.display().to_string(),
+        state = state.display().to_string(),
+        build = aur.build.display().to_string(),
+        tarballs = aur.cache.display().to_string(),
+        clones = aur.clones.display().to_string(),
+        hashes = aur.hashes.display().to_string(),
+        git = aur.git,
+        hotedit = aur.hotedit,
+        shellcheck = aur.shellcheck,
+        diff = aur.diff,
+        delmakedeps = aur.delmakedeps,
+        clean = aur.clean,
+        clean_after = aur.clean_after,
+        warn_unknowns = aur.warn_unknowns,
+        nocheck = aur.nocheck,
+        reverse = aur.reverse,
+        endpoints = aur.endpoints,
+        retries = aur.retries,
+        snapshots = backups.snapshots.display().to_string(),
+        automatic = backups.automatic,
+        docs_url = open.docs_url,
+        repo_url = open.repo_url,
+        bug_url = open.bug_url,
+        license_url = open.license_url,
+        aur_url = open.aur_url,
+        header = crate::theme::name(&colors.header),
+        success = crate::theme::name(&colors.success),
+        warning = crate::theme::name(&colors.warning),
+        error = crate::theme::name(&colors.error),
+        highlight = crate::theme::name(&colors.highlight),
+    )
+}
+
 /// Aura's runtime environment, as a combination of settings specified in its
 /// config file, as well as options passed from the command line.
 #[derive(Debug, Serialize)]
@@ -87,6 +304,10 @@ pub(crate) struct Env {
     pub(crate) aur: Aur,
     /// Saving and restoring package states.
     pub(crate) backups: Backups,
+    /// The webpages opened by `aura open`.
+    pub(crate) open: Open,
+    /// The colours of Aura's output.
+    pub(crate) colors: Colors,
     /// Settings from a `pacman.conf`.
     #[serde(skip_serializing)]
     pub(crate) pacman: pacmanconf::Config,
@@ -96,6 +317,13 @@ pub(crate) struct Env {
     /// Is Aura being run by by the root user?
     #[serde(skip_serializing)]
     pub(crate) is_root: bool,
+    /// Packages held back from this sysupgrade, if it is one.
+    #[serde(skip_serializing)]
+    pub(crate) holds: BTreeSet<String>,
+    /// Packages read by `--packages-from`, less any also named on the command
+    /// line.
+    #[serde(skip_serializing)]
+    pub(crate) listed: Vec<String>,
 }
 
 impl Env {
@@ -104,14 +332,21 @@ impl Env {
         // because sensible defaults can (probably) be set anyway.
         let raw: Option<RawEnv> = RawEnv::try_new();
         debug!("Raw config parse successful: {}", raw.is_some());
-        let (general, aur, backups) = match raw {
+        let (general, aur, backups, open, colors) = match raw {
             Some(re) => (
-                re.general.map(|rg| rg.into()),
-                re.aur.map(|ra| ra.try_into()),
-                re.backups.map(|rb| rb.try_into()),
+                re.general.map(General::from),
+                re.aur,
+                re.backups,
+                re.open.map(Open::from),
+                re.colors,
             ),
-            None => (None, None, None),
+            None => (None, None, None, None, None),
         };
+        let general = general.unwrap_or_default();
+
+        // Other default directories live within the cache, so it must be
+        // determined first.
+        let cache = dirs::cache_dir(general.cache_dir.as_deref()).map_err(Error::Dirs)?;
 
         let makepkg = match crate::makepkg::Makepkg::new() {
             Ok(m) => Some(m),
@@ -122,14 +357,27 @@ impl Env {
         };
 
         let mut e = Env {
-            general: general.unwrap_or_default(),
-            aur: aur.unwrap_or_else(Aur::try_default).map_err(Error::Dirs)?,
-            backups: backups
-                .unwrap_or_else(Backups::try_default)
-                .map_err(Error::Dirs)?,
+            general,
+            aur: match aur {
+                Some(ra) => Aur::from_raw(ra, &cache),
+                None => Aur::try_default(&cache),
+            }
+            .map_err(Error::Dirs)?,
+            backups: match backups {
+                Some(rb) => Backups::from_raw(rb, &cache),
+                None => Backups::try_default(&cache),
+            }
+            .map_err(Error::Dirs)?,
+            open: open.unwrap_or_default(),
+            colors: match colors {
+                Some(rc) => Colors::try_from(rc).map_err(Error::Color)?,
+                None => Colors::default(),
+            },
             pacman: pacmanconf::Config::new().map_err(Error::PConf)?,
             makepkg,
             is_root: crate::utils::is_root_user(),
+            holds: BTreeSet::new(),
+            listed: Vec::new(),
         };
 
         // Special override. When running as root, we intend to build as
@@ -156,10 +404,15 @@ impl Env {
     /// Open a series of connections to ALPM handles. The quantity matches the
     /// number of CPUs available on the machine.
     pub(crate) fn alpm_pool(&self) -> Result<Pool<AlpmManager>, Error> {
+        self.alpm_pool_of(self.general.cpus)
+    }
+
+    /// Like [`Env::alpm_pool`], but with a specific number of connections.
+    pub(crate) fn alpm_pool_of(&self, size: u32) -> Result<Pool<AlpmManager>, Error> {
         // FIXME Thu Jun  9 2022 Unfortunate clone here.
         let mngr = AlpmManager::new(self.pacman.clone());
         let pool = Pool::builder()
-            .max_size(self.general.cpus)
+            .max_size(size)
             .build(mngr)
             .map_err(Error::R2d2)?;
 
@@ -181,6 +434,20 @@ impl Env {
             .collect()
     }
 
+    /// Aura's cache directory, created if it doesn't exist yet.
+    pub(crate) fn cache_dir(&self) -> Result<PathBuf, Error> {
+        dirs::cache_dir(self.general.cache_dir.as_deref())
+            .and_then(dirs::mkdir)
+            .map_err(Error::Dirs)
+    }
+
+    /// Aura's state directory, created if it doesn't exist yet.
+    pub(crate) fn state_dir(&self) -> Result<PathBuf, Error> {
+        dirs::state_dir(self.general.state_dir.as_deref())
+            .and_then(dirs::mkdir)
+            .map_err(Error::Dirs)
+    }
+
     /// Path to the ALPM log file.
     pub(crate) fn alpm_log(&self) -> &Path {
         Path::new(&self.pacman.log_file)
@@ -188,8 +455,11 @@ impl Env {
 
     /// Allow CLI flags to override settings from `aura.toml`.
     pub(crate) fn reconcile_cli(&mut self, flags: &aura_pm::flags::Args) {
-        if let aura_pm::flags::SubCmd::Aur(a) = &flags.subcmd {
-            self.aur.reconcile(self.makepkg.as_ref(), a)
+        match &flags.subcmd {
+            aura_pm::flags::SubCmd::Aur(a) => self.aur.reconcile(self.makepkg.as_ref(), a),
+            // `-Syu --aur` ignores the same packages in both phases.
+            aura_pm::flags::SubCmd::Sync(s) if s.aur => self.aur.ignores.extend(s.ignore.clone()),
+            _ => {}
         }
 
         // HACK 2024-08-08 Remove after transition period.
@@ -198,12 +468,49 @@ impl Env {
         }
 
         self.general.reconcile(flags);
+
+        // There's nobody to edit build files.
+        if self.general.interactive.not() {
+            self.aur.hotedit = false;
+        }
+    }
+
+    /// Leave held packages alone during a sysupgrade, in both its Pacman and
+    /// AUR phases.
+    pub(crate) fn hold(&mut self) {
+        self.aur.ignores.extend(self.general.holds.iter().cloned());
+        self.holds = self.general.holds.clone();
+    }
+
+    /// Install the packages listed in the given file too, alongside those
+    /// already named on the command line.
+    pub(crate) fn list(&mut self, path: &Path, given: &[String]) -> Result<(), Error> {
+        let listed = crate::utils::packages_from(path)
+            .map_err(|e| Error::PackagesFrom(path.to_path_buf(), e))?;
+        self.listed = crate::utils::unseen(given, listed);
+        Ok(())
     }
 
     /// Before continuing, confirm that the settled `Env` is valid to use.
     pub(crate) fn validate(&self) -> Result<(), Error> {
-        if self.aur.hotedit {
-            which::which(&self.general.editor).map_err(|_| Error::MissingEditor)?;
+        // A missing editor falls back to the default one, but there must be
+        // something to edit with.
+        if self.aur.hotedit
+            && Program::find(&self.general.editor)
+                .or_else(|| Program::find(DEFAULT_EDITOR))
+                .is_none()
+        {
+            return Err(Error::MissingEditor);
+        }
+
+        // Catch typos before any request is attempted.
+        if let Some(bad) = self
+            .aur
+            .endpoints
+            .iter()
+            .find(|e| crate::fetch::is_endpoint(e).not())
+        {
+            return Err(Error::Endpoint(bad.clone()));
         }
 
         Ok(())
@@ -213,20 +520,43 @@ impl Env {
 #[derive(Debug, Deserialize)]
 struct RawGeneral {
     cpus: Option<u32>,
+    refresh_jobs: Option<u32>,
     editor: Option<String>,
     doas: Option<bool>,
     language: Option<String>,
     noconfirm: Option<bool>,
+    relative_dates: Option<bool>,
+    stale_db_days: Option<u64>,
+    cache_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+    #[serde(default)]
+    holds: BTreeSet<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub(crate) struct General {
     pub(crate) cpus: u32,
+    /// How many sync databases to download at once during `-Sy`.
+    pub(crate) refresh_jobs: u32,
     pub(crate) editor: String,
     pub(crate) doas: bool,
     pub(crate) language: LanguageIdentifier,
     /// Don't ask the user for confirmation.
     pub(crate) noconfirm: bool,
+    /// Show dates relative to now.
+    pub(crate) relative_dates: bool,
+    /// How old the sync databases may be before `-Su` warns about them.
+    pub(crate) stale_db_days: u64,
+    /// Override the location of Aura's cache directory.
+    pub(crate) cache_dir: Option<PathBuf>,
+    /// Override the location of Aura's state directory.
+    pub(crate) state_dir: Option<PathBuf>,
+    /// Packages held back from every sysupgrade by `aura hold`.
+    pub(crate) holds: BTreeSet<String>,
+    /// Can the user be prompted, or shown a pager? Never set by config; see
+    /// [`aura_pm::flags::Args::interactive`].
+    #[serde(skip)]
+    pub(crate) interactive: bool,
 }
 
 impl General {
@@ -240,6 +570,24 @@ impl General {
         if flags.noconfirm {
             self.noconfirm = true;
         }
+
+        if flags.relative {
+            self.relative_dates = true;
+        } else if flags.absolute {
+            self.relative_dates = false;
+        }
+
+        if let aura_pm::flags::SubCmd::Sync(s) = &flags.subcmd {
+            if let Some(n) = s.refresh_jobs {
+                self.refresh_jobs = n;
+            }
+        }
+
+        // Prompts would hang, so they all take their default answer.
+        if flags.interactive().not() {
+            self.interactive = false;
+            self.noconfirm = true;
+        }
     }
 }
 
@@ -247,10 +595,17 @@ impl Default for General {
     fn default() -> Self {
         Self {
             cpus: num_cpus::get() as u32,
+            refresh_jobs: REFRESH_JOBS,
             editor: editor(),
             doas: false,
-            language: aura_pm::ENGLISH,
+            language: env_language().unwrap_or(aura_pm::ENGLISH),
             noconfirm: false,
+            relative_dates: false,
+            stale_db_days: STALE_DB_DAYS,
+            cache_dir: None,
+            state_dir: None,
+            holds: BTreeSet::new(),
+            interactive: true,
         }
     }
 }
@@ -259,6 +614,7 @@ impl From<RawGeneral> for General {
     fn from(raw: RawGeneral) -> Self {
         General {
             cpus: raw.cpus.unwrap_or_else(|| num_cpus::get() as u32),
+            refresh_jobs: raw.refresh_jobs.unwrap_or(REFRESH_JOBS).max(1),
             editor: raw.editor.unwrap_or_else(editor),
             doas: raw.doas.unwrap_or(false),
             // Precedence: We check config first for a language setting. If
@@ -266,10 +622,16 @@ impl From<RawGeneral> for General {
             // English. This can further be overridden by CLI flags.
             language: raw
                 .language
-                .or_else(|| std::env::var("LANG").ok())
                 .and_then(identifier_from_locale)
+                .or_else(env_language)
                 .unwrap_or(aura_pm::ENGLISH),
             noconfirm: raw.noconfirm.unwrap_or(false),
+            relative_dates: raw.relative_dates.unwrap_or(false),
+            stale_db_days: raw.stale_db_days.unwrap_or(STALE_DB_DAYS),
+            cache_dir: raw.cache_dir,
+            state_dir: raw.state_dir,
+            holds: raw.holds,
+            interactive: true,
         }
     }
 }
@@ -298,17 +660,26 @@ struct RawAur {
     shellcheck: bool,
     #[serde(default)]
     diff: bool,
+    difftool: Option<String>,
     #[serde(default)]
     delmakedeps: bool,
     #[serde(default)]
     clean: bool,
     #[serde(default)]
+    clean_after: bool,
+    #[serde(default)]
     noconfirm: bool,
     #[serde(default)]
     nocheck: bool,
     #[serde(default)]
     reverse: bool,
     warn_unknowns: Option<bool>,
+    #[serde(default)]
+    endpoints: Vec<String>,
+    #[serde(default)]
+    retries: u32,
+    connect_timeout: Option<u64>,
+    read_timeout: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -330,10 +701,18 @@ pub(crate) struct Aur {
     pub(crate) shellcheck: bool,
     /// View diffs of PKGBUILDs (etc.) before building.
     pub(crate) diff: bool,
+    /// The program to view those diffs with, instead of `git diff` itself.
+    pub(crate) difftool: Option<String>,
+    /// Summarize diffs before offering to show them in full.
+    #[serde(skip_serializing)]
+    pub(crate) stat: bool,
     /// Delete makedeps after building.
     pub(crate) delmakedeps: bool,
     /// Delete a package's build directory after the built tarball has been copied.
     pub(crate) clean: bool,
+    /// Delete the `src/` and `pkg/` directories of a package's build once it's
+    /// been installed.
+    pub(crate) clean_after: bool,
     /// If `false`, suppress warnings involving unknown packages.
     pub(crate) warn_unknowns: bool,
     /// Don't ask the user for confirmation.
@@ -355,21 +734,42 @@ pub(crate) struct Aur {
     /// (Makepkg) Do not verify source files with PGP signatures.
     #[serde(skip_serializing)]
     pub(crate) skippgpcheck: bool,
+    /// Check source signatures against the PKGBUILD's trusted PGP keys before
+    /// building.
+    #[serde(skip_serializing)]
+    pub(crate) verify: bool,
     /// Always reverse the search results.
     pub(crate) reverse: bool,
+    /// faur instances to query for package data, tried in order.
+    pub(crate) endpoints: Vec<String>,
+    /// How many times to retry a failed RPC request, clone, or pull.
+    pub(crate) retries: u32,
+    /// Seconds to wait for an RPC request to connect.
+    pub(crate) connect_timeout: Option<u64>,
+    /// Seconds to wait for a stalled transfer to receive anything.
+    pub(crate) read_timeout: Option<u64>,
     /// Give installed packages the "non-explicit" status.
     #[serde(skip_serializing)]
     pub(crate) asdeps: bool,
+    /// Don't stop to ask when a package fails to build.
+    #[serde(skip_serializing)]
+    pub(crate) keep_going: bool,
+    /// The number of independent packages to build at once.
+    #[serde(skip_serializing)]
+    pub(crate) jobs: u32,
+    /// Offer to undo a transaction that fails partway.
+    #[serde(skip_serializing)]
+    pub(crate) rollback: bool,
 }
 
 impl Aur {
     /// Attempt to form sane defaults.
-    fn try_default() -> Result<Self, dirs::Error> {
+    fn try_default(cache: &Path) -> Result<Self, dirs::Error> {
         let a = Aur {
-            build: dirs::builds()?,
-            cache: dirs::tarballs()?,
-            clones: dirs::clones()?,
-            hashes: dirs::hashes()?,
+            build: dirs::builds(cache)?,
+            cache: dirs::tarballs(cache)?,
+            clones: dirs::clones(cache)?,
+            hashes: dirs::hashes(cache)?,
             builduser: None,
             chroot: HashSet::new(),
             ignores: HashSet::new(),
@@ -377,16 +777,27 @@ impl Aur {
             hotedit: false,
             shellcheck: false,
             diff: false,
+            difftool: None,
+            stat: false,
             delmakedeps: false,
             clean: false,
+            clean_after: false,
             warn_unknowns: true,
             noconfirm: false,
             nocheck: false,
             skipdepcheck: false,
             skipinteg: false,
             skippgpcheck: false,
+            verify: true,
             reverse: false,
+            endpoints: vec![FAUR_URL.to_string()],
+            retries: 0,
+            connect_timeout: None,
+            read_timeout: None,
             asdeps: false,
+            keep_going: false,
+            jobs: 1,
+            rollback: false,
         };
 
         Ok(a)
@@ -415,10 +826,32 @@ impl Aur {
             self.diff = true;
         }
 
+        if let Some(tool) = flags.difftool.as_deref() {
+            self.difftool = Some(tool.to_string());
+        }
+
+        if flags.stat {
+            self.stat = true;
+        }
+
         if flags.delmakedeps {
             self.delmakedeps = true;
         }
 
+        if flags.clean {
+            self.clean = true;
+        }
+
+        if flags.clean_after {
+            self.clean_after = true;
+        }
+
+        // Sources are kept for inspection, no matter what config says.
+        if flags.keep_sources {
+            self.clean = false;
+            self.clean_after = false;
+        }
+
         if let Some(pb) = flags.build.as_deref() {
             self.build = pb.to_path_buf();
         }
@@ -446,6 +879,11 @@ impl Aur {
             self.skippgpcheck = true;
         }
 
+        // NOTE Makepkg would skip the signatures anyway.
+        if flags.no_verify || self.skippgpcheck || self.skipinteg {
+            self.verify = false;
+        }
+
         if flags.reverse {
             self.reverse = true;
         }
@@ -454,20 +892,73 @@ impl Aur {
             self.asdeps = true;
         }
 
+        if flags.keep_going {
+            self.keep_going = true;
+        }
+
+        if let Some(n) = flags.jobs {
+            self.jobs = n;
+        }
+
+        if flags.rollback {
+            self.rollback = true;
+        }
+
+        if let Some(url) = flags.endpoint.as_deref() {
+            self.endpoints = vec![url.to_string()];
+        }
+
+        if let Some(n) = flags.retries {
+            self.retries = n;
+        }
+
+        if let Some(t) = flags.connect_timeout {
+            self.connect_timeout = Some(t);
+        }
+
+        if let Some(t) = flags.read_timeout {
+            self.read_timeout = Some(t);
+        }
+
         // Harmless clone, as we don't expect many "ignores" to be passed on the
         // command line.
         self.ignores.extend(flags.ignore.clone());
     }
-}
 
-impl TryFrom<RawAur> for Aur {
-    type Error = dirs::Error;
+    /// Where to reach the AUR, and how patiently to deal with the network.
+    pub(crate) fn network(&self) -> crate::fetch::Network<'_> {
+        crate::fetch::Network {
+            endpoints: &self.endpoints,
+            retries: self.retries,
+            connect_timeout: self.connect_timeout.map(Duration::from_secs),
+            read_timeout: self.read_timeout.map(Duration::from_secs),
+        }
+    }
 
-    fn try_from(raw: RawAur) -> Result<Self, Self::Error> {
-        let build = raw.build.map(Ok).unwrap_or_else(dirs::builds)?;
-        let cache = raw.cache.map(Ok).unwrap_or_else(dirs::tarballs)?;
-        let clones = raw.clones.map(Ok).unwrap_or_else(dirs::clones)?;
-        let hashes = raw.hashes.map(Ok).unwrap_or_else(dirs::hashes)?;
+    /// Settings from `aura.toml`, where unset directories fall back to
+    /// locations within the given Aura cache.
+    fn from_raw(raw: RawAur, aura_cache: &Path) -> Result<Self, dirs::Error> {
+        let build = raw
+            .build
+            .map(Ok)
+            .unwrap_or_else(|| dirs::builds(aura_cache))?;
+        let cache = raw
+            .cache
+            .map(Ok)
+            .unwrap_or_else(|| dirs::tarballs(aura_cache))?;
+        let clones = raw
+            .clones
+            .map(Ok)
+            .unwrap_or_else(|| dirs::clones(aura_cache))?;
+        let hashes = raw
+            .hashes
+            .map(Ok)
+            .unwrap_or_else(|| dirs::hashes(aura_cache))?;
+        let endpoints = if raw.endpoints.is_empty() {
+            vec![FAUR_URL.to_string()]
+        } else {
+            raw.endpoints
+        };
 
         let a = Aur {
             build,
@@ -481,16 +972,27 @@ impl TryFrom<RawAur> for Aur {
             hotedit: raw.hotedit,
             shellcheck: raw.shellcheck,
             diff: raw.diff,
+            difftool: raw.difftool,
+            stat: false,
             delmakedeps: raw.delmakedeps,
             clean: raw.clean,
+            clean_after: raw.clean_after,
             warn_unknowns: raw.warn_unknowns.unwrap_or(true),
             noconfirm: raw.noconfirm,
             nocheck: raw.nocheck,
             skipdepcheck: false,
             skipinteg: false,
             skippgpcheck: false,
+            verify: true,
             reverse: raw.reverse,
+            endpoints,
+            retries: raw.retries,
+            connect_timeout: raw.connect_timeout,
+            read_timeout: raw.read_timeout,
             asdeps: false,
+            keep_going: false,
+            jobs: 1,
+            rollback: false,
         };
 
         Ok(a)
@@ -517,22 +1019,23 @@ pub(crate) struct Backups {
 
 impl Backups {
     /// Attempt to form sane defaults.
-    fn try_default() -> Result<Self, dirs::Error> {
+    fn try_default(cache: &Path) -> Result<Self, dirs::Error> {
         let g = Backups {
-            snapshots: dirs::snapshot()?,
+            snapshots: dirs::snapshot(cache)?,
             automatic: true,
         };
 
         Ok(g)
     }
-}
 
-impl TryFrom<RawBackups> for Backups {
-    type Error = dirs::Error;
-
-    fn try_from(raw: RawBackups) -> Result<Self, Self::Error> {
+    /// Settings from `aura.toml`, where an unset snapshot directory falls back
+    /// to a location within the given Aura cache.
+    fn from_raw(raw: RawBackups, cache: &Path) -> Result<Self, dirs::Error> {
         let b = Backups {
-            snapshots: raw.snapshots.map(Ok).unwrap_or_else(dirs::snapshot)?,
+            snapshots: raw
+                .snapshots
+                .map(Ok)
+                .unwrap_or_else(|| dirs::snapshot(cache))?,
             automatic: raw.automatic,
         };
 
@@ -540,9 +1043,91 @@ impl TryFrom<RawBackups> for Backups {
     }
 }
 
+#[derive(Deserialize)]
+struct RawOpen {
+    docs_url: Option<String>,
+    repo_url: Option<String>,
+    bug_url: Option<String>,
+    license_url: Option<String>,
+    aur_url: Option<String>,
+    browser: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct Open {
+    pub(crate) docs_url: String,
+    pub(crate) repo_url: String,
+    pub(crate) bug_url: String,
+    pub(crate) license_url: String,
+    pub(crate) aur_url: String,
+    /// The program to open pages with, instead of `xdg-open`.
+    pub(crate) browser: Option<String>,
+}
+
+impl Default for Open {
+    fn default() -> Self {
+        Open {
+            docs_url: DOCS_URL.to_string(),
+            repo_url: REPO_URL.to_string(),
+            bug_url: BUG_URL.to_string(),
+            license_url: LICENSE_URL.to_string(),
+            aur_url: AUR_URL.to_string(),
+            browser: None,
+        }
+    }
+}
+
+impl From<RawOpen> for Open {
+    /// Unset URLs fall back to Aura's own pages.
+    fn from(raw: RawOpen) -> Self {
+        let d = Open::default();
+
+        Open {
+            docs_url: raw.docs_url.unwrap_or(d.docs_url),
+            repo_url: raw.repo_url.unwrap_or(d.repo_url),
+            bug_url: raw.bug_url.unwrap_or(d.bug_url),
+            license_url: raw.license_url.unwrap_or(d.license_url),
+            aur_url: raw.aur_url.unwrap_or(d.aur_url),
+            browser: raw.browser,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawColors {
+    header: Option<String>,
+    success: Option<String>,
+    warning: Option<String>,
+    error: Option<String>,
+    highlight: Option<String>,
+}
+
+impl TryFrom<RawColors> for Colors {
+    /// The first colour name that couldn't be understood.
+    type Error = String;
+
+    /// Unset roles keep their default colours.
+    fn try_from(raw: RawColors) -> Result<Self, Self::Error> {
+        let d = Colors::default();
+        let pick = |name: Option<String>, default| match name {
+            None => Ok(default),
+            Some(n) => crate::theme::parse(&n).ok_or(n),
+        };
+
+        Ok(Colors {
+            header: pick(raw.header, d.header)?,
+            success: pick(raw.success, d.success)?,
+            warning: pick(raw.warning, d.warning)?,
+            error: pick(raw.error, d.error)?,
+            highlight: pick(raw.highlight, d.highlight)?,
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::utils::TempDir;
 
     #[test]
     fn empty_config_file() {
@@ -550,6 +1135,16 @@ mod test {
         assert!(e.general.is_none());
         assert!(e.aur.is_none());
         assert!(e.backups.is_none());
+        assert!(e.open.is_none());
+    }
+
+    #[test]
+    fn open_overrides() {
+        let toml = "[open]\nrepo_url = \"https://git.example.com/aura\"\n";
+        let open = Open::from(basic_toml::from_str::<RawEnv>(toml).unwrap().open.unwrap());
+        assert_eq!("https://git.example.com/aura", open.repo_url);
+        assert_eq!(DOCS_URL, open.docs_url);
+        assert_eq!(AUR_URL, open.aur_url);
     }
 
     #[test]
@@ -568,4 +1163,195 @@ mod test {
         let exp: HashSet<_> = ["foo".to_string(), "bar".to_string()].into();
         assert_eq!(exp, aur.ignores);
     }
+
+    #[test]
+    fn colour_overrides() {
+        use crate::theme::Role;
+        use colored::Color;
+
+        let toml = "[colors]\nsuccess = \"blue\"\nheader = \"#102030\"\n";
+        let raw = basic_toml::from_str::<RawEnv>(toml)
+            .unwrap()
+            .colors
+            .unwrap();
+        let colors = Colors::try_from(raw).unwrap();
+        let d = Colors::default();
+
+        assert_eq!(Color::Blue, colors.of(Role::Success));
+        assert_eq!(
+            Color::TrueColor {
+                r: 0x10,
+                g: 0x20,
+                b: 0x30
+            },
+            colors.of(Role::Header)
+        );
+        assert_eq!(d.of(Role::Warning), colors.of(Role::Warning));
+        assert_eq!(d.of(Role::Error), colors.of(Role::Error));
+        assert_eq!(d.of(Role::Highlight), colors.of(Role::Highlight));
+
+        let toml = "[colors]\nerror = \"rouge\"\n";
+        let raw = basic_toml::from_str::<RawEnv>(toml)
+            .unwrap()
+            .colors
+            .unwrap();
+        assert_eq!(Err("rouge".to_string()), Colors::try_from(raw));
+    }
+
+    #[test]
+    fn endpoint_list() {
+        let toml = "[aur]\nendpoints = [\"https://a.example.com\", \"http://b.example.com\"]\n";
+        let aur = basic_toml::from_str::<RawEnv>(toml).unwrap().aur.unwrap();
+        assert_eq!(
+            vec!["https://a.example.com", "http://b.example.com"],
+            aur.endpoints
+        );
+
+        let aur = basic_toml::from_str::<RawEnv>("[aur]\n")
+            .unwrap()
+            .aur
+            .unwrap();
+        assert!(aur.endpoints.is_empty());
+    }
+
+    #[test]
+    fn template_config() {
+        let dir = TempDir::new("template");
+        let tmp = dir.to_path_buf();
+        let aur = Aur::try_default(&tmp).unwrap();
+        let backups = Backups::try_default(&tmp).unwrap();
+        let state = tmp.join("state");
+        let open = Open::default();
+        let colors = Colors::default();
+        let file = render_template(
+            &General::default(),
+            &aur,
+            &backups,
+            &open,
+            &colors,
+            &tmp,
+            &state,
+        );
+
+        // It's a legal config.
+        let e = basic_toml::from_str::<RawEnv>(&file).unwrap();
+        let general = General::from(e.general.unwrap());
+        assert_eq!(Some(tmp.clone()), general.cache_dir);
+        assert_eq!(Some(state), general.state_dir);
+        assert!(e.aur.unwrap().warn_unknowns.unwrap());
+        assert!(e.backups.unwrap().automatic);
+
+        // And it mentions every setting.
+        type Table = std::collections::BTreeMap<String, serde_json::Value>;
+        let raw = basic_toml::from_str::<std::collections::BTreeMap<String, Table>>(&file).unwrap();
+        let keys =
+            |section: &str| -> Vec<&str> { raw[section].keys().map(|k| k.as_str()).collect() };
+
+        let mut exp = vec![
+            "cpus",
+            "refresh_jobs",
+            "editor",
+            "doas",
+            "language",
+            "noconfirm",
+            "relative_dates",
+            "stale_db_days",
+            "cache_dir",
+            "state_dir",
+            "holds",
+        ];
+        exp.sort();
+        assert_eq!(exp, keys("general"));
+
+        // `builduser`, `difftool`, and the timeouts have no default, so they
+        // only appear as comments.
+        let mut exp = vec![
+            "build",
+            "cache",
+            "clones",
+            "hashes",
+            "chroot",
+            "ignores",
+            "git",
+            "hotedit",
+            "shellcheck",
+            "diff",
+            "delmakedeps",
+            "clean",
+            "clean_after",
+            "warn_unknowns",
+            "nocheck",
+            "reverse",
+            "endpoints",
+            "retries",
+        ];
+        exp.sort();
+        assert_eq!(exp, keys("aur"));
+        assert!(file.contains("# builduser = "));
+        assert!(file.contains("# difftool = "));
+        assert!(file.contains("# connect_timeout = "));
+        assert!(file.contains("# read_timeout = "));
+
+        assert_eq!(vec!["automatic", "snapshots"], keys("backups"));
+
+        let mut exp = vec!["docs_url", "repo_url", "bug_url", "license_url", "aur_url"];
+        exp.sort();
+        assert_eq!(exp, keys("open"));
+        assert!(file.contains("# browser = "));
+
+        let mut exp = vec!["header", "success", "warning", "error", "highlight"];
+        exp.sort();
+        assert_eq!(exp, keys("colors"));
+        let colors = Colors::try_from(e.colors.unwrap()).unwrap();
+        assert_eq!(Colors::default(), colors);
+    }
+
+    #[test]
+    fn non_interactive() {
+        use clap::Parser;
+
+        let args =
+            aura_pm::flags::Args::try_parse_from(["aura", "-Au", "--non-interactive"]).unwrap();
+        let mut general = General::default();
+        general.reconcile(&args);
+
+        assert!(general.interactive.not());
+        assert!(general.noconfirm);
+    }
+
+    #[test]
+    fn relative_dates() {
+        use clap::Parser;
+
+        let mut general = General::default();
+        assert!(general.relative_dates.not());
+
+        let args =
+            aura_pm::flags::Args::try_parse_from(["aura", "-Ai", "aura", "--relative"]).unwrap();
+        general.reconcile(&args);
+        assert!(general.relative_dates);
+
+        // The flag overrides the config.
+        let args =
+            aura_pm::flags::Args::try_parse_from(["aura", "-Li", "aura", "--absolute"]).unwrap();
+        general.reconcile(&args);
+        assert!(general.relative_dates.not());
+
+        let args =
+            aura_pm::flags::Args::try_parse_from(["aura", "-Li", "--relative", "--absolute"]);
+        assert!(args.is_err());
+    }
+
+    #[test]
+    fn refresh_jobs() {
+        use clap::Parser;
+
+        let mut general = General::default();
+        assert_eq!(REFRESH_JOBS, general.refresh_jobs);
+
+        let args =
+            aura_pm::flags::Args::try_parse_from(["aura", "-Syy", "--refresh-jobs", "8"]).unwrap();
+        general.reconcile(&args);
+        assert_eq!(8, general.refresh_jobs);
+    }
 }
@@ -12,6 +12,10 @@ pub(crate) enum Error {
     C(crate::command::cache::Error),
     L(crate::command::logs::Error),
     O(crate::command::orphans::Error),
+    P(crate::command::analysis::Error),
+    R(crate::command::remove::Error),
+    S(crate::command::sync::Error),
+    U(crate::command::tarball::Error),
     /// A non-zero exit code was returned from a call to Pacman.
     Pacman(crate::pacman::Error),
     Env(crate::env::Error),
@@ -19,6 +23,59 @@ pub(crate) enum Error {
     Check(crate::check::Error),
     Stats(crate::stats::Error),
     Deps(crate::deps::Error),
+    Home(crate::command::home::Error),
+    Hold(crate::command::hold::Error),
+    Open(crate::command::open::Error),
+    Upgrade(crate::command::upgrade::Error),
+    Query(crate::command::query::Error),
+}
+
+impl From<crate::command::query::Error> for Error {
+    fn from(v: crate::command::query::Error) -> Self {
+        Self::Query(v)
+    }
+}
+
+impl From<crate::command::sync::Error> for Error {
+    fn from(v: crate::command::sync::Error) -> Self {
+        Self::S(v)
+    }
+}
+
+impl From<crate::command::tarball::Error> for Error {
+    fn from(v: crate::command::tarball::Error) -> Self {
+        Self::U(v)
+    }
+}
+
+impl From<crate::command::remove::Error> for Error {
+    fn from(v: crate::command::remove::Error) -> Self {
+        Self::R(v)
+    }
+}
+
+impl From<crate::command::upgrade::Error> for Error {
+    fn from(v: crate::command::upgrade::Error) -> Self {
+        Self::Upgrade(v)
+    }
+}
+
+impl From<crate::command::open::Error> for Error {
+    fn from(v: crate::command::open::Error) -> Self {
+        Self::Open(v)
+    }
+}
+
+impl From<crate::command::home::Error> for Error {
+    fn from(v: crate::command::home::Error) -> Self {
+        Self::Home(v)
+    }
+}
+
+impl From<crate::command::hold::Error> for Error {
+    fn from(v: crate::command::hold::Error) -> Self {
+        Self::Hold(v)
+    }
 }
 
 impl From<crate::deps::Error> for Error {
@@ -39,6 +96,12 @@ impl From<crate::conf::Error> for Error {
     }
 }
 
+impl From<crate::command::analysis::Error> for Error {
+    fn from(v: crate::command::analysis::Error) -> Self {
+        Self::P(v)
+    }
+}
+
 impl From<crate::command::orphans::Error> for Error {
     fn from(v: crate::command::orphans::Error) -> Self {
         Self::O(v)
@@ -90,12 +153,21 @@ impl Nested for Error {
             Error::C(e) => e.nested(),
             Error::L(e) => e.nested(),
             Error::O(e) => e.nested(),
+            Error::P(e) => e.nested(),
+            Error::R(e) => e.nested(),
+            Error::S(e) => e.nested(),
+            Error::U(e) => e.nested(),
             Error::Pacman(e) => e.nested(),
             Error::Env(e) => e.nested(),
             Error::Conf(e) => e.nested(),
             Error::Check(e) => e.nested(),
             Error::Stats(e) => e.nested(),
             Error::Deps(e) => e.nested(),
+            Error::Home(e) => e.nested(),
+            Error::Hold(e) => e.nested(),
+            Error::Open(e) => e.nested(),
+            Error::Upgrade(e) => e.nested(),
+            Error::Query(e) => e.nested(),
         }
     }
 }
@@ -108,16 +180,59 @@ impl Localised for Error {
             Error::C(e) => e.localise(fll),
             Error::L(e) => e.localise(fll),
             Error::O(e) => e.localise(fll),
+            Error::P(e) => e.localise(fll),
+            Error::R(e) => e.localise(fll),
+            Error::S(e) => e.localise(fll),
+            Error::U(e) => e.localise(fll),
             Error::Pacman(e) => e.localise(fll),
             Error::Env(e) => e.localise(fll),
             Error::Conf(e) => e.localise(fll),
             Error::Check(e) => e.localise(fll),
             Error::Stats(e) => e.localise(fll),
             Error::Deps(e) => e.localise(fll),
+            Error::Home(e) => e.localise(fll),
+            Error::Hold(e) => e.localise(fll),
+            Error::Open(e) => e.localise(fll),
+            Error::Upgrade(e) => e.localise(fll),
+            Error::Query(e) => e.localise(fll),
+        }
+    }
+}
+
+impl Exited for Error {
+    fn exit_code(&self) -> Option<u8> {
+        match self {
+            Error::A(e) => e.exit_code(),
+            Error::B(e) => e.exit_code(),
+            Error::C(e) => e.exit_code(),
+            Error::L(e) => e.exit_code(),
+            Error::O(e) => e.exit_code(),
+            Error::P(_) => None,
+            Error::R(_) => None,
+            Error::S(e) => e.exit_code(),
+            Error::U(_) => None,
+            Error::Pacman(e) => e.exit_code(),
+            Error::Env(_) => None,
+            Error::Conf(e) => e.exit_code(),
+            Error::Check(_) => None,
+            Error::Stats(_) => None,
+            Error::Deps(e) => e.exit_code(),
+            Error::Home(e) => e.exit_code(),
+            Error::Hold(_) => None,
+            Error::Open(e) => e.exit_code(),
+            Error::Upgrade(e) => e.exit_code(),
+            Error::Query(_) => None,
         }
     }
 }
 
+/// Errors that may stem from a wrapped external program (like Pacman) exiting
+/// unsuccessfully. Aura then exits with that program's code, so that scripts
+/// checking `$?` see the real failure.
+pub(crate) trait Exited {
+    fn exit_code(&self) -> Option<u8>;
+}
+
 /// Do something with the nested errors of this type.
 ///
 /// Quite weak as far as typeclasses go; it's entirely lawless. It's entire
@@ -131,8 +246,8 @@ impl Nested for aura_core::git::Error {
     fn nested(&self) {
         match self {
             aura_core::git::Error::Io(e) => error!("{e}"),
-            aura_core::git::Error::Clone(_) => {}
-            aura_core::git::Error::Pull(_) => {}
+            aura_core::git::Error::Clone(_, _) => {}
+            aura_core::git::Error::Pull(_, _) => {}
             aura_core::git::Error::Diff(_) => {}
             aura_core::git::Error::ReadHash(e) => error!("{e}"),
         }
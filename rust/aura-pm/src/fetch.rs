@@ -2,23 +2,58 @@
 
 use crate::error::Nested;
 use crate::localization::Localised;
+use aura_core::faur::FAUR_URL;
 use curl::easy::Easy;
 use i18n_embed::fluent::FluentLanguageLoader;
 use i18n_embed_fl::fl;
 use log::debug;
 use log::error;
+use log::warn;
 use serde::de::DeserializeOwned;
+use std::ops::Not;
+use std::time::Duration;
+
+/// The HTTP status of a rate-limited request.
+const TOO_MANY_REQUESTS: u32 = 429;
+
+/// How many times a rate-limited request is retried before giving up.
+const RETRIES: u32 = 3;
+
+/// The wait before the first retry, doubled after each one.
+const BACKOFF: Duration = Duration::from_secs(2);
+
+/// Where to reach the AUR, and how patiently to deal with a flaky network, as
+/// set in the `[aur]` section of the config.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Network<'a> {
+    /// The faur instances to query, in order of preference.
+    pub(crate) endpoints: &'a [String],
+    /// How many times to retry a failed request, clone, or pull.
+    pub(crate) retries: u32,
+    /// Give up on a request, clone, or pull that can't connect within this long.
+    pub(crate) connect_timeout: Option<Duration>,
+    /// Give up on a transfer that has received nothing for this long.
+    pub(crate) read_timeout: Option<Duration>,
+}
 
 pub enum Error {
     Curl(curl::Error),
+    /// A request that failed every one of the given number of attempts.
+    Retried(String, u32, curl::Error),
     Json(String, serde_json::Error),
+    RateLimited(String),
+    /// Every configured endpoint failed, in order.
+    Unreachable(Vec<Error>),
 }
 
 impl Nested for Error {
     fn nested(&self) {
         match self {
             Error::Curl(e) => error!("{e}"),
+            Error::Retried(_, _, e) => error!("{e}"),
             Error::Json(_, e) => error!("{e}"),
+            Error::RateLimited(_) => {}
+            Error::Unreachable(es) => es.iter().for_each(|e| e.nested()),
         }
     }
 }
@@ -27,13 +62,132 @@ impl Localised for Error {
     fn localise(&self, fll: &FluentLanguageLoader) -> String {
         match self {
             Error::Curl(e) => fl!(fll, "err-curl", err = e.to_string()),
+            Error::Retried(url, n, e) => fl!(
+                fll,
+                "err-curl-retried",
+                url = url.as_str(),
+                n = *n,
+                err = e.to_string()
+            ),
             Error::Json(url, _) => fl!(fll, "err-json-decode", url = url.as_str()),
+            Error::RateLimited(url) => fl!(fll, "err-rate-limited", url = url.as_str()),
+            Error::Unreachable(es) => fl!(fll, "err-unreachable", n = es.len()),
         }
     }
 }
 
-/// Fetch JSON from some given endpoint.
-pub(crate) fn fetch_json<T>(url: &str) -> Result<T, Error>
+impl Network<'_> {
+    /// The same settings, for the cloning and pulling of AUR repositories.
+    pub(crate) fn git(&self) -> aura_core::git::Retry {
+        aura_core::git::Retry {
+            retries: self.retries,
+            backoff: BACKOFF,
+            connect_timeout: self.connect_timeout,
+            read_timeout: self.read_timeout,
+        }
+    }
+}
+
+/// Fetch JSON from the first of the configured endpoints that answers, backing
+/// off and retrying with each if the server reports that it's rate-limiting us,
+/// or if the request fails and retries have been configured.
+///
+/// The `url` is one formed by [`aura_core::faur`] against the main instance,
+/// which each endpoint stands in for in turn.
+pub(crate) fn fetch_json<T>(network: &Network, url: &str) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    with_fallback(network.endpoints, url, |u| {
+        with_backoff(
+            u,
+            network.retries,
+            |u| fetch_once(network, u),
+            std::thread::sleep,
+        )
+    })
+}
+
+/// Is the given string usable as a faur endpoint? It must be an `http` or
+/// `https` URL naming some host.
+pub(crate) fn is_endpoint(url: &str) -> bool {
+    url.strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .filter(|_| url.contains(char::is_whitespace).not())
+        .and_then(|rest| rest.split(['/', '?', '#']).next())
+        .map(|host| host.is_empty().not())
+        .unwrap_or(false)
+}
+
+/// Move on to the next endpoint if one can't be reached or answers with an HTTP
+/// error. Anything else, like a response that isn't the JSON we expect, is
+/// reported as-is.
+fn with_fallback<T, F>(endpoints: &[String], url: &str, fetch: F) -> Result<T, Error>
+where
+    F: Fn(&str) -> Result<T, Error>,
+{
+    let mut failures = Vec::new();
+
+    for endpoint in endpoints {
+        let rebased = rebase(url, endpoint);
+
+        match fetch(&rebased) {
+            Err(e @ (Error::Curl(_) | Error::Retried(_, _, _) | Error::RateLimited(_))) => {
+                warn!("{endpoint} failed. Trying the next endpoint, if any.");
+                failures.push(e);
+            }
+            result => return result,
+        }
+    }
+
+    match failures.len() {
+        1 => Err(failures.remove(0)),
+        _ => Err(Error::Unreachable(failures)),
+    }
+}
+
+/// Swap the main faur instance in a URL for some other endpoint.
+fn rebase(url: &str, endpoint: &str) -> String {
+    match url.strip_prefix(FAUR_URL) {
+        Some(rest) => format!("{}{}", endpoint.trim_end_matches('/'), rest),
+        None => url.to_string(),
+    }
+}
+
+/// Rate-limiting is always backed off from, up to [`RETRIES`] times. Other
+/// failures of the request itself are only retried the given number of times,
+/// and none by default.
+fn with_backoff<T, F, S>(url: &str, retries: u32, fetch: F, mut sleep: S) -> Result<T, Error>
+where
+    F: Fn(&str) -> Result<T, Error>,
+    S: FnMut(Duration),
+{
+    let mut wait = BACKOFF;
+    let mut limited = 0;
+    let mut failed = 0;
+
+    loop {
+        match fetch(url) {
+            Err(Error::RateLimited(_)) if limited < RETRIES => {
+                limited += 1;
+                warn!("Rate-limited by {url}. Retrying in {}s.", wait.as_secs());
+            }
+            Err(Error::Curl(e)) if failed < retries => {
+                failed += 1;
+                warn!("{url} failed: {e}. Retrying in {}s.", wait.as_secs());
+            }
+            Err(Error::Curl(e)) if failed > 0 => {
+                return Err(Error::Retried(url.to_string(), failed + 1, e));
+            }
+            result => return result,
+        }
+
+        sleep(wait);
+        wait *= 2;
+    }
+}
+
+fn fetch_once<T>(network: &Network, url: &str) -> Result<T, Error>
 where
     T: DeserializeOwned,
 {
@@ -44,7 +198,20 @@ where
     handle.url(url).map_err(Error::Curl)?;
     handle.fail_on_error(true).map_err(Error::Curl)?;
 
-    // Blocked off to allow `data` to be borrowed again down below.
+    if let Some(t) = network.connect_timeout {
+        handle.connect_timeout(t).map_err(Error::Curl)?;
+    }
+
+    // Curl has no timeout between reads as such, so a transfer that has
+    // stalled below a byte per second for that long is abandoned instead.
+    if let Some(t) = network.read_timeout {
+        handle.low_speed_limit(1).map_err(Error::Curl)?;
+        handle.low_speed_time(t).map_err(Error::Curl)?;
+    }
+
+    let performed;
+
+    // Blocked off to allow `data` and `handle` to be borrowed again down below.
     {
         let mut tx = handle.transfer();
         tx.write_function(|bytes| {
@@ -52,9 +219,209 @@ where
             Ok(bytes.len())
         })
         .map_err(Error::Curl)?;
-        tx.perform().map_err(Error::Curl)?;
+        performed = tx.perform();
     }
 
+    // With `fail_on_error`, a 429 surfaces as a generic HTTP error.
+    performed.map_err(|e| match handle.response_code() {
+        Ok(TOO_MANY_REQUESTS) => Error::RateLimited(url.to_string()),
+        _ => Error::Curl(e),
+    })?;
+
     let json = serde_json::from_slice(&data).map_err(|e| Error::Json(url.to_string(), e))?;
     Ok(json)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn backs_off_then_succeeds() {
+        let calls = Cell::new(0);
+        let mut waits = Vec::new();
+        let fetch = |url: &str| {
+            calls.set(calls.get() + 1);
+            match calls.get() {
+                1 | 2 => Err(Error::RateLimited(url.to_string())),
+                _ => Ok(vec!["aura".to_string()]),
+            }
+        };
+
+        let result = with_backoff("https://faur.fosskers.ca", 0, fetch, |d| waits.push(d));
+        assert_eq!(Some(vec!["aura".to_string()]), result.ok());
+        assert_eq!(3, calls.get());
+        assert_eq!(vec![BACKOFF, BACKOFF * 2], waits);
+    }
+
+    #[test]
+    fn gives_up_when_rate_limited() {
+        let fll = crate::localization::load(None).unwrap();
+        let calls = Cell::new(0);
+        let mut waits = Vec::new();
+        let fetch = |url: &str| -> Result<(), Error> {
+            calls.set(calls.get() + 1);
+            Err(Error::RateLimited(url.to_string()))
+        };
+
+        let msg = match with_backoff("https://faur.fosskers.ca", 0, fetch, |d| waits.push(d)) {
+            Err(e @ Error::RateLimited(_)) => e.localise(&fll),
+            _ => panic!("Expected a rate-limit error"),
+        };
+
+        assert_eq!(RETRIES + 1, calls.get());
+        assert_eq!(vec![BACKOFF, BACKOFF * 2, BACKOFF * 4], waits);
+        assert!(msg.contains("rate-limiting"));
+        assert!(msg.contains("https://faur.fosskers.ca"));
+    }
+
+    #[test]
+    fn endpoint_validation() {
+        assert!(is_endpoint("https://faur.fosskers.ca"));
+        assert!(is_endpoint("http://localhost:8080/"));
+        assert!(is_endpoint("https://mirror.example.com/faur"));
+        assert!(is_endpoint("ftp://mirror.example.com").not());
+        assert!(is_endpoint("mirror.example.com").not());
+        assert!(is_endpoint("https://").not());
+        assert!(is_endpoint("https://mirror example.com").not());
+    }
+
+    #[test]
+    fn endpoint_rebasing() {
+        let url = format!("{FAUR_URL}/packages?names=aura");
+        assert_eq!(
+            "https://mirror.example.com/faur/packages?names=aura",
+            rebase(&url, "https://mirror.example.com/faur/")
+        );
+        assert_eq!(url, rebase(&url, FAUR_URL));
+    }
+
+    #[test]
+    fn falls_back_in_order() {
+        let endpoints = vec![
+            "https://down.example.com".to_string(),
+            "https://busy.example.com".to_string(),
+            "https://up.example.com".to_string(),
+        ];
+        let seen = std::cell::RefCell::new(Vec::new());
+        let fetch = |url: &str| {
+            seen.borrow_mut().push(url.to_string());
+            match url {
+                u if u.starts_with("https://down") => Err(Error::Curl(curl::Error::new(7))),
+                u if u.starts_with("https://busy") => Err(Error::RateLimited(u.to_string())),
+                _ => Ok(vec!["aura".to_string()]),
+            }
+        };
+
+        let url = format!("{FAUR_URL}/packages?names=aura");
+        let result = with_fallback(&endpoints, &url, fetch);
+        assert_eq!(Some(vec!["aura".to_string()]), result.ok());
+        assert_eq!(
+            vec![
+                "https://down.example.com/packages?names=aura",
+                "https://busy.example.com/packages?names=aura",
+                "https://up.example.com/packages?names=aura",
+            ],
+            seen.into_inner()
+        );
+    }
+
+    #[test]
+    fn aggregates_when_all_fail() {
+        let fll = crate::localization::load(None).unwrap();
+        let endpoints = vec![
+            "https://a.example.com".to_string(),
+            "https://b.example.com".to_string(),
+        ];
+        let fetch = |_: &str| -> Result<(), Error> { Err(Error::Curl(curl::Error::new(7))) };
+
+        let msg = match with_fallback(&endpoints, FAUR_URL, fetch) {
+            Err(e @ Error::Unreachable(_)) => e.localise(&fll),
+            _ => panic!("Expected every endpoint to fail"),
+        };
+        assert!(msg.contains('2'));
+
+        // A single endpoint's failure is reported as it was.
+        let fetch = |_: &str| -> Result<(), Error> { Err(Error::Curl(curl::Error::new(7))) };
+        let result = with_fallback(&endpoints[..1], FAUR_URL, fetch);
+        assert!(matches!(result, Err(Error::Curl(_))));
+    }
+
+    #[test]
+    fn bad_json_does_not_fall_back() {
+        let endpoints = vec![
+            "https://a.example.com".to_string(),
+            "https://b.example.com".to_string(),
+        ];
+        let calls = Cell::new(0);
+        let fetch = |url: &str| -> Result<(), Error> {
+            calls.set(calls.get() + 1);
+            Err(Error::Json(
+                url.to_string(),
+                serde_json::from_str::<()>("{").unwrap_err(),
+            ))
+        };
+
+        let result = with_fallback(&endpoints, FAUR_URL, fetch);
+        assert!(matches!(result, Err(Error::Json(_, _))));
+        assert_eq!(1, calls.get());
+    }
+
+    #[test]
+    fn other_errors_are_not_retried() {
+        let calls = Cell::new(0);
+        let fetch = |url: &str| -> Result<(), Error> {
+            calls.set(calls.get() + 1);
+            Err(Error::Json(
+                url.to_string(),
+                serde_json::from_str::<()>("{").unwrap_err(),
+            ))
+        };
+
+        let result = with_backoff("https://faur.fosskers.ca", 2, fetch, |_| {
+            panic!("No backoff")
+        });
+        assert!(matches!(result, Err(Error::Json(_, _))));
+        assert_eq!(1, calls.get());
+    }
+
+    #[test]
+    fn retries_failed_requests() {
+        let fll = crate::localization::load(None).unwrap();
+        let calls = Cell::new(0);
+        let mut waits = Vec::new();
+        let fetch = |_: &str| -> Result<(), Error> {
+            calls.set(calls.get() + 1);
+            Err(Error::Curl(curl::Error::new(28)))
+        };
+
+        let msg = match with_backoff("https://faur.fosskers.ca", 2, fetch, |d| waits.push(d)) {
+            Err(e @ Error::Retried(_, 3, _)) => e.localise(&fll),
+            _ => panic!("Expected the retries to run out"),
+        };
+        assert_eq!(3, calls.get());
+        assert_eq!(vec![BACKOFF, BACKOFF * 2], waits);
+        assert!(msg.contains("https://faur.fosskers.ca"));
+        assert!(msg.contains('3'));
+
+        // Without retries, failures are reported as they were.
+        let result = with_backoff("https://faur.fosskers.ca", 0, fetch, |_| {
+            panic!("No backoff")
+        });
+        assert!(matches!(result, Err(Error::Curl(_))));
+
+        // A success along the way is kept.
+        calls.set(0);
+        let fetch = |_: &str| {
+            calls.set(calls.get() + 1);
+            match calls.get() {
+                1 => Err(Error::Curl(curl::Error::new(7))),
+                _ => Ok(vec!["aura".to_string()]),
+            }
+        };
+        let result = with_backoff("https://faur.fosskers.ca", 2, fetch, |_| {});
+        assert_eq!(Some(vec!["aura".to_string()]), result.ok());
+        assert_eq!(2, calls.get());
+    }
+}
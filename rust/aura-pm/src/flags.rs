@@ -1,11 +1,16 @@
 //! Types and utilities for parsing flags from the command line.
 
+use crate::Age;
 use crate::Date;
+use clap::builder::PossibleValuesParser;
 use clap::ArgAction;
 use clap::Parser;
 use clap::Subcommand;
+use simplelog::ColorChoice;
 use simplelog::LevelFilter;
+use std::io::IsTerminal;
 use std::ops::Not;
+use std::path::Path;
 use std::path::PathBuf;
 use unic_langid::LanguageIdentifier;
 
@@ -56,6 +61,49 @@ pub const AURA_GLOBALS: &[&str] = &[
     "--한국어",
     "--hindi",
     "--हिंदी",
+    "--log-to-file-only",
+    "--non-interactive",
+    "--relative",
+    "--absolute",
+];
+
+/// The label of the field Aura adds to `-Qi` output, after "Install Reason", for
+/// packages installed as dependencies.
+pub const PULLED_IN_BY: &str = "Pulled In By";
+
+/// The label of the field Aura adds to `-Qi` output, after "Installed Size", with
+/// `--file-count`.
+pub const FILE_COUNT: &str = "File Count";
+
+/// Fields of `-Si` and `-Qi` output that can be selected with `--field`, paired
+/// with the labels Pacman gives them.
+pub const INFO_FIELDS: &[(&str, &str)] = &[
+    ("repository", "Repository"),
+    ("name", "Name"),
+    ("version", "Version"),
+    ("description", "Description"),
+    ("architecture", "Architecture"),
+    ("url", "URL"),
+    ("licenses", "Licenses"),
+    ("groups", "Groups"),
+    ("provides", "Provides"),
+    ("depends", "Depends On"),
+    ("optdepends", "Optional Deps"),
+    ("required-by", "Required By"),
+    ("optional-for", "Optional For"),
+    ("conflicts", "Conflicts With"),
+    ("replaces", "Replaces"),
+    ("download-size", "Download Size"),
+    ("installed-size", "Installed Size"),
+    ("file-count", FILE_COUNT),
+    ("packager", "Packager"),
+    ("build-date", "Build Date"),
+    ("install-date", "Install Date"),
+    ("install-reason", "Install Reason"),
+    ("pulled-in-by", PULLED_IN_BY),
+    ("install-script", "Install Script"),
+    ("validated-by", "Validated By"),
+    ("backup-files", "Backup Files"),
 ];
 
 /// Commandline arguments to the Aura executable.
@@ -321,9 +369,42 @@ pub struct Args {
     /// Minimum level of Aura log messages to display.
     #[clap(long, value_name = "level", global = true, display_order = 9)]
     pub log_level: Option<LevelFilter>,
+    /// The format of Aura's log messages.
+    #[clap(
+        long,
+        value_name = "format",
+        value_parser = ["text", "json"],
+        global = true,
+        display_order = 9
+    )]
+    pub log_format: Option<String>,
+    /// Also write Aura's log messages to the given file.
+    #[clap(long, value_name = "path", global = true, display_order = 9)]
+    pub aura_log_file: Option<PathBuf>,
+    /// Only write Aura's log messages to the --aura-log-file.
+    #[clap(long, requires = "aura_log_file", global = true, display_order = 9)]
+    pub log_to_file_only: bool,
     /// Do not ask for any confirmation.
     #[clap(long, global = true, display_order = 9)]
     pub noconfirm: bool,
+    /// Never prompt or open a pager. Implied when `CI` is set or stdin/stdout isn't a terminal.
+    #[clap(long, global = true, display_order = 9)]
+    pub non_interactive: bool,
+    /// Show `-Ai` and `-Li` dates relative to now, like "3 months ago".
+    #[clap(long, global = true, conflicts_with = "absolute", display_order = 9)]
+    pub relative: bool,
+    /// Show `-Ai` and `-Li` dates as they are, overriding `relative_dates` in the config.
+    #[clap(long, global = true, display_order = 9)]
+    pub absolute: bool,
+    /// Colorize the output. `NO_COLOR` turns this off unless `always` is given.
+    #[clap(
+        long,
+        value_name = "when",
+        value_parser = ["always", "never", "auto"],
+        global = true,
+        display_order = 9
+    )]
+    pub color: Option<String>,
     /// The Pacman/Aura subcommand to run.
     #[clap(subcommand)]
     pub subcmd: SubCmd,
@@ -362,6 +443,53 @@ impl Args {
             _ => None,
         }
     }
+
+    /// Should Aura's own output be coloured? An explicit `--color` takes
+    /// precedence over the `NO_COLOR` environment variable, and otherwise colour
+    /// is only used when writing to a terminal.
+    pub fn color_choice(&self) -> ColorChoice {
+        let tty = std::io::stdout().is_terminal();
+
+        resolve_color(self.color.as_deref(), no_color(), tty)
+    }
+
+    /// Is anyone around to answer prompts and page through files? Not if
+    /// `--non-interactive` was given, if the `CI` environment variable is set,
+    /// or if stdin or stdout isn't a terminal.
+    pub fn interactive(&self) -> bool {
+        let ci = std::env::var("CI").is_ok_and(|v| matches!(v.as_str(), "" | "0" | "false").not());
+        let tty = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+
+        resolve_interactive(self.non_interactive, ci, tty)
+    }
+}
+
+/// Parse a possibly relative date against the current local date.
+fn local_date(s: &str) -> Result<Date, String> {
+    Date::resolve(s, crate::now_local().date())
+}
+
+/// Settle whether Aura may prompt the user or open a pager.
+fn resolve_interactive(flag: bool, ci: bool, tty: bool) -> bool {
+    flag.not() && ci.not() && tty
+}
+
+/// Is the `NO_COLOR` environment variable set to something? Per its
+/// convention, an empty value doesn't count.
+pub fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| v.is_empty().not())
+}
+
+/// Settle on [`ColorChoice::Always`] or [`ColorChoice::Never`]. In order of
+/// precedence: an explicit `always` or `never`, then `NO_COLOR`, then whether
+/// we're writing to a terminal. `auto` is the same as no flag at all.
+fn resolve_color(flag: Option<&str>, no_color: bool, tty: bool) -> ColorChoice {
+    match flag {
+        Some("always") => ColorChoice::Always,
+        Some("never") => ColorChoice::Never,
+        _ if no_color || tty.not() => ColorChoice::Never,
+        _ => ColorChoice::Always,
+    }
 }
 
 /// The Aura Package Manager.
@@ -405,12 +533,21 @@ pub enum SubCmd {
     /// Handle orphan packages.
     #[clap(display_order = 1)]
     Orphans(Orphans),
+    /// Perform security analysis of a PKGBUILD.
+    #[clap(display_order = 1)]
+    Analysis(Analysis),
     /// Validate your system.
     Check(Check),
     /// View various configuration settings and files.
     Conf(Conf),
+    /// Manage a consistent system environment.
+    Home(Home),
+    /// Hold packages back from every sysupgrade.
+    Hold(Hold),
     /// Output a dependency graph.
     Deps(Deps),
+    /// Open various webpages related to Aura.
+    Open(Open),
     /// State of Free Software installed on the system.
     Free(Free),
     /// View statistics about your machine or about Aura itself.
@@ -419,6 +556,34 @@ pub enum SubCmd {
     Thanks,
 }
 
+impl SubCmd {
+    /// Will this command upgrade every installed package, be it from the
+    /// repositories or the AUR?
+    pub fn sysupgrade(&self) -> bool {
+        match self {
+            SubCmd::Sync(s) => s.upgrades(),
+            SubCmd::Aur(a) => a.sysupgrade,
+            _ => false,
+        }
+    }
+
+    /// The file given to `--packages-from`, along with the packages named
+    /// directly on the command line.
+    pub fn packages_from(&self) -> Option<(&Path, &[String])> {
+        match self {
+            SubCmd::Sync(s) => s
+                .packages_from
+                .as_deref()
+                .map(|p| (p, s.packages.as_slice())),
+            SubCmd::Aur(a) => a
+                .packages_from
+                .as_deref()
+                .map(|p| (p, a.packages.as_slice())),
+            _ => None,
+        }
+    }
+}
+
 /// Synchronize official packages.
 #[derive(Parser, Debug)]
 #[clap(short_flag = 'S', long_flag = "sync")]
@@ -454,6 +619,16 @@ pub struct Sync {
         display_order = 1
     )]
     info: Vec<String>,
+    /// [-i] Only show the given fields, in order (can be used more than once).
+    #[clap(
+        long,
+        value_name = "field",
+        requires = "info",
+        action(ArgAction::Append),
+        value_parser = PossibleValuesParser::new(INFO_FIELDS.iter().map(|(f, _)| *f)),
+        display_order = 2
+    )]
+    pub field: Vec<String>,
     /// View a list of packages in a repo.
     #[clap(group = "sync", long, short, value_name = "repo", display_order = 1)]
     list: Option<String>,
@@ -475,23 +650,41 @@ pub struct Sync {
         display_order = 1
     )]
     sysupgrade: u8,
+    /// [-u] Afterward, upgrade AUR packages as well.
+    #[clap(long, requires = "sysupgrade", display_order = 2)]
+    pub aur: bool,
+    /// [-u] Don't warn about sync databases that have gone stale.
+    #[clap(long, requires = "sysupgrade", display_order = 2)]
+    pub no_mirror_check: bool,
     /// Be verbose.
     #[clap(long, short, display_order = 2)]
     verbose: bool,
     /// Download packages but do not install/upgrade anything.
     #[clap(long, short = 'w', display_order = 1)]
     downloadonly: bool,
+    /// [-w] Print a manifest of the downloaded tarballs as JSON.
+    #[clap(long, requires = "downloadonly", display_order = 2)]
+    pub json: bool,
     /// Download fresh package databases from the server (-yy to force a refresh even if up to date).
     #[clap(long, short = 'y', action(ArgAction::Count), display_order = 1)]
-    refresh: u8,
+    pub refresh: u8,
+    /// [-y] Refresh up to N databases at once.
+    #[clap(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "refresh",
+        display_order = 2
+    )]
+    pub refresh_jobs: Option<u32>,
     /// Set an alternate architecture.
     #[clap(long)]
     arch: Option<String>,
     /// Install packages as non-explicitly installed.
-    #[clap(long)]
+    #[clap(group = "reason", long)]
     asdeps: bool,
     /// Install pacakges as explicitly installed.
-    #[clap(long)]
+    #[clap(group = "reason", long)]
     asexplicit: bool,
     /// Add a virtual package to satisfy dependencies.
     #[clap(long, value_name = "package=version")]
@@ -499,9 +692,6 @@ pub struct Sync {
     /// Set an alternate package cache location.
     #[clap(long, value_name = "path")]
     cachedir: Option<PathBuf>,
-    /// Colorize the output.
-    #[clap(long, value_name = "when", value_parser = ["always", "never", "auto"])]
-    color: Option<String>,
     /// Set an alternate Pacman configuration file.
     #[clap(long, value_name = "path")]
     config: Option<String>,
@@ -527,11 +717,11 @@ pub struct Sync {
     #[clap(long, value_name = "dir")]
     hookdir: Option<String>,
     /// Ignore a package upgrade (can be used more than once).
-    #[clap(long, value_name = "pkg")]
-    ignore: Option<String>,
+    #[clap(long, value_name = "pkg", action(ArgAction::Append))]
+    pub ignore: Vec<String>,
     /// Ignore a group ugrade (can be used more than once).
-    #[clap(long, value_name = "grp")]
-    ignoregroup: Option<String>,
+    #[clap(long, value_name = "grp", action(ArgAction::Append))]
+    ignoregroup: Vec<String>,
     /// Set an alternate log file.
     #[clap(long, value_name = "path")]
     logfile: Option<PathBuf>,
@@ -556,6 +746,9 @@ pub struct Sync {
     /// Operate on a mounted guest system (root-only).
     #[clap(long)]
     sysroot: bool,
+    /// Also install the packages listed in a file, one per line (- for stdin).
+    #[clap(long, value_name = "path")]
+    pub packages_from: Option<PathBuf>,
     /// Packages to search/install.
     packages: Vec<String>,
 }
@@ -565,6 +758,35 @@ impl Sync {
     pub fn needs_sudo(&self) -> bool {
         (self.info.is_empty().not() || self.search || self.list.is_some() || self.print).not()
     }
+
+    /// Can Aura refresh the databases itself, in Pacman's place? Not if Pacman
+    /// has been pointed at some other system or configuration.
+    pub fn own_refresh(&self) -> bool {
+        self.refresh > 0 && self.elsewhere().not()
+    }
+
+    /// Has Pacman been pointed at some system or configuration other than the
+    /// one Aura reads?
+    pub fn elsewhere(&self) -> bool {
+        self.config.is_some() || self.dbpath.is_some() || self.root.is_some() || self.sysroot
+    }
+
+    /// Is this a `-Su`, which upgrades everything installed?
+    pub fn upgrades(&self) -> bool {
+        self.sysupgrade > 0
+    }
+
+    /// Is a refresh all that this `-S` was asked to do?
+    pub fn only_refresh(&self) -> bool {
+        self.clean == 0
+            && self.groups == 0
+            && self.info.is_empty()
+            && self.list.is_none()
+            && self.search.not()
+            && self.sysupgrade == 0
+            && self.downloadonly.not()
+            && self.packages.is_empty()
+    }
 }
 
 // TODO Reconcile `pacman -Th` and the manpage entry for -T.
@@ -580,9 +802,6 @@ pub struct DepTest {
     /// Set an alternate architecture.
     #[clap(long)]
     arch: Option<String>,
-    /// Colorize the output.
-    #[clap(long, value_name = "when", value_parser = ["always", "never", "auto"])]
-    color: Option<String>,
     /// Set an alternate Pacman configuration file.
     #[clap(long, value_name = "path")]
     config: Option<String>,
@@ -632,6 +851,9 @@ pub struct Upgrade {
     /// Print the targets instead of performing the operation.
     #[clap(long, short, display_order = 1)]
     print: bool,
+    /// [-p] Print the targets as JSON, with their dependencies.
+    #[clap(long, requires = "print", display_order = 2)]
+    pub json: bool,
     /// Be verbose.
     #[clap(long, short, display_order = 1)]
     verbose: bool,
@@ -639,17 +861,14 @@ pub struct Upgrade {
     #[clap(long)]
     arch: Option<String>,
     /// Install packages as non-explicitly installed.
-    #[clap(long)]
+    #[clap(group = "reason", long)]
     asdeps: bool,
     /// Install pacakges as explicitly installed.
-    #[clap(long)]
+    #[clap(group = "reason", long)]
     asexplicit: bool,
     /// Add a virtual package to satisfy dependencies.
     #[clap(long, value_name = "package=version")]
     assumed_installed: Option<String>,
-    /// Colorize the output.
-    #[clap(long, value_name = "when", value_parser = ["always", "never", "auto"])]
-    color: Option<String>,
     /// Set an alternate Pacman configuration file.
     #[clap(long, value_name = "path")]
     config: Option<String>,
@@ -675,11 +894,11 @@ pub struct Upgrade {
     #[clap(long, value_name = "dir")]
     hookdir: Option<String>,
     /// Ignore a package upgrade (can be used more than once).
-    #[clap(long, value_name = "pkg")]
-    ignore: Option<String>,
+    #[clap(long, value_name = "pkg", action(ArgAction::Append))]
+    ignore: Vec<String>,
     /// Ignore a group ugrade (can be used more than once).
-    #[clap(long, value_name = "grp")]
-    ignoregroup: Option<String>,
+    #[clap(long, value_name = "grp", action(ArgAction::Append))]
+    ignoregroup: Vec<String>,
     /// Set an alternate log file.
     #[clap(long, value_name = "path")]
     logfile: Option<PathBuf>,
@@ -708,7 +927,7 @@ pub struct Upgrade {
     #[clap(long)]
     sysroot: bool,
     /// Packages to install, either a tarball or a URL.
-    packages: Vec<String>,
+    pub packages: Vec<String>,
 }
 
 impl Upgrade {
@@ -741,9 +960,6 @@ pub struct Files {
     /// Set an alternate architecture.
     #[clap(long)]
     arch: Option<String>,
-    /// Colorize the output.
-    #[clap(long, value_name = "when", value_parser = ["always", "never", "auto"])]
-    color: Option<String>,
     /// Set an alternate Pacman configuration file.
     #[clap(long, value_name = "path")]
     config: Option<String>,
@@ -797,7 +1013,7 @@ impl Files {
 pub struct Remove {
     /// Remove packages and all packages that depend on them.
     #[clap(long, short, display_order = 1)]
-    cascade: bool,
+    pub cascade: bool,
     /// Skip dependency version checks (-dd to skip all checks).
     #[clap(long, short = 'd', action = ArgAction::Count, display_order = 1)]
     nodeps: u8,
@@ -807,9 +1023,12 @@ pub struct Remove {
     /// Print the targets instead of performing the operation.
     #[clap(long, short, display_order = 1)]
     print: bool,
+    /// [-p] Print the removal plan as JSON, with versions and sizes.
+    #[clap(long, requires = "print", display_order = 2)]
+    pub json: bool,
     /// Remove unnecessary dependencies (-ss includes explicitly installed dependencies).
     #[clap(long, short = 's', action = ArgAction::Count, display_order = 1)]
-    recursive: u8,
+    pub recursive: u8,
     /// Remove unneeded packages.
     #[clap(long, short, display_order = 1)]
     unneeded: bool,
@@ -825,9 +1044,6 @@ pub struct Remove {
     /// Set an alternate package cache location.
     #[clap(long, value_name = "path")]
     cachedir: Option<PathBuf>,
-    /// Colorize the output.
-    #[clap(long, value_name = "when", value_parser = ["always", "never", "auto"])]
-    color: Option<String>,
     /// Set an alternate Pacman configuration file.
     #[clap(long, value_name = "path")]
     config: Option<String>,
@@ -874,7 +1090,7 @@ pub struct Remove {
     #[clap(long)]
     sysroot: bool,
     /// Packages to remove.
-    packages: Vec<String>,
+    pub packages: Vec<String>,
 }
 
 impl Remove {
@@ -901,14 +1117,11 @@ pub struct Database {
     #[clap(long)]
     arch: Option<String>,
     /// Mark packages as non-explicitly installed.
-    #[clap(long, display_order = 1)]
+    #[clap(group = "reason", long, display_order = 1)]
     asdeps: bool,
     /// Mark pacakges as explicitly installed.
-    #[clap(long, display_order = 1)]
+    #[clap(group = "reason", long, display_order = 1)]
     asexplicit: bool,
-    /// Colorize the output.
-    #[clap(long, value_name = "when", value_parser = ["always", "never", "auto"])]
-    color: Option<String>,
     /// Set an alternate Pacman configuration file.
     #[clap(long, value_name = "path")]
     config: Option<String>,
@@ -958,47 +1171,77 @@ impl Database {
 pub struct Query {
     /// View the changelog of a package.
     #[clap(long, short, display_order = 1)]
-    changelog: bool,
+    pub changelog: bool,
+    /// [-c] View the changelogs of all packages changed by the last upgrade.
+    #[clap(long, requires = "changelog", display_order = 2)]
+    pub all: bool,
     /// List packages installed as dependencies [filter].
     #[clap(long, short, display_order = 1)]
-    deps: bool,
+    pub deps: bool,
     /// List packages explicitly installed [filter].
     #[clap(long, short, display_order = 1)]
-    explicit: bool,
+    pub explicit: bool,
+    /// List explicitly installed packages that nothing else (optionally) requires.
+    #[clap(long, conflicts_with = "deps", display_order = 1)]
+    pub leaves: bool,
     /// View all members of a package group.
     #[clap(long, short, display_order = 1)]
     groups: bool,
     /// View package information (-ii for backup files).
     #[clap(long, short, display_order = 1)]
     info: bool,
+    /// [-i] Only show the given fields, in order (can be used more than once).
+    #[clap(
+        long,
+        value_name = "field",
+        requires = "info",
+        action(ArgAction::Append),
+        value_parser = PossibleValuesParser::new(INFO_FIELDS.iter().map(|(f, _)| *f)),
+        display_order = 2
+    )]
+    pub field: Vec<String>,
+    /// [-i] Show how many files each package owns.
+    #[clap(long, requires = "info", display_order = 2)]
+    pub file_count: bool,
+    /// [-i] Show what pulled in each package installed as a dependency.
+    #[clap(long, requires = "info", display_order = 2)]
+    pub pulled_in_by: bool,
     /// Check that package files exist (-kk for file properties).
     #[clap(long, short = 'k', action(ArgAction::Count), display_order = 1)]
     check: u8,
     /// List the files owned by the queried package.
     #[clap(long, short, display_order = 1)]
     list: bool,
+    /// [-l] Only list owned files that no longer exist on disk.
+    #[clap(long, requires = "list", display_order = 2)]
+    pub missing: bool,
     /// List installed packages not found in sync db(s) [filter].
     #[clap(long, short = 'm', display_order = 1)]
     foreign: bool,
     /// List installed packages only found in sync db(s) [filter].
     #[clap(long, short, display_order = 1)]
-    /// Print the targets instead of performing the operation.
     native: bool,
-    /// Query the package that owns <file>.
-    #[clap(long, short, value_name = "file", display_order = 1)]
-    owns: Option<String>,
+    /// Query the packages that own the given files.
+    #[clap(long, short, value_name = "file", num_args = 1.., display_order = 1)]
+    pub owns: Vec<String>,
     /// Query a package file instead of the database.
     #[clap(long, short = 'p', value_name = "package", display_order = 1)]
     file: Option<String>,
     /// Show less information for query and search.
     #[clap(long, short, display_order = 1)]
-    quiet: bool,
+    pub quiet: bool,
     /// Search remote repositories for matchings strings.
     #[clap(long, short, display_order = 1)]
     search: bool,
+    /// [-s] Also match near-misses of the search terms, best matches first.
+    #[clap(long, requires = "search", display_order = 2)]
+    pub fuzzy: bool,
     /// List packages not (optionally) required by any package (-tt to ignore optdepends) [filter].
     #[clap(long, short = 't', display_order = 1)]
     unrequired: bool,
+    /// [-t] Also list packages that would become unrequired were the others removed.
+    #[clap(long, requires = "unrequired", display_order = 2)]
+    pub recursive: bool,
     /// List outdated packages [filter].
     #[clap(long, short, display_order = 1)]
     upgrades: bool,
@@ -1011,9 +1254,6 @@ pub struct Query {
     /// Set an alternate package cache location.
     #[clap(long, value_name = "path")]
     cachedir: Option<PathBuf>,
-    /// Colorize the output.
-    #[clap(long, value_name = "when", value_parser = ["always", "never", "auto"])]
-    color: Option<String>,
     /// Set an alternate Pacman configuration file.
     #[clap(long, value_name = "path")]
     config: Option<String>,
@@ -1048,7 +1288,49 @@ pub struct Query {
     #[clap(long)]
     sysroot: bool,
     /// Packages to query.
-    packages: Vec<String>,
+    pub packages: Vec<String>,
+}
+
+impl Query {
+    /// Is this a plain `-Qi` of installed packages, which Aura can add to? Not
+    /// for package files, other operations, or if Pacman has been pointed at
+    /// some other system.
+    pub fn local_info(&self) -> bool {
+        self.info
+            && self.file.is_none()
+            && self.list.not()
+            && self.check == 0
+            && self.changelog.not()
+            && self.groups.not()
+            && self.config.is_none()
+            && self.dbpath.is_none()
+            && self.root.is_none()
+            && self.sysroot.not()
+    }
+
+    /// The filters given to narrow which installed packages are listed.
+    pub fn filters(&self) -> QueryFilters {
+        QueryFilters {
+            deps: self.deps,
+            explicit: self.explicit,
+            foreign: self.foreign,
+            native: self.native,
+            unrequired: self.unrequired,
+            upgrades: self.upgrades,
+        }
+    }
+}
+
+/// The `-Q` flags marked `[filter]`. As with Pacman, a package must pass every
+/// one of them to be listed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct QueryFilters {
+    pub deps: bool,
+    pub explicit: bool,
+    pub foreign: bool,
+    pub native: bool,
+    pub unrequired: bool,
+    pub upgrades: bool,
 }
 
 /// Perform security analysis of a PKGBUILD.
@@ -1073,12 +1355,24 @@ pub struct Orphans {
     /// Mark a package as being explicitly installed.
     #[clap(group = "orphans", long, short, value_name = "packages", num_args = 1..)]
     pub adopt: Vec<String>,
+    /// Mark a package as being installed as a dependency.
+    #[clap(group = "orphans", long, short, value_name = "packages", num_args = 1..)]
+    pub disown: Vec<String>,
     /// Uninstall all orphan packages.
     #[clap(group = "orphans", long, short = 'j')]
     pub abandon: bool,
+    /// [-j] List what would be removed, without removing it.
+    #[clap(long, requires = "abandon")]
+    pub dry_run: bool,
     /// Display all explicitly installed, top-level packages.
     #[clap(group = "orphans", long, short = 'e')]
     pub elderly: bool,
+    /// [-e] Show each package with its tree of dependencies.
+    #[clap(long, requires = "elderly")]
+    pub tree: bool,
+    /// [-e --tree] The number of layers of dependencies to show.
+    #[clap(long, value_name = "n", requires = "tree")]
+    pub depth: Option<u8>,
 }
 
 /// View various configuration settings and files.
@@ -1096,6 +1390,92 @@ pub struct Conf {
     /// Output your current, full Aura config as legal TOML.
     #[clap(group = "conf", long, short, display_order = 1)]
     pub gen: bool,
+    /// [-g] Write the config to the given file instead of stdout.
+    #[clap(long, value_name = "path", requires = "gen", display_order = 2)]
+    pub output: Option<PathBuf>,
+    /// [-g] With --output, overwrite the file if it already exists.
+    #[clap(long, requires = "output", display_order = 2)]
+    pub force: bool,
+    /// Output a commented config template with default values.
+    #[clap(group = "conf", long, short, display_order = 1)]
+    pub template: bool,
+    /// Edit ~/.config/aura/config.toml, and check it afterward.
+    #[clap(group = "conf", long, short, display_order = 1)]
+    pub edit: bool,
+    /// List the .pacnew and .pacsave files that await review.
+    #[clap(group = "conf", long, short, display_order = 1)]
+    pub new: bool,
+}
+
+/// Manage a consistent system environment.
+#[derive(Parser, Debug)]
+pub struct Home {
+    /// Report drift from ~/.config/aura/home.toml, and fail if there is any.
+    #[clap(group = "home", long, short, display_order = 1)]
+    pub check: bool,
+    /// Install missing packages, and mark undeclared ones as dependencies.
+    #[clap(group = "home", long, short, display_order = 1)]
+    pub sync: bool,
+    /// Write the current set of explicitly installed packages to home.toml.
+    #[clap(group = "home", long, short, display_order = 1)]
+    pub dump: bool,
+}
+
+/// Hold packages back from every sysupgrade.
+#[derive(Parser, Debug)]
+#[clap(disable_help_subcommand = true)]
+pub struct Hold {
+    /// What to do with the held packages (lists them by default).
+    #[clap(subcommand)]
+    pub action: Option<HoldAction>,
+}
+
+/// Changes to, or views of, the held packages.
+#[derive(Subcommand, Debug)]
+pub enum HoldAction {
+    /// Hold packages, such that sysupgrades leave them alone.
+    Add {
+        /// The packages to hold.
+        #[clap(required = true)]
+        packages: Vec<String>,
+    },
+    /// Release held packages, such that sysupgrades consider them again.
+    Remove {
+        /// The packages to release.
+        #[clap(required = true)]
+        packages: Vec<String>,
+    },
+    /// List the held packages.
+    List,
+}
+
+/// Open various webpages related to Aura.
+#[derive(Parser, Debug)]
+pub struct Open {
+    /// Open Aura's documentation (the default).
+    #[clap(group = "open", long, short, display_order = 1)]
+    pub docs: bool,
+    /// Open Aura's source code repository.
+    #[clap(group = "open", long, short, display_order = 1)]
+    pub repo: bool,
+    /// File a bug report.
+    #[clap(group = "open", long, short, display_order = 1)]
+    pub bug: bool,
+    /// Open Aura's license.
+    #[clap(group = "open", long, short, display_order = 1)]
+    pub license: bool,
+    /// Open Aura's AUR page.
+    #[clap(group = "open", long, short, display_order = 1)]
+    pub aur: bool,
+    /// Read Aura's man page, which needs no network.
+    #[clap(group = "open", long, short, display_order = 1)]
+    pub man: bool,
+    /// Print the URL instead of opening it.
+    #[clap(long, conflicts_with = "man", display_order = 2)]
+    pub print: bool,
+    /// Open the page with this program, like `firefox` or `w3m`.
+    #[clap(long, value_name = "prog", conflicts_with_all = ["man", "print"], display_order = 2)]
+    pub browser: Option<String>,
 }
 
 #[derive(Parser, Debug)]
@@ -1110,12 +1490,21 @@ pub struct Log {
     #[clap(group = "log", long, short, value_name = "term", display_order = 1)]
     pub search: Option<String>,
 
-    /// Only display log entries from before the given date.
-    #[clap(long, short, value_name = "YYYY-MM-DD")]
+    /// Output the history as text, CSV, or JSON, with one record per change.
+    #[clap(
+        long,
+        value_name = "format",
+        value_parser = ["text", "csv", "json"],
+        display_order = 2
+    )]
+    pub format: Option<String>,
+
+    /// Only display log entries from before the given date (YYYY-MM-DD, 7d, 2w, 1m, yesterday).
+    #[clap(long, short, value_name = "date", value_parser = local_date)]
     pub before: Option<Date>,
 
-    /// Only display log entries from after the given date.
-    #[clap(long, short, value_name = "YYYY-MM-DD")]
+    /// Only display log entries from after the given date (YYYY-MM-DD, 7d, 2w, 1m, yesterday).
+    #[clap(long, short, value_name = "date", value_parser = local_date)]
     pub after: Option<Date>,
 
     /// Set an alternate log file.
@@ -1130,13 +1519,71 @@ pub struct Stats {
     #[clap(group = "stats", long, short, display_order = 1)]
     pub lang: bool,
 
-    /// View all installed package groups.
+    /// View all installed package groups, heaviest first.
     #[clap(group = "stats", long, short, display_order = 1)]
     pub groups: bool,
 
-    /// View the Top 10 heaviest installed packages.
-    #[clap(group = "stats", long, display_order = 1)]
-    pub heavy: bool,
+    /// View the Top N (default 10) heaviest installed packages. 0 shows all.
+    #[clap(
+        group = "stats",
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        default_missing_value = "10",
+        display_order = 1
+    )]
+    pub heavy: Option<usize>,
+
+    /// Summarise the package cache and how much cleaning it would free.
+    #[clap(group = "stats", long, short, display_order = 1)]
+    pub cache: bool,
+
+    /// With --cache, the number of versions of each package to keep.
+    #[clap(
+        long,
+        value_name = "N",
+        default_value_t = 3,
+        requires = "cache",
+        display_order = 2
+    )]
+    pub keep: usize,
+
+    /// With --heavy, show every package.
+    #[clap(long, requires = "heavy", display_order = 2)]
+    pub all: bool,
+
+    /// With --heavy, total installed sizes by package group instead.
+    #[clap(long, requires = "heavy", display_order = 2)]
+    pub by_group: bool,
+
+    /// With --heavy, show the lightest installations instead.
+    #[clap(long, requires = "heavy", display_order = 2)]
+    pub reverse: bool,
+
+    /// With --groups, only print the group names.
+    #[clap(
+        long,
+        short,
+        requires = "groups",
+        conflicts_with = "json",
+        display_order = 2
+    )]
+    pub quiet: bool,
+
+    /// Print the results as JSON.
+    #[clap(long, display_order = 2)]
+    pub json: bool,
+}
+
+impl Stats {
+    /// How many packages `--heavy` should show. `None` means all of them.
+    pub fn heavy_count(&self) -> Option<usize> {
+        match self.heavy {
+            _ if self.all => None,
+            Some(0) => None,
+            n => n,
+        }
+    }
 }
 
 /// State of Free Software installed on the system.
@@ -1190,6 +1637,16 @@ pub struct Aur {
     #[clap(long, display_order = 2)]
     pub abc: bool,
 
+    /// [-s/-v] Sort results by name, votes (default), popularity, or last update.
+    #[clap(
+        long,
+        value_name = "key",
+        value_parser = ["name", "votes", "popularity", "updated"],
+        conflicts_with = "abc",
+        display_order = 2
+    )]
+    pub sort: Option<String>,
+
     /// [-s/-v] Limit the results to N results.
     #[clap(long, value_name = "N", display_order = 2, alias = "head")]
     pub limit: Option<usize>,
@@ -1202,13 +1659,57 @@ pub struct Aur {
     #[clap(long, short, display_order = 2)]
     pub quiet: bool,
 
+    /// [-s/-v] Only show packages that are installed.
+    #[clap(long, conflicts_with = "not_installed", display_order = 2)]
+    pub installed: bool,
+
+    /// [-s/-v] Only show packages that aren't installed.
+    #[clap(long, display_order = 2)]
+    pub not_installed: bool,
+
+    /// [-s] Match search terms against package names only.
+    #[clap(long, conflicts_with = "description", display_order = 2)]
+    pub name_only: bool,
+
+    /// [-s] Match search terms against package names and descriptions (default).
+    #[clap(long, display_order = 2)]
+    pub description: bool,
+
     /// Open a given package's AUR page.
     #[clap(group = "aur", long, short, value_name = "package", display_order = 1)]
     pub open: Option<String>,
 
-    /// View a package's PKGBUILD.
-    #[clap(group = "aur", long, short, value_name = "package", display_order = 1)]
-    pub pkgbuild: Option<String>,
+    /// View the PKGBUILDs of the given packages.
+    #[clap(
+        group = "aur",
+        long,
+        short,
+        value_name = "packages",
+        num_args = 1..,
+        display_order = 1
+    )]
+    pub pkgbuild: Vec<String>,
+
+    /// Analyse and lint the PKGBUILDs of the given packages, but build nothing.
+    #[clap(group = "aur", long, display_order = 1)]
+    pub validate: bool,
+
+    /// Query this faur instance for package data, instead of the configured ones.
+    #[clap(long, value_name = "url", display_order = 4)]
+    pub endpoint: Option<String>,
+
+    /// Retry a failed RPC request, clone, or pull up to N times.
+    #[clap(long, value_name = "N", display_order = 4)]
+    pub retries: Option<u32>,
+
+    /// Give up on requests, clones, and pulls that can't connect within this
+    /// many seconds.
+    #[clap(long, value_name = "secs", display_order = 4)]
+    pub connect_timeout: Option<u64>,
+
+    /// Give up on transfers that receive nothing for this many seconds.
+    #[clap(long, value_name = "secs", display_order = 4)]
+    pub read_timeout: Option<u64>,
 
     /// The path in which to build packages.
     #[clap(long, display_order = 4, value_name = "path")]
@@ -1222,6 +1723,14 @@ pub struct Aur {
     #[clap(long, short = 'k', display_order = 3)]
     pub diff: bool,
 
+    /// [-k] Only show a summary of the lines changed in each build file.
+    #[clap(long, display_order = 3)]
+    pub stat: bool,
+
+    /// [-k] View the diffs with this program, like `delta`.
+    #[clap(long, value_name = "prog", display_order = 3)]
+    pub difftool: Option<String>,
+
     /// View/edit PKGBUILDs and related build files before building.
     #[clap(long, display_order = 4)]
     pub hotedit: bool,
@@ -1238,10 +1747,30 @@ pub struct Aur {
     #[clap(long, short, display_order = 4)]
     pub clean: bool,
 
+    /// Once a package is installed, delete the src/ and pkg/ directories left in its build directory.
+    #[clap(long, display_order = 4)]
+    pub clean_after: bool,
+
+    /// Keep every build directory intact, regardless of --clean, --clean-after, or config.
+    #[clap(long, conflicts_with_all = ["clean", "clean_after"], display_order = 4)]
+    pub keep_sources: bool,
+
     /// Give installed packages the "non-explicit" status.
     #[clap(long, display_order = 4)]
     pub asdeps: bool,
 
+    /// Continue building other packages after a failure, skipping only those that depend on it.
+    #[clap(long, display_order = 4)]
+    pub keep_going: bool,
+
+    /// Build up to N packages at once, when they don't depend on each other.
+    #[clap(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..), display_order = 4)]
+    pub jobs: Option<u32>,
+
+    /// If building or installing fails partway, offer to restore prior versions.
+    #[clap(long, display_order = 4)]
+    pub rollback: bool,
+
     /// Upgrade all installed AUR packages.
     #[clap(group = "aur", long, short = 'u', display_order = 1)]
     pub sysupgrade: bool,
@@ -1259,10 +1788,19 @@ pub struct Aur {
     )]
     pub ignore: Vec<String>,
 
-    /// [-u] Show available upgrades, but do not perform them.
-    #[clap(long, short = 'd', display_order = 3)]
+    /// [-u] Show available upgrades (with --git, VCS rebuilds too), but clone and build nothing.
+    #[clap(long, short = 'd', alias = "dry-run", display_order = 3)]
     pub dryrun: bool,
 
+    /// [-u] Only attempt the packages that failed to build during the last `-Au`.
+    #[clap(
+        long,
+        requires = "sysupgrade",
+        conflicts_with = "dryrun",
+        display_order = 3
+    )]
+    pub retry_failed: bool,
+
     /// Clone a package's AUR repository, but don't build anything.
     #[clap(
         group = "aur",
@@ -1299,6 +1837,21 @@ pub struct Aur {
     #[clap(long, display_order = 5)]
     pub skippgpcheck: bool,
 
+    /// Check source signatures against the PKGBUILD's trusted PGP keys before
+    /// building. The default.
+    // NOTE Never read. This exists only so that a later `--verify` cancels an
+    // earlier `--no-verify`, as from a shell alias; clap then unsets `no_verify`.
+    #[clap(long, overrides_with = "no_verify", display_order = 5)]
+    pub verify: bool,
+
+    /// Build without checking source signatures.
+    #[clap(long, overrides_with = "verify", display_order = 5)]
+    pub no_verify: bool,
+
+    /// Also install the packages listed in a file, one per line (- for stdin).
+    #[clap(long, value_name = "path", display_order = 5)]
+    pub packages_from: Option<PathBuf>,
+
     /// Packages to install.
     pub packages: Vec<String>,
 }
@@ -1307,7 +1860,11 @@ pub struct Aur {
 #[derive(Parser, Debug)]
 #[clap(short_flag = 'B', long_flag = "backup")]
 pub struct Backup {
-    /// Show all saved package snapshot filenames.
+    /// Save a snapshot labelled with a name, to later restore by.
+    #[clap(group = "backup", long, short, value_name = "name", display_order = 1)]
+    pub save: Option<String>,
+
+    /// Show all saved package snapshot filenames, along with their names.
     #[clap(group = "backup", long, short, display_order = 1)]
     pub list: bool,
 
@@ -1315,9 +1872,17 @@ pub struct Backup {
     #[clap(group = "backup", long, short, display_order = 1)]
     pub clean: bool,
 
-    /// Restore to a previous package snapshot.
-    #[clap(group = "backup", long, short, display_order = 1)]
-    pub restore: bool,
+    /// Restore to a previous package snapshot, given by filename, date, or
+    /// name, or selected from a list.
+    #[clap(
+        group = "backup",
+        long,
+        short,
+        value_name = "snapshot",
+        num_args = 0..=1,
+        display_order = 1
+    )]
+    pub restore: Option<Option<String>>,
 }
 
 /// Manage the package cache.
@@ -1328,15 +1893,31 @@ pub struct Cache {
     #[clap(group = "cache", short, long, value_name = "term", display_order = 1)]
     pub search: Option<String>,
 
+    /// [-s] Also match near-misses of the term, best matches first.
+    #[clap(long, requires = "search", display_order = 2)]
+    pub fuzzy: bool,
+
     // TODO Make other options elsewhere that expect a path have `PathBuf` too.
     /// Back up the package cache to a given directory.
     #[clap(group = "cache", long, short, value_name = "target", display_order = 1)]
     pub backup: Option<PathBuf>,
 
+    /// [--backup] Afterward, check every backed-up file against its original.
+    #[clap(long = "verify", requires = "backup", display_order = 2)]
+    pub verify_backup: bool,
+
     /// Save the most recent <N> versions of a package.
     #[clap(group = "cache", short, long, value_name = "N", display_order = 1)]
     pub clean: Option<usize>,
 
+    /// Delete tarballs older than a given age, like 30d, 12h, or 6w.
+    #[clap(group = "cache", long, value_name = "age", display_order = 1)]
+    pub clean_older_than: Option<Age>,
+
+    /// [--clean-older-than] List what would be deleted, without deleting it.
+    #[clap(long, requires = "clean_older_than", display_order = 2)]
+    pub dry_run: bool,
+
     /// [-c] Delete only those tarballs which aren't present in a snapshot.
     #[clap(group = "cache", long = "notsaved", short = 'n', display_order = 1)]
     pub clean_unsaved: bool,
@@ -1345,10 +1926,18 @@ pub struct Cache {
     #[clap(group = "cache", short, long, value_name = "pkg(s)", num_args = 1.., display_order = 1)]
     pub info: Vec<String>,
 
+    /// List every cached version of specific packages, newest first.
+    #[clap(group = "cache", long, value_name = "pkg(s)", num_args = 1.., display_order = 1)]
+    pub versions: Vec<String>,
+
     /// Print the contents of the package cache.
     #[clap(group = "cache", short, long, display_order = 1)]
     pub list: bool,
 
+    /// [-l, -i] Print tab-separated records, for scripts.
+    #[clap(long, display_order = 2)]
+    pub machinereadable: bool,
+
     /// Download tarballs of installed packages that are missing from the cache.
     #[clap(group = "cache", short = 'y', long, display_order = 1)]
     pub refresh: bool,
@@ -1357,6 +1946,19 @@ pub struct Cache {
     #[clap(group = "cache", short = 't', long, display_order = 1)]
     pub invalid: bool,
 
+    /// Report corrupt, unsigned, and badly signed tarballs. Deletes nothing
+    /// unless paired with --invalid.
+    #[clap(
+        long = "verify-all",
+        conflicts_with_all = ["search", "backup", "clean", "clean_unsaved", "info", "list", "refresh", "missing"],
+        display_order = 1
+    )]
+    pub verify: bool,
+
+    /// [--verify-all, --invalid] The number of tarballs to check at once.
+    #[clap(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..), display_order = 2)]
+    pub jobs: Option<u32>,
+
     /// Display packages that don't have a tarball in the cache.
     #[clap(group = "cache", long, short, display_order = 1)]
     pub missing: bool,
@@ -1380,6 +1982,10 @@ pub struct Deps {
     #[clap(long, short, value_name = "n", display_order = 1)]
     pub limit: Option<u8>,
 
+    /// Follow every layer up or down, however many there are. The default without --limit.
+    #[clap(long, conflicts_with = "limit", display_order = 1)]
+    pub all: bool,
+
     /// Print the raw DOT output.
     #[clap(long, display_order = 2)]
     pub raw: bool,
@@ -1388,10 +1994,380 @@ pub struct Deps {
     #[clap(long, conflicts_with = "raw", display_order = 2)]
     pub open: bool,
 
+    /// Write the graph to a file. Its extension (svg, png, pdf, dot) sets the format.
+    #[clap(long, value_name = "path", conflicts_with = "raw", display_order = 2)]
+    pub output: Option<PathBuf>,
+
+    /// Instead of the graph, print the packages that nothing in it depends on.
+    #[clap(long, conflicts_with_all = ["raw", "open", "output"], display_order = 3)]
+    pub roots: bool,
+
+    /// Instead of the graph, print the packages in it with no dependencies of their own.
+    #[clap(long, conflicts_with_all = ["raw", "open", "output"], display_order = 3)]
+    pub leaves: bool,
+
     /// Packages to focus on.
     pub packages: Vec<String>,
 }
 
 /// Validate your system.
 #[derive(Parser, Debug)]
-pub struct Check {}
+pub struct Check {
+    /// Only show failed checks.
+    #[clap(long, short)]
+    pub quiet: bool,
+    /// Only count the .pacnew and .pacsave files that await review.
+    #[clap(long)]
+    pub pacnew: bool,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn color_resolution() {
+        assert_eq!(ColorChoice::Always, resolve_color(None, false, true));
+        assert_eq!(ColorChoice::Never, resolve_color(None, false, false));
+        assert_eq!(ColorChoice::Never, resolve_color(None, true, true));
+        assert_eq!(ColorChoice::Never, resolve_color(Some("auto"), true, true));
+        assert_eq!(
+            ColorChoice::Always,
+            resolve_color(Some("auto"), false, true)
+        );
+        assert_eq!(
+            ColorChoice::Always,
+            resolve_color(Some("always"), false, false)
+        );
+        assert_eq!(
+            ColorChoice::Never,
+            resolve_color(Some("never"), false, true)
+        );
+        assert_eq!(
+            ColorChoice::Always,
+            resolve_color(Some("always"), true, false)
+        );
+    }
+
+    #[test]
+    fn heavy_counts() {
+        let count = |args: &[&str]| match Args::try_parse_from(args).unwrap().subcmd {
+            SubCmd::Stats(s) => s.heavy_count(),
+            _ => panic!("Expected stats"),
+        };
+
+        assert_eq!(Some(10), count(&["aura", "stats", "--heavy"]));
+        assert_eq!(Some(25), count(&["aura", "stats", "--heavy", "25"]));
+        assert_eq!(None, count(&["aura", "stats", "--heavy", "0"]));
+        assert_eq!(None, count(&["aura", "stats", "--heavy", "--all"]));
+        assert_eq!(
+            Some(5),
+            count(&["aura", "stats", "--heavy", "5", "--reverse"])
+        );
+    }
+
+    #[test]
+    fn deps_depth() {
+        let limit = |args: &[&str]| match Args::try_parse_from(args).unwrap().subcmd {
+            SubCmd::Deps(d) => d.limit,
+            _ => panic!("Expected deps"),
+        };
+
+        assert_eq!(None, limit(&["aura", "deps", "gcc"]));
+        assert_eq!(None, limit(&["aura", "deps", "gcc", "--all"]));
+        assert_eq!(Some(3), limit(&["aura", "deps", "gcc", "--limit", "3"]));
+        assert!(Args::try_parse_from(["aura", "deps", "gcc", "--all", "--limit", "3"]).is_err());
+    }
+
+    #[test]
+    fn repeated_ignores() {
+        let args = Args::try_parse_from([
+            "aura",
+            "-Syu",
+            "--ignore",
+            "linux",
+            "--ignore=firefox",
+            "--ignoregroup",
+            "plasma",
+            "--ignoregroup",
+            "xorg",
+        ])
+        .unwrap();
+
+        match args.subcmd {
+            SubCmd::Sync(s) => {
+                assert_eq!(vec!["linux", "firefox"], s.ignore);
+                assert_eq!(vec!["plasma", "xorg"], s.ignoregroup);
+            }
+            _ => panic!("Expected -S"),
+        }
+
+        let args = Args::try_parse_from([
+            "aura",
+            "-U",
+            "foo.pkg.tar.zst",
+            "--ignore",
+            "a",
+            "--ignore",
+            "b",
+        ])
+        .unwrap();
+
+        match args.subcmd {
+            SubCmd::Upgrade(u) => assert_eq!(vec!["a", "b"], u.ignore),
+            _ => panic!("Expected -U"),
+        }
+    }
+
+    #[test]
+    fn search_fields() {
+        let aur = |args: &[&str]| match Args::try_parse_from(args).unwrap().subcmd {
+            SubCmd::Aur(a) => (a.name_only, a.description),
+            _ => panic!("Expected -A"),
+        };
+
+        assert_eq!((false, false), aur(&["aura", "-As", "aura"]));
+        assert_eq!((true, false), aur(&["aura", "-As", "aura", "--name-only"]));
+        assert_eq!(
+            (false, true),
+            aur(&["aura", "-As", "aura", "--description"])
+        );
+        assert!(
+            Args::try_parse_from(["aura", "-As", "aura", "--name-only", "--description"]).is_err()
+        );
+    }
+
+    #[test]
+    fn build_jobs() {
+        let jobs = |args: &[&str]| match Args::try_parse_from(args).unwrap().subcmd {
+            SubCmd::Aur(a) => a.jobs,
+            _ => panic!("Expected -A"),
+        };
+
+        assert_eq!(Some(4), jobs(&["aura", "-A", "--jobs", "4", "foo"]));
+        assert_eq!(None, jobs(&["aura", "-A", "foo"]));
+        assert!(Args::try_parse_from(["aura", "-A", "--jobs", "0", "foo"]).is_err());
+    }
+
+    #[test]
+    fn refresh_only() {
+        let sync = |args: &[&str]| match Args::try_parse_from(args).unwrap().subcmd {
+            SubCmd::Sync(s) => (s.own_refresh(), s.only_refresh()),
+            _ => panic!("Expected -S"),
+        };
+
+        assert_eq!((true, true), sync(&["aura", "-Sy"]));
+        assert_eq!((true, true), sync(&["aura", "-Syy", "--refresh-jobs", "2"]));
+        assert_eq!((true, false), sync(&["aura", "-Syu"]));
+        assert_eq!((true, false), sync(&["aura", "-Sy", "firefox"]));
+        assert_eq!((false, true), sync(&["aura", "-Sy", "--dbpath", "/tmp/db"]));
+        assert_eq!((false, false), sync(&["aura", "-S", "firefox"]));
+        assert!(Args::try_parse_from(["aura", "-S", "--refresh-jobs", "2"]).is_err());
+        assert!(Args::try_parse_from(["aura", "-Sy", "--refresh-jobs", "0"]).is_err());
+    }
+
+    #[test]
+    fn verify_toggle() {
+        let off = |args: &[&str]| match Args::try_parse_from(args).unwrap().subcmd {
+            SubCmd::Aur(a) => a.no_verify,
+            _ => panic!("Expected -A"),
+        };
+
+        assert!(off(&["aura", "-A", "--no-verify", "foo"]));
+        assert!(off(&["aura", "-A", "--verify", "--no-verify", "foo"]));
+        assert!(off(&["aura", "-A", "--no-verify", "--verify", "foo"]).not());
+        assert!(off(&["aura", "-A", "foo"]).not());
+    }
+
+    #[test]
+    fn log_format() {
+        let log = |args: &[&str]| match Args::try_parse_from(args).unwrap().subcmd {
+            SubCmd::Log(l) => (l.format, l.info, l.search),
+            _ => panic!("Expected -L"),
+        };
+        let json = Some("json".to_string());
+
+        assert_eq!(
+            (json.clone(), vec![], None),
+            log(&["aura", "-L", "--format", "json"])
+        );
+        assert_eq!(
+            (json.clone(), vec!["git".to_string()], None),
+            log(&["aura", "-Li", "git", "--format", "json"])
+        );
+        assert_eq!(
+            (json, vec![], Some("linux".to_string())),
+            log(&[
+                "aura",
+                "-L",
+                "--format",
+                "json",
+                "-s",
+                "linux",
+                "--after",
+                "2024-08-01"
+            ])
+        );
+        assert!(Args::try_parse_from(["aura", "-Li", "git", "--format", "xml"]).is_err());
+    }
+
+    #[test]
+    fn conf_output() {
+        let conf = |args: &[&str]| match Args::try_parse_from(args).unwrap().subcmd {
+            SubCmd::Conf(c) => (c.gen, c.output, c.force),
+            _ => panic!("Expected conf"),
+        };
+
+        assert_eq!((true, None, false), conf(&["aura", "conf", "--gen"]));
+        assert_eq!(
+            (true, Some(PathBuf::from("aura.toml")), true),
+            conf(&["aura", "conf", "-g", "--output", "aura.toml", "--force"])
+        );
+        assert!(Args::try_parse_from(["aura", "conf", "--output", "aura.toml"]).is_err());
+        assert!(Args::try_parse_from(["aura", "conf", "-g", "--force"]).is_err());
+    }
+
+    #[test]
+    fn packages_from() {
+        let listed = |args: &[&str]| {
+            let args = Args::try_parse_from(args).unwrap();
+            args.subcmd
+                .packages_from()
+                .map(|(p, given)| (p.to_path_buf(), given.to_vec()))
+        };
+
+        assert_eq!(None, listed(&["aura", "-S", "bat"]));
+        assert_eq!(
+            Some((PathBuf::from("pkgs.txt"), vec!["bat".to_string()])),
+            listed(&["aura", "-S", "--packages-from", "pkgs.txt", "bat"])
+        );
+        assert_eq!(
+            Some((PathBuf::from("-"), vec![])),
+            listed(&["aura", "-A", "--packages-from", "-"])
+        );
+        assert_eq!(
+            Some((PathBuf::from("aur.txt"), vec!["aura".to_string()])),
+            listed(&["aura", "-A", "aura", "--packages-from=aur.txt"])
+        );
+        assert_eq!(None, listed(&["aura", "-Q"]));
+    }
+
+    #[test]
+    fn holds() {
+        let hold = |args: &[&str]| match Args::try_parse_from(args).unwrap().subcmd {
+            SubCmd::Hold(h) => h.action,
+            _ => panic!("Expected hold"),
+        };
+
+        assert!(hold(&["aura", "hold"]).is_none());
+        assert!(matches!(
+            hold(&["aura", "hold", "list"]),
+            Some(HoldAction::List)
+        ));
+        assert!(matches!(
+            hold(&["aura", "hold", "add", "linux", "nvidia"]),
+            Some(HoldAction::Add { packages }) if packages == ["linux", "nvidia"]
+        ));
+        assert!(matches!(
+            hold(&["aura", "hold", "remove", "linux"]),
+            Some(HoldAction::Remove { packages }) if packages == ["linux"]
+        ));
+        assert!(Args::try_parse_from(["aura", "hold", "add"]).is_err());
+
+        let upgrades = |args: &[&str]| Args::try_parse_from(args).unwrap().subcmd.sysupgrade();
+        assert!(upgrades(&["aura", "-Syu"]));
+        assert!(upgrades(&["aura", "-Au"]));
+        assert!(upgrades(&["aura", "-Sy"]).not());
+        assert!(upgrades(&["aura", "-A", "aura-bin"]).not());
+    }
+
+    #[test]
+    fn local_info() {
+        let query = |args: &[&str]| match Args::try_parse_from(args).unwrap().subcmd {
+            SubCmd::Query(q) => q.local_info(),
+            _ => panic!("Expected -Q"),
+        };
+
+        assert!(query(&["aura", "-Qi", "git"]));
+        assert!(query(&["aura", "-Qi"]));
+        assert!(query(&["aura", "-Qil", "git"]).not());
+        assert!(query(&["aura", "-Qip", "git-2.43.0-1-x86_64.pkg.tar.zst"]).not());
+        assert!(query(&["aura", "-Qi", "--root", "/mnt", "git"]).not());
+        assert!(query(&["aura", "-Q", "git"]).not());
+    }
+
+    #[test]
+    fn info_extras() {
+        let extras = |args: &[&str]| match Args::try_parse_from(args).unwrap().subcmd {
+            SubCmd::Query(q) => (q.file_count, q.pulled_in_by),
+            _ => panic!("Expected -Q"),
+        };
+
+        assert_eq!((false, false), extras(&["aura", "-Qi", "git"]));
+        assert_eq!(
+            (false, true),
+            extras(&["aura", "-Qi", "--pulled-in-by", "git"])
+        );
+        assert_eq!(
+            (true, true),
+            extras(&["aura", "-Qi", "--file-count", "--pulled-in-by"])
+        );
+        assert!(Args::try_parse_from(["aura", "-Q", "--pulled-in-by"]).is_err());
+    }
+
+    #[test]
+    fn query_filters() {
+        let filters = |args: &[&str]| match Args::try_parse_from(args).unwrap().subcmd {
+            SubCmd::Query(q) => q.filters(),
+            _ => panic!("Expected -Q"),
+        };
+
+        assert_eq!(QueryFilters::default(), filters(&["aura", "-Q"]));
+        assert_eq!(
+            QueryFilters {
+                explicit: true,
+                foreign: true,
+                ..Default::default()
+            },
+            filters(&["aura", "-Q", "--explicit", "--foreign"])
+        );
+        assert_eq!(
+            QueryFilters {
+                deps: true,
+                native: true,
+                unrequired: true,
+                upgrades: true,
+                ..Default::default()
+            },
+            filters(&["aura", "-Qdntu"])
+        );
+    }
+
+    #[test]
+    fn install_reasons() {
+        let parse = |op: &str, flags: &[&str]| {
+            let args = ["aura", op].iter().chain(flags).chain(&["foo"]);
+            Args::try_parse_from(args).map_err(|e| e.kind())
+        };
+
+        for op in ["-S", "-U", "-D"] {
+            assert!(parse(op, &["--asdeps"]).is_ok());
+            assert!(parse(op, &["--asexplicit"]).is_ok());
+            assert_eq!(
+                Some(clap::error::ErrorKind::ArgumentConflict),
+                parse(op, &["--asdeps", "--asexplicit"]).err()
+            );
+        }
+    }
+
+    #[test]
+    fn interactivity() {
+        assert!(resolve_interactive(false, false, true));
+        assert!(resolve_interactive(true, false, true).not());
+        assert!(resolve_interactive(false, true, true).not());
+        assert!(resolve_interactive(false, false, false).not());
+
+        let args = Args::try_parse_from(["aura", "-Au", "--non-interactive"]).unwrap();
+        assert!(args.interactive().not());
+    }
+}
@@ -2,7 +2,9 @@
 
 pub mod flags;
 
+use std::ops::Not;
 use std::str::FromStr;
+use std::time::Duration;
 use unic_langid::langid;
 use unic_langid::LanguageIdentifier;
 
@@ -22,6 +24,89 @@ impl FromStr for Date {
     }
 }
 
+impl Date {
+    /// Parse either an absolute `YYYY-MM-DD` date, or a relative one resolved
+    /// against `today`. Relative dates are `today`, `yesterday`, a count of
+    /// days, weeks, or months like `7d`, `2w`, or `1m`, or the same spelled out
+    /// like `7 days ago`.
+    pub fn resolve(s: &str, today: time::Date) -> Result<Date, String> {
+        s.parse()
+            .ok()
+            .or_else(|| relative(s.trim(), today).map(Date))
+            .ok_or_else(|| format!("Invalid date (use YYYY-MM-DD, 7d, 2w, 1m, or yesterday): {s}"))
+    }
+}
+
+/// The current local time. Falls back to UTC if the local offset can't be
+/// determined, which the `time` crate refuses to do once other threads exist.
+pub fn now_local() -> time::OffsetDateTime {
+    time::OffsetDateTime::now_local().unwrap_or_else(|e| {
+        log::warn!("Using UTC, as the local time zone is unknown: {e}");
+        time::OffsetDateTime::now_utc()
+    })
+}
+
+fn relative(s: &str, today: time::Date) -> Option<time::Date> {
+    match s {
+        "today" => return Some(today),
+        "yesterday" => return today.previous_day(),
+        _ => {}
+    }
+
+    let (n, unit) = match s.strip_suffix(" ago") {
+        Some(spelled) => spelled.split_once(' ')?,
+        None => s.split_at(s.find(|c: char| c.is_ascii_digit().not())?),
+    };
+    let n: u32 = n.trim().parse().ok()?;
+
+    match unit.trim() {
+        "d" | "day" | "days" => today.checked_sub(time::Duration::days(n.into())),
+        "w" | "week" | "weeks" => today.checked_sub(time::Duration::weeks(n.into())),
+        "m" | "month" | "months" => months_before(today, n),
+        _ => None,
+    }
+}
+
+/// The same day of the month, `n` months earlier. Days that don't exist in the
+/// earlier month are clamped to its end, such that a month before March 31st is
+/// the last day of February.
+fn months_before(date: time::Date, n: u32) -> Option<time::Date> {
+    let months =
+        date.year() * 12 + i32::from(u8::from(date.month())) - 1 - i32::try_from(n).ok()?;
+    let year = months.div_euclid(12);
+    let month = time::Month::try_from(u8::try_from(months.rem_euclid(12) + 1).ok()?).ok()?;
+    let day = date.day().min(time::util::days_in_year_month(year, month));
+
+    time::Date::from_calendar_date(year, month, day).ok()
+}
+
+/// A wrapper around [`Duration`] that parses human-friendly ages like `30d`,
+/// `12h`, or `6w`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Age(pub Duration);
+
+impl FromStr for Age {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split = s.len() - s.chars().last().map(|c| c.len_utf8()).unwrap_or(0);
+        let (num, unit) = s.split_at(split);
+        let n: u64 = num.parse().map_err(|_| format!("Invalid duration: {s}"))?;
+        let secs = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 60 * 60 * 24,
+            "w" => 60 * 60 * 24 * 7,
+            _ => return Err(format!("Unknown duration unit (use s, m, h, d, w): {s}")),
+        };
+
+        n.checked_mul(secs)
+            .map(|total| Age(Duration::from_secs(total)))
+            .ok_or_else(|| format!("Duration too large: {s}"))
+    }
+}
+
 pub const CROATIAN: LanguageIdentifier = langid!("hr-HR");
 pub const ENGLISH: LanguageIdentifier = langid!("en-US");
 pub const GERMAN: LanguageIdentifier = langid!("de-DE");
@@ -47,3 +132,77 @@ pub const VIETNAMESE: LanguageIdentifier = langid!("vi-VN");
 pub const CZECH: LanguageIdentifier = langid!("cs-CZ");
 pub const KOREAN: LanguageIdentifier = langid!("ko-KR");
 pub const HINDI: LanguageIdentifier = langid!("hi-IN");
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ages() {
+        let day = 60 * 60 * 24;
+        assert_eq!(Ok(Age(Duration::from_secs(30 * day))), "30d".parse());
+        assert_eq!(Ok(Age(Duration::from_secs(12 * 60 * 60))), "12h".parse());
+        assert_eq!(Ok(Age(Duration::from_secs(6 * 7 * day))), "6w".parse());
+        assert!("30".parse::<Age>().is_err());
+        assert!("d".parse::<Age>().is_err());
+        assert!("3y".parse::<Age>().is_err());
+        assert!("".parse::<Age>().is_err());
+    }
+
+    #[test]
+    fn relative_dates() {
+        let today = time::macros::date!(2024 - 03 - 31);
+        let resolve = |s: &str| Date::resolve(s, today).map(|d| d.0);
+
+        assert_eq!(Ok(time::macros::date!(2024 - 03 - 31)), resolve("today"));
+        assert_eq!(
+            Ok(time::macros::date!(2024 - 03 - 30)),
+            resolve("yesterday")
+        );
+        assert_eq!(Ok(time::macros::date!(2024 - 03 - 24)), resolve("7d"));
+        assert_eq!(
+            Ok(time::macros::date!(2024 - 03 - 24)),
+            resolve("7 days ago")
+        );
+        assert_eq!(Ok(time::macros::date!(2024 - 03 - 17)), resolve("2w"));
+        assert_eq!(
+            Ok(time::macros::date!(2024 - 03 - 17)),
+            resolve("2 weeks ago")
+        );
+        assert_eq!(Ok(time::macros::date!(2024 - 02 - 29)), resolve("1m"));
+        assert_eq!(
+            Ok(time::macros::date!(2024 - 02 - 29)),
+            resolve("1 month ago")
+        );
+        assert_eq!(Ok(time::macros::date!(2023 - 12 - 31)), resolve("3m"));
+        assert_eq!(Ok(time::macros::date!(2023 - 02 - 28)), resolve("13m"));
+        assert_eq!(Ok(time::macros::date!(2024 - 02 - 29)), resolve("31d"));
+        assert!(resolve("3y").is_err());
+        assert!(resolve("d").is_err());
+        assert!(resolve("soon").is_err());
+    }
+
+    #[test]
+    fn relative_month_boundaries() {
+        let new_year = time::macros::date!(2024 - 01 - 01);
+        let resolve = |s: &str| Date::resolve(s, new_year).map(|d| d.0);
+
+        assert_eq!(
+            Ok(time::macros::date!(2023 - 12 - 31)),
+            resolve("yesterday")
+        );
+        assert_eq!(Ok(time::macros::date!(2023 - 12 - 25)), resolve("1w"));
+        assert_eq!(Ok(time::macros::date!(2023 - 12 - 01)), resolve("1m"));
+    }
+
+    #[test]
+    fn absolute_dates() {
+        let today = time::macros::date!(2024 - 03 - 31);
+
+        assert_eq!(
+            Ok(time::macros::date!(2022 - 06 - 24)),
+            Date::resolve("2022-06-24", today).map(|d| d.0)
+        );
+        assert!("7d".parse::<Date>().is_err());
+    }
+}
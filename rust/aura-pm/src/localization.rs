@@ -12,6 +12,7 @@ use nonempty_collections::*;
 use rust_embed::RustEmbed;
 use std::collections::HashMap;
 use std::num::NonZeroUsize;
+use std::ops::Not;
 use unic_langid::LanguageIdentifier;
 
 pub(crate) const TRANSLATORS: &[(&str, &str)] = &[
@@ -83,6 +84,20 @@ where
     }
 }
 
+/// The language requested by the environment, if Aura ships it.
+///
+/// As with POSIX, the first non-empty variable of `LC_ALL`, `LC_MESSAGES`, and
+/// `LANG` decides. If that locale isn't one Aura has localizations for, the
+/// others aren't consulted.
+pub(crate) fn env_language() -> Option<LanguageIdentifier> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|v| std::env::var(v).ok())
+        .find(|l| l.is_empty().not())
+        .and_then(identifier_from_locale)
+        .filter(|l| available_languages().contains(l))
+}
+
 /// Convert from the format found in `/etc/locale.gen`, `locale -a`, or `LANG`
 /// to the format parsable by us to produce [`LanguageIdentifier`]s.
 pub(crate) fn code_and_country(locale: &str) -> (&str, Option<&str>) {
@@ -107,8 +122,14 @@ impl Localised for aura_core::git::Error {
     fn localise(&self, fll: &FluentLanguageLoader) -> String {
         match self {
             aura_core::git::Error::Io(_) => fl!(fll, "git-io"),
-            aura_core::git::Error::Clone(p) => fl!(fll, "git-clone", dir = p.utf8()),
-            aura_core::git::Error::Pull(p) => fl!(fll, "git-pull", dir = p.utf8()),
+            aura_core::git::Error::Clone(p, 1) => fl!(fll, "git-clone", dir = p.utf8()),
+            aura_core::git::Error::Clone(p, n) => {
+                fl!(fll, "git-clone-retried", dir = p.utf8(), n = *n)
+            }
+            aura_core::git::Error::Pull(p, 1) => fl!(fll, "git-pull", dir = p.utf8()),
+            aura_core::git::Error::Pull(p, n) => {
+                fl!(fll, "git-pull-retried", dir = p.utf8(), n = *n)
+            }
             aura_core::git::Error::Diff(p) => fl!(fll, "git-diff", file = p.utf8()),
             aura_core::git::Error::ReadHash(_) => fl!(fll, "git-hash"),
         }
@@ -250,4 +271,25 @@ mod test {
         assert_eq!(("en", Some("US")), code_and_country("en-US.UTF-8"));
         assert_eq!(("en", None), code_and_country("en"));
     }
+
+    #[test]
+    fn locale_from_env() {
+        std::env::set_var("LC_ALL", "");
+        std::env::set_var("LC_MESSAGES", "ja_JP.UTF-8");
+        std::env::set_var("LANG", "de_DE.UTF-8");
+        assert_eq!(Some(aura_pm::JAPANESE), env_language());
+
+        std::env::set_var("LC_ALL", "fr_FR.UTF-8");
+        assert_eq!(Some(aura_pm::FRENCH), env_language());
+
+        std::env::remove_var("LC_ALL");
+        std::env::remove_var("LC_MESSAGES");
+        assert_eq!(Some(aura_pm::GERMAN), env_language());
+
+        std::env::set_var("LANG", "C.UTF-8");
+        assert_eq!(None, env_language());
+
+        std::env::remove_var("LANG");
+        assert_eq!(None, env_language());
+    }
 }
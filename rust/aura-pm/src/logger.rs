@@ -0,0 +1,220 @@
+//! Setup of Aura's own logging, to the terminal and/or to a file.
+
+use aura_pm::flags::Args;
+use log::LevelFilter;
+use log::Log;
+use log::Metadata;
+use log::Record;
+use serde::Serialize;
+use simplelog::ColorChoice;
+use simplelog::CombinedLogger;
+use simplelog::Config;
+use simplelog::SharedLogger;
+use simplelog::TermLogger;
+use simplelog::TerminalMode;
+use simplelog::WriteLogger;
+use std::fs::File;
+use std::io::Write;
+use std::ops::Not;
+use std::path::Path;
+use std::sync::Mutex;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// The level used for `--aura-log-file` when no `--log-level` was given.
+const FILE_DEFAULT: LevelFilter = LevelFilter::Info;
+
+/// A single log record, as written by [`JsonLogger`].
+#[derive(Serialize)]
+struct Line<'a> {
+    timestamp: String,
+    level: &'a str,
+    target: &'a str,
+    message: String,
+}
+
+/// A logger that emits one JSON object per line.
+pub(crate) struct JsonLogger<W> {
+    level: LevelFilter,
+    out: Mutex<W>,
+}
+
+impl<W> JsonLogger<W> {
+    fn new(level: LevelFilter, out: W) -> Box<JsonLogger<W>> {
+        Box::new(JsonLogger {
+            level,
+            out: Mutex::new(out),
+        })
+    }
+}
+
+impl<W> Log for JsonLogger<W>
+where
+    W: Write + Send + 'static,
+{
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            if let (Some(line), Ok(mut out)) = (render(record), self.out.lock()) {
+                let _ = writeln!(out, "{line}");
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut out) = self.out.lock() {
+            let _ = out.flush();
+        }
+    }
+}
+
+impl<W> SharedLogger for JsonLogger<W>
+where
+    W: Write + Send + 'static,
+{
+    fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    fn config(&self) -> Option<&Config> {
+        None
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+/// Install Aura's loggers according to the CLI flags. Messages go to the
+/// terminal if `--log-level` was given, and additionally to a file if
+/// `--aura-log-file` was given.
+pub(crate) fn init(args: &Args, color: ColorChoice) {
+    let json = matches!(args.log_format.as_deref(), Some("json"));
+    let mut loggers: Vec<Box<dyn SharedLogger>> = Vec::new();
+
+    if let Some(l) = args.log_level.filter(|_| args.log_to_file_only.not()) {
+        if json {
+            loggers.push(JsonLogger::new(l, std::io::stderr()));
+        } else {
+            loggers.push(TermLogger::new(
+                l,
+                Config::default(),
+                TerminalMode::Mixed,
+                color,
+            ));
+        }
+    }
+
+    if let Some(path) = args.aura_log_file.as_deref() {
+        let level = args.log_level.unwrap_or(FILE_DEFAULT);
+
+        match file_logger(level, json, path) {
+            Ok(logger) => loggers.push(logger),
+            Err(e) => eprintln!("Failed to open {}: {e}", path.display()),
+        }
+    }
+
+    // Silently ignore logger init failure. Realistically it should never
+    // fail, since its docs claim this only occurs when a logger has been
+    // previously initialized.
+    if loggers.is_empty().not() {
+        let _ = CombinedLogger::init(loggers);
+    }
+}
+
+/// A logger that appends to the given file.
+fn file_logger(
+    level: LevelFilter,
+    json: bool,
+    path: &Path,
+) -> Result<Box<dyn SharedLogger>, std::io::Error> {
+    let file = File::options().create(true).append(true).open(path)?;
+
+    if json {
+        Ok(JsonLogger::new(level, file))
+    } else {
+        Ok(WriteLogger::new(level, Config::default(), file))
+    }
+}
+
+/// Render a log record as a single line of JSON.
+fn render(record: &Record) -> Option<String> {
+    let line = Line {
+        timestamp: OffsetDateTime::now_utc().format(&Rfc3339).ok()?,
+        level: record.level().as_str(),
+        target: record.target(),
+        message: record.args().to_string(),
+    };
+
+    serde_json::to_string(&line).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::TempDir;
+    use log::Level;
+
+    #[test]
+    fn json_record() {
+        // `format_args!` borrows temporaries, so the record can't outlive this
+        // statement.
+        let line = render(
+            &Record::builder()
+                .args(format_args!("Language: {}", "en"))
+                .level(Level::Info)
+                .target("aura")
+                .build(),
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+
+        assert_eq!("INFO", json["level"]);
+        assert_eq!("aura", json["target"]);
+        assert_eq!("Language: en", json["message"]);
+        assert!(json["timestamp"]
+            .as_str()
+            .is_some_and(|t| OffsetDateTime::parse(t, &Rfc3339).is_ok()));
+    }
+
+    fn log_to_file(json: bool) -> String {
+        let dir = TempDir::new("log");
+        let path = dir.join("aura.log");
+        let logger = file_logger(LevelFilter::Info, json, &path).unwrap();
+
+        for (level, msg) in [(Level::Info, "kept"), (Level::Debug, "dropped")] {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!("{msg}"))
+                    .level(level)
+                    .target("aura")
+                    .build(),
+            );
+        }
+
+        logger.flush();
+        std::fs::read_to_string(&path).unwrap()
+    }
+
+    #[test]
+    fn text_file() {
+        let content = log_to_file(false);
+        assert!(content.contains("kept"));
+        assert!(content.contains("dropped").not());
+    }
+
+    #[test]
+    fn json_file() {
+        let content = log_to_file(true);
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+
+        assert_eq!(1, lines.len());
+        assert_eq!("kept", lines[0]["message"]);
+    }
+}
@@ -4,7 +4,12 @@
 /// Print a coloured Aura message.
 macro_rules! aln {
     ($msg:expr) => {
-        println!("{} {} {}", "aura".bold(), "::".cyan().bold(), $msg.bold());
+        println!(
+            "{} {} {}",
+            "aura".bold(),
+            $crate::theme::Themed::header("::").bold(),
+            $msg.bold()
+        );
     };
 }
 
@@ -12,7 +17,12 @@ macro_rules! aln {
 /// Format a message according to the Aura style.
 macro_rules! a {
     ($msg:expr) => {
-        format!("{} {} {}", "aura".bold(), "::".cyan().bold(), $msg.bold())
+        format!(
+            "{} {} {}",
+            "aura".bold(),
+            $crate::theme::Themed::header("::").bold(),
+            $msg.bold()
+        )
     };
 }
 
@@ -28,70 +38,35 @@ macro_rules! aura {
 }
 
 #[macro_export]
-/// Print a localized Aura message in green, given some Fluent tag.
+/// Print a localized Aura message in the "success" colour, given some Fluent tag.
 macro_rules! green {
     ($fll:expr, $msg:expr, $($arg:expr),*) => {
-        $crate::aln!(i18n_embed_fl::fl!($fll, $msg, $($arg)*).green());
+        $crate::aln!($crate::theme::Themed::success(i18n_embed_fl::fl!($fll, $msg, $($arg)*).as_str()));
     };
     ($fll:expr, $msg:expr) => {
-        $crate::aln!(i18n_embed_fl::fl!($fll, $msg).green());
+        $crate::aln!($crate::theme::Themed::success(i18n_embed_fl::fl!($fll, $msg).as_str()));
     };
 }
 
 #[macro_export]
-/// Print a localized Aura message in yellow, given some Fluent tag.
+/// Print a localized Aura message in the "warning" colour, given some Fluent tag.
 macro_rules! yellow {
     ($fll:expr, $msg:expr, $($arg:expr),*) => {
-        $crate::aln!(i18n_embed_fl::fl!($fll, $msg, $($arg)*).yellow());
+        $crate::aln!($crate::theme::Themed::warning(i18n_embed_fl::fl!($fll, $msg, $($arg)*).as_str()));
     };
     ($fll:expr, $msg:expr) => {
-        $crate::aln!(i18n_embed_fl::fl!($fll, $msg).yellow());
+        $crate::aln!($crate::theme::Themed::warning(i18n_embed_fl::fl!($fll, $msg).as_str()));
     };
 }
 
 #[macro_export]
-/// Print a localized Aura message in red, given some Fluent tag.
+/// Print a localized Aura message in the "error" colour, given some Fluent tag.
 macro_rules! red {
     ($fll:expr, $msg:expr, $($arg:expr),*) => {
-        $crate::aln!(i18n_embed_fl::fl!($fll, $msg, $($arg)*).red());
+        $crate::aln!($crate::theme::Themed::error(i18n_embed_fl::fl!($fll, $msg, $($arg)*).as_str()));
     };
     ($fll:expr, $msg:expr) => {
-        $crate::aln!(i18n_embed_fl::fl!($fll, $msg).red());
-    };
-}
-
-#[macro_export]
-/// Detect an executable or warn if it's missing.
-///
-/// Used by the `check` module.
-macro_rules! executable {
-    ($fll:expr, $exec:expr, $msg:expr, $($arg:expr),*) => {
-        let good = which::which($exec).is_ok();
-        let symb = if good { $crate::command::check::GOOD.green() } else { $crate::command::check::BAD.red() };
-        println!(
-            "  [{}] {}",
-            symb,
-            i18n_embed_fl::fl!($fll, $msg, $($arg)*)
-        );
-
-        if !good {
-            let msg = fl!($fll, "check-missing-exec", exec = $exec.cyan().bold().to_string());
-            println!("      └─ {}", msg);
-        }
-    };
-    ($fll:expr, $exec:expr, $msg:expr) => {
-        let good = which::which($exec).is_ok();
-        let symb = if good { $crate::command::check::GOOD.green() } else { $crate::command::check::BAD.red() };
-        println!(
-            "  [{}] {}",
-            symb,
-            i18n_embed_fl::fl!($fll, $msg)
-        );
-
-        if !good {
-            let msg = fl!($fll, "check-missing-exec", exec = $exec.cyan().bold().to_string());
-            println!("      └─ {}", msg);
-        }
+        $crate::aln!($crate::theme::Themed::error(i18n_embed_fl::fl!($fll, $msg).as_str()));
     };
 }
 
@@ -35,29 +35,54 @@ pub(crate) mod env;
 pub(crate) mod error;
 pub(crate) mod fetch;
 pub(crate) mod localization;
+pub(crate) mod logger;
 mod macros;
 pub(crate) mod makepkg;
 pub(crate) mod pacman;
+pub(crate) mod satisfy;
+pub(crate) mod theme;
 pub(crate) mod utils;
 
+use crate::command::analysis;
 use crate::command::aur;
+use crate::command::aur::Installed;
 use crate::command::aur::Mode;
+use crate::command::aur::Order;
 use crate::command::cache;
 use crate::command::check;
 use crate::command::conf;
 use crate::command::deps;
+use crate::command::hold;
+use crate::command::home;
 use crate::command::logs;
+use crate::command::open;
 use crate::command::orphans;
+use crate::command::query;
+use crate::command::remove;
 use crate::command::snapshot;
 use crate::command::stats;
+use crate::command::sync;
+use crate::command::tarball;
 use crate::command::thanks;
+use crate::command::upgrade;
 use crate::error::Error;
+use crate::error::Exited;
 use crate::error::Nested;
 use crate::localization::Localised;
+use crate::pacman::Extra;
+use crate::theme::Themed;
+use aura_core::faur::SearchBy;
+use aura_pm::flags::Analysis;
 use aura_pm::flags::Args;
+use aura_pm::flags::Backup;
 use aura_pm::flags::Cache;
+use aura_pm::flags::HoldAction;
+use aura_pm::flags::QueryFilters;
 use aura_pm::flags::SubCmd;
+use aura_pm::flags::Sync;
 use aura_pm::flags::AURA_GLOBALS;
+use aura_pm::flags::FILE_COUNT;
+use aura_pm::flags::PULLED_IN_BY;
 use aura_pm::ENGLISH;
 use clap::Parser;
 use colored::Colorize;
@@ -68,23 +93,21 @@ use i18n_embed::LanguageLoader;
 use log::debug;
 use log::info;
 use simplelog::ColorChoice;
-use simplelog::Config;
-use simplelog::TermLogger;
-use simplelog::TerminalMode;
+use std::collections::BTreeSet;
 use std::ops::Not;
+use std::path::Path;
 use std::process::ExitCode;
 
 fn main() -> ExitCode {
     // Parse all CLI input. Exits immediately if invalid input is given.
     let args = aura_pm::flags::Args::parse();
 
-    // --- Terminal Logging --- //
-    if let Some(l) = args.log_level {
-        // Silently ignore logger init failure. Realistically it should never
-        // fail, since its docs claim this only occurs when a logger has been
-        // previously initialized.
-        let _ = TermLogger::init(l, Config::default(), TerminalMode::Mixed, ColorChoice::Auto);
-    }
+    // --- Colour --- //
+    let color = args.color_choice();
+    colored::control::set_override(matches!(color, ColorChoice::Always));
+
+    // --- Logging --- //
+    logger::init(&args, color);
 
     debug!("{:#?}", args);
 
@@ -93,11 +116,11 @@ fn main() -> ExitCode {
         Err(e) => {
             match localization::load(Some(ENGLISH)) {
                 Err(e1) => {
-                    aln!("Aura utterly failed to establish a runtime environment.".red());
+                    aln!("Aura utterly failed to establish a runtime environment.".error());
                     println!("\n  {}", e1);
                 }
                 Ok(fll) => {
-                    aln!(e.localise(&fll).red());
+                    aln!(e.localise(&fll).error());
                 }
             }
 
@@ -109,15 +132,17 @@ fn main() -> ExitCode {
             // --- Localisation --- //
             match localization::load(Some(env.general.language.clone())) {
                 Err(e) => {
-                    aln!("Runtime environment loaded, but failed to localise Aura!".red());
+                    aln!("Runtime environment loaded, but failed to localise Aura!".error());
                     println!("\n  {}", e);
                     ExitCode::FAILURE
                 }
                 Ok(fll) => match work(args, env, &fll) {
                     Err(e) => {
                         e.nested();
-                        aln!(e.localise(&fll).red());
-                        ExitCode::FAILURE
+                        aln!(e.localise(&fll).error());
+                        e.exit_code()
+                            .map(ExitCode::from)
+                            .unwrap_or(ExitCode::FAILURE)
                     }
                     Ok(_) => ExitCode::SUCCESS,
                 },
@@ -130,7 +155,17 @@ fn main() -> ExitCode {
 fn env(args: &Args) -> Result<Env, Error> {
     let mut env = crate::env::Env::try_new()?;
     env.reconcile_cli(args);
+
+    if args.subcmd.sysupgrade() {
+        env.hold();
+    }
+
+    if let Some((path, given)) = args.subcmd.packages_from() {
+        env.list(path, given)?;
+    }
+
     env.validate()?;
+    crate::theme::set(env.colors);
     Ok(env)
 }
 
@@ -141,93 +176,268 @@ fn work(args: Args, env: Env, fll: &FluentLanguageLoader) -> Result<(), Error> {
         // --- Pacman Commands --- //
         SubCmd::Database(d) => pacman(&env, d.needs_sudo())?,
         SubCmd::Files(f) => pacman(&env, f.needs_sudo())?,
+        SubCmd::Query(q) if q.field.is_empty().not() && q.local_info() => {
+            let alpm = env.alpm()?;
+            let extras = [
+                Extra::file_count(FILE_COUNT, |p| query::files_owned(&alpm, p)),
+                Extra::pulled_in_by(PULLED_IN_BY, |p| query::pulled_in(&alpm, "None", p)),
+            ];
+            pacman_fields(&q.field, &extras)?
+        }
+        SubCmd::Query(q) if q.field.is_empty().not() => pacman_fields(&q.field, &[])?,
+        SubCmd::Query(q) if q.local_info() && (q.file_count || q.pulled_in_by) => query::info(
+            &env.alpm()?,
+            pacman_call_args(&env),
+            q.file_count,
+            q.pulled_in_by,
+        )?,
+        SubCmd::Query(q) if q.recursive => orphans::unrequired(&env.alpm()?, &q.filters(), q.quiet),
+        SubCmd::Query(q) if q.leaves => {
+            orphans::elderly(fll, &env.alpm()?, &q.filters(), q.quiet, false, None)
+        }
+        SubCmd::Query(q) if q.fuzzy => query::fuzzy_search(&env.alpm()?, &q.packages, q.quiet),
+        SubCmd::Query(q) if q.missing => {
+            query::missing(fll, &env.alpm()?, &q.filters(), q.quiet, &q.packages)
+        }
+        SubCmd::Query(q) if q.owns.is_empty().not() => {
+            query::owns(fll, &env.alpm()?, q.quiet, &q.owns)?
+        }
+        SubCmd::Query(q) if q.changelog && (q.all || q.packages.is_empty()) => {
+            logs::changelogs(fll, &env.alpm()?, env.alpm_log())?
+        }
         SubCmd::Query(_) => pacman(&env, false)?,
+        SubCmd::Remove(r) if r.json => {
+            remove::print_json(&env.alpm()?, &r.packages, r.cascade, r.recursive)?
+        }
         SubCmd::Remove(r) => pacman(&env, r.needs_sudo())?,
-        SubCmd::Sync(s) => pacman(&env, s.needs_sudo())?,
+        SubCmd::Sync(s) if s.field.is_empty().not() => pacman_fields(&s.field, &[])?,
+        SubCmd::Sync(s) if s.json => sync::print_manifest(
+            &sync::Pacman::new(&env, pacman_call_args(&env)),
+            &env.caches(),
+        )?,
+        SubCmd::Sync(s) if s.aur => {
+            refresh(fll, &env, &s)?;
+            mirror_check(fll, &env, &s);
+            upgrade::sysupgrade(fll, &env, || sync_pacman(&env, &s))?
+        }
+        SubCmd::Sync(s) => {
+            refresh(fll, &env, &s)?;
+            mirror_check(fll, &env, &s);
+            sync_pacman(&env, &s)?
+        }
         SubCmd::DepTest(_) => pacman(&env, false)?,
+        SubCmd::Upgrade(u) if u.json => tarball::print_json(&env.alpm()?, &u.packages)?,
         SubCmd::Upgrade(u) => pacman(&env, u.needs_sudo())?,
         // --- AUR Packages --- //
-        SubCmd::Aur(a) if a.info.is_empty().not() => aur::info(fll, &a.info)?,
+        SubCmd::Aur(a) if a.info.is_empty().not() => aur::info(
+            fll,
+            &env.alpm()?,
+            &env.aur.network(),
+            env.general.relative_dates,
+            &a.info,
+        )?,
         SubCmd::Aur(a) if a.search.is_empty().not() => aur::search(
             &env.alpm()?,
-            a.abc,
+            &env.aur.network(),
+            Order::from_flags(a.abc, a.sort.as_deref()),
             env.aur.reverse,
             a.limit,
             a.quiet,
+            Installed::from_flags(a.installed, a.not_installed),
+            if a.name_only { SearchBy::Name } else { SearchBy::NameDesc },
             a.search,
         )?,
         SubCmd::Aur(a) if a.provides.is_some() => aur::provides(
             &env.alpm()?,
-            a.abc,
+            &env.aur.network(),
+            Order::from_flags(a.abc, a.sort.as_deref()),
             a.reverse,
             a.limit,
             a.quiet,
+            Installed::from_flags(a.installed, a.not_installed),
             a.provides.unwrap(),
         )?,
         SubCmd::Aur(a) if a.open.is_some() => aur::open(&a.open.unwrap())?,
-        SubCmd::Aur(a) if a.pkgbuild.is_some() => {
-            aur::pkgbuild(&a.pkgbuild.unwrap(), &env.aur.clones)?
+        SubCmd::Aur(a) if a.pkgbuild.is_empty().not() => {
+            let (clones, net) = (&env.aur.clones, &env.aur.network());
+            aur::pkgbuilds(env.general.interactive, &a.pkgbuild, clones, net)?
+        }
+        SubCmd::Aur(a) if a.wclone.is_empty().not() => {
+            aur::clone_aur_repos(fll, &env.aur.network().git(), &a.wclone)?
+        }
+        SubCmd::Aur(a) if a.validate => {
+            aur::validate(fll, &env.aur.clones, &env.aur.network(), &a.packages)?
+        }
+        SubCmd::Aur(a) if a.retry_failed => aur::retry_failed(fll, &env)?,
+        SubCmd::Aur(a) if a.sysupgrade => {
+            aur::upgrade(fll, &env.alpm()?, &env, a.dryrun)?;
+        }
+        SubCmd::Aur(a) if a.refresh => {
+            aur::refresh(fll, &env.aur.clones, &env.aur.network().git())?
         }
-        SubCmd::Aur(a) if a.wclone.is_empty().not() => aur::clone_aur_repos(fll, &a.wclone)?,
-        SubCmd::Aur(a) if a.sysupgrade => aur::upgrade(fll, &env.alpm()?, env, a.dryrun)?,
-        SubCmd::Aur(a) if a.refresh => aur::refresh(fll, &env.aur.clones)?,
         SubCmd::Aur(a) => aur::install(
             fll,
             &env,
             Mode::Install,
-            a.packages.iter().map(|s| s.as_str()),
+            a.packages.iter().chain(&env.listed).map(|s| s.as_str()),
         )?,
         // --- Package Sets --- //
         SubCmd::Backup(b) if b.clean => snapshot::clean(fll, &env)?,
         SubCmd::Backup(b) if b.list => snapshot::list(&env.backups.snapshots)?,
-        SubCmd::Backup(b) if b.restore => snapshot::restore(&env, fll, &env.alpm()?)?,
-        SubCmd::Backup(_) => snapshot::save(fll, &env.alpm()?, &env.backups.snapshots)?,
+        SubCmd::Backup(Backup {
+            restore: Some(id), ..
+        }) => snapshot::restore(&env, fll, &env.alpm()?, id.as_deref())?,
+        SubCmd::Backup(b) => {
+            snapshot::save(fll, &env.alpm()?, &env.backups.snapshots, b.save.as_deref())?
+        }
         // --- Cache Management --- //
+        SubCmd::Cache(c) if !c.info.is_empty() && c.machinereadable => {
+            cache::info_records(&env.alpm()?, &env.caches(), c.info)?
+        }
         SubCmd::Cache(c) if !c.info.is_empty() => {
             cache::info(fll, &env.alpm()?, &env.caches(), c.info)?
         }
-        SubCmd::Cache(c) if c.search.is_some() => cache::search(&env.caches(), &c.search.unwrap())?,
-        SubCmd::Cache(c) if c.backup.is_some() => cache::backup(fll, &env, &c.backup.unwrap())?,
+        SubCmd::Cache(c) if c.versions.is_empty().not() => {
+            cache::versions(fll, &env.alpm()?, &env.caches(), &c.versions)?
+        }
+        SubCmd::Cache(c) if c.search.is_some() => {
+            cache::search(&env.caches(), &c.search.unwrap(), c.fuzzy)?
+        }
+        SubCmd::Cache(c) if c.backup.is_some() => {
+            cache::backup(fll, &env, &c.backup.unwrap(), c.verify_backup)?
+        }
         SubCmd::Cache(Cache { clean: Some(n), .. }) => cache::clean(&env, fll, n)?,
+        SubCmd::Cache(Cache {
+            clean_older_than: Some(age),
+            dry_run,
+            ..
+        }) => cache::clean_older_than(&env, fll, age.0, dry_run)?,
         SubCmd::Cache(c) if c.clean_unsaved => cache::clean_not_saved(fll, &env)?,
-        SubCmd::Cache(c) if c.invalid => cache::invalid(&env, fll, &env.alpm()?, &env.caches())?,
+        SubCmd::Cache(c) if c.verify => {
+            let jobs = c.jobs.unwrap_or(env.general.cpus);
+            let pool = env.alpm_pool_of(jobs)?;
+            cache::verify(&env, fll, &pool, &env.caches(), jobs, c.invalid)?
+        }
+        SubCmd::Cache(c) if c.invalid => {
+            let jobs = c.jobs.unwrap_or(env.general.cpus);
+            let pool = env.alpm_pool_of(jobs)?;
+            cache::invalid(&env, fll, &pool, &env.caches(), jobs)?
+        }
+        SubCmd::Cache(c) if c.list && c.machinereadable => cache::list_records(&env.caches())?,
         SubCmd::Cache(c) if c.list => cache::list(&env.caches())?,
         SubCmd::Cache(c) if c.refresh => cache::refresh(&env, fll, &env.alpm()?)?,
         SubCmd::Cache(c) if c.missing => cache::missing(&env.alpm()?, &env.caches()),
         SubCmd::Cache(c) => cache::downgrade(&env, fll, c.packages)?,
         // --- Logs --- //
-        SubCmd::Log(l) if l.search.is_some() => logs::search(env.alpm_log(), l.search.unwrap())?,
-        SubCmd::Log(l) if !l.info.is_empty() => logs::info(fll, env.alpm_log(), l.info)?,
+        SubCmd::Log(l) if l.format.as_deref() == Some("csv") => logs::history(
+            env.alpm_log(),
+            &l.info,
+            l.search.as_deref(),
+            l.before,
+            l.after,
+            logs::Format::Csv,
+        )?,
+        SubCmd::Log(l) if l.format.as_deref() == Some("json") => logs::history(
+            env.alpm_log(),
+            &l.info,
+            l.search.as_deref(),
+            l.before,
+            l.after,
+            logs::Format::Json,
+        )?,
+        SubCmd::Log(l) if l.search.is_some() => {
+            logs::search(fll, env.alpm_log(), l.search.unwrap())?
+        }
+        SubCmd::Log(l) if !l.info.is_empty() => {
+            logs::info(fll, env.alpm_log(), env.general.relative_dates, l.info)?
+        }
         SubCmd::Log(l) => logs::view(env.alpm_log(), l.before, l.after)?,
         // --- Orphan Packages --- //
-        SubCmd::Orphans(o) if o.abandon => orphans::remove(&env, &env.alpm()?, fll)?,
+        SubCmd::Orphans(o) if o.abandon => orphans::remove(&env, &env.alpm()?, fll, o.dry_run)?,
         SubCmd::Orphans(o) if !o.adopt.is_empty() => {
             orphans::adopt(&env, &env.alpm()?, fll, o.adopt)?
         }
-        SubCmd::Orphans(o) if o.elderly => orphans::elderly(&env.alpm()?),
+        SubCmd::Orphans(o) if !o.disown.is_empty() => {
+            orphans::disown(&env, &env.alpm()?, fll, o.disown)?
+        }
+        SubCmd::Orphans(o) if o.elderly => {
+            let filters = QueryFilters::default();
+            orphans::elderly(fll, &env.alpm()?, &filters, false, o.tree, o.depth)
+        }
         SubCmd::Orphans(_) => orphans::list(&env.alpm()?),
         // --- PKGBUILD Analysis --- //
-        // SubCmd::Analysis(_) => unimplemented!(),
+        SubCmd::Analysis(a) if a.audit => analysis::audit(&env, fll, &env.alpm()?)?,
+        SubCmd::Analysis(Analysis { file: Some(f), .. }) => analysis::file(fll, Path::new(&f))?,
+        SubCmd::Analysis(Analysis { dir: Some(d), .. }) => {
+            analysis::file(fll, &Path::new(&d).join("PKGBUILD"))?
+        }
+        SubCmd::Analysis(_) => analysis::stdin(fll)?,
         // --- Configuration --- //
-        SubCmd::Conf(c) if c.pacman => conf::open_pacman_conf()?,
-        SubCmd::Conf(c) if c.aura => conf::open_aura_conf()?,
-        SubCmd::Conf(c) if c.makepkg => conf::open_makepkg_conf()?,
-        SubCmd::Conf(c) if c.gen => conf::gen(&env)?,
+        SubCmd::Conf(c) if c.pacman => conf::open_pacman_conf(&env)?,
+        SubCmd::Conf(c) if c.aura => conf::open_aura_conf(&env)?,
+        SubCmd::Conf(c) if c.makepkg => conf::open_makepkg_conf(&env)?,
+        SubCmd::Conf(c) if c.gen => conf::gen(fll, &env, c.output.as_deref(), c.force)?,
+        SubCmd::Conf(c) if c.template => conf::template()?,
+        SubCmd::Conf(c) if c.edit => conf::edit(fll, &env)?,
+        SubCmd::Conf(c) if c.new => conf::new(fll),
         SubCmd::Conf(_) => conf::general(&env),
+        // --- Declarative Environment --- //
+        SubCmd::Home(h) if h.check => home::check(fll, &env.alpm()?)?,
+        SubCmd::Home(h) if h.sync => home::sync(&env, fll, &env.alpm()?)?,
+        SubCmd::Home(h) if h.dump => home::dump(&env, fll, &env.alpm()?)?,
+        SubCmd::Home(_) => home::status(fll, &env.alpm()?)?,
+        SubCmd::Hold(h) => match h.action {
+            Some(HoldAction::Add { packages }) => hold::add(fll, &packages)?,
+            Some(HoldAction::Remove { packages }) => hold::remove(fll, &packages)?,
+            Some(HoldAction::List) | None => hold::list(&env),
+        },
         // --- Statistics --- //
-        SubCmd::Stats(s) if s.lang => stats::localization()?,
-        SubCmd::Stats(s) if s.heavy => stats::heavy_packages(&env.alpm()?),
-        SubCmd::Stats(s) if s.groups => stats::groups(&env.alpm()?),
-        SubCmd::Stats(_) => stats::stats(&env, fll)?,
-        // --- Dependency Management --- //
-        SubCmd::Deps(d) if d.reverse => {
-            deps::reverse(&env.alpm()?, d.limit, d.optional, d.raw, d.open, d.packages)?
+        SubCmd::Stats(s) if s.lang => stats::localization(s.json)?,
+        SubCmd::Stats(s) if s.heavy.is_some() && s.by_group => {
+            stats::heavy_groups(fll, &env.alpm()?, s.reverse, s.json)?
         }
-        SubCmd::Deps(d) => {
-            deps::graph(&env.alpm()?, d.limit, d.optional, d.raw, d.open, d.packages)?
+        SubCmd::Stats(s) if s.heavy.is_some() => {
+            stats::heavy_packages(&env.alpm()?, s.heavy_count(), s.reverse, s.json)?
         }
+        SubCmd::Stats(s) if s.groups => stats::groups(fll, &env.alpm()?, s.quiet, s.json)?,
+        SubCmd::Stats(s) if s.cache => stats::cache(&env, fll, s.keep, s.json)?,
+        SubCmd::Stats(s) => stats::stats(&env, fll, s.json)?,
+        // --- Dependency Management --- //
+        SubCmd::Deps(d) if d.roots || d.leaves => deps::ends(
+            fll,
+            &env.alpm()?,
+            d.reverse,
+            d.limit,
+            d.optional,
+            d.roots,
+            d.leaves,
+            d.packages,
+        ),
+        SubCmd::Deps(d) if d.reverse => deps::reverse(
+            fll,
+            &env.alpm()?,
+            d.limit,
+            d.optional,
+            d.raw,
+            d.open,
+            d.output,
+            d.packages,
+        )?,
+        SubCmd::Deps(d) => deps::graph(
+            fll,
+            &env.alpm()?,
+            d.limit,
+            d.optional,
+            d.raw,
+            d.open,
+            d.output,
+            d.packages,
+        )?,
+        // --- Opening Webpages --- //
+        SubCmd::Open(o) => open::open(&env, &o)?,
         // --- System Validation --- //
-        SubCmd::Check(_) => check::check(fll, &env).map_err(Error::Check)?,
+        SubCmd::Check(c) if c.pacnew => check::pacnew(fll, c.quiet).map_err(Error::Check)?,
+        SubCmd::Check(c) => check::check(fll, &env, c.quiet).map_err(Error::Check)?,
         // --- Credits --- //
         SubCmd::Thanks => thanks::thanks(fll),
         // --- Free Software --- //
@@ -240,20 +450,7 @@ fn work(args: Args, env: Env, fll: &FluentLanguageLoader) -> Result<(), Error> {
 
 /// Run a Pacman command.
 fn pacman(env: &Env, sudo: bool) -> Result<(), crate::pacman::Error> {
-    let mut raws: Vec<String> = std::env::args()
-        .skip(1)
-        .filter(|a| !(AURA_GLOBALS.contains(&a.as_str()) || a.starts_with("--log-level=")))
-        .collect();
-
-    // Special consideration for split cases like `--log-level debug`.
-    if let Some(ix) = raws
-        .iter()
-        .enumerate()
-        .find_map(|(i, v)| (v == "--log-level").then_some(i))
-    {
-        raws.remove(ix); // --log-level
-        raws.remove(ix); // Its argument.
-    }
+    let raws = pacman_call_args(env);
 
     debug!("Passing to Pacman: {:?}", raws);
     if sudo {
@@ -262,3 +459,255 @@ fn pacman(env: &Env, sudo: bool) -> Result<(), crate::pacman::Error> {
         pacman::pacman(raws)
     }
 }
+
+/// With `-y`, refresh the sync databases concurrently, if Aura can do so in
+/// Pacman's place.
+fn refresh(fll: &FluentLanguageLoader, env: &Env, s: &Sync) -> Result<(), Error> {
+    if s.own_refresh().not() {
+        Ok(())
+    } else if env.pacman.xfer_command.is_empty() {
+        sync::refresh(fll, env, s.refresh > 1).map_err(Error::from)
+    } else {
+        // Pacman must download with the user's own program, but still ahead of
+        // the rest of the command, such that the mirror check sees the result.
+        let mut raws = vec!["-S".to_string()];
+        raws.extend((0..s.refresh).map(|_| "--refresh".to_string()));
+        no_color_for_pacman(&mut raws, aura_pm::flags::no_color());
+
+        pacman::sudo_pacman_batch(env, raws).map_err(Error::from)
+    }
+}
+
+/// Before a `-Su`, warn if the sync databases look stale. Databases of some
+/// other system aren't the ones Aura can see, so they go unchecked.
+fn mirror_check(fll: &FluentLanguageLoader, env: &Env, s: &Sync) {
+    if s.upgrades() && s.no_mirror_check.not() && s.elsewhere().not() {
+        sync::warn_stale(fll, env);
+    }
+}
+
+/// Run a `-S` through Pacman, minus any refresh that has already been done.
+fn sync_pacman(env: &Env, s: &Sync) -> Result<(), crate::pacman::Error> {
+    if s.own_refresh().not() {
+        pacman(env, s.needs_sudo())
+    } else if s.only_refresh() {
+        Ok(())
+    } else {
+        let raws = sync::unrefreshed(&pacman_call_args(env));
+
+        debug!("Passing to Pacman: {:?}", raws);
+        pacman::sudo_pacman_batch(env, raws)
+    }
+}
+
+/// The original CLI arguments, as Pacman should see them.
+fn pacman_call_args(env: &Env) -> Vec<String> {
+    let mut raws = pacman_args();
+
+    // Pacman would otherwise wait for an answer that never comes.
+    if env.general.interactive.not() && raws.iter().any(|a| a == "--noconfirm").not() {
+        raws.push("--noconfirm".to_string());
+    }
+
+    no_color_for_pacman(&mut raws, aura_pm::flags::no_color());
+    holds_for_pacman(&mut raws, &env.holds);
+    raws.extend(env.listed.iter().cloned());
+
+    raws
+}
+
+/// Pacman doesn't know about `NO_COLOR` itself, so unless the user chose a
+/// `--color` explicitly, tell it.
+fn no_color_for_pacman(raws: &mut Vec<String>, no_color: bool) {
+    if no_color && raws.iter().any(|a| a.starts_with("--color")).not() {
+        raws.push("--color".to_string());
+        raws.push("never".to_string());
+    }
+}
+
+/// Packages held by `aura hold` are ignored by Pacman too.
+fn holds_for_pacman(raws: &mut Vec<String>, holds: &BTreeSet<String>) {
+    if holds.is_empty().not() {
+        let held: Vec<_> = holds.iter().map(|h| h.as_str()).collect();
+        raws.push("--ignore".to_string());
+        raws.push(held.join(","));
+    }
+}
+
+/// Run `pacman -Si` or `-Qi`, but only display certain fields.
+fn pacman_fields(fields: &[String], extras: &[Extra]) -> Result<(), crate::pacman::Error> {
+    let raws = pacman_args();
+
+    debug!("Passing to Pacman: {:?}", raws);
+    pacman::pacman_fields(raws, fields, extras)
+}
+
+/// The original CLI arguments, minus any that only Aura understands.
+fn pacman_args() -> Vec<String> {
+    strip_aura_args(std::env::args().skip(1))
+}
+
+/// Remove Aura-only flags from some CLI arguments. Everything else, including
+/// repeated flags like `--ignore`, is kept as-is and in order.
+fn strip_aura_args<I>(args: I) -> Vec<String>
+where
+    I: IntoIterator<Item = String>,
+{
+    // Aura's own flags that appear alongside Pacman's.
+    let own = [
+        "--aur",
+        "--json",
+        "--no-mirror-check",
+        "--file-count",
+        "--pulled-in-by",
+    ];
+    let mut raws: Vec<String> = args
+        .into_iter()
+        .filter(|a| !AURA_GLOBALS.contains(&a.as_str()) && !own.contains(&a.as_str()))
+        .collect();
+
+    for flag in [
+        "--log-level",
+        "--log-format",
+        "--aura-log-file",
+        "--field",
+        "--refresh-jobs",
+        "--packages-from",
+    ] {
+        remove_with_value(&mut raws, flag);
+    }
+
+    raws
+}
+
+/// Remove every occurrence of a flag and its value, whether given as
+/// `--flag=value` or split as `--flag value`.
+fn remove_with_value(raws: &mut Vec<String>, flag: &str) {
+    let joined = format!("{flag}=");
+    raws.retain(|a| a.starts_with(&joined).not());
+
+    while let Some(ix) = raws.iter().position(|v| v == flag) {
+        raws.remove(ix); // The flag.
+        if ix < raws.len() {
+            raws.remove(ix); // Its argument.
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::TempDir;
+
+    #[test]
+    fn stripped_args() {
+        let args = [
+            "-Syu",
+            "--ignore",
+            "linux",
+            "--log-level",
+            "debug",
+            "--ignore=firefox",
+            "--english",
+            "--ignoregroup",
+            "plasma",
+            "--ignoregroup",
+            "xorg",
+            "--logfile",
+            "/tmp/pacman.log",
+        ];
+        let expected = vec![
+            "-Syu",
+            "--ignore",
+            "linux",
+            "--ignore=firefox",
+            "--ignoregroup",
+            "plasma",
+            "--ignoregroup",
+            "xorg",
+            "--logfile",
+            "/tmp/pacman.log",
+        ];
+
+        assert_eq!(expected, strip_aura_args(args.map(String::from)));
+    }
+
+    #[test]
+    fn stripped_aur_flag() {
+        let args = ["-Syu", "--aur", "--ignore", "linux"];
+        let expected = vec!["-Syu", "--ignore", "linux"];
+
+        assert_eq!(expected, strip_aura_args(args.map(String::from)));
+
+        let args = ["-Syu", "--no-mirror-check"];
+        let expected = vec!["-Syu"];
+
+        assert_eq!(expected, strip_aura_args(args.map(String::from)));
+
+        let args = ["-Sw", "--json", "glibc"];
+        let expected = vec!["-Sw", "glibc"];
+
+        assert_eq!(expected, strip_aura_args(args.map(String::from)));
+
+        let args = ["-Syu", "--refresh-jobs", "8", "--refresh-jobs=2"];
+        let expected = vec!["-Syu"];
+
+        assert_eq!(expected, strip_aura_args(args.map(String::from)));
+
+        let args = [
+            "-S",
+            "--packages-from",
+            "-",
+            "bat",
+            "--packages-from=pkgs.txt",
+        ];
+        let expected = vec!["-S", "bat"];
+
+        assert_eq!(expected, strip_aura_args(args.map(String::from)));
+    }
+
+    #[test]
+    fn no_color_reaches_pacman() {
+        let mut raws = vec!["-Syu".to_string()];
+        no_color_for_pacman(&mut raws, false);
+        assert_eq!(vec!["-Syu"], raws);
+
+        no_color_for_pacman(&mut raws, true);
+        assert_eq!(vec!["-Syu", "--color", "never"], raws);
+
+        let mut raws = vec!["-Syu".to_string(), "--color=always".to_string()];
+        no_color_for_pacman(&mut raws, true);
+        assert_eq!(vec!["-Syu", "--color=always"], raws);
+    }
+
+    #[test]
+    fn holds_reach_pacman() {
+        let mut raws = vec!["-Syu".to_string()];
+        holds_for_pacman(&mut raws, &BTreeSet::new());
+        assert_eq!(vec!["-Syu"], raws);
+
+        let holds = BTreeSet::from(["nvidia".to_string(), "linux".to_string()]);
+        holds_for_pacman(&mut raws, &holds);
+        assert_eq!(vec!["-Syu", "--ignore", "linux,nvidia"], raws);
+    }
+
+    #[test]
+    fn custom_dbpath_sudo() {
+        let sudo = |args: &[&str]| match Args::try_parse_from(args).unwrap().subcmd {
+            SubCmd::Files(f) => f.needs_sudo(),
+            SubCmd::Sync(s) => s.needs_sudo(),
+            SubCmd::Database(d) => d.needs_sudo(),
+            _ => panic!("Expected -F, -S, or -D"),
+        };
+
+        // Pacman refuses to write anywhere unless it's run as root, even to
+        // databases owned by whoever is running Aura.
+        let dir = TempDir::new("dbpath");
+        let db = dir.to_str().unwrap();
+        assert!(sudo(&["aura", "-Fy", "--dbpath", db]));
+        assert!(sudo(&["aura", "-Sy", "--dbpath", db]));
+        assert!(sudo(&["aura", "-D", "--asdeps", "--dbpath", db, "foo"]));
+        assert!(sudo(&["aura", "-F", "--dbpath", db, "foo"]).not());
+        assert!(sudo(&["aura", "-Ss", "--dbpath", db, "foo"]).not());
+    }
+}
@@ -1,27 +1,32 @@
 //! Sugar for interacting with Pacman.
 
 use crate::env::Env;
+use crate::error::Exited;
 use crate::error::Nested;
 use crate::localization::Localised;
+use crate::utils::Succeeded;
+use aura_pm::flags::INFO_FIELDS;
 use i18n_embed_fl::fl;
 use log::error;
 use std::ffi::OsStr;
+use std::ops::Not;
 use std::process::Command;
+use std::process::Stdio;
 
 pub(crate) enum Error {
     ExternalCmd(std::io::Error),
-    InstallFromTarball,
-    InstallFromRepos,
-    Misc,
+    InstallFromTarball(Option<u8>),
+    InstallFromRepos(Option<u8>),
+    Exit(u8),
 }
 
 impl Nested for Error {
     fn nested(&self) {
         match self {
             Error::ExternalCmd(e) => error!("{e}"),
-            Error::InstallFromTarball => {}
-            Error::InstallFromRepos => {}
-            Error::Misc => {}
+            Error::InstallFromTarball(_) => {}
+            Error::InstallFromRepos(_) => {}
+            Error::Exit(_) => {}
         }
     }
 }
@@ -30,9 +35,20 @@ impl Localised for Error {
     fn localise(&self, fll: &i18n_embed::fluent::FluentLanguageLoader) -> String {
         match self {
             Error::ExternalCmd(_) => fl!(fll, "pacman-external"),
-            Error::InstallFromTarball => fl!(fll, "pacman-u"),
-            Error::InstallFromRepos => fl!(fll, "pacman-s"),
-            Error::Misc => fl!(fll, "pacman-misc"),
+            Error::InstallFromTarball(_) => fl!(fll, "pacman-u"),
+            Error::InstallFromRepos(_) => fl!(fll, "pacman-s"),
+            Error::Exit(_) => fl!(fll, "pacman-misc"),
+        }
+    }
+}
+
+impl Exited for Error {
+    fn exit_code(&self) -> Option<u8> {
+        match self {
+            Error::ExternalCmd(_) => None,
+            Error::InstallFromTarball(code) => *code,
+            Error::InstallFromRepos(code) => *code,
+            Error::Exit(code) => Some(*code),
         }
     }
 }
@@ -47,9 +63,217 @@ where
         .args(args)
         .status()
         .map_err(Error::ExternalCmd)?
-        .success()
-        .then_some(())
-        .ok_or(Error::Misc)
+        .succeeded()
+        .map_err(Error::Exit)
+}
+
+/// The label of the "Installed Size" field of `-Qi` output, as Pacman gives it
+/// without localisation.
+const SIZE_FIELD: &str = "Installed Size";
+
+/// The label of the "Install Reason" field of `-Qi` output.
+const REASON_FIELD: &str = "Install Reason";
+
+/// Some field's value for a package, given its name.
+type Value<'a> = Box<dyn Fn(&str) -> Option<String> + 'a>;
+
+/// A field that Aura adds to each package of `-Qi` output.
+pub(crate) struct Extra<'a> {
+    /// The label of the Pacman field that this one follows.
+    after: &'static str,
+    label: &'a str,
+    /// The field's value for some package, if it has one.
+    value: Value<'a>,
+}
+
+impl<'a> Extra<'a> {
+    /// What pulled in a package installed as a dependency, following its
+    /// install reason.
+    pub(crate) fn pulled_in_by<F>(label: &'a str, value: F) -> Extra<'a>
+    where
+        F: Fn(&str) -> Option<String> + 'a,
+    {
+        Extra {
+            after: REASON_FIELD,
+            label,
+            value: Box::new(value),
+        }
+    }
+
+    /// How many files a package owns, following its installed size.
+    pub(crate) fn file_count<F>(label: &'a str, value: F) -> Extra<'a>
+    where
+        F: Fn(&str) -> Option<String> + 'a,
+    {
+        Extra {
+            after: SIZE_FIELD,
+            label,
+            value: Box::new(value),
+        }
+    }
+}
+
+/// Make a shell call to `pacman -Qi`, adding the given fields to each package.
+/// These must be labelled in English, as Pacman's own fields will be.
+pub(crate) fn pacman_info<I, S>(args: I, extras: &[Extra]) -> Result<(), Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    // Pacman's fields are found by their labels, so these mustn't be
+    // localised.
+    let output = Command::new("pacman")
+        .args(args)
+        .env("LC_ALL", "C")
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(Error::ExternalCmd)?;
+
+    // Unknown packages fail the call, but the others are still shown.
+    print!(
+        "{}",
+        with_extras(&String::from_utf8_lossy(&output.stdout), extras)
+    );
+
+    output.status.succeeded().map_err(Error::Exit)
+}
+
+/// Make a shell call to `pacman -Si` or `-Qi`, but only display the requested
+/// fields of each package. For `-Qi`, these can include the given `extras`,
+/// which must be labelled in English.
+pub(crate) fn pacman_fields<I, S>(args: I, fields: &[String], extras: &[Extra]) -> Result<(), Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    // Field labels are localised by Pacman, so we force them to be English in
+    // order to find the ones we want.
+    let output = Command::new("pacman")
+        .args(args)
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(Error::ExternalCmd)?;
+
+    output.status.succeeded().map_err(Error::Exit)?;
+
+    let labels: Vec<&str> = fields
+        .iter()
+        .filter_map(|f| INFO_FIELDS.iter().find(|(name, _)| name == f))
+        .map(|(_, label)| *label)
+        .collect();
+
+    let output = with_extras(&String::from_utf8_lossy(&output.stdout), extras);
+    print!("{}", select_fields(&output, &labels));
+
+    Ok(())
+}
+
+/// Given the output of `pacman -Qi`, add each extra field to the packages it
+/// has something to say about, aligned with Pacman's own fields.
+fn with_extras(output: &str, extras: &[Extra]) -> String {
+    let mut annotated = String::new();
+
+    for block in output.split("\n\n").filter(|b| b.trim().is_empty().not()) {
+        let fields = fields_of(block);
+        let first = fields
+            .first()
+            .and_then(|(_, lines)| lines.first())
+            .map(|line| plain(line));
+        let name = first
+            .as_deref()
+            .and_then(|line| line.split_once(": "))
+            .map(|(_, name)| name.trim());
+
+        for (label, lines) in fields.iter() {
+            for line in lines {
+                annotated.push_str(line);
+                annotated.push('\n');
+            }
+
+            let width = lines
+                .first()
+                .map(|line| plain(line))
+                .and_then(|line| line.split_once(':').map(|(l, _)| l.chars().count()))
+                .unwrap_or(0);
+
+            for extra in extras.iter().filter(|e| e.after == label.as_str()) {
+                if let Some(value) = name.and_then(|n| (extra.value)(n)) {
+                    let label = extra.label;
+                    annotated.push_str(&format!("{label:width$}: {value}\n"));
+                }
+            }
+        }
+
+        annotated.push('\n');
+    }
+
+    annotated
+}
+
+/// Given the output of `pacman -Si` or `-Qi`, retain only the given fields of
+/// each package, in the order given.
+fn select_fields(output: &str, labels: &[&str]) -> String {
+    let mut selected = String::new();
+
+    for block in output.split("\n\n").filter(|b| b.trim().is_empty().not()) {
+        let fields = fields_of(block);
+
+        for label in labels {
+            if let Some((_, lines)) = fields.iter().find(|(l, _)| l == label) {
+                for line in lines {
+                    selected.push_str(line);
+                    selected.push('\n');
+                }
+            }
+        }
+
+        selected.push('\n');
+    }
+
+    selected
+}
+
+/// Each field of a package in `-Si` or `-Qi` output, labelled and paired with
+/// all of its lines. Values that span multiple lines are continued with leading
+/// whitespace.
+fn fields_of(block: &str) -> Vec<(String, Vec<&str>)> {
+    let mut fields: Vec<(String, Vec<&str>)> = Vec::new();
+
+    for line in block.lines() {
+        match fields.last_mut() {
+            Some((_, lines)) if line.starts_with(char::is_whitespace) => lines.push(line),
+            _ => {
+                let label = plain(line)
+                    .split_once(':')
+                    .map(|(l, _)| l.trim().to_string())
+                    .unwrap_or_default();
+                fields.push((label, vec![line]));
+            }
+        }
+    }
+
+    fields
+}
+
+/// A line of Pacman's output without its colours, as given by `--color always`.
+fn plain(line: &str) -> String {
+    let mut plain = String::with_capacity(line.len());
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip the whole escape sequence, which ends with a letter.
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            plain.push(c);
+        }
+    }
+
+    plain
 }
 
 /// Make an elevated shell call to `pacman`.
@@ -72,9 +296,8 @@ where
         .args(args)
         .status()
         .map_err(Error::ExternalCmd)?
-        .success()
-        .then_some(())
-        .ok_or(Error::Misc)
+        .succeeded()
+        .map_err(Error::Exit)
 }
 
 /// Make an elevated shell call to `pacman`, passing all arguments to pacman as-is.
@@ -88,9 +311,45 @@ where
         .args(args)
         .status()
         .map_err(Error::ExternalCmd)?
-        .success()
-        .then_some(())
-        .ok_or(Error::Misc)
+        .succeeded()
+        .map_err(Error::Exit)
+}
+
+/// Like [`sudo_pacman_batch`], but with Pacman's own output sent to stderr,
+/// leaving stdout free for Aura's.
+pub(crate) fn sudo_pacman_to_stderr<I, S>(env: &Env, args: I) -> Result<(), Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    Command::new(env.sudo())
+        .arg("pacman")
+        .args(args)
+        .stdout(std::io::stderr())
+        .status()
+        .map_err(Error::ExternalCmd)?
+        .succeeded()
+        .map_err(Error::Exit)
+}
+
+/// Make a shell call to `pacman` and collect the non-empty lines it prints.
+pub(crate) fn pacman_lines<I, S>(args: I) -> Result<Vec<String>, Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let output = Command::new("pacman")
+        .args(args)
+        .output()
+        .map_err(Error::ExternalCmd)?;
+
+    output.status.succeeded().map_err(Error::Exit)?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| l.trim().is_empty().not())
+        .map(|l| l.to_string())
+        .collect())
 }
 
 /// Call `sudo pacman -U`.
@@ -105,7 +364,7 @@ where
     S: AsRef<OsStr>,
     T: AsRef<OsStr>,
 {
-    sudo_pacman(env, "-U", flags, args).map_err(|_| Error::InstallFromTarball)
+    sudo_pacman(env, "-U", flags, args).map_err(|e| Error::InstallFromTarball(e.exit_code()))
 }
 
 /// Call `sudo pacman -S`.
@@ -120,5 +379,124 @@ where
     S: AsRef<OsStr>,
     T: AsRef<OsStr>,
 {
-    sudo_pacman(env, "-S", flags, args).map_err(|_| Error::InstallFromRepos)
+    sudo_pacman(env, "-S", flags, args).map_err(|e| Error::InstallFromRepos(e.exit_code()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aura_pm::flags::FILE_COUNT;
+    use aura_pm::flags::PULLED_IN_BY;
+
+    #[test]
+    fn field_selection() {
+        let output = std::fs::read_to_string("tests/pacman-qi.txt").unwrap();
+        let exp = "\
+Depends On      : curl  expat  grep  openssl  pcre2  perl  perl-error
+                  perl-mailtools  shadow  zlib-ng-compat
+Version         : 2.43.0-1
+
+Depends On      : glibc
+Version         : 1.2.1-1
+
+";
+        let res = select_fields(&output, &["Depends On", "Version"]);
+        assert_eq!(exp, res);
+
+        let exp = "Name            : git\n\nName            : nano\n\n";
+        let res = select_fields(&output, &["Name"]);
+        assert_eq!(exp, res);
+    }
+
+    #[test]
+    fn pulled_in_by_field() {
+        let output = std::fs::read_to_string("tests/pacman-qi-deps.txt").unwrap();
+        let pulled = |name: &str| match name {
+            "libxml2" => Some("calibre".to_string()),
+            _ => Some("None".to_string()),
+        };
+        let res = with_extras(&output, &[Extra::pulled_in_by(PULLED_IN_BY, pulled)]);
+
+        // Every package gains the field, right after its reason.
+        assert_eq!(2, res.matches(PULLED_IN_BY).count());
+        assert!(res.contains(
+            "Install Reason  : Installed as a dependency for another package
+Pulled In By    : calibre
+Install Script  : No
+"
+        ));
+
+        let res = select_fields(&res, &["Name", "Install Reason", PULLED_IN_BY]);
+        let exp = "\
+Name            : libxml2
+Install Reason  : Installed as a dependency for another package
+Pulled In By    : calibre
+
+Name            : calibre
+Install Reason  : Explicitly installed
+Pulled In By    : None
+
+";
+        assert_eq!(exp, res);
+    }
+
+    #[test]
+    fn coloured_extras() {
+        // As Pacman colours its labels with `--color always`.
+        let output: String = std::fs::read_to_string("tests/pacman-qi-deps.txt")
+            .unwrap()
+            .lines()
+            .map(|line| match line.split_once(':') {
+                Some((label, rest)) if line.starts_with(' ').not() => {
+                    format!("\x1b[0;1m{label}:\x1b[0m{rest}\n")
+                }
+                _ => format!("{line}\n"),
+            })
+            .collect();
+        let pulled = |name: &str| (name == "libxml2").then(|| "calibre".to_string());
+        let res = with_extras(&output, &[Extra::pulled_in_by(PULLED_IN_BY, pulled)]);
+
+        assert!(res.contains(
+            "\x1b[0;1mInstall Reason  :\x1b[0m Installed as a dependency for another package
+Pulled In By    : calibre
+\x1b[0;1mInstall Script  :\x1b[0m No
+"
+        ));
+        assert_eq!(
+            "Name            : git",
+            plain("\x1b[0;1mName            :\x1b[0m git")
+        );
+    }
+
+    #[test]
+    fn file_count_field() {
+        let output = std::fs::read_to_string("tests/pacman-qi.txt").unwrap();
+        let listing = std::fs::read_to_string("tests/pacman-ql-nano.txt").unwrap();
+        let owned: Vec<_> = listing
+            .lines()
+            .filter_map(|l| l.split_once(' '))
+            .filter(|(pkg, _)| *pkg == "nano")
+            .map(|(_, path)| path)
+            .collect();
+        let count = |name: &str| (name == "nano").then(|| owned.len().to_string());
+        let extras = [
+            Extra::file_count(FILE_COUNT, count),
+            Extra::pulled_in_by(PULLED_IN_BY, |_| None),
+        ];
+        let res = with_extras(&output, &extras);
+
+        // The count follows the installed size, and matches the file list.
+        assert_eq!(21, owned.len());
+        assert!(res.contains(
+            "Installed Size  : 2.48 MiB
+File Count      : 21
+Packager        : Andreas Radke"
+        ));
+
+        let res = select_fields(&res, &["Name", FILE_COUNT]);
+        assert_eq!(
+            "Name            : git\n\nName            : nano\nFile Count      : 21\n\n",
+            res
+        );
+    }
 }
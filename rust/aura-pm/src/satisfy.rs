@@ -0,0 +1,49 @@
+//! Where the dependencies of some package would come from.
+
+use serde::Serialize;
+
+/// Package databases in which dependencies can be sought.
+pub(crate) trait Satisfier {
+    /// Is something that satisfies the dependency already installed?
+    fn installed(&self, dep: &str) -> bool;
+
+    /// Could something that satisfies the dependency be installed from the
+    /// official repos?
+    fn in_repos(&self, dep: &str) -> bool;
+}
+
+impl Satisfier for alpm::Alpm {
+    fn installed(&self, dep: &str) -> bool {
+        self.localdb().pkgs().find_satisfier(dep).is_some()
+    }
+
+    fn in_repos(&self, dep: &str) -> bool {
+        self.syncdbs().find_satisfier(dep).is_some()
+    }
+}
+
+/// Where a dependency would come from, were its package installed now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DepStatus {
+    Installed,
+    Repo,
+    /// Nothing known to Pacman provides it, so it could only be built, say from
+    /// the AUR.
+    Missing,
+}
+
+impl DepStatus {
+    pub(crate) fn of<D>(dbs: &D, dep: &str) -> DepStatus
+    where
+        D: Satisfier,
+    {
+        if dbs.installed(dep) {
+            DepStatus::Installed
+        } else if dbs.in_repos(dep) {
+            DepStatus::Repo
+        } else {
+            DepStatus::Missing
+        }
+    }
+}
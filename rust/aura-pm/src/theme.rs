@@ -0,0 +1,189 @@
+//! Colouring Aura's output according to the `[colors]` section of its config.
+//!
+//! Output is coloured by [`Role`], not by fixed colour, so that users whose
+//! terminal themes clash with the defaults can choose their own. Disabling
+//! colour entirely (e.g. `--color never`) still takes precedence.
+
+use colored::Color;
+use colored::ColoredString;
+use colored::Colorize;
+use serde::Serialize;
+use serde::Serializer;
+use std::sync::OnceLock;
+
+/// The colours in effect for this run, settled once the config has been read.
+static THEME: OnceLock<Colors> = OnceLock::new();
+
+/// The parts of Aura's output that are coloured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Role {
+    /// The `::` that begins Aura's messages.
+    Header,
+    /// Things that went well.
+    Success,
+    /// Things worth a second look.
+    Warning,
+    /// Things that went wrong.
+    Error,
+    /// Package names, paths, commands, and other details to pick out.
+    Highlight,
+}
+
+/// A colour for each [`Role`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub(crate) struct Colors {
+    #[serde(serialize_with = "by_name")]
+    pub(crate) header: Color,
+    #[serde(serialize_with = "by_name")]
+    pub(crate) success: Color,
+    #[serde(serialize_with = "by_name")]
+    pub(crate) warning: Color,
+    #[serde(serialize_with = "by_name")]
+    pub(crate) error: Color,
+    #[serde(serialize_with = "by_name")]
+    pub(crate) highlight: Color,
+}
+
+impl Default for Colors {
+    fn default() -> Self {
+        Colors {
+            header: Color::Cyan,
+            success: Color::Green,
+            warning: Color::Yellow,
+            error: Color::Red,
+            highlight: Color::Cyan,
+        }
+    }
+}
+
+impl Colors {
+    /// The colour assigned to some role.
+    pub(crate) fn of(&self, role: Role) -> Color {
+        match role {
+            Role::Header => self.header,
+            Role::Success => self.success,
+            Role::Warning => self.warning,
+            Role::Error => self.error,
+            Role::Highlight => self.highlight,
+        }
+    }
+}
+
+/// A colour by name, like `red` or `bright blue`, or by a `#rrggbb` code.
+pub(crate) fn parse(name: &str) -> Option<Color> {
+    match name.strip_prefix('#') {
+        Some(hex) if hex.len() == 6 => {
+            let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+
+            Some(Color::TrueColor {
+                r: byte(0)?,
+                g: byte(2)?,
+                b: byte(4)?,
+            })
+        }
+        Some(_) => None,
+        None => name.parse().ok(),
+    }
+}
+
+/// The name that [`parse`] reads back as the same colour.
+pub(crate) fn name(color: &Color) -> String {
+    match color {
+        Color::Black => "black".to_string(),
+        Color::Red => "red".to_string(),
+        Color::Green => "green".to_string(),
+        Color::Yellow => "yellow".to_string(),
+        Color::Blue => "blue".to_string(),
+        Color::Magenta => "magenta".to_string(),
+        Color::Cyan => "cyan".to_string(),
+        Color::White => "white".to_string(),
+        Color::BrightBlack => "bright black".to_string(),
+        Color::BrightRed => "bright red".to_string(),
+        Color::BrightGreen => "bright green".to_string(),
+        Color::BrightYellow => "bright yellow".to_string(),
+        Color::BrightBlue => "bright blue".to_string(),
+        Color::BrightMagenta => "bright magenta".to_string(),
+        Color::BrightCyan => "bright cyan".to_string(),
+        Color::BrightWhite => "bright white".to_string(),
+        Color::TrueColor { r, g, b } => format!("#{r:02x}{g:02x}{b:02x}"),
+    }
+}
+
+fn by_name<S>(color: &Color, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    s.serialize_str(&name(color))
+}
+
+/// Settle the colours for the rest of the run.
+pub(crate) fn set(colors: Colors) {
+    let _ = THEME.set(colors);
+}
+
+/// The colour currently assigned to some role. Before the config has been read,
+/// these are the defaults.
+pub(crate) fn color(role: Role) -> Color {
+    THEME.get().copied().unwrap_or_default().of(role)
+}
+
+/// Injection of role-based colouring into everything [`Colorize`] is.
+pub(crate) trait Themed: Colorize + Sized {
+    fn header(self) -> ColoredString {
+        self.color(color(Role::Header))
+    }
+
+    fn success(self) -> ColoredString {
+        self.color(color(Role::Success))
+    }
+
+    fn warning(self) -> ColoredString {
+        self.color(color(Role::Warning))
+    }
+
+    fn error(self) -> ColoredString {
+        self.color(color(Role::Error))
+    }
+
+    fn highlight(self) -> ColoredString {
+        self.color(color(Role::Highlight))
+    }
+}
+
+impl<T> Themed for T where T: Colorize {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn colour_names() {
+        assert_eq!(Some(Color::Red), parse("red"));
+        assert_eq!(Some(Color::BrightBlue), parse("bright blue"));
+        assert_eq!(Some(Color::Magenta), parse("Purple"));
+        assert_eq!(
+            Some(Color::TrueColor {
+                r: 0xff,
+                g: 0x88,
+                b: 0x00
+            }),
+            parse("#ff8800")
+        );
+        assert_eq!(None, parse("rouge"));
+        assert_eq!(None, parse("#ff88"));
+        assert_eq!(None, parse("#gg8800"));
+    }
+
+    #[test]
+    fn names_round_trip() {
+        let colors = [
+            Color::BrightBlack,
+            Color::Cyan,
+            Color::TrueColor { r: 1, g: 2, b: 3 },
+        ];
+
+        for c in colors {
+            assert_eq!(Some(c), parse(&name(&c)));
+        }
+    }
+}
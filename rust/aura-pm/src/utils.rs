@@ -9,10 +9,16 @@ use i18n_embed::fluent::FluentLanguageLoader;
 use i18n_embed_fl::fl;
 use karen::RunningAs;
 use nonempty_collections::NEVec;
+use std::collections::HashSet;
+use std::io::BufRead;
+use std::io::Read;
 use std::io::Write;
 use std::iter::Peekable;
+use std::ops::Not;
+use std::os::unix::process::ExitStatusExt;
 use std::path::Path;
 use std::process::Command;
+use std::process::ExitStatus;
 use std::str::FromStr;
 use unic_langid::LanguageIdentifier;
 
@@ -45,6 +51,31 @@ impl<T, E, R> ResultVoid<E, R> for Result<T, E> {
     }
 }
 
+/// Injection of the `succeeded` method into [`ExitStatus`], so that a wrapped
+/// external program's failure can become Aura's own.
+pub(crate) trait Succeeded {
+    /// The program's exit code, if it didn't succeed. Following the shell's
+    /// convention, a program killed by signal `n` is reported as `128 + n`.
+    fn succeeded(self) -> Result<(), u8>;
+}
+
+impl Succeeded for ExitStatus {
+    fn succeeded(self) -> Result<(), u8> {
+        if self.success() {
+            return Ok(());
+        }
+
+        let code = self
+            .code()
+            .or_else(|| self.signal().map(|n| 128 + n))
+            .and_then(|c| u8::try_from(c).ok())
+            .filter(|c| *c != 0)
+            .unwrap_or(1);
+
+        Err(code)
+    }
+}
+
 /// Produce a proper UTF-8 `String` from a `Path`-like type.
 pub(crate) trait PathStr {
     fn utf8(&self) -> String;
@@ -94,13 +125,74 @@ fn pad(mult: usize, longest: usize, s: &str) -> usize {
     mult * (longest - s.chars().count())
 }
 
+/// How long ago something happened, to the largest whole unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Elapsed {
+    /// Less than a minute ago, or in the future.
+    Now,
+    Minutes(i64),
+    Hours(i64),
+    Days(i64),
+    Weeks(i64),
+    Months(i64),
+    Years(i64),
+}
+
+impl Elapsed {
+    /// Round some span of time down to its largest whole unit. Months are
+    /// taken to be 30 days, and years 365.
+    pub(crate) fn of(span: time::Duration) -> Elapsed {
+        let days = span.whole_days();
+
+        match () {
+            _ if span.whole_minutes() < 1 => Elapsed::Now,
+            _ if span.whole_hours() < 1 => Elapsed::Minutes(span.whole_minutes()),
+            _ if days < 1 => Elapsed::Hours(span.whole_hours()),
+            _ if days < 7 => Elapsed::Days(days),
+            _ if days < 30 => Elapsed::Weeks(span.whole_weeks()),
+            _ if days < 365 => Elapsed::Months(days / 30),
+            _ => Elapsed::Years(days / 365),
+        }
+    }
+
+    /// A phrase like "3 months ago".
+    pub(crate) fn localise(&self, fll: &FluentLanguageLoader) -> String {
+        match self {
+            Elapsed::Now => fl!(fll, "time-now"),
+            Elapsed::Minutes(n) => fl!(fll, "time-minutes", n = *n),
+            Elapsed::Hours(n) => fl!(fll, "time-hours", n = *n),
+            Elapsed::Days(n) => fl!(fll, "time-days", n = *n),
+            Elapsed::Weeks(n) => fl!(fll, "time-weeks", n = *n),
+            Elapsed::Months(n) => fl!(fll, "time-months", n = *n),
+            Elapsed::Years(n) => fl!(fll, "time-years", n = *n),
+        }
+    }
+}
+
+/// A phrase like "3 months ago", for how long before `now` something happened.
+pub(crate) fn ago<D>(fll: &FluentLanguageLoader, then: D, now: D) -> String
+where
+    D: std::ops::Sub<Output = time::Duration>,
+{
+    Elapsed::of(now - then).localise(fll)
+}
+
 /// Prompt the user for confirmation.
 pub(crate) fn prompt(fll: &FluentLanguageLoader, msg: &str) -> Option<()> {
     print!("{msg}");
     std::io::stdout().flush().ok()?;
 
+    prompt_from(fll, std::io::stdin().lock())
+}
+
+/// The answer to a yes/no prompt, read from some input. An empty answer, as
+/// given at the end of the input, accepts the default of "yes".
+fn prompt_from<R>(fll: &FluentLanguageLoader, mut reader: R) -> Option<()>
+where
+    R: BufRead,
+{
     let mut input = String::new();
-    match std::io::stdin().read_line(&mut input) {
+    match reader.read_line(&mut input) {
         Err(_) => todo!(),
         Ok(_) => {
             let line = input.trim();
@@ -113,25 +205,98 @@ pub(crate) fn prompt(fll: &FluentLanguageLoader, msg: &str) -> Option<()> {
 
 /// Prompt the user for a numerical selection.
 pub(crate) fn select(msg: &str, max: usize) -> Result<usize, std::io::Error> {
+    select_from(msg, max, std::io::stdin().lock())
+}
+
+/// Like [`select`], but also accepting any answer that `named` resolves to a
+/// selection, such as the name of an item.
+pub(crate) fn select_named<F>(msg: &str, max: usize, named: F) -> Result<usize, std::io::Error>
+where
+    F: Fn(&str) -> Option<usize>,
+{
+    choose_from(msg, std::io::stdin().lock(), |s| {
+        numbered(s, max).or_else(|| named(s))
+    })
+}
+
+/// Like [`select`], but reading from some input.
+fn select_from<R>(msg: &str, max: usize, reader: R) -> Result<usize, std::io::Error>
+where
+    R: BufRead,
+{
+    choose_from(msg, reader, |s| numbered(s, max))
+}
+
+fn numbered(input: &str, max: usize) -> Option<usize> {
+    usize::from_str(input).ok().filter(|num| max >= *num)
+}
+
+/// Ask until `choice` accepts an answer. Fails rather than asking forever once
+/// the input has ended.
+fn choose_from<R, F>(msg: &str, mut reader: R, choice: F) -> Result<usize, std::io::Error>
+where
+    R: BufRead,
+    F: Fn(&str) -> Option<usize>,
+{
     let mut input = String::new();
-    let stdin = std::io::stdin();
     let mut stdout = std::io::stdout();
 
     loop {
         print!("{msg}");
         stdout.flush()?;
-        stdin.read_line(&mut input)?;
+        input.clear();
 
-        if let Ok(num) = usize::from_str(input.trim()) {
-            if max >= num {
-                return Ok(num);
-            }
-        } else {
-            input.clear();
+        if reader.read_line(&mut input)? == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+
+        if let Some(num) = choice(input.trim()) {
+            return Ok(num);
         }
     }
 }
 
+/// The packages listed in a file, as given to `--packages-from`. A path of `-`
+/// reads them from stdin instead.
+pub(crate) fn packages_from(path: &Path) -> Result<Vec<String>, std::io::Error> {
+    packages_from_or(path, std::io::stdin().lock())
+}
+
+/// Like [`packages_from`], but with some other input standing in for stdin.
+fn packages_from_or<R>(path: &Path, stdin: R) -> Result<Vec<String>, std::io::Error>
+where
+    R: Read,
+{
+    let content = if path == Path::new("-") {
+        std::io::read_to_string(stdin)?
+    } else {
+        std::fs::read_to_string(path)?
+    };
+
+    Ok(package_list(&content))
+}
+
+/// Package names, one per line. Blank lines and `#` comments are skipped, and
+/// only the first of any duplicates is kept.
+fn package_list(content: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+
+    content
+        .lines()
+        .map(|l| l.split_once('#').map_or(l, |(name, _)| name).trim())
+        .filter(|l| l.is_empty().not() && seen.insert(*l))
+        .map(String::from)
+        .collect()
+}
+
+/// The listed packages not already given on the command line.
+pub(crate) fn unseen(given: &[String], listed: Vec<String>) -> Vec<String> {
+    listed
+        .into_iter()
+        .filter(|p| given.contains(p).not())
+        .collect()
+}
+
 pub(crate) struct SudoError;
 
 impl Nested for SudoError {
@@ -234,3 +399,157 @@ pub(crate) fn cmd_lines(cmd: &str, args: &[&str]) -> Option<NEVec<String>> {
             NEVec::from_vec(v)
         })
 }
+
+/// A fresh, empty directory for a single test, removed again when dropped, even
+/// if the test panics.
+#[cfg(test)]
+pub(crate) struct TempDir(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    /// A new directory whose name starts with the given label. Names are unique
+    /// across test threads and concurrent test runs.
+    pub(crate) fn new(label: &str) -> TempDir {
+        static COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+        loop {
+            let n = COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let name = format!("aura-{label}-{}-{n}", std::process::id());
+            let path = std::env::temp_dir().join(name);
+
+            match std::fs::create_dir(&path) {
+                Ok(()) => return TempDir(path),
+                // Left behind by some earlier run whose pid we now share.
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => panic!("Couldn't create {}: {e}", path.display()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn prompt_defaults() {
+        let fll = crate::localization::load(None).unwrap();
+
+        // No input at all, as when stdin is closed.
+        assert_eq!(Some(()), prompt_from(&fll, std::io::empty()));
+        assert_eq!(Some(()), prompt_from(&fll, "\n".as_bytes()));
+        assert_eq!(None, prompt_from(&fll, "n\n".as_bytes()));
+    }
+
+    #[test]
+    fn elapsed_units() {
+        use time::Duration;
+
+        assert_eq!(Elapsed::Now, Elapsed::of(Duration::seconds(59)));
+        assert_eq!(Elapsed::Now, Elapsed::of(Duration::hours(-3)));
+        assert_eq!(Elapsed::Minutes(1), Elapsed::of(Duration::seconds(60)));
+        assert_eq!(Elapsed::Minutes(59), Elapsed::of(Duration::minutes(59)));
+        assert_eq!(
+            Elapsed::Hours(23),
+            Elapsed::of(Duration::minutes(23 * 60 + 59))
+        );
+        assert_eq!(Elapsed::Days(1), Elapsed::of(Duration::hours(24)));
+        assert_eq!(Elapsed::Weeks(1), Elapsed::of(Duration::days(7)));
+        assert_eq!(Elapsed::Weeks(4), Elapsed::of(Duration::days(29)));
+        assert_eq!(Elapsed::Months(1), Elapsed::of(Duration::days(30)));
+        assert_eq!(Elapsed::Months(12), Elapsed::of(Duration::days(364)));
+        assert_eq!(Elapsed::Years(1), Elapsed::of(Duration::days(365)));
+        assert_eq!(
+            Elapsed::Years(3),
+            Elapsed::of(Duration::days(3 * 365 + 200))
+        );
+    }
+
+    #[test]
+    fn ago_phrases() {
+        use time::macros::datetime;
+
+        let fll = crate::localization::load(None).unwrap();
+        let then = datetime!(2023-06-01 12:00);
+
+        assert_eq!("just now", ago(&fll, then, then));
+        assert_eq!("1 minute ago", ago(&fll, then, datetime!(2023-06-01 12:01)));
+        assert_eq!("5 hours ago", ago(&fll, then, datetime!(2023-06-01 17:30)));
+        assert_eq!("1 day ago", ago(&fll, then, datetime!(2023-06-02 12:00)));
+        assert_eq!("3 months ago", ago(&fll, then, datetime!(2023-09-15 12:00)));
+        assert_eq!("2 years ago", ago(&fll, then, datetime!(2025-07-01 12:00)));
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(Ok(()), ExitStatus::from_raw(0).succeeded());
+        // A normal exit with code 3.
+        assert_eq!(Err(3), ExitStatus::from_raw(3 << 8).succeeded());
+        // Killed by SIGKILL.
+        assert_eq!(Err(137), ExitStatus::from_raw(9).succeeded());
+    }
+
+    #[test]
+    fn select_ends_with_input() {
+        assert_eq!(2, select_from(">>> ", 3, "x\n2\n".as_bytes()).unwrap());
+        assert!(select_from(">>> ", 3, std::io::empty()).is_err());
+        assert_eq!(1, select_from(">>> ", 3, "9\n1\n".as_bytes()).unwrap());
+
+        let named = |s: &str| (s == "work").then_some(5);
+        let answer = choose_from(">>> ", "play\nwork\n".as_bytes(), named).unwrap();
+        assert_eq!(5, answer);
+    }
+
+    #[test]
+    fn listed_packages() {
+        let content = "# Core\r\nlinux\r\n\r\nfirefox  # The browser\n   \n#mesa\nlinux\n\tgimp\n";
+        assert_eq!(vec!["linux", "firefox", "gimp"], package_list(content));
+        assert!(package_list("").is_empty());
+        assert!(package_list("# Nothing\n\n").is_empty());
+
+        // A lone `-` reads from stdin instead of a file.
+        let stdin = "aura\r\n# Unused\nbat\n".as_bytes();
+        let listed = packages_from_or(Path::new("-"), stdin).unwrap();
+        assert_eq!(vec!["aura", "bat"], listed);
+
+        let dir = TempDir::new("listed");
+        let path = dir.join("packages");
+        std::fs::write(&path, "bat\ncowsay\n").unwrap();
+        let listed = packages_from_or(&path, "aura\n".as_bytes()).unwrap();
+        assert_eq!(vec!["bat", "cowsay"], listed);
+
+        let missing = packages_from_or(Path::new("/nonexistent/aura-list"), std::io::empty());
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn unseen_packages() {
+        let given = vec!["bat".to_string(), "aura".to_string()];
+        let listed = vec!["cowsay".to_string(), "aura".to_string(), "exa".to_string()];
+        assert_eq!(vec!["cowsay", "exa"], unseen(&given, listed));
+        assert!(unseen(&given, Vec::new()).is_empty());
+    }
+}